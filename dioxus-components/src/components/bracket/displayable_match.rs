@@ -40,6 +40,10 @@ pub(crate) fn DisplayMatch(cx: Scope, m: MinimalMatch) -> Element {
     // NOTE: removing the invisible caracter does not draw the padding match,
     // which messes up first round bracket display
     let seeds = m.get_seeds();
+    let set_length = m
+        .get_set_length()
+        .map(|s| s.to_string())
+        .unwrap_or_default();
     let seed1_content = if is_padding_match {
         INVIS_CHAR.into()
     } else {
@@ -80,6 +84,7 @@ pub(crate) fn DisplayMatch(cx: Scope, m: MinimalMatch) -> Element {
             },
 
             class: "col-span-1 flex flex-col my-auto {start} {outerStyle}",
+            title: "{set_length}",
             style: "max-width: 140px;",
 
             rsx! { row1 }
@@ -13,7 +13,7 @@ use totsugeki_display::loser_bracket::lines as loser_bracket_lines;
 use totsugeki_display::loser_bracket::reorder as reorder_loser_bracket;
 use totsugeki_display::winner_bracket::lines as winner_bracket_lines;
 use totsugeki_display::winner_bracket::reorder as reorder_winner_bracket;
-use totsugeki_display::{from_participants, MinimalMatch};
+use totsugeki_display::{from_bracket, MinimalMatch};
 
 /// View of a double elimination bracket with interactible elements to update
 /// its state
@@ -34,18 +34,13 @@ pub(crate) fn View(cx: Scope) -> Element {
         return None;
     };
 
-    let participants = bracket.get_participants();
-
     let Ok(wb_rounds_matches) = dev.partition_winner_bracket() else {
         log::warn!("winner bracket");
         return None;
     };
     let mut wb_rounds = vec![];
     for r in wb_rounds_matches {
-        let round = r
-            .iter()
-            .map(|m| from_participants(m, &participants))
-            .collect();
+        let round = r.iter().map(|m| from_bracket(m, &bracket)).collect();
         wb_rounds.push(round);
     }
     reorder_winner_bracket(&mut wb_rounds);
@@ -80,10 +75,7 @@ pub(crate) fn View(cx: Scope) -> Element {
     };
     let mut lb_rounds: Vec<Vec<MinimalMatch>> = vec![];
     for r in lb_rounds_matches {
-        let round = r
-            .iter()
-            .map(|m| from_participants(m, &participants))
-            .collect();
+        let round = r.iter().map(|m| from_bracket(m, &bracket)).collect();
         lb_rounds.push(round);
     }
     reorder_loser_bracket(&mut lb_rounds);
@@ -115,8 +107,9 @@ pub(crate) fn View(cx: Scope) -> Element {
         log::error!("grand finals+reset");
         return None;
     };
-    let gf = from_participants(&gf, &participants);
-    let gf_reset = from_participants(&gf_reset, &participants);
+    let gf = from_bracket(&gf, &bracket);
+    // grand finals are not always played again
+    let gf_reset = gf_reset.map(|gf_reset| from_bracket(&gf_reset, &bracket));
 
     cx.render(rsx!(
         MatchEditModal { isHidden: isMatchEditModalHidden }
@@ -161,10 +154,14 @@ pub(crate) fn View(cx: Scope) -> Element {
             "Grand finals",
         }
         DisplayMatch(cx, gf)
-        h1 {
-            class: "text-lg",
-            "Grand final reset",
+        if let Some(gf_reset) = gf_reset {
+            rsx! {
+                h1 {
+                    class: "text-lg",
+                    "Grand final reset",
+                }
+                DisplayMatch(cx, gf_reset)
+            }
         }
-        DisplayMatch(cx, gf_reset)
     ))
 }
@@ -0,0 +1,65 @@
+//! View of a round robin pool
+#![allow(non_snake_case)]
+
+use crate::components::bracket::displayable_round::Round;
+use crate::components::bracket::match_edit::MatchEditModal;
+use crate::Modal;
+use dioxus::prelude::*;
+use totsugeki::bracket::round_robin_variant::Variant as RoundRobinVariant;
+use totsugeki::bracket::Bracket;
+use totsugeki_display::from_bracket;
+
+/// View over round robin pool: rounds of matches then standings
+pub(crate) fn View(cx: Scope) -> Element {
+    let modal = use_shared_state::<Option<Modal>>(cx).expect("modal to show");
+    let isMatchEditModalHidden = !matches!(*modal.read(), Some(Modal::EnterMatchResult(_, _, _)));
+    let bracket = match use_shared_state::<Bracket>(cx) {
+        Some(bracket_ref) => bracket_ref.read().clone(),
+        None => Bracket::default(),
+    };
+    let participants = bracket.get_participants();
+    let Ok(rrv) = RoundRobinVariant::try_from(bracket.clone()) else {
+        return None;
+    };
+    let Ok(match_by_rounds) = rrv.partition_by_round() else {
+        return None;
+    };
+    let rounds = match_by_rounds
+        .iter()
+        .map(|r| {
+            r.iter()
+                .map(|m| from_bracket(m, &bracket))
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    let standings = rrv
+        .standings()
+        .into_iter()
+        .enumerate()
+        .map(|(i, s)| {
+            let name = participants
+                .get(s.player_id)
+                .map(|p| p.get_name())
+                .unwrap_or_default();
+            format!(
+                "{}. {name} ({}-{}, {:+})",
+                i + 1,
+                s.wins,
+                s.losses,
+                s.game_differential
+            )
+        })
+        .collect::<Vec<_>>();
+
+    cx.render(rsx!(
+        div {
+            class: "flex flex-row gap-4",
+            rounds.into_iter().map(|r| Round(cx, r))
+        }
+        h3 { class: "text-lg", "Standings" }
+        for line in standings.iter() {
+            p { line.clone() }
+        }
+        MatchEditModal { isHidden: isMatchEditModalHidden }
+    ))
+}
@@ -7,7 +7,8 @@ use crate::{
 use dioxus::prelude::*;
 use totsugeki::bracket::single_elimination_variant::Variant as SingleEliminationVariant;
 use totsugeki::bracket::Bracket;
-use totsugeki_display::from_participants;
+use totsugeki::matches::Match;
+use totsugeki_display::from_bracket;
 use totsugeki_display::winner_bracket::lines;
 use totsugeki_display::winner_bracket::reorder;
 
@@ -23,27 +24,72 @@ pub(crate) fn View(cx: Scope) -> Element {
     };
     let sev: SingleEliminationVariant = bracket.clone().try_into().expect("partition");
 
-    let participants = bracket.get_participants();
-
     // let mut rounds = winner_bracket(matches, &participants);
     let match_by_rounds = sev.partition_by_round().expect("rounds");
+    let Some(ui_primitives) = bracket_primitives(match_by_rounds, &bracket) else {
+        // TODO log error
+        return None;
+    };
+    let columns = ui_primitives.len();
+    let playoffs = sev
+        .playoffs()
+        .into_iter()
+        .filter_map(|playoff| {
+            let primitives = bracket_primitives(playoff.rounds, &bracket)?;
+            Some((playoff.placement.to_string(), primitives.len(), primitives))
+        })
+        .collect::<Vec<_>>();
+
+    cx.render(rsx!(div {
+        MatchEditModal { isHidden: isMatchEditModalHidden }
+        div {
+            class: "grid grid-rows-1 grid-cols-{columns} flex",
+            for s in ui_primitives.into_iter() {
+                match s {
+                    BracketPrimitives::Match(round) => rsx! { Round(cx, round) },
+                    BracketPrimitives::Block(line) => rsx! {
+                        ConnectMatchesBetweenRounds(line)
+                    },
+                }
+            }
+        }
+        for (placement, columns, primitives) in playoffs.into_iter() {
+            div {
+                class: "pt-4",
+                p { "Placements {placement}" }
+                div {
+                    class: "grid grid-rows-1 grid-cols-{columns} flex",
+                    for s in primitives.into_iter() {
+                        match s {
+                    BracketPrimitives::Match(round) => rsx! { Round(cx, round) },
+                    BracketPrimitives::Block(line) => rsx! {
+                        ConnectMatchesBetweenRounds(line)
+                    },
+                }
+                    }
+                }
+            }
+        }
+    }))
+}
+
+/// Returns columns of single elimination bracket with matches partitionned by
+/// round
+fn bracket_primitives(
+    match_by_rounds: Vec<Vec<Match>>,
+    bracket: &Bracket,
+) -> Option<Vec<BracketPrimitives>> {
     let mut rounds = vec![];
     // FIXME find a way to map vec of vec from one type to another
     // Note: did not find a way to map a vec of vec of Match into vec of vec of
     // Displayable match
     for r in match_by_rounds {
-        let round = r
-            .iter()
-            .map(|m| from_participants(m, &participants))
-            .collect();
+        let round = r.iter().map(|m| from_bracket(m, bracket)).collect();
         rounds.push(round);
     }
     reorder(&mut rounds);
 
-    let Some(lines) = lines(&rounds) else {
-        // TODO log error
-        return None;
-    };
+    let lines = lines(&rounds)?;
 
     // NOTE: given a number of players, the number of the matches is know
     // Then I can deal with an array of fixed size for the matches. It's not
@@ -64,20 +110,5 @@ pub(crate) fn View(cx: Scope) -> Element {
             rounds.into_iter().last().expect("element"),
         ));
     }
-    let columns = ui_primitives.len();
-
-    cx.render(rsx!(div {
-        MatchEditModal { isHidden: isMatchEditModalHidden }
-        div {
-            class: "grid grid-rows-1 grid-cols-{columns} flex",
-            for s in ui_primitives.into_iter() {
-                match s {
-                    BracketPrimitives::Match(round) => rsx! { Round(cx, round) },
-                    BracketPrimitives::Block(line) => rsx! {
-                        ConnectMatchesBetweenRounds(line)
-                    },
-                }
-            }
-        }
-    }))
+    Some(ui_primitives)
 }
@@ -0,0 +1,63 @@
+//! View of a swiss tournament
+#![allow(non_snake_case)]
+
+use crate::components::bracket::displayable_round::Round;
+use crate::components::bracket::match_edit::MatchEditModal;
+use crate::Modal;
+use dioxus::prelude::*;
+use totsugeki::bracket::swiss_variant::Variant as SwissVariant;
+use totsugeki::bracket::Bracket;
+use totsugeki_display::from_bracket;
+
+/// View over swiss tournament: rounds generated so far then standings
+pub(crate) fn View(cx: Scope) -> Element {
+    let modal = use_shared_state::<Option<Modal>>(cx).expect("modal to show");
+    let isMatchEditModalHidden = !matches!(*modal.read(), Some(Modal::EnterMatchResult(_, _, _)));
+    let bracket = match use_shared_state::<Bracket>(cx) {
+        Some(bracket_ref) => bracket_ref.read().clone(),
+        None => Bracket::default(),
+    };
+    let participants = bracket.get_participants();
+    let Ok(sv) = SwissVariant::try_from(bracket.clone()) else {
+        return None;
+    };
+    let match_by_rounds = sv.partition_by_round();
+    let rounds = match_by_rounds
+        .iter()
+        .map(|r| {
+            r.iter()
+                .map(|m| from_bracket(m, &bracket))
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    let standings = sv
+        .standings()
+        .into_iter()
+        .enumerate()
+        .map(|(i, s)| {
+            let name = participants
+                .get(s.player_id)
+                .map(|p| p.get_name())
+                .unwrap_or_default();
+            format!(
+                "{}. {name} ({}-{}, Buchholz {})",
+                i + 1,
+                s.wins,
+                s.losses,
+                s.buchholz
+            )
+        })
+        .collect::<Vec<_>>();
+
+    cx.render(rsx!(
+        div {
+            class: "flex flex-row gap-4",
+            rounds.into_iter().map(|r| Round(cx, r))
+        }
+        h3 { class: "text-lg", "Standings" }
+        for line in standings.iter() {
+            p { line.clone() }
+        }
+        MatchEditModal { isHidden: isMatchEditModalHidden }
+    ))
+}
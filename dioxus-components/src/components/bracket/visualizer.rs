@@ -3,8 +3,9 @@
 
 use crate::{
     components::bracket::double_elimination_bracket_view::View as DoubleEliminationBracketView,
+    components::bracket::round_robin_view::View as RoundRobinView,
     components::bracket::single_elimination_bracket_view::View as SingleEliminationBracketView,
-    components::Submit,
+    components::bracket::swiss_view::View as SwissView, components::Submit,
 };
 use chrono::prelude::*;
 use dioxus::prelude::*;
@@ -73,7 +74,14 @@ pub fn UpdateBracketDetails(cx: Scope) -> Element {
                 select {
                     name: "format",
                     option { "double-elimination" }
+                    option { "double-elimination-alternating-drops" }
+                    option { "double-elimination-no-reset" }
+                    option { "double-elimination-winner-side-advantage" }
                     option { "single-elimination" }
+                    option { "single-elimination-third-place" }
+                    option { "single-elimination-consolation" }
+                    option { "round-robin" }
+                    option { "swiss" }
                 }
             }
 
@@ -117,8 +125,10 @@ pub fn View(cx: Scope) -> Element {
         None => Bracket::default().get_format(),
     };
     let view = match format {
-        Format::SingleElimination => SingleEliminationBracketView(cx),
-        Format::DoubleElimination => DoubleEliminationBracketView(cx),
+        Format::SingleElimination { .. } => SingleEliminationBracketView(cx),
+        Format::DoubleElimination { .. } => DoubleEliminationBracketView(cx),
+        Format::RoundRobin => RoundRobinView(cx),
+        Format::Swiss { .. } => SwissView(cx),
     };
 
     cx.render(rsx!(
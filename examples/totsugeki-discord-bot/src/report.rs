@@ -48,11 +48,11 @@ async fn report(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
         for m in new_matches {
             let player1 = match m.get_players()[0] {
                 Opponent::Player(p) => p,
-                Opponent::Unknown => panic!("cannot parse opponent"),
+                Opponent::Unknown | Opponent::Bye => panic!("cannot parse opponent"),
             };
             let player2 = match m.get_players()[1] {
                 Opponent::Player(p) => p,
-                Opponent::Unknown => panic!("cannot parse opponent"),
+                Opponent::Unknown | Opponent::Bye => panic!("cannot parse opponent"),
             };
             new_matches_message = format!("{}\n{} VS {}", new_matches_message, player1, player2);
         }
@@ -128,11 +128,11 @@ async fn tournament_organiser_reports(
         for m in new_matches {
             let player1 = match m.get_players()[0] {
                 Opponent::Player(p) => p,
-                Opponent::Unknown => panic!("cannot parse opponent"),
+                Opponent::Unknown | Opponent::Bye => panic!("cannot parse opponent"),
             };
             let player2 = match m.get_players()[1] {
                 Opponent::Player(p) => p,
-                Opponent::Unknown => panic!("cannot parse opponent"),
+                Opponent::Unknown | Opponent::Bye => panic!("cannot parse opponent"),
             };
             new_matches_message = format!("{}\n{} VS {}", new_matches_message, player1, player2);
         }
@@ -112,7 +112,9 @@ mod tests {
     fn get_data(n: usize) -> Bracket {
         let mut bracket = Bracket::new(
             "",
-            totsugeki::format::Format::SingleElimination,
+            totsugeki::format::Format::SingleElimination {
+                playoffs: totsugeki::format::Playoffs::Disabled,
+            },
             totsugeki::seeding::Method::Strict,
             DateTime::default(),
             true,
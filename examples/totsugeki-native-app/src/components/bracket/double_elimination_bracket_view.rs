@@ -107,7 +107,8 @@ pub(crate) fn View(cx: Scope) -> Element {
         return None;
     };
     let gf = from_participants(&gf, &participants);
-    let gf_reset = from_participants(&gf_reset, &participants);
+    // grand finals are not always played again
+    let gf_reset = gf_reset.map(|gf_reset| from_participants(&gf_reset, &participants));
 
     cx.render(rsx!(
         MatchEditModal { isHidden: isMatchEditModalHidden }
@@ -152,10 +153,14 @@ pub(crate) fn View(cx: Scope) -> Element {
             "Grand finals",
         }
         DisplayMatch(cx, gf)
-        h1 {
-            class: "text-lg",
-            "Grand final reset",
+        if let Some(gf_reset) = gf_reset {
+            rsx! {
+                h1 {
+                    class: "text-lg",
+                    "Grand final reset",
+                }
+                DisplayMatch(cx, gf_reset)
+            }
         }
-        DisplayMatch(cx, gf_reset)
     ))
 }
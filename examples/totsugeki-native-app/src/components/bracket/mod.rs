@@ -5,6 +5,8 @@ pub(crate) mod displayable_match;
 mod displayable_round;
 mod double_elimination_bracket_view;
 mod match_edit;
+mod round_robin_view;
 mod single_elimination_bracket_view;
+mod swiss_view;
 mod ui_primitives;
 pub mod visualizer;
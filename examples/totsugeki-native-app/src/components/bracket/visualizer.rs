@@ -3,7 +3,9 @@
 
 use crate::{
     components::bracket::double_elimination_bracket_view::View as DoubleEliminationBracketView,
+    components::bracket::round_robin_view::View as RoundRobinView,
     components::bracket::single_elimination_bracket_view::View as SingleEliminationBracketView,
+    components::bracket::swiss_view::View as SwissView,
     components::Submit,
 };
 use chrono::prelude::*;
@@ -73,6 +75,8 @@ pub fn UpdateBracketDetails(cx: Scope) -> Element {
                     name: "format",
                     option { "double-elimination" }
                     option { "single-elimination" }
+                    option { "round-robin" }
+                    option { "swiss" }
                 }
             }
 
@@ -114,8 +118,10 @@ pub fn View(cx: Scope) -> Element {
         None => Bracket::default().get_format(),
     };
     let view = match format {
-        Format::SingleElimination => SingleEliminationBracketView(cx),
-        Format::DoubleElimination => DoubleEliminationBracketView(cx),
+        Format::SingleElimination { .. } => SingleEliminationBracketView(cx),
+        Format::DoubleElimination { .. } => DoubleEliminationBracketView(cx),
+        Format::RoundRobin => RoundRobinView(cx),
+        Format::Swiss { .. } => SwissView(cx),
     };
 
     cx.render(rsx!(
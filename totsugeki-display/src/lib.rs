@@ -11,7 +11,8 @@
 #![forbid(unsafe_code)]
 
 use serde::Serialize;
-use totsugeki::matches::{Id as MatchId, Match};
+use totsugeki::bracket::Bracket;
+use totsugeki::matches::{Id as MatchId, Link, Match, SetLength};
 use totsugeki::opponent::Opponent;
 use totsugeki::player::Id as PlayerId;
 use totsugeki::player::{Participants, Player};
@@ -32,6 +33,10 @@ pub struct MinimalMatch {
     seeds: [usize; 2],
     /// Indicate which row it belongs to, starting from 0 index
     row_hint: Option<usize>,
+    /// Expected set length of match, if bracket has match rules
+    set_length: Option<SetLength>,
+    /// Where winner of match plays next
+    winner_goes_to: Option<Link>,
 }
 
 impl Default for MinimalMatch {
@@ -45,6 +50,8 @@ impl Default for MinimalMatch {
             score: (0, 0),
             seeds: [0, 0],
             row_hint: None,
+            set_length: None,
+            winner_goes_to: None,
         }
     }
 }
@@ -58,6 +65,33 @@ impl MinimalMatch {
         }
     }
 
+    /// Link every match of `rounds` to the match of the next round where its
+    /// top seed plays, like brackets generated by `totsugeki` do
+    #[cfg(test)]
+    fn link_by_seeds(rounds: &mut [Vec<MinimalMatch>]) {
+        for i in 1..rounds.len() {
+            let (previous, next) = rounds.split_at_mut(i);
+            for m in &mut previous[i - 1] {
+                m.winner_goes_to =
+                    next[0]
+                        .iter()
+                        .find(|n| n.seeds.contains(&m.seeds[0]))
+                        .map(|n| Link {
+                            match_id: n.id,
+                            is_player_1: n.seeds[0] == m.seeds[0],
+                        });
+            }
+        }
+    }
+
+    /// Returns `Some(true)` if winner of this match plays next as player 1 of
+    /// `match_id`, `Some(false)` as player 2 and `None` if they don't
+    fn winner_slot_in(&self, match_id: MatchId) -> Option<bool> {
+        self.winner_goes_to
+            .filter(|link| link.match_id == match_id)
+            .map(|link| link.is_player_1)
+    }
+
     #[cfg(test)]
     fn summary(&self) -> String {
         format!("{:?}; row hint = {:?}", self.seeds, self.row_hint)
@@ -92,6 +126,12 @@ impl MinimalMatch {
     pub fn get_id(&self) -> MatchId {
         self.id
     }
+
+    /// Get expected set length of match (for instance "Bo3")
+    #[must_use]
+    pub fn get_set_length(&self) -> Option<SetLength> {
+        self.set_length
+    }
 }
 
 /// Display lines using boxes and their borders
@@ -137,11 +177,11 @@ pub fn from_participants(m: &Match, participants: &Participants) -> MinimalMatch
     // only the id and doing some work to get back id and name.
     let p1 = match m.get_players()[0] {
         Opponent::Player(id) => id,
-        Opponent::Unknown => PlayerId::new_v4(),
+        Opponent::Unknown | Opponent::Bye => PlayerId::new_v4(),
     };
     let p2 = match m.get_players()[1] {
         Opponent::Player(id) => id,
-        Opponent::Unknown => PlayerId::new_v4(),
+        Opponent::Unknown | Opponent::Bye => PlayerId::new_v4(),
     };
     let top_seed = m.get_players()[0].get_name(&players);
     let bottom_seed = m.get_players()[1].get_name(&players);
@@ -154,5 +194,60 @@ pub fn from_participants(m: &Match, participants: &Participants) -> MinimalMatch
         score: m.get_score(),
         seeds: m.get_seeds(),
         row_hint: None,
+        set_length: None,
+        winner_goes_to: m.get_links().winner,
+    }
+}
+
+/// Convert match struct from Totsugeki library into minimal struct, using
+/// `bracket` to fill in name of players and expected set length.
+///
+/// # Panics
+/// When match is not part of bracket
+#[must_use]
+pub fn from_bracket(m: &Match, bracket: &Bracket) -> MinimalMatch {
+    MinimalMatch {
+        set_length: bracket.set_length_of(m.get_id()).expect("match of bracket"),
+        ..from_participants(m, &bracket.get_participants())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::from_bracket;
+    use totsugeki::bracket::builder::Builder;
+    use totsugeki::bracket::match_rules::MatchRules;
+    use totsugeki::format::{Format, GrandFinals, LoserDrops};
+    use totsugeki::matches::SetLength;
+
+    #[test]
+    fn minimal_match_shows_expected_set_length() {
+        let bracket = Builder::default()
+            .set_format(Format::DoubleElimination {
+                loser_drops: LoserDrops::Standard,
+                grand_finals: GrandFinals::Reset,
+            })
+            .set_new_players(4)
+            .build()
+            .expect("bracket");
+        let gf = *bracket.get_matches().iter().rev().nth(1).expect("gf");
+        assert_eq!(from_bracket(&gf, &bracket).get_set_length(), None);
+
+        let bracket = bracket
+            .set_match_rules(
+                MatchRules::new(SetLength::BestOf(3)).set_grand_finals(SetLength::FirstTo(3)),
+            )
+            .expect("match rules");
+        let first_match = bracket.get_matches()[0];
+        assert_eq!(
+            from_bracket(&first_match, &bracket)
+                .get_set_length()
+                .map(|s| s.to_string()),
+            Some("Bo3".into())
+        );
+        assert_eq!(
+            from_bracket(&gf, &bracket).get_set_length(),
+            Some(SetLength::FirstTo(3))
+        );
     }
 }
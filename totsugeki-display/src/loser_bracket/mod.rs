@@ -39,18 +39,20 @@ pub fn reorder(rounds: &mut [Vec<MinimalMatch>]) {
 
         // iterate over previous round and set positional hints
         for (j, m) in rounds[i + 1].iter().enumerate() {
-            let winner_seed = m.seeds[0];
-            // (first) player of round 1 with highest seed is expected to win
-            if let Some(m) = round.iter_mut().find(|r_m| r_m.seeds[0] == winner_seed) {
-                m.row_hint = Some(rounds[i + 1][j].row_hint.expect("row hint") * 2);
-            }
-            let loser_seed = m.seeds[1];
-            if let Some(m) = round.iter_mut().find(|r_m| r_m.seeds[0] == loser_seed) {
-                if (lb_rounds_count - i) % 2 == 0 {
-                    m.row_hint = rounds[i + 1][j].row_hint;
-                } else {
+            // matches sending their winner to `m` are placed next to it
+            for r_m in &mut round {
+                match r_m.winner_slot_in(m.id) {
+                    Some(true) => {
+                        r_m.row_hint = Some(rounds[i + 1][j].row_hint.expect("row hint") * 2);
+                    }
+                    Some(false) if (lb_rounds_count - i) % 2 == 0 => {
+                        r_m.row_hint = rounds[i + 1][j].row_hint;
+                    }
                     // 7-10 (1), 8-9 (3)
-                    m.row_hint = Some(rounds[i + 1][j].row_hint.expect("row hint") * 2 + 1);
+                    Some(false) => {
+                        r_m.row_hint = Some(rounds[i + 1][j].row_hint.expect("row hint") * 2 + 1);
+                    }
+                    None => {}
                 }
             }
         }
@@ -67,6 +67,7 @@ mod tests {
     #[test]
     fn _3_players_bracket() {
         let mut rounds = [vec![MinimalMatch::new([2, 3])]];
+        MinimalMatch::link_by_seeds(&mut rounds);
         reorder(&mut rounds);
 
         let lines = lines(rounds.to_vec()).expect("lines");
@@ -81,6 +82,7 @@ mod tests {
             vec![MinimalMatch::new([2, 3])],
         ];
 
+        MinimalMatch::link_by_seeds(&mut rounds);
         reorder(&mut rounds);
         let lines = lines(rounds.to_vec()).expect("lines");
 
@@ -96,6 +98,7 @@ mod tests {
             vec![MinimalMatch::new([2, 3])],
         ];
 
+        MinimalMatch::link_by_seeds(&mut rounds);
         reorder(&mut rounds);
         let lines = lines(rounds.to_vec()).expect("lines");
 
@@ -118,6 +121,7 @@ mod tests {
             vec![MinimalMatch::new([2, 3])],
         ];
 
+        MinimalMatch::link_by_seeds(&mut rounds);
         reorder(&mut rounds);
         let lines = lines(rounds.to_vec()).expect("lines");
 
@@ -140,6 +144,7 @@ mod tests {
             vec![MinimalMatch::new([2, 3])],
         ];
 
+        MinimalMatch::link_by_seeds(&mut rounds);
         reorder(&mut rounds);
         let lines = lines(rounds.to_vec()).expect("lines");
 
@@ -10,6 +10,7 @@ mod tests {
     fn _3_players() {
         let mut rounds = [vec![MinimalMatch::new([2, 3])]];
 
+        MinimalMatch::link_by_seeds(&mut rounds);
         reorder(&mut rounds);
 
         assert_eq!(rounds.len(), 1);
@@ -23,6 +24,7 @@ mod tests {
             vec![MinimalMatch::new([2, 3])],
         ];
 
+        MinimalMatch::link_by_seeds(&mut rounds);
         reorder(&mut rounds);
 
         assert_eq!(rounds.len(), 2);
@@ -38,6 +40,7 @@ mod tests {
             vec![MinimalMatch::new([2, 3])],
         ];
 
+        MinimalMatch::link_by_seeds(&mut rounds);
         reorder(&mut rounds);
 
         assert_eq!(rounds.len(), 3);
@@ -59,6 +62,7 @@ mod tests {
             vec![MinimalMatch::new([2, 3])],
         ];
 
+        MinimalMatch::link_by_seeds(&mut rounds);
         reorder(&mut rounds);
 
         assert_eq!(rounds.len(), 3);
@@ -77,6 +81,7 @@ mod tests {
             vec![MinimalMatch::new([2, 3])],
         ];
 
+        MinimalMatch::link_by_seeds(&mut rounds);
         reorder(&mut rounds);
 
         assert_eq!(rounds.len(), 4);
@@ -98,6 +103,30 @@ mod tests {
         );
     }
 
+    #[test]
+    fn _8_players_with_alternating_loser_drops() {
+        // 3 and 4 trade places when dropping from winner bracket
+        let mut rounds = [
+            vec![MinimalMatch::new([5, 8]), MinimalMatch::new([6, 7])],
+            vec![MinimalMatch::new([4, 6]), MinimalMatch::new([3, 5])],
+            vec![MinimalMatch::new([4, 3])],
+            vec![MinimalMatch::new([2, 4])],
+        ];
+
+        MinimalMatch::link_by_seeds(&mut rounds);
+        reorder(&mut rounds);
+
+        assert_eq!(rounds[1][0].seeds, [4, 6]);
+        assert_eq!(rounds[1][0].row_hint, Some(0));
+        assert_eq!(rounds[1][1].seeds, [3, 5]);
+        assert_eq!(rounds[1][1].row_hint, Some(1));
+        // winners of first round are placed next to the match they play next
+        assert_eq!(rounds[0][0].seeds, [6, 7], "{}", rounds[0][0].summary());
+        assert_eq!(rounds[0][0].row_hint, Some(0));
+        assert_eq!(rounds[0][1].seeds, [5, 8], "{}", rounds[0][1].summary());
+        assert_eq!(rounds[0][1].row_hint, Some(1));
+    }
+
     #[test]
     fn _9_players() {
         let mut rounds = [
@@ -113,6 +142,7 @@ mod tests {
             vec![MinimalMatch::new([2, 3])],
         ];
 
+        MinimalMatch::link_by_seeds(&mut rounds);
         reorder(&mut rounds);
 
         assert_eq!(rounds.len(), 5);
@@ -156,6 +186,7 @@ mod tests {
             vec![MinimalMatch::new([2, 3])],
         ];
 
+        MinimalMatch::link_by_seeds(&mut rounds);
         reorder(&mut rounds);
 
         assert_eq!(rounds.len(), 5);
@@ -201,6 +232,7 @@ mod tests {
             vec![MinimalMatch::new([2, 3])],
         ];
 
+        MinimalMatch::link_by_seeds(&mut rounds);
         reorder(&mut rounds);
 
         assert_eq!(rounds.len(), 5);
@@ -248,6 +280,7 @@ mod tests {
             vec![MinimalMatch::new([2, 3])],
         ];
 
+        MinimalMatch::link_by_seeds(&mut rounds);
         reorder(&mut rounds);
 
         assert_eq!(rounds.len(), 5);
@@ -125,14 +125,13 @@ pub fn reorder(rounds: &mut [Vec<MinimalMatch>]) {
             let row_hint_1 = 2 * j;
             let row_hint_2 = 2 * j + 1;
 
-            let seed_1 = m.seeds[0];
-            // (first) player of round 1 with highest seed is expected to win
-            if let Some(m) = round.iter_mut().find(|r_m| r_m.seeds[0] == seed_1) {
-                m.row_hint = Some(row_hint_1);
-            }
-            let seed_2 = m.seeds[1];
-            if let Some(m) = round.iter_mut().find(|r_m| r_m.seeds[0] == seed_2) {
-                m.row_hint = Some(row_hint_2);
+            // matches sending their winner to `m` are placed next to it
+            for r_m in &mut round {
+                match r_m.winner_slot_in(m.id) {
+                    Some(true) => r_m.row_hint = Some(row_hint_1),
+                    Some(false) => r_m.row_hint = Some(row_hint_2),
+                    None => {}
+                }
             }
         }
 
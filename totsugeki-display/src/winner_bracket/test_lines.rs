@@ -10,7 +10,9 @@ mod tests {
 
     fn get_data(n: usize) -> Bracket {
         Builder::default()
-            .set_format(totsugeki::format::Format::SingleElimination)
+            .set_format(totsugeki::format::Format::SingleElimination {
+                playoffs: totsugeki::format::Playoffs::Disabled,
+            })
             .set_new_players(n)
             .build()
             .expect("bracket")
@@ -229,5 +231,36 @@ mod tests {
         );
     }
 
+    #[test]
+    fn consolation_bracket_of_8_participants_bracket() {
+        let bracket = Builder::default()
+            .set_format(totsugeki::format::Format::SingleElimination {
+                playoffs: totsugeki::format::Playoffs::Consolation,
+            })
+            .set_new_players(8)
+            .build()
+            .expect("bracket");
+        let participants = bracket.get_participants();
+        let sev: Variant = bracket.try_into().expect("single elimination bracket");
+        let playoffs = sev.playoffs();
+        // 5th to 8th place is decided like a 4 participants bracket
+        let playoff = playoffs
+            .iter()
+            .find(|p| p.placement.to_string() == "5-8")
+            .expect("consolation bracket");
+        let mut rounds = vec![];
+        for r in &playoff.rounds {
+            let round = r
+                .iter()
+                .map(|m| from_participants(m, &participants))
+                .collect();
+            rounds.push(round);
+        }
+        reorder(&mut rounds);
+
+        let lines = lines(&rounds).expect("lines");
+        assert_eq!(lines, vec![LINES_TO_WINNERS_FINALS,]);
+    }
+
     // TODO add test cases for many to help if someone refactors this later
 }
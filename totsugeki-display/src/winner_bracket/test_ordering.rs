@@ -12,6 +12,7 @@ mod tests {
             vec![MinimalMatch::new([1, 2])],
         ];
 
+        MinimalMatch::link_by_seeds(&mut rounds);
         reorder(&mut rounds);
 
         assert_eq!(rounds[0][0].row_hint, Some(1));
@@ -25,6 +26,7 @@ mod tests {
             vec![MinimalMatch::new([1, 2])],
         ];
 
+        MinimalMatch::link_by_seeds(&mut rounds);
         reorder(&mut rounds);
 
         assert_eq!(rounds[0][0].row_hint, Some(0));
@@ -41,6 +43,7 @@ mod tests {
             vec![MinimalMatch::new([1, 2])],
         ];
 
+        MinimalMatch::link_by_seeds(&mut rounds);
         reorder(&mut rounds);
 
         // 3 filler match (0-2), then first real match
@@ -66,6 +69,7 @@ mod tests {
             vec![MinimalMatch::new([1, 2])],
         ];
 
+        MinimalMatch::link_by_seeds(&mut rounds);
         reorder(&mut rounds);
 
         assert_eq!(rounds[0][7].row_hint, Some(1)); // 8-9
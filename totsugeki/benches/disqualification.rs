@@ -1,4 +1,4 @@
-use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
 // use mycrate::fibonacci;
 use totsugeki::{bracket::Bracket, player::Player};
 
@@ -39,9 +39,11 @@ pub fn criterion_benchmark(c: &mut Criterion) {
     // FIXME tweak significance level until there is very few "regression/
     // improvement" when benchmarking the same code
     group.significance_level(0.02).sample_size(12);
-    group.bench_function("all players", |b| {
-        b.iter(|| disqualify_many_people(black_box(500)))
-    });
+    for n in [250, 500, 1000] {
+        group.bench_with_input(BenchmarkId::new("all players", n), &n, |b, n| {
+            b.iter(|| disqualify_many_people(black_box(*n)))
+        });
+    }
     group.finish();
 }
 
@@ -7,7 +7,11 @@ use chrono::prelude::*;
 use itertools::Itertools;
 use num_bigint::BigInt;
 use totsugeki::{
-    bracket::Bracket, format::Format, matches::ReportedResult, opponent::Opponent, player::Player,
+    bracket::Bracket,
+    format::{Format, GrandFinals, LoserDrops, Playoffs},
+    matches::ReportedResult,
+    opponent::Opponent,
+    player::Player,
     seeding::Method,
 };
 use totsugeki_fuzz::{BracketFormat, LotsOfEvents, MatchEvent};
@@ -17,32 +21,37 @@ use totsugeki_fuzz::{BracketFormat, LotsOfEvents, MatchEvent};
 // ALARM: working on the last Unit for 1201 seconds
 //        and the timeout value is 1200 (use -timeout=N to change)
 fuzz_target!(|data: (LotsOfEvents, BracketFormat, u128)| {
-    let (events, format, permutation_index) = data;
+    let (events, bracket_format, permutation_index) = data;
 
     let total_events = events.0.len();
 
-    let total_players = match (format, total_events) {
+    let total_players = match (bracket_format, total_events) {
         (BracketFormat::SingleElimination, t_e) => t_e + 1, // n - 1 = t_e
         (BracketFormat::DoubleElimination, t_e) => (t_e + 1) / 2, // 2 * n - 1 = t_e
     };
 
-    let format = match format {
-        BracketFormat::SingleElimination => Format::SingleElimination,
-        BracketFormat::DoubleElimination => Format::DoubleElimination,
+    let format = match bracket_format {
+        BracketFormat::SingleElimination => Format::SingleElimination {
+            playoffs: Playoffs::Disabled,
+        },
+        BracketFormat::DoubleElimination => Format::DoubleElimination {
+            loser_drops: LoserDrops::Standard,
+            grand_finals: GrandFinals::Reset,
+        },
     };
 
-    let mut min_permutations: BigInt = match format {
-        Format::SingleElimination => 2.into(),
-        Format::DoubleElimination => 5.into(),
+    let mut min_permutations: BigInt = match bracket_format {
+        BracketFormat::SingleElimination => 2.into(),
+        BracketFormat::DoubleElimination => 5.into(),
     };
     let mut min_player_count = 3;
     let p_index_big_int = <u128 as Into<BigInt>>::into(permutation_index);
 
     for player_count in 3..total_players {
         if min_permutations < p_index_big_int {
-            let next = match format {
-                Format::SingleElimination => player_count,
-                Format::DoubleElimination => player_count * 2 - 1,
+            let next = match bracket_format {
+                BracketFormat::SingleElimination => player_count,
+                BracketFormat::DoubleElimination => player_count * 2 - 1,
             };
             min_permutations = min_permutations * <usize as Into<BigInt>>::into(next);
             min_player_count = player_count;
@@ -58,9 +67,9 @@ fuzz_target!(|data: (LotsOfEvents, BracketFormat, u128)| {
         }
 
         // required events in this loop
-        let event_count = match format {
-            Format::SingleElimination => player_count - 1,
-            Format::DoubleElimination => 2 * player_count - 1,
+        let event_count = match bracket_format {
+            BracketFormat::SingleElimination => player_count - 1,
+            BracketFormat::DoubleElimination => 2 * player_count - 1,
         };
         let mut bracket = Bracket::new(
             "",
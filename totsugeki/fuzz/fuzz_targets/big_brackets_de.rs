@@ -7,7 +7,11 @@ use chrono::prelude::*;
 use itertools::Itertools;
 use num_bigint::BigInt;
 use totsugeki::{
-    bracket::Bracket, format::Format, matches::ReportedResult, opponent::Opponent, player::Player,
+    bracket::Bracket,
+    format::{Format, GrandFinals, LoserDrops},
+    matches::ReportedResult,
+    opponent::Opponent,
+    player::Player,
     seeding::Method,
 };
 use totsugeki_fuzz::{LotsOfEvents, MatchEvent};
@@ -25,7 +29,10 @@ fuzz_target!(|data: (LotsOfEvents, u128)| {
 
     let total_players = (total_events + 1) / 2; // 2 * n - 1 = t_e
 
-    let format = Format::DoubleElimination;
+    let format = Format::DoubleElimination {
+        loser_drops: LoserDrops::Standard,
+        grand_finals: GrandFinals::Reset,
+    };
 
     let mut min_permutations: BigInt = 5.into();
     let mut min_player_count = 3;
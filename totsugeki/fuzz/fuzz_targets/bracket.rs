@@ -6,7 +6,11 @@ extern crate libfuzzer_sys;
 
 use chrono::prelude::*;
 use totsugeki::{
-    bracket::Bracket, format::Format, matches::ReportedResult, opponent::Opponent, player::Player,
+    bracket::Bracket,
+    format::{Format, GrandFinals, LoserDrops, Playoffs},
+    matches::ReportedResult,
+    opponent::Opponent,
+    player::Player,
     seeding::Method,
 };
 use totsugeki_fuzz::{BracketFormat, Events, MatchEvent};
@@ -33,8 +37,13 @@ fuzz_target!(|data: (Events, BracketFormat)| {
     };
 
     let format = match format {
-        BracketFormat::SingleElimination => Format::SingleElimination,
-        BracketFormat::DoubleElimination => Format::DoubleElimination,
+        BracketFormat::SingleElimination => Format::SingleElimination {
+            playoffs: Playoffs::Disabled,
+        },
+        BracketFormat::DoubleElimination => Format::DoubleElimination {
+            loser_drops: LoserDrops::Standard,
+            grand_finals: GrandFinals::Reset,
+        },
     };
 
     let mut bracket = Bracket::new(
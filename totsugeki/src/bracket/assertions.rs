@@ -1,16 +1,18 @@
 //! Assertion to make against a bracket
 
 use crate::bracket::Bracket;
+use std::borrow::Cow;
 
 impl Bracket {
     /// Check all available assertions for bracket
     pub(crate) fn check_all_assertions(&self) {
-        self.format
-            .get_progression(
-                self.matches.clone(),
-                &self.participants,
-                self.automatic_match_progression,
-            )
-            .check_all_assertions();
+        if let Ok(p) = self.format.get_progression(
+            Cow::Borrowed(&self.matches),
+            &self.participants,
+            self.automatic_match_progression,
+            &self.disqualified_between_rounds,
+        ) {
+            p.check_all_assertions();
+        }
     }
 }
@@ -42,9 +42,12 @@ impl Builder {
             format,
             ..Bracket::default()
         };
-        bracket = bracket
-            .regenerate_matches(participants)
-            .expect("no math overflow errors");
+        for player in participants.get_players_list() {
+            bracket = bracket
+                .unchecked_join_skip_matches_generation(player)
+                .expect("bracket accepting participants");
+        }
+        let bracket = bracket.generate_matches().expect("no math overflow errors");
 
         Ok(bracket)
     }
@@ -80,3 +83,73 @@ impl Builder {
         }
     }
 }
+
+/// Brackets to use in tests
+#[cfg(test)]
+pub(crate) mod fixture {
+    use crate::{
+        bracket::Bracket, format::Format, matches::Id as MatchId, opponent::Opponent,
+        player::Id as PlayerId, seeding::Method as SeedingMethod,
+    };
+    use chrono::prelude::*;
+
+    /// Open bracket without participants, created on 2000-01-01 with strict
+    /// seeding
+    pub(crate) fn empty_bracket(format: Format, automatic_match_validation: bool) -> Bracket {
+        Bracket::new(
+            "bracket",
+            format,
+            SeedingMethod::Strict,
+            Utc.with_ymd_and_hms(2000, 1, 1, 0, 0, 0).unwrap(),
+            automatic_match_validation,
+        )
+    }
+
+    /// Players "p1" to "pN" join `bracket` in seeding order
+    pub(crate) fn with_players(bracket: Bracket, n: usize) -> Bracket {
+        (1..=n).fold(bracket, |bracket, i| {
+            bracket
+                .add_participant(format!("p{i}").as_str())
+                .expect("participant")
+        })
+    }
+
+    /// Open bracket where players "p1" to "pN" joined in seeding order
+    pub(crate) fn bracket(format: Format, n: usize) -> Bracket {
+        with_players(empty_bracket(format, true), n)
+    }
+
+    /// Started bracket of `n` players with its seeding
+    pub(crate) fn started_bracket(format: Format, n: usize) -> (Bracket, Vec<PlayerId>) {
+        let bracket = bracket(format, n);
+        let seeding = bracket.get_participants().get_seeding();
+        (bracket.start().expect("started").0, seeding)
+    }
+
+    /// Tournament organiser reports stronger seed of `match_id` won 2-0
+    pub(crate) fn play(bracket: Bracket, match_id: MatchId) -> Bracket {
+        let m = bracket.matches.find(match_id).expect("match");
+        let [Opponent::Player(p1), Opponent::Player(p2)] = m.get_players() else {
+            panic!("expected players");
+        };
+        let result = if m.get_seeds()[0] < m.get_seeds()[1] {
+            (2, 0)
+        } else {
+            (0, 2)
+        };
+        bracket
+            .tournament_organiser_reports_result(p1, result, p2)
+            .expect("result")
+            .0
+    }
+
+    /// Play matches until bracket is over, stronger seed winning every match
+    pub(crate) fn play_all(bracket: Bracket) -> Bracket {
+        let mut bracket = bracket;
+        while !bracket.is_over() {
+            let match_id = bracket.matches_to_play()[0].get_id();
+            bracket = play(bracket, match_id);
+        }
+        bracket
+    }
+}
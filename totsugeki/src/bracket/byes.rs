@@ -0,0 +1,115 @@
+//! Matches of players advancing without an opponent
+//!
+//! In elimination brackets where the number of players is not a power of
+//! two, top seeds skip the first round. Those byes are implicit in bracket
+//! matches. They can be materialised on demand so callers treat "player X
+//! advances via bye" like any other match.
+
+use crate::{
+    bracket::{Bracket, Id as BracketId},
+    format::Format,
+    matches::{Id as MatchId, Link, Links, Match},
+    seeding::single_elimination_seeded_bracket::get_bye_matches,
+};
+
+impl Bracket {
+    /// Returns bye matches of the first round followed by bracket matches.
+    /// Winner of a bye match is sent to the match where they play next.
+    ///
+    /// Byes are only materialised for the first round of single elimination
+    /// brackets and winner brackets of double elimination brackets. Swiss
+    /// brackets already have bye matches. Bye matches are not part of
+    /// bracket matches and cannot be reported. Bye matches keep the same
+    /// identifier as long as the seed with a bye does not change
+    #[must_use]
+    pub fn matches_with_byes(&self) -> Vec<Match> {
+        let seeding = self.participants.get_seeding();
+        if self.matches.is_empty()
+            || !matches!(
+                self.format,
+                Format::SingleElimination { .. } | Format::DoubleElimination { .. }
+            )
+        {
+            return self.matches.to_vec();
+        }
+        let byes = get_bye_matches(&seeding).unwrap_or_default();
+        byes.into_iter()
+            .map(|bye| {
+                let seed = bye.seeds[0];
+                // top seeds with a bye play first in the second round of
+                // the winner bracket, which comes first
+                let winner = self
+                    .matches
+                    .iter()
+                    .find(|m| m.seeds.contains(&seed))
+                    .map(|m| Link {
+                        match_id: m.id,
+                        is_player_1: m.seeds[0] == seed,
+                    });
+                Match {
+                    id: bye_match_id(self.bracket_id, seed),
+                    ..bye.with_links(Links {
+                        winner,
+                        loser: None,
+                    })
+                }
+            })
+            .chain(self.matches.iter().copied())
+            .collect()
+    }
+}
+
+/// Identifier of the bye match of `seed` in bracket `bracket_id`
+fn bye_match_id(bracket_id: BracketId, seed: usize) -> MatchId {
+    MatchId::new_v5(&bracket_id, seed.to_string().as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        bracket::builder::fixture::bracket,
+        format::{Format, Playoffs},
+        opponent::Opponent,
+    };
+
+    #[test]
+    fn top_seeds_advance_via_bye_to_second_round() {
+        let bracket = bracket(
+            Format::SingleElimination {
+                playoffs: Playoffs::ThirdPlaceMatch,
+            },
+            5,
+        );
+        let seeding = bracket.get_participants().get_seeding();
+        let matches = bracket.matches_with_byes();
+
+        // 1st, 2nd and 3rd seeds skip first round of 8 players
+        assert_eq!(matches.len(), bracket.get_matches().len() + 3);
+        for (i, bye) in matches[..3].iter().enumerate() {
+            assert!(bye.is_bye());
+            assert_eq!(bye.get_winner(), Opponent::Player(seeding[i]));
+            let link = bye.get_links().winner.expect("winner link");
+            let next = matches
+                .iter()
+                .find(|m| m.get_id() == link.match_id)
+                .expect("next match");
+            let slot = usize::from(!link.is_player_1);
+            assert_eq!(next.get_players()[slot], Opponent::Player(seeding[i]));
+        }
+        assert!(matches[3..].iter().all(|m| !m.is_bye()));
+        // bye matches can be referred to across calls
+        assert_eq!(bracket.matches_with_byes(), matches);
+    }
+
+    #[test]
+    fn there_is_no_bye_with_power_of_two_players() {
+        let double_elimination = bracket(Format::default(), 8);
+
+        assert_eq!(
+            double_elimination.matches_with_byes(),
+            double_elimination.get_matches()
+        );
+        let round_robin = bracket(Format::RoundRobin, 5);
+        assert_eq!(round_robin.matches_with_byes(), round_robin.get_matches());
+    }
+}
@@ -0,0 +1,309 @@
+//! Settle matches where players reported conflicting results
+//!
+//! A match is disputed as long as both players' reports disagree. The
+//! tournament organiser resolves it by keeping one of the reports or by
+//! entering a new result. The reason of their decision is kept in the event
+//! log.
+
+use crate::{
+    bracket::{
+        events::{generated_match_ids, BracketEvent, Entry},
+        match_lifecycle::sync_lifecycles,
+        matches::index::added_matches,
+        Bracket, Error,
+    },
+    matches::{Error as MatchError, Id as MatchId, Match},
+    opponent::Opponent,
+    player::Id as PlayerId,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+
+/// How the tournament organiser settles a disputed match
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Resolution {
+    /// Keep result reported by this player
+    KeepReportOf(PlayerId),
+    /// Replace both reports with this result, from the point of view of
+    /// the player
+    NewResult(PlayerId, (i8, i8)),
+}
+
+/// Dispute settled by the tournament organiser
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolvedDispute {
+    /// When dispute was resolved
+    pub at: DateTime<Utc>,
+    /// Disputed match
+    pub match_id: MatchId,
+    /// Decision of tournament organiser
+    pub resolution: Resolution,
+    /// Why tournament organiser took this decision
+    pub reason: String,
+}
+
+impl Bracket {
+    /// Returns matches where players reported conflicting results and that
+    /// need the tournament organiser's attention
+    #[must_use]
+    pub fn disputed_matches(&self) -> Vec<Match> {
+        self.matches
+            .iter()
+            .filter(|m| m.is_disputed())
+            .copied()
+            .collect()
+    }
+
+    /// Tournament organiser settles disputed `match_id` with `resolution`
+    /// for `reason`. The match is validated with the chosen result. Only the
+    /// resolution is logged. Returns updated bracket and new matches to play
+    ///
+    /// # Errors
+    /// thrown when match is unknown or not disputed, when kept report is not
+    /// from a player of this match or when result cannot be validated
+    pub fn resolve_dispute(
+        self,
+        match_id: MatchId,
+        resolution: Resolution,
+        reason: &str,
+    ) -> Result<(Bracket, Vec<Match>), Error> {
+        let Some(index) = self.matches.position(match_id) else {
+            return Err(Error::UnknownMatch(self.bracket_id, match_id));
+        };
+        let m = self.matches[index];
+        if !m.is_disputed() {
+            return Err(Error::MatchIsNotDisputed(self.bracket_id, match_id));
+        }
+        let [Opponent::Player(p1), Opponent::Player(p2)] = m.get_players() else {
+            return Err(Error::MatchUpdate(
+                self.bracket_id,
+                MatchError::MissingOpponent(m.get_players()),
+            ));
+        };
+        let [r1, r2] = m.reported_results;
+        let (player, result) = match resolution {
+            Resolution::KeepReportOf(p) if p == p1 => (p1, r1),
+            Resolution::KeepReportOf(p) if p == p2 => (p2, r2),
+            Resolution::NewResult(p, result) if p == p1 || p == p2 => (p, result),
+            Resolution::KeepReportOf(p) | Resolution::NewResult(p, _) => {
+                return Err(Error::MatchUpdate(
+                    self.bracket_id,
+                    MatchError::UnknownPlayer(p, m.get_players()),
+                ))
+            }
+        };
+        let opponent = if player == p1 { p2 } else { p1 };
+        self.check_score(match_id, result)?;
+
+        let old_matches = self.matches_to_play();
+        let mut p = self
+            .format
+            .get_progression(
+                Cow::Borrowed(&self.matches),
+                &self.participants,
+                self.automatic_match_progression,
+                &self.disqualified_between_rounds,
+            )
+            .map_err(|e| self.get_from_progression_error(e))?;
+        if let Err(e) = p.organiser_reports(player, result, opponent) {
+            return Err(self.get_from_progression_error(e));
+        }
+        if !self.automatic_match_progression {
+            if let Err(e) = p.validate(match_id) {
+                return Err(self.get_from_progression_error(e));
+            }
+        }
+        let matches = p.into_matches();
+        let generated_match_ids = generated_match_ids(&self.matches, &matches);
+        let at = Utc::now();
+        let matches = sync_lifecycles(&self.matches, matches, at);
+        let bracket = Self { matches, ..self }.free_stations().log_at(
+            BracketEvent::DisputeResolved {
+                match_id,
+                resolution,
+                reason: reason.to_string(),
+                generated_match_ids,
+            },
+            at,
+        );
+        let bracket = Self {
+            accept_match_results: !bracket.is_over(),
+            ..bracket
+        };
+        let new_matches = added_matches(&old_matches, &bracket.matches_to_play());
+        bracket.check_all_assertions();
+        Ok((bracket, new_matches))
+    }
+
+    /// Returns disputes settled by the tournament organiser, from oldest to
+    /// newest
+    #[must_use]
+    pub fn get_resolved_disputes(&self) -> Vec<ResolvedDispute> {
+        self.events
+            .iter()
+            .filter_map(|Entry { at, event }| match event {
+                BracketEvent::DisputeResolved {
+                    match_id,
+                    resolution,
+                    reason,
+                    ..
+                } => Some(ResolvedDispute {
+                    at: *at,
+                    match_id: *match_id,
+                    resolution: *resolution,
+                    reason: reason.clone(),
+                }),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Resolution;
+    use crate::{
+        bracket::{
+            builder::fixture::{empty_bracket, with_players},
+            Bracket, Error,
+        },
+        format::{Format, Playoffs},
+        matches::Error as MatchError,
+        opponent::Opponent,
+        player::Id as PlayerId,
+    };
+
+    fn disputed_bracket(automatic_match_validation: bool) -> (Bracket, Vec<PlayerId>) {
+        let bracket = with_players(
+            empty_bracket(
+                Format::SingleElimination {
+                    playoffs: Playoffs::Disabled,
+                },
+                automatic_match_validation,
+            ),
+            4,
+        );
+        let seeding = bracket.get_participants().get_seeding();
+        let (bracket, _) = bracket.start().expect("start");
+        let (bracket, _, _) = bracket.report_result(seeding[0], (2, 0)).expect("report");
+        let (bracket, _, _) = bracket.report_result(seeding[3], (2, 1)).expect("report");
+        (bracket, seeding)
+    }
+
+    #[test]
+    fn conflicting_reports_dispute_match() {
+        for automatic_match_validation in [true, false] {
+            let (bracket, seeding) = disputed_bracket(automatic_match_validation);
+            let disputed = bracket.disputed_matches();
+            assert_eq!(disputed.len(), 1);
+            assert!(disputed[0].contains(seeding[0]));
+
+            // player fixing their report settles the dispute
+            let (bracket, _, _) = bracket.report_result(seeding[3], (0, 2)).expect("report");
+            assert!(bracket.disputed_matches().is_empty());
+        }
+    }
+
+    #[test]
+    fn tournament_organiser_keeps_one_report() {
+        for automatic_match_validation in [true, false] {
+            let (bracket, seeding) = disputed_bracket(automatic_match_validation);
+            let match_id = bracket.disputed_matches()[0].get_id();
+            let logged = bracket.get_events().len();
+
+            let (bracket, _) = bracket
+                .resolve_dispute(
+                    match_id,
+                    Resolution::KeepReportOf(seeding[3]),
+                    "stream footage",
+                )
+                .expect("resolved");
+
+            let m = bracket
+                .get_matches()
+                .into_iter()
+                .find(|m| m.get_id() == match_id)
+                .expect("match");
+            assert_eq!(m.get_winner(), Opponent::Player(seeding[3]));
+            assert_eq!(m.get_score(), (1, 2));
+            assert!(bracket.disputed_matches().is_empty());
+            let resolved = bracket.get_resolved_disputes();
+            assert_eq!(resolved.len(), 1);
+            assert_eq!(resolved[0].match_id, match_id);
+            assert_eq!(resolved[0].reason, "stream footage");
+            assert_eq!(
+                bracket.get_events().len(),
+                logged + 1,
+                "only resolution is logged"
+            );
+
+            let replayed = Bracket::replay(&bracket.get_events()).expect("replay");
+            assert_eq!(replayed.get_matches(), bracket.get_matches());
+            assert_eq!(replayed.get_resolved_disputes(), resolved);
+        }
+    }
+
+    #[test]
+    fn tournament_organiser_enters_new_result() {
+        let (bracket, seeding) = disputed_bracket(true);
+        let match_id = bracket.disputed_matches()[0].get_id();
+
+        let (bracket, new_matches) = bracket
+            .resolve_dispute(
+                match_id,
+                Resolution::NewResult(seeding[0], (2, 1)),
+                "both reports were wrong",
+            )
+            .expect("resolved");
+
+        let m = bracket
+            .get_matches()
+            .into_iter()
+            .find(|m| m.get_id() == match_id)
+            .expect("match");
+        assert_eq!(m.get_winner(), Opponent::Player(seeding[0]));
+        assert_eq!(m.get_score(), (2, 1));
+        assert!(new_matches.is_empty());
+        let replayed = Bracket::replay(&bracket.get_events()).expect("replay");
+        assert_eq!(replayed.get_matches(), bracket.get_matches());
+    }
+
+    #[test]
+    fn only_disputed_matches_can_be_resolved() {
+        let (bracket, seeding) = disputed_bracket(true);
+        let disputed = bracket.disputed_matches()[0].get_id();
+        let other = bracket
+            .matches_to_play()
+            .into_iter()
+            .find(|m| m.get_id() != disputed)
+            .expect("other match")
+            .get_id();
+
+        assert!(matches!(
+            bracket
+                .clone()
+                .resolve_dispute(other, Resolution::KeepReportOf(seeding[1]), ""),
+            Err(Error::MatchIsNotDisputed(_, id)) if id == other
+        ));
+        assert!(matches!(
+            bracket.resolve_dispute(disputed, Resolution::KeepReportOf(seeding[1]), ""),
+            Err(Error::MatchUpdate(_, _))
+        ));
+    }
+
+    #[test]
+    fn dispute_with_missing_opponent_cannot_be_resolved() {
+        let (mut bracket, seeding) = disputed_bracket(true);
+        let match_id = bracket.disputed_matches()[0].get_id();
+        let index = bracket.matches.position(match_id).expect("match");
+        let mut m = bracket.matches[index];
+        m.players[1] = Opponent::Unknown;
+        bracket.matches.replace(index, m);
+
+        assert!(matches!(
+            bracket.resolve_dispute(match_id, Resolution::KeepReportOf(seeding[0]), ""),
+            Err(Error::MatchUpdate(_, MatchError::MissingOpponent(_)))
+        ));
+    }
+}
@@ -1,25 +1,16 @@
 //! Disqualification of player in bracket and all side-effects
 
 use crate::{
-    bracket::{Bracket, Error},
+    bracket::{
+        events::{generated_match_ids, BracketEvent},
+        match_lifecycle::sync_lifecycles,
+        Bracket, Error,
+    },
     matches::Match,
     player::Id as PlayerId,
 };
-
-use super::matches::is_disqualified;
-
-/// Returns new matches when comparing old bracket and new bracket
-pub(crate) fn get_new_matches(old_bracket: &[Match], new_bracket: &[Match]) -> Vec<Match> {
-    new_bracket
-        .iter()
-        .filter(|new_m| {
-            !old_bracket
-                .iter()
-                .any(|old_m| old_m.get_id() == new_m.get_id())
-        })
-        .copied()
-        .collect::<Vec<Match>>()
-}
+use chrono::Utc;
+use std::borrow::Cow;
 
 impl Bracket {
     /// Disqualify player from bracket, advance opponent in bracket and returns
@@ -39,16 +30,41 @@ impl Bracket {
             ));
         }
 
-        let p = self.format.get_progression(
-            self.get_matches(),
-            &self.get_participants(),
-            self.automatic_match_progression,
-        );
-        let (matches, matches_to_play) = match p.disqualify_participant(player_id) {
+        let mut p = self
+            .format
+            .get_progression(
+                Cow::Borrowed(&self.matches),
+                &self.participants,
+                self.automatic_match_progression,
+                &self.disqualified_between_rounds,
+            )
+            .map_err(|e| self.get_from_progression_error(e))?;
+        let matches_to_play = match p.disqualify(player_id) {
             Ok(v) => v,
             Err(e) => return Err(self.get_from_progression_error(e)),
         };
-        let bracket = Self { matches, ..self };
+        let matches = p.into_matches();
+        let generated_match_ids = generated_match_ids(&self.matches, &matches);
+        let mut disqualified_between_rounds = self.disqualified_between_rounds.clone();
+        // swiss players waiting for the next round have no match to record it
+        if !matches.is_disqualified(player_id) {
+            disqualified_between_rounds.push(player_id);
+        }
+        let at = Utc::now();
+        let matches = sync_lifecycles(&self.matches, matches, at);
+        let bracket = Self {
+            matches,
+            disqualified_between_rounds,
+            ..self
+        }
+        .free_stations()
+        .log_at(
+            BracketEvent::ParticipantDisqualified {
+                player_id,
+                generated_match_ids,
+            },
+            at,
+        );
         bracket.check_all_assertions();
         Ok((bracket, matches_to_play))
     }
@@ -56,20 +72,28 @@ impl Bracket {
     /// Returns true if player is disqualified
     #[must_use]
     pub fn is_disqualified(&self, player_id: PlayerId) -> bool {
-        is_disqualified(player_id, &self.matches)
+        self.disqualified_between_rounds.contains(&player_id)
+            || self.matches.is_disqualified(player_id)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{format::Format, player::Player};
-    use std::time::Instant;
+    use crate::{
+        format::{Format, Playoffs},
+        opponent::Opponent,
+        player::Player,
+        seeding::Method as SeedingMethod,
+    };
+    use std::{num::NonZeroUsize, time::Instant};
 
     #[test]
     fn cannot_disqualify_player_before_bracket_starts() {
         let mut bracket = Bracket {
-            format: Format::SingleElimination,
+            format: Format::SingleElimination {
+                playoffs: Playoffs::Disabled,
+            },
             ..Bracket::default()
         };
 
@@ -90,6 +114,66 @@ mod tests {
         }
     }
 
+    #[test]
+    fn swiss_player_disqualified_between_rounds_is_not_paired_again() {
+        let mut bracket = Bracket::new(
+            "swiss",
+            Format::Swiss {
+                rounds: NonZeroUsize::new(3),
+            },
+            SeedingMethod::Strict,
+            Utc::now(),
+            true,
+        );
+        for i in 1..=4 {
+            bracket = bracket
+                .add_participant(format!("p{i}").as_str())
+                .expect("add player");
+        }
+        let seeding = bracket.get_participants().get_seeding();
+        let (bracket, _) = bracket.start().expect("started");
+        let (Opponent::Player(opponent), _, _) = bracket.next_opponent(seeding[0]).expect("match")
+        else {
+            panic!("expected opponent of p1");
+        };
+        let (bracket, played, _) = bracket
+            .tournament_organiser_reports_result(seeding[0], (2, 0), opponent)
+            .expect("reported");
+        let played_match = bracket
+            .get_matches()
+            .into_iter()
+            .find(|m| m.get_id() == played);
+
+        let (bracket, _) = bracket
+            .disqualify_participant(seeding[0])
+            .expect("disqualified while waiting for round 2");
+
+        assert!(bracket.is_disqualified(seeding[0]));
+        assert_eq!(
+            bracket
+                .get_matches()
+                .into_iter()
+                .find(|m| m.get_id() == played),
+            played_match,
+            "played match is left untouched"
+        );
+        let m = bracket.matches_to_play()[0];
+        let [Opponent::Player(p1), Opponent::Player(p2)] = m.get_players() else {
+            panic!("expected players in {m}");
+        };
+        let (bracket, _, _) = bracket
+            .tournament_organiser_reports_result(p1, (2, 0), p2)
+            .expect("reported");
+        let round_2 = &bracket.get_matches()[2..];
+        assert_eq!(round_2.len(), 2);
+        assert!(round_2.iter().all(|m| !m.contains(seeding[0])));
+        assert!(round_2.iter().any(Match::is_bye));
+
+        let replayed = Bracket::replay(&bracket.get_events()).expect("replayed");
+        assert!(replayed.is_disqualified(seeding[0]));
+        assert_eq!(replayed.get_matches(), bracket.get_matches());
+    }
+
     // cargo t disqualify_8000 -- --include-ignored --nocapture
     #[test]
     #[ignore]
@@ -4,7 +4,7 @@
 use super::winner_bracket::winner_bracket;
 use crate::bracket::Bracket;
 use crate::bracket::PartitionError;
-use crate::format::Format;
+use crate::format::{Format, GrandFinals, LoserDrops};
 use crate::matches::partition_double_elimination_matches as partition;
 use crate::matches::Match;
 
@@ -26,7 +26,7 @@ impl TryFrom<Bracket> for Variant {
     type Error = TryIntoError;
 
     fn try_from(bracket: Bracket) -> Result<Self, Self::Error> {
-        if bracket.format != Format::DoubleElimination {
+        if !matches!(bracket.format, Format::DoubleElimination { .. }) {
             return Err(TryIntoError::ExpectedDoubleEliminationFormat);
         }
 
@@ -37,7 +37,10 @@ impl TryFrom<Bracket> for Variant {
 impl std::default::Default for Variant {
     fn default() -> Self {
         let bracket = Bracket {
-            format: Format::DoubleElimination,
+            format: Format::DoubleElimination {
+                loser_drops: LoserDrops::Standard,
+                grand_finals: GrandFinals::Reset,
+            },
             ..Default::default()
         };
         Self { bracket }
@@ -51,7 +54,7 @@ impl Variant {
     /// Returns an error when there is less than 3 players in the bracket
     pub fn partition_matches(
         &self,
-    ) -> Result<(Vec<Match>, Vec<Match>, Match, Match), PartitionError> {
+    ) -> Result<(Vec<Match>, Vec<Match>, Match, Option<Match>), PartitionError> {
         if self.bracket.participants.len() < 3 {
             return Err(PartitionError::NotEnoughPlayersInBracket);
         }
@@ -92,7 +95,7 @@ impl Variant {
     ///
     /// # Errors
     /// When there is not enough players in the bracket for matches
-    pub fn grand_finals_and_reset(&self) -> Result<(Match, Match), PartitionError> {
+    pub fn grand_finals_and_reset(&self) -> Result<(Match, Option<Match>), PartitionError> {
         if self.bracket.participants.len() < 3 {
             return Err(PartitionError::NotEnoughPlayersInBracket);
         }
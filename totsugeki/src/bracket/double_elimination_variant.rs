@@ -4,7 +4,7 @@
 use super::winner_bracket::winner_bracket;
 use crate::bracket::Bracket;
 use crate::bracket::PartitionError;
-use crate::format::Format;
+use crate::format::{Format, GrandFinals, LoserDrops};
 use crate::matches::partition_double_elimination_matches as partition;
 use crate::matches::Match;
 
@@ -15,6 +15,10 @@ pub struct Variant {
     bracket: Bracket,
 }
 
+/// Winner bracket, loser bracket, grand finals and grand finals reset, if
+/// grand finals can be reset
+pub type Partition = (Vec<Match>, Vec<Match>, Match, Option<Match>);
+
 /// Error with double elimination brackets
 #[derive(Debug)]
 pub enum TryIntoError {
@@ -25,11 +29,8 @@ pub enum TryIntoError {
 impl TryFrom<Bracket> for Variant {
     type Error = TryIntoError;
 
-    // TODO try to use type state pattern instead of throwing errors
-    // trying to coerce smth into smth else is a sign that a fallible process
-    // should be made not fallible
     fn try_from(bracket: Bracket) -> Result<Self, Self::Error> {
-        if bracket.format != Format::DoubleElimination {
+        if !matches!(bracket.format, Format::DoubleElimination { .. }) {
             return Err(TryIntoError::ExpectedDoubleEliminationFormat);
         }
 
@@ -40,7 +41,10 @@ impl TryFrom<Bracket> for Variant {
 impl std::default::Default for Variant {
     fn default() -> Self {
         let bracket = Bracket {
-            format: Format::DoubleElimination,
+            format: Format::DoubleElimination {
+                loser_drops: LoserDrops::Standard,
+                grand_finals: GrandFinals::Reset,
+            },
             ..Default::default()
         };
         Self { bracket }
@@ -48,13 +52,12 @@ impl std::default::Default for Variant {
 }
 
 impl Variant {
-    /// Returns winner bracket, loser bracket, grand finals and grand final reset
+    /// Returns winner bracket, loser bracket, grand finals and grand final
+    /// reset, if grand finals can be reset
     ///
     /// # Errors
     /// Returns an error when there is less than 3 players in the bracket
-    pub fn partition_matches(
-        &self,
-    ) -> Result<(Vec<Match>, Vec<Match>, Match, Match), PartitionError> {
+    pub fn partition_matches(&self) -> Result<Partition, PartitionError> {
         if self.bracket.participants.len() < 3 {
             return Err(PartitionError::NotEnoughPlayersInBracket);
         }
@@ -91,11 +94,12 @@ impl Variant {
         Ok(loser_bracket(lb_matches))
     }
 
-    /// Returns Grand Finals and Grand finals reset
+    /// Returns Grand Finals and Grand finals reset, if grand finals can be
+    /// reset
     ///
     /// # Errors
     /// When there is not enough players in the bracket for matches
-    pub fn grand_finals_and_reset(&self) -> Result<(Match, Match), PartitionError> {
+    pub fn grand_finals_and_reset(&self) -> Result<(Match, Option<Match>), PartitionError> {
         if self.bracket.participants.len() < 3 {
             return Err(PartitionError::NotEnoughPlayersInBracket);
         }
@@ -106,7 +110,7 @@ impl Variant {
 }
 
 /// Partition loser brackets matches into rounds
-fn loser_bracket(lb_matches: Vec<Match>) -> Vec<Vec<Match>> {
+pub(crate) fn loser_bracket(lb_matches: Vec<Match>) -> Vec<Vec<Match>> {
     // 2 is grand finals and grand finals reset
     let mut rounds = vec![];
 
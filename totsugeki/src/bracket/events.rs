@@ -0,0 +1,681 @@
+//! Append-only log of everything that happened to a bracket. The log can be
+//! replayed to rebuild the bracket.
+//!
+//! Events record the outcome of non-deterministic operations (random seeding,
+//! identifiers of generated matches) so replaying them always yields the same
+//! bracket.
+
+use crate::{
+    bracket::{
+        disputes::Resolution,
+        match_rules::MatchRules,
+        matches::index::{added_matches, Matches},
+        stations::Id as StationId,
+        Bracket, Error, Id as BracketId,
+    },
+    format::Format,
+    game::GameResult,
+    matches::{Id as MatchId, Match},
+    player::{Id as PlayerId, Participants, Player},
+    seeding::Method as SeedingMethod,
+};
+use chrono::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Operation that changed a bracket
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BracketEvent {
+    /// Bracket was created
+    Created {
+        /// Identifier of bracket
+        bracket_id: BracketId,
+        /// Name of bracket
+        name: String,
+        /// Format of bracket
+        format: Format,
+        /// Seeding method of bracket
+        seeding_method: SeedingMethod,
+        /// Advertised start time
+        start_time: DateTime<Utc>,
+        /// Matches are automatically validated if both players agree on
+        /// result
+        automatic_match_validation: bool,
+    },
+    /// Player joined bracket
+    ParticipantAdded {
+        /// New participant
+        player: Player,
+    },
+    /// Players joined bracket without any check nor generating matches.
+    /// Consecutive unchecked joins share this event
+    ParticipantsAddedUnchecked {
+        /// New participants, in joining order
+        players: Vec<Player>,
+    },
+    /// Player left bracket before it started
+    ParticipantRemoved {
+        /// Participant who left
+        player_id: PlayerId,
+    },
+    /// Participant was replaced by another player before playing
+    ParticipantSubstituted {
+        /// Replaced participant
+        player_id: PlayerId,
+        /// Player taking their place
+        substitute: Player,
+    },
+    /// Seeding was updated
+    SeedingUpdated {
+        /// Resulting seeding, from first to last seed
+        seeding: Vec<PlayerId>,
+        /// Value seeding the random draw, if players were drawn randomly
+        #[serde(default)]
+        random_seed: Option<u64>,
+    },
+    /// Bracket stopped accepting new participants
+    Closed,
+    /// Set length of matches was updated
+    MatchRulesUpdated {
+        /// New rules
+        rules: MatchRules,
+    },
+    /// Bracket started
+    Started {
+        /// Identifiers of all matches of bracket when it started
+        match_ids: Vec<MatchId>,
+    },
+    /// Player reported result of their match
+    ResultReported {
+        /// Player reporting
+        player_id: PlayerId,
+        /// Reported result
+        result: (i8, i8),
+        /// Identifiers of matches generated by this operation
+        generated_match_ids: Vec<MatchId>,
+    },
+    /// Tournament organiser reported result for both players
+    TournamentOrganiserReportedResult {
+        /// First player
+        player1: PlayerId,
+        /// Result of first player
+        result: (i8, i8),
+        /// Second player
+        player2: PlayerId,
+        /// Identifiers of matches generated by this operation
+        generated_match_ids: Vec<MatchId>,
+    },
+    /// Result of match was validated
+    MatchResultValidated {
+        /// Validated match
+        match_id: MatchId,
+        /// Identifiers of matches generated by this operation
+        generated_match_ids: Vec<MatchId>,
+    },
+    /// Participant was disqualified
+    ParticipantDisqualified {
+        /// Disqualified participant
+        player_id: PlayerId,
+        /// Identifiers of matches generated by this operation
+        generated_match_ids: Vec<MatchId>,
+    },
+    /// Games of a match were recorded along its result
+    GamesRecorded {
+        /// Match played
+        match_id: MatchId,
+        /// Player who reported the games
+        player_id: PlayerId,
+        /// Games of match
+        games: Vec<GameResult>,
+    },
+    /// Match was reopened
+    MatchReopened {
+        /// Reopened match
+        match_id: MatchId,
+        /// Matches that followed from reopened match were reopened as well
+        cascade: bool,
+    },
+    /// Players of match were called to play it
+    MatchCalled {
+        /// Called match
+        match_id: MatchId,
+    },
+    /// Match started
+    MatchStarted {
+        /// Started match
+        match_id: MatchId,
+    },
+    /// Station was added
+    StationAdded {
+        /// Identifier of station
+        station_id: StationId,
+        /// Name of station
+        name: String,
+        /// Station is streamed
+        is_stream: bool,
+    },
+    /// Station was removed
+    StationRemoved {
+        /// Removed station
+        station_id: StationId,
+    },
+    /// Match was pinned to a stream station
+    MatchPinned {
+        /// Pinned match
+        match_id: MatchId,
+        /// Stream station
+        station_id: StationId,
+    },
+    /// Matches were assigned to free stations
+    StationsAssigned {
+        /// Station and the match assigned to it
+        assignments: Vec<(StationId, MatchId)>,
+    },
+    /// Tournament organiser resolved a disputed match
+    DisputeResolved {
+        /// Disputed match
+        match_id: MatchId,
+        /// Decision of tournament organiser
+        resolution: Resolution,
+        /// Why tournament organiser took this decision
+        reason: String,
+        /// Identifiers of matches generated by this operation
+        generated_match_ids: Vec<MatchId>,
+    },
+    /// Tournament organiser declared winner of match regardless of reports
+    WinnerForced {
+        /// Match with forced result
+        match_id: MatchId,
+        /// Declared winner
+        winner: PlayerId,
+        /// Identifiers of matches generated by this operation
+        generated_match_ids: Vec<MatchId>,
+    },
+    /// Tournament organiser swapped two players who had yet to play
+    PlayersSwapped {
+        /// First player
+        player1: PlayerId,
+        /// Second player
+        player2: PlayerId,
+    },
+}
+
+/// Event with the time it happened
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Entry {
+    /// When event happened
+    pub at: DateTime<Utc>,
+    /// What happened
+    pub event: BracketEvent,
+}
+
+/// Returns identifiers of matches present in `after` but not in `before`
+pub(crate) fn generated_match_ids(before: &[Match], after: &[Match]) -> Vec<MatchId> {
+    added_matches(before, after)
+        .iter()
+        .map(Match::get_id)
+        .collect()
+}
+
+impl Bracket {
+    /// Append `event` to the log of this bracket
+    #[must_use]
+    pub(crate) fn log(self, event: BracketEvent) -> Self {
+        self.log_at(event, Utc::now())
+    }
+
+    /// Append `event` that happened `at` to the log of this bracket
+    #[must_use]
+    pub(crate) fn log_at(self, event: BracketEvent, at: DateTime<Utc>) -> Self {
+        let mut events = self.events;
+        events.push(Entry { at, event });
+        Self { events, ..self }
+    }
+
+    /// Log `player` joining without any check. Consecutive unchecked joins
+    /// share one event, so joining many players does not log one event each
+    #[must_use]
+    pub(crate) fn log_unchecked_join(self, player: Player) -> Self {
+        let mut events = self.events;
+        match events.last_mut() {
+            Some(Entry {
+                event: BracketEvent::ParticipantsAddedUnchecked { players },
+                ..
+            }) => players.push(player),
+            _ => events.push(Entry {
+                at: Utc::now(),
+                event: BracketEvent::ParticipantsAddedUnchecked {
+                    players: vec![player],
+                },
+            }),
+        }
+        Self { events, ..self }
+    }
+
+    /// Returns all events of this bracket, from oldest to newest
+    #[must_use]
+    pub fn get_events(&self) -> Vec<Entry> {
+        self.events.clone()
+    }
+
+    /// Returns bracket without its event log, which grows with every
+    /// operation. Use it when sending a bracket to clients that do not
+    /// replay it
+    #[must_use]
+    pub fn without_events(self) -> Self {
+        Self {
+            events: vec![],
+            ..self
+        }
+    }
+
+    /// Rebuild bracket from its event log
+    ///
+    /// # Errors
+    /// thrown when log does not start with the creation of the bracket or an
+    /// event cannot be applied
+    pub fn replay(entries: &[Entry]) -> Result<Bracket, Error> {
+        let Some((
+            created @ Entry {
+                event:
+                    BracketEvent::Created {
+                        bracket_id,
+                        name,
+                        format,
+                        seeding_method,
+                        start_time,
+                        automatic_match_validation,
+                    },
+                ..
+            },
+            entries,
+        )) = entries.split_first()
+        else {
+            return Err(Error::MissingCreationEvent);
+        };
+        let mut bracket = Bracket {
+            bracket_id: *bracket_id,
+            events: vec![created.clone()],
+            ..Bracket::new(
+                name,
+                *format,
+                *seeding_method,
+                *start_time,
+                *automatic_match_validation,
+            )
+        };
+        for entry in entries {
+            bracket = bracket.apply(entry)?;
+        }
+        Ok(bracket)
+    }
+
+    /// Order participants as `seeding` while replaying the event log
+    ///
+    /// # Errors
+    /// thrown when a seeded player is not a participant
+    fn apply_seeding(self, seeding: &[PlayerId], random_seed: Option<u64>) -> Result<Self, Error> {
+        let mut participants = Participants::default();
+        for player_id in seeding {
+            let Some(player) = self.participants.get(*player_id) else {
+                return Err(Error::UnknownPlayer(
+                    *player_id,
+                    self.participants.clone(),
+                    self.bracket_id,
+                ));
+            };
+            participants = participants.add_participant(player)?;
+        }
+        Ok(Self {
+            participants,
+            random_seed,
+            ..self
+        })
+    }
+
+    /// Let `players` join without any check while replaying the event log
+    fn apply_unchecked_joins(self, players: &[Player]) -> Result<Self, Error> {
+        players
+            .iter()
+            .cloned()
+            .try_fold(self, Bracket::unchecked_join_skip_matches_generation)
+    }
+
+    /// Apply event of `entry` to bracket while replaying the event log
+    fn apply(mut self, entry: &Entry) -> Result<Bracket, Error> {
+        // replayed operations log themselves again: set the log aside and
+        // keep the original entry instead
+        let mut events = std::mem::take(&mut self.events);
+        let before = self.matches.clone();
+        let bracket = self.apply_event(&entry.event, &before, entry.at)?;
+        events.push(entry.clone());
+        Ok(Self { events, ..bracket }.restamp_lifecycles(&before, entry.at))
+    }
+
+    /// Run operation of `event` that happened `at`. Matches it generates take
+    /// the identifiers they had when it was recorded, `before` being the
+    /// matches prior to the operation
+    fn apply_event(
+        self,
+        event: &BracketEvent,
+        before: &[Match],
+        at: DateTime<Utc>,
+    ) -> Result<Bracket, Error> {
+        Ok(match event {
+            BracketEvent::Created { .. } => return Err(Error::MissingCreationEvent),
+            BracketEvent::ParticipantAdded { player } => self.join(player.clone())?,
+            BracketEvent::ParticipantsAddedUnchecked { players } => {
+                self.apply_unchecked_joins(players)?
+            }
+            BracketEvent::ParticipantRemoved { player_id } => {
+                self.remove_participant(*player_id)?
+            }
+            BracketEvent::ParticipantSubstituted {
+                player_id,
+                substitute,
+            } => self.substitute_participant(*player_id, substitute.clone())?,
+            BracketEvent::SeedingUpdated {
+                seeding,
+                random_seed,
+            } => self.apply_seeding(seeding, *random_seed)?,
+            BracketEvent::Closed => self.close(),
+            BracketEvent::MatchRulesUpdated { rules } => self.set_match_rules(rules.clone())?,
+            BracketEvent::Started { match_ids } => {
+                self.generate_matches()?
+                    .with_generated_ids(&[], match_ids)
+                    .start()?
+                    .0
+            }
+            BracketEvent::ResultReported {
+                player_id,
+                result,
+                generated_match_ids,
+            } => self
+                .report_result(*player_id, *result)?
+                .0
+                .with_generated_ids(before, generated_match_ids),
+            BracketEvent::TournamentOrganiserReportedResult {
+                player1,
+                result,
+                player2,
+                generated_match_ids,
+            } => self
+                .tournament_organiser_reports_result(*player1, *result, *player2)?
+                .0
+                .with_generated_ids(before, generated_match_ids),
+            BracketEvent::MatchResultValidated {
+                match_id,
+                generated_match_ids,
+            } => self
+                .validate_match_result(*match_id)?
+                .0
+                .with_generated_ids(before, generated_match_ids),
+            BracketEvent::ParticipantDisqualified {
+                player_id,
+                generated_match_ids,
+            } => self
+                .disqualify_participant(*player_id)?
+                .0
+                .with_generated_ids(before, generated_match_ids),
+            BracketEvent::GamesRecorded {
+                match_id,
+                player_id,
+                games,
+            } => self.record_games(*match_id, *player_id, games.clone()),
+            BracketEvent::MatchReopened { match_id, cascade } => {
+                self.reopen_match(*match_id, *cascade)?.0
+            }
+            BracketEvent::DisputeResolved {
+                match_id,
+                resolution,
+                reason,
+                generated_match_ids,
+            } => self
+                .resolve_dispute(*match_id, *resolution, reason)?
+                .0
+                .with_generated_ids(before, generated_match_ids),
+            BracketEvent::MatchCalled { match_id } => self.call_match(*match_id)?,
+            BracketEvent::MatchStarted { match_id } => self.start_match(*match_id)?,
+            BracketEvent::StationAdded {
+                station_id,
+                name,
+                is_stream,
+            } => self.insert_station(*station_id, name, *is_stream),
+            BracketEvent::StationRemoved { station_id } => self.remove_station(*station_id)?,
+            BracketEvent::MatchPinned {
+                match_id,
+                station_id,
+            } => self.pin_match_to_station(*match_id, *station_id)?,
+            BracketEvent::WinnerForced {
+                match_id,
+                winner,
+                generated_match_ids,
+            } => self
+                .force_winner(*match_id, *winner)?
+                .0
+                .with_generated_ids(before, generated_match_ids),
+            BracketEvent::PlayersSwapped { player1, player2 } => {
+                self.swap_players(*player1, *player2)?.0
+            }
+            BracketEvent::StationsAssigned { assignments } => {
+                self.apply_assignments(assignments, at)
+            }
+        })
+    }
+
+    /// Timestamp with `at` states that matches entered since `before`, so
+    /// replayed matches keep the timestamps of the original event
+    fn restamp_lifecycles(self, before: &Matches, at: DateTime<Utc>) -> Self {
+        let mut matches = self.matches;
+        for i in 0..matches.len() {
+            let m = matches[i];
+            let before = before
+                .find(m.get_id())
+                .map(|b| b.get_lifecycle())
+                .unwrap_or_default();
+            let lifecycle = m.lifecycle.restamp(before, at);
+            if lifecycle != m.lifecycle {
+                matches.replace(i, Match { lifecycle, ..m });
+            }
+        }
+        Self { matches, ..self }
+    }
+
+    /// Give matches generated since `before` the identifiers they had when
+    /// the event was recorded
+    fn with_generated_ids(self, before: &[Match], ids: &[MatchId]) -> Self {
+        if ids.is_empty() {
+            return self;
+        }
+        Self {
+            matches: with_ids(before, &self.matches, ids).into(),
+            ..self
+        }
+    }
+}
+
+/// Returns `matches` where matches absent from `before` take identifiers from
+/// `ids`, in order. Links to renamed matches follow them
+fn with_ids(before: &[Match], matches: &[Match], ids: &[MatchId]) -> Vec<Match> {
+    let renamed = added_matches(before, matches)
+        .iter()
+        .zip(ids)
+        .map(|(m, id)| (m.get_id(), *id))
+        .collect::<HashMap<_, _>>();
+    let rename = |id| renamed.get(&id).copied().unwrap_or(id);
+    matches
+        .iter()
+        .map(|m| Match {
+            id: rename(m.get_id()),
+            links: m.get_links().renamed(rename),
+            ..*m
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        bracket::builder::fixture::{
+            bracket, empty_bracket, play, play_all, started_bracket, with_players,
+        },
+        format::{GrandFinals, LoserDrops, Playoffs},
+    };
+
+    fn assert_same_bracket(replayed: &Bracket, bracket: &Bracket) {
+        assert_eq!(replayed.get_id(), bracket.get_id());
+        assert_eq!(replayed.get_matches(), bracket.get_matches());
+        assert_eq!(
+            replayed.get_participants().get_seeding(),
+            bracket.get_participants().get_seeding()
+        );
+        assert_eq!(replayed.accept_match_results, bracket.accept_match_results);
+        assert_eq!(replayed.is_closed, bracket.is_closed);
+        assert_eq!(replayed.get_events(), bracket.get_events());
+    }
+
+    #[test]
+    fn every_operation_is_logged() {
+        let bracket = with_players(
+            empty_bracket(
+                Format::SingleElimination {
+                    playoffs: Playoffs::Disabled,
+                },
+                false,
+            ),
+            4,
+        );
+        let seeding = bracket.get_participants().get_seeding();
+        let bracket = bracket
+            .update_seeding(&seeding.iter().rev().copied().collect::<Vec<_>>())
+            .expect("seeding");
+        let seeding = bracket.get_participants().get_seeding();
+        let (bracket, _) = bracket.start().expect("start");
+        let (bracket, match_id, _) = bracket
+            .report_result(seeding[0], (2, 0))
+            .expect("reported result");
+        let (bracket, _, _) = bracket
+            .report_result(seeding[3], (0, 2))
+            .expect("reported result");
+        let (bracket, _) = bracket.validate_match_result(match_id).expect("validated");
+        let (bracket, _) = bracket.disqualify_participant(seeding[1]).expect("dq");
+
+        let events = bracket
+            .get_events()
+            .into_iter()
+            .map(|e| e.event)
+            .collect::<Vec<_>>();
+        assert!(matches!(events[0], BracketEvent::Created { .. }));
+        assert!(events[1..5]
+            .iter()
+            .all(|e| matches!(e, BracketEvent::ParticipantAdded { .. })));
+        assert!(
+            matches!(&events[5], BracketEvent::SeedingUpdated { seeding: s, .. } if *s == seeding)
+        );
+        assert!(matches!(events[6], BracketEvent::Started { .. }));
+        assert!(matches!(
+            events[7],
+            BracketEvent::ResultReported { player_id, .. } if player_id == seeding[0]
+        ));
+        assert!(matches!(events[8], BracketEvent::ResultReported { .. }));
+        assert!(matches!(
+            events[9],
+            BracketEvent::MatchResultValidated { match_id: id, .. } if id == match_id
+        ));
+        assert!(matches!(
+            events[10],
+            BracketEvent::ParticipantDisqualified { player_id, .. } if player_id == seeding[1]
+        ));
+        assert_eq!(events.len(), 11);
+    }
+
+    #[test]
+    fn replay_rebuilds_bracket() {
+        let bracket = bracket(
+            Format::DoubleElimination {
+                loser_drops: LoserDrops::Standard,
+                grand_finals: GrandFinals::Reset,
+            },
+            6,
+        );
+        let seeding = bracket.get_participants().get_seeding();
+        let bracket = bracket
+            .update_seeding(&seeding.iter().rev().copied().collect::<Vec<_>>())
+            .expect("seeding");
+        let (mut bracket, _) = bracket.start().expect("start");
+        for _ in 0..4 {
+            let match_id = bracket.matches_to_play()[0].get_id();
+            bracket = play(bracket, match_id);
+        }
+        let first_match = bracket.get_matches()[0].get_id();
+        let (bracket, _) = bracket.reopen_match(first_match, true).expect("reopen");
+
+        let replayed = Bracket::replay(&bracket.get_events()).expect("replay");
+
+        assert_same_bracket(&replayed, &bracket);
+    }
+
+    #[test]
+    fn replay_keeps_identifiers_of_generated_swiss_rounds() {
+        let (bracket, _) = started_bracket(Format::Swiss { rounds: None }, 4);
+        let bracket = play_all(bracket);
+        assert_eq!(bracket.get_matches().len(), 4);
+
+        let replayed = Bracket::replay(&bracket.get_events()).expect("replay");
+
+        assert_same_bracket(&replayed, &bracket);
+    }
+
+    #[test]
+    fn replay_can_stop_at_any_point() {
+        let (bracket, _) = started_bracket(
+            Format::SingleElimination {
+                playoffs: Playoffs::Disabled,
+            },
+            4,
+        );
+        let started = bracket.clone();
+        let match_id = bracket.matches_to_play()[0].get_id();
+        let bracket = play(bracket, match_id);
+        let events = bracket.get_events();
+
+        let replayed = Bracket::replay(&events[..events.len() - 1]).expect("replay");
+
+        assert_same_bracket(&replayed, &started);
+    }
+
+    #[test]
+    fn replay_requires_creation_event() {
+        let bracket = bracket(
+            Format::SingleElimination {
+                playoffs: Playoffs::Disabled,
+            },
+            3,
+        );
+        let events = bracket.get_events();
+        assert!(matches!(
+            Bracket::replay(&events[1..]),
+            Err(Error::MissingCreationEvent)
+        ));
+    }
+
+    #[test]
+    fn unchecked_joins_are_logged_as_one_event() {
+        let mut bracket = empty_bracket(Format::default(), true);
+        for i in 1..=5 {
+            bracket = bracket
+                .unchecked_join_skip_matches_generation(Player::new(format!("p{i}")))
+                .expect("joined");
+        }
+
+        let events = bracket.get_events();
+        assert_eq!(events.len(), 2);
+        assert!(matches!(
+            &events[1].event,
+            BracketEvent::ParticipantsAddedUnchecked { players } if players.len() == 5
+        ));
+        let replayed = Bracket::replay(&events).expect("replay");
+        assert_same_bracket(&replayed, &bracket);
+    }
+}
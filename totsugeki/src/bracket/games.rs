@@ -0,0 +1,255 @@
+//! Report match results game by game. Set score is derived from games.
+//!
+//! Games are stored in the bracket rather than in matches so matches stay
+//! small and copyable. Games are kept per reporter: the games of a match are
+//! those of the latest reporter who agrees with its result.
+
+use crate::{
+    bracket::{events::BracketEvent, Bracket, Error},
+    game::{score_of, GameResult},
+    matches::{Error as MatchError, Id as MatchId, Match, MatchPlayers},
+    opponent::Opponent,
+    player::Id as PlayerId,
+};
+
+impl Bracket {
+    /// Report result of a match game by game. The set score of `player_id`
+    /// is derived from `games`. Returns updated bracket, affected match and
+    /// new matches to play
+    ///
+    /// # Errors
+    /// thrown when result cannot be reported, too many games were played or
+    /// a game winner is not playing this match
+    pub fn report_games(
+        self,
+        player_id: PlayerId,
+        games: Vec<GameResult>,
+    ) -> Result<(Bracket, MatchId, Vec<Match>), Error> {
+        let result =
+            score_of(player_id, &games).map_err(|e| Error::GameUpdate(self.bracket_id, e))?;
+        // without a next opponent, reporting the result fails below
+        if let Ok((opponent, _, _)) = self.next_opponent(player_id) {
+            self.check_game_winners([Opponent::Player(player_id), opponent], &games)?;
+        }
+        let (bracket, match_id, new_matches) = self.report_result(player_id, result)?;
+        Ok((
+            bracket.record_games(match_id, player_id, games),
+            match_id,
+            new_matches,
+        ))
+    }
+
+    /// Tournament organiser reports result of a match between `player1` and
+    /// `player2` game by game. Returns updated bracket, affected match and new
+    /// matches to play
+    ///
+    /// # Errors
+    /// thrown when result cannot be reported, too many games were played or
+    /// a game winner is not playing this match
+    pub fn tournament_organiser_reports_games(
+        self,
+        player1: PlayerId,
+        games: Vec<GameResult>,
+        player2: PlayerId,
+    ) -> Result<(Bracket, MatchId, Vec<Match>), Error> {
+        let result =
+            score_of(player1, &games).map_err(|e| Error::GameUpdate(self.bracket_id, e))?;
+        self.check_game_winners(
+            [Opponent::Player(player1), Opponent::Player(player2)],
+            &games,
+        )?;
+        let (bracket, match_id, new_matches) =
+            self.tournament_organiser_reports_result(player1, result, player2)?;
+        Ok((
+            bracket.record_games(match_id, player1, games),
+            match_id,
+            new_matches,
+        ))
+    }
+
+    /// Returns games played in `match_id`. Empty until the match has a
+    /// winner or if only the set score was reported
+    #[must_use]
+    pub fn get_games(&self, match_id: MatchId) -> Vec<GameResult> {
+        let Some(i) = self.matches.position(match_id) else {
+            return vec![];
+        };
+        let m = &self.matches[i];
+        if m.get_winner() == Opponent::Unknown {
+            return vec![];
+        }
+        self.games
+            .get(&match_id)
+            .and_then(|reports| {
+                reports
+                    .iter()
+                    .rev()
+                    .find(|(reporter, games)| {
+                        score_of(*reporter, games).is_ok_and(|s| s == score_of_player(m, *reporter))
+                    })
+                    .map(|(_, games)| games.clone())
+            })
+            .unwrap_or_default()
+    }
+
+    /// Store `games` of `match_id` reported by `player_id`, replacing games
+    /// they previously reported
+    pub(crate) fn record_games(
+        self,
+        match_id: MatchId,
+        player_id: PlayerId,
+        games: Vec<GameResult>,
+    ) -> Bracket {
+        let mut all_games = self.games.clone();
+        let reports = all_games.entry(match_id).or_default();
+        reports.retain(|(reporter, _)| *reporter != player_id);
+        reports.push((player_id, games.clone()));
+        Self {
+            games: all_games,
+            ..self
+        }
+        .log(BracketEvent::GamesRecorded {
+            match_id,
+            player_id,
+            games,
+        })
+    }
+
+    /// Forget games of matches without a result anymore
+    pub(crate) fn forget_games_of_reopened_matches(self) -> Bracket {
+        let mut games = self.games.clone();
+        games.retain(|id, _| {
+            self.matches
+                .position(*id)
+                .is_some_and(|i| self.matches[i].has_result())
+        });
+        Self { games, ..self }
+    }
+
+    /// Check every game winner of `games` is one of `players`
+    fn check_game_winners(&self, players: MatchPlayers, games: &[GameResult]) -> Result<(), Error> {
+        match games
+            .iter()
+            .find(|g| !players.contains(&Opponent::Player(g.winner)))
+        {
+            Some(g) => Err(Error::MatchUpdate(
+                self.bracket_id,
+                MatchError::UnknownPlayer(g.winner, players),
+            )),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Set score of `m` from the point of view of `player_id`
+fn score_of_player(m: &Match, player_id: PlayerId) -> (i8, i8) {
+    let (score, opponent_score) = m.get_score();
+    if m.get_players()[0] == Opponent::Player(player_id) {
+        (score, opponent_score)
+    } else {
+        (opponent_score, score)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        bracket::{builder::fixture::started_bracket, disputes::Resolution, Bracket, Error},
+        format::{Format, Playoffs},
+        game::{Error as GameError, GameResult},
+        matches::Error as MatchError,
+        player::Id as PlayerId,
+    };
+
+    fn bracket(n: usize) -> (Bracket, Vec<PlayerId>) {
+        started_bracket(
+            Format::SingleElimination {
+                playoffs: Playoffs::Disabled,
+            },
+            n,
+        )
+    }
+
+    #[test]
+    fn set_score_is_derived_from_games() {
+        let (bracket, seeding) = bracket(4);
+        let games = vec![
+            GameResult::new(seeding[0])
+                .set_character(seeding[0], "Ryu")
+                .set_character(seeding[3], "Ken")
+                .set_stage("Training"),
+            GameResult::new(seeding[3]).set_metadata("perfect", "yes"),
+            GameResult::new(seeding[0]),
+        ];
+
+        let (bracket, match_id, _) = bracket
+            .tournament_organiser_reports_games(seeding[0], games.clone(), seeding[3])
+            .expect("reported games");
+
+        let m = bracket
+            .get_matches()
+            .into_iter()
+            .find(|m| m.get_id() == match_id)
+            .expect("match");
+        assert_eq!(m.get_score(), (2, 1));
+        let recorded = bracket.get_games(match_id);
+        assert_eq!(recorded, games);
+        assert_eq!(recorded[0].get_character(seeding[3]), Some("Ken"));
+
+        let replayed = Bracket::replay(&bracket.get_events()).expect("replay");
+        assert_eq!(replayed.get_games(match_id), games);
+    }
+
+    #[test]
+    fn game_winner_must_play_the_match() {
+        let (bracket, seeding) = bracket(4);
+        let games = vec![GameResult::new(seeding[0]), GameResult::new(seeding[1])];
+        assert!(matches!(
+            bracket.report_games(seeding[0], games),
+            Err(Error::MatchUpdate(_, MatchError::UnknownPlayer(p, _))) if p == seeding[1]
+        ));
+    }
+
+    #[test]
+    fn too_many_games_are_rejected() {
+        let (bracket, seeding) = bracket(4);
+        let games = vec![GameResult::new(seeding[0]); 130];
+        assert!(matches!(
+            bracket.tournament_organiser_reports_games(seeding[0], games, seeding[3]),
+            Err(Error::GameUpdate(_, GameError::TooManyGames(130)))
+        ));
+    }
+
+    #[test]
+    fn games_of_disputed_match_are_those_of_the_kept_report() {
+        let (bracket, seeding) = bracket(4);
+        let games_of_1 = vec![GameResult::new(seeding[0]), GameResult::new(seeding[0])];
+        let games_of_4 = vec![GameResult::new(seeding[3]), GameResult::new(seeding[3])];
+        let (bracket, match_id, _) = bracket
+            .report_games(seeding[0], games_of_1.clone())
+            .expect("reported games");
+        let (bracket, _, _) = bracket
+            .report_games(seeding[3], games_of_4)
+            .expect("reported games");
+        assert!(bracket.get_games(match_id).is_empty());
+
+        let (bracket, _) = bracket
+            .resolve_dispute(match_id, Resolution::KeepReportOf(seeding[0]), "video")
+            .expect("resolved");
+
+        assert_eq!(bracket.get_games(match_id), games_of_1);
+    }
+
+    #[test]
+    fn reopening_match_forgets_its_games() {
+        let (bracket, seeding) = bracket(4);
+        let games = vec![GameResult::new(seeding[0]), GameResult::new(seeding[0])];
+        let (bracket, match_id, _) = bracket
+            .tournament_organiser_reports_games(seeding[0], games, seeding[3])
+            .expect("reported games");
+
+        let (bracket, _) = bracket.reopen_match(match_id, false).expect("reopen");
+
+        assert!(bracket.get_games(match_id).is_empty());
+    }
+}
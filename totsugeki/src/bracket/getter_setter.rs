@@ -4,7 +4,7 @@ use crate::{
     format::Format, matches::Match, player::Participants, seeding::Method as SeedingMethod,
 };
 
-use super::{Bracket, Id};
+use super::{events::BracketEvent, Bracket, Id};
 
 impl Bracket {
     /// Bar new participants from entering bracket
@@ -14,6 +14,7 @@ impl Bracket {
             is_closed: true,
             ..self
         }
+        .log(BracketEvent::Closed)
     }
 
     /// Return bracket format
@@ -31,7 +32,7 @@ impl Bracket {
     /// Returns matches
     #[must_use]
     pub fn get_matches(&self) -> Vec<Match> {
-        self.matches.clone()
+        self.matches.to_vec()
     }
 
     /// Get name of bracket
@@ -52,6 +53,13 @@ impl Bracket {
         self.seeding_method
     }
 
+    /// Returns value that seeded the random draw of participants. Seeding
+    /// again with this value gives the same draw
+    #[must_use]
+    pub fn get_random_seed(&self) -> Option<u64> {
+        self.random_seed
+    }
+
     /// Returns true if match are validated automatically whenever possible
     #[must_use]
     pub fn is_validating_matches_automatically(&self) -> bool {
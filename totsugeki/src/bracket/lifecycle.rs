@@ -0,0 +1,601 @@
+//! Bracket lifecycle checked at compile time
+//!
+//! A bracket goes through registration, then runs until every match is
+//! played and is finally finished. Each stage is its own type exposing only
+//! what can be done at that stage: results cannot be reported during
+//! registration and participants cannot join a running bracket. Transitions
+//! consume the bracket and return it in its next stage.
+//!
+//! [`BracketState`] holds a bracket in any stage. It is (de)serialized as a
+//! plain [`Bracket`] and its stage is derived from it once it is validated.
+
+use crate::{
+    bracket::{
+        disputes::Resolution, match_rules::MatchRules, standings::Standings,
+        stations::Id as StationId, Bracket, Error,
+    },
+    format::Format,
+    game::GameResult,
+    matches::{Id as MatchId, Match},
+    player::{Id as PlayerId, Player},
+    rating::Ratings,
+    seeding::{separation::Separation, Method as SeedingMethod},
+};
+use chrono::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Forward method `$name` of [`Bracket`] that keeps the bracket in its stage,
+/// wrapping the returned bracket back into that stage
+macro_rules! forward {
+    ($(#[$attr:meta])* fn $name:ident($($arg:ident: $ty:ty),* $(,)?) -> Self) => {
+        $(#[$attr])*
+        #[must_use]
+        pub fn $name(self, $($arg: $ty),*) -> Self {
+            Self {
+                bracket: self.bracket.$name($($arg),*),
+            }
+        }
+    };
+    ($(#[$attr:meta])* fn $name:ident($($arg:ident: $ty:ty),* $(,)?) -> (Self, $out:ty)) => {
+        $(#[$attr])*
+        #[must_use]
+        pub fn $name(self, $($arg: $ty),*) -> (Self, $out) {
+            let (bracket, out) = self.bracket.$name($($arg),*);
+            (Self { bracket }, out)
+        }
+    };
+    ($(#[$attr:meta])* fn $name:ident($($arg:ident: $ty:ty),* $(,)?) -> Result<Self>) => {
+        $(#[$attr])*
+        pub fn $name(self, $($arg: $ty),*) -> Result<Self, Error> {
+            let bracket = self.bracket.$name($($arg),*)?;
+            Ok(Self { bracket })
+        }
+    };
+    (
+        $(#[$attr:meta])*
+        fn $name:ident($($arg:ident: $ty:ty),* $(,)?) -> Result<(Self, $out1:ty, $out2:ty)>
+    ) => {
+        $(#[$attr])*
+        pub fn $name(self, $($arg: $ty),*) -> Result<(Self, $out1, $out2), Error> {
+            let (bracket, out1, out2) = self.bracket.$name($($arg),*)?;
+            Ok((Self { bracket }, out1, out2))
+        }
+    };
+    ($(#[$attr:meta])* fn $name:ident($($arg:ident: $ty:ty),* $(,)?) -> Result<(Self, $out:ty)>) => {
+        $(#[$attr])*
+        pub fn $name(self, $($arg: $ty),*) -> Result<(Self, $out), Error> {
+            let (bracket, out) = self.bracket.$name($($arg),*)?;
+            Ok((Self { bracket }, out))
+        }
+    };
+}
+
+/// Bracket accepting participants. Matches cannot be played yet
+#[derive(Debug, Clone)]
+pub struct Registration {
+    /// Bracket in registration
+    bracket: Bracket,
+}
+
+/// Started bracket where matches are played. Participants cannot join
+#[derive(Debug, Clone)]
+pub struct Running {
+    /// Started bracket
+    bracket: Bracket,
+}
+
+/// Bracket where every match has been played
+#[derive(Debug, Clone)]
+pub struct Finished {
+    /// Bracket that is over
+    bracket: Bracket,
+}
+
+/// Bracket at any stage of its lifecycle
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "Bracket", into = "Bracket")]
+pub enum BracketState {
+    /// Bracket accepting participants
+    Registration(Registration),
+    /// Bracket where matches are played
+    Running(Running),
+    /// Bracket that is over
+    Finished(Finished),
+}
+
+impl Registration {
+    /// Create new bracket open for registration
+    #[must_use]
+    pub fn new(
+        name: &str,
+        format: Format,
+        seeding_method: SeedingMethod,
+        start_time: DateTime<Utc>,
+        automatic_match_validation: bool,
+    ) -> Self {
+        Self {
+            bracket: Bracket::new(
+                name,
+                format,
+                seeding_method,
+                start_time,
+                automatic_match_validation,
+            ),
+        }
+    }
+
+    /// Returns bracket in registration
+    #[must_use]
+    pub fn get_bracket(&self) -> &Bracket {
+        &self.bracket
+    }
+
+    forward! {
+        /// Add new participant named `name`
+        ///
+        /// # Errors
+        /// thrown when participant cannot be added
+        fn add_participant(name: &str) -> Result<Self>
+    }
+
+    forward! {
+        /// Adds `player` to participants
+        ///
+        /// # Errors
+        /// thrown when bracket is closed or `player` already joined
+        fn join(player: Player) -> Result<Self>
+    }
+
+    forward! {
+        /// Removes `participant_id` from participants
+        ///
+        /// # Errors
+        /// thrown when participant is unknown
+        fn remove_participant(participant_id: PlayerId) -> Result<Self>
+    }
+
+    forward! {
+        /// Replace `participant_id` with `substitute` at the same seeding
+        /// position
+        ///
+        /// # Errors
+        /// thrown when participant is unknown or substitute already joined
+        fn substitute_participant(participant_id: PlayerId, substitute: Player) -> Result<Self>
+    }
+
+    forward! {
+        /// Seed participants in the order of `players`
+        ///
+        /// # Errors
+        /// thrown when `players` are not the participants of this bracket
+        fn update_seeding(players: &[PlayerId]) -> Result<Self>
+    }
+
+    forward! {
+        /// Seed participants using `ratings`, `separation` and `random_seed`
+        /// when the seeding method needs them. See
+        /// [`Bracket::update_seeding_with`]
+        ///
+        /// # Errors
+        /// thrown when participants cannot be seeded
+        fn update_seeding_with(
+            players: &[PlayerId],
+            ratings: Option<&Ratings>,
+            separation: Option<&Separation>,
+            random_seed: Option<u64>
+        ) -> Result<Self>
+    }
+
+    forward! {
+        /// Use `rules` for set length of matches
+        ///
+        /// # Errors
+        /// thrown when a set length of `rules` cannot be won
+        fn set_match_rules(rules: MatchRules) -> Result<Self>
+    }
+
+    forward! {
+        /// Bar new participants from entering bracket
+        fn close() -> Self
+    }
+
+    /// Start bracket. Returns running bracket and matches to play
+    ///
+    /// # Errors
+    /// thrown if there is not enough participants
+    pub fn start(self) -> Result<(Running, Vec<Match>), Error> {
+        let (bracket, matches) = self.bracket.start()?;
+        Ok((Running { bracket }, matches))
+    }
+}
+
+impl Running {
+    /// Returns running bracket
+    #[must_use]
+    pub fn get_bracket(&self) -> &Bracket {
+        &self.bracket
+    }
+
+    forward! {
+        /// Report result of `player_id`. Returns updated bracket, match id
+        /// where result is reported and new matches to play
+        ///
+        /// # Errors
+        /// thrown when result cannot be reported
+        fn report_result(player_id: PlayerId, result: (i8, i8)) -> Result<(Self, MatchId, Vec<Match>)>
+    }
+
+    forward! {
+        /// Tournament organiser reports result of `player1` against
+        /// `player2`. Returns updated bracket, affected match id and new
+        /// matches to play
+        ///
+        /// # Errors
+        /// thrown when result cannot be reported
+        fn tournament_organiser_reports_result(
+            player1: PlayerId,
+            result_player1: (i8, i8),
+            player2: PlayerId
+        ) -> Result<(Self, MatchId, Vec<Match>)>
+    }
+
+    forward! {
+        /// Report `games` of `player_id`. Returns updated bracket, affected
+        /// match and new matches to play
+        ///
+        /// # Errors
+        /// thrown when result cannot be reported
+        fn report_games(player_id: PlayerId, games: Vec<GameResult>) -> Result<(Self, MatchId, Vec<Match>)>
+    }
+
+    forward! {
+        /// Tournament organiser reports `games` of `player1` against
+        /// `player2`. Returns updated bracket, affected match and new
+        /// matches to play
+        ///
+        /// # Errors
+        /// thrown when result cannot be reported
+        fn tournament_organiser_reports_games(
+            player1: PlayerId,
+            games: Vec<GameResult>,
+            player2: PlayerId
+        ) -> Result<(Self, MatchId, Vec<Match>)>
+    }
+
+    forward! {
+        /// Validate result of `match_id`. Returns updated bracket and new
+        /// matches to play
+        ///
+        /// # Errors
+        /// thrown when match cannot be validated
+        fn validate_match_result(match_id: MatchId) -> Result<(Self, Vec<Match>)>
+    }
+
+    forward! {
+        /// Disqualify `player_id`. Returns updated bracket and new matches to
+        /// play
+        ///
+        /// # Errors
+        /// thrown when participant is unknown or already disqualified
+        fn disqualify_participant(player_id: PlayerId) -> Result<(Self, Vec<Match>)>
+    }
+
+    forward! {
+        /// Reopen `match_id`. See [`Bracket::reopen_match`]
+        ///
+        /// # Errors
+        /// thrown when match cannot be reopened
+        fn reopen_match(match_id: MatchId, cascade: bool) -> Result<(Self, Vec<Match>)>
+    }
+
+    forward! {
+        /// Settle disputed `match_id` with `resolution` for `reason`
+        ///
+        /// # Errors
+        /// thrown when match is not disputed or result cannot be validated
+        fn resolve_dispute(
+            match_id: MatchId,
+            resolution: Resolution,
+            reason: &str
+        ) -> Result<(Self, Vec<Match>)>
+    }
+
+    forward! {
+        /// Replace `participant_id` with `substitute` until they play. See
+        /// [`Bracket::substitute_participant`]
+        ///
+        /// # Errors
+        /// thrown when participant is unknown or has already played or when
+        /// substitute already is a participant
+        fn substitute_participant(participant_id: PlayerId, substitute: Player) -> Result<Self>
+    }
+
+    forward! {
+        /// Tournament organiser declares `winner` of `match_id` regardless of
+        /// reported results. See [`Bracket::force_winner`]
+        ///
+        /// # Errors
+        /// thrown when match is unknown, over or `winner` does not play in it
+        fn force_winner(match_id: MatchId, winner: PlayerId) -> Result<(Self, Vec<Match>)>
+    }
+
+    forward! {
+        /// Tournament organiser swaps `player1` and `player2` who have yet to
+        /// play. See [`Bracket::swap_players`]
+        ///
+        /// # Errors
+        /// thrown when a player is not a participant or has already played
+        fn swap_players(player1: PlayerId, player2: PlayerId) -> Result<(Self, Vec<Match>)>
+    }
+
+    forward! {
+        /// Call players of `match_id` to play
+        ///
+        /// # Errors
+        /// thrown when match is unknown or cannot be called
+        fn call_match(match_id: MatchId) -> Result<Self>
+    }
+
+    forward! {
+        /// Mark `match_id` as being played
+        ///
+        /// # Errors
+        /// thrown when match is unknown or cannot be started
+        fn start_match(match_id: MatchId) -> Result<Self>
+    }
+
+    forward! {
+        /// Use `rules` for set length of matches. Results already reported
+        /// are not checked again
+        ///
+        /// # Errors
+        /// thrown when a set length of `rules` cannot be won
+        fn set_match_rules(rules: MatchRules) -> Result<Self>
+    }
+
+    forward! {
+        /// Add a station named `name`. Returns updated bracket and station id
+        fn add_station(name: &str, is_stream: bool) -> (Self, StationId)
+    }
+
+    forward! {
+        /// Remove `station_id`
+        ///
+        /// # Errors
+        /// thrown when station is unknown
+        fn remove_station(station_id: StationId) -> Result<Self>
+    }
+
+    forward! {
+        /// Only ever play `match_id` on stream station `station_id`
+        ///
+        /// # Errors
+        /// thrown when match or station is unknown or station is not streamed
+        fn pin_match_to_station(match_id: MatchId, station_id: StationId) -> Result<Self>
+    }
+
+    forward! {
+        /// Assign ready matches to free stations. Returns updated bracket and
+        /// new assignments
+        fn assign_stations() -> (Self, Vec<(StationId, MatchId)>)
+    }
+
+    /// Finish bracket once every match has been played
+    ///
+    /// # Errors
+    /// thrown when some matches still need to be played
+    pub fn finalize(self) -> Result<Finished, Error> {
+        if !self.bracket.is_over() {
+            return Err(Error::NotOver(self.bracket.bracket_id));
+        }
+        Ok(Finished {
+            bracket: self.bracket,
+        })
+    }
+}
+
+impl Finished {
+    /// Returns finished bracket
+    #[must_use]
+    pub fn get_bracket(&self) -> &Bracket {
+        &self.bracket
+    }
+
+    /// Returns final standings
+    ///
+    /// # Errors
+    /// thrown when standings cannot be computed for this bracket
+    pub fn standings(&self) -> Result<Standings, Error> {
+        self.bracket.standings()
+    }
+
+    /// Reopen `match_id`, which makes the bracket run again. See
+    /// [`Bracket::reopen_match`]
+    ///
+    /// # Errors
+    /// thrown when match cannot be reopened
+    pub fn reopen_match(
+        self,
+        match_id: MatchId,
+        cascade: bool,
+    ) -> Result<(Running, Vec<Match>), Error> {
+        let (bracket, matches) = self.bracket.reopen_match(match_id, cascade)?;
+        Ok((Running { bracket }, matches))
+    }
+}
+
+impl BracketState {
+    /// Returns bracket, whatever its stage
+    #[must_use]
+    pub fn get_bracket(&self) -> &Bracket {
+        match self {
+            BracketState::Registration(Registration { bracket })
+            | BracketState::Running(Running { bracket })
+            | BracketState::Finished(Finished { bracket }) => bracket,
+        }
+    }
+}
+
+impl TryFrom<Bracket> for BracketState {
+    type Error = Error;
+
+    /// A bracket is finished once every match has been played. Otherwise, it
+    /// is running if it accepts match results
+    ///
+    /// # Errors
+    /// thrown when bracket is malformed, for instance when it does not accept
+    /// results while some matches were played
+    fn try_from(bracket: Bracket) -> Result<Self, Self::Error> {
+        if let Err(issues) = bracket.validate() {
+            return Err(Error::Malformed(bracket.bracket_id, issues));
+        }
+        Ok(if !bracket.matches.is_empty() && bracket.is_over() {
+            BracketState::Finished(Finished { bracket })
+        } else if bracket.accept_match_results {
+            BracketState::Running(Running { bracket })
+        } else {
+            BracketState::Registration(Registration { bracket })
+        })
+    }
+}
+
+impl From<BracketState> for Bracket {
+    fn from(state: BracketState) -> Self {
+        match state {
+            BracketState::Registration(Registration { bracket })
+            | BracketState::Running(Running { bracket })
+            | BracketState::Finished(Finished { bracket }) => bracket,
+        }
+    }
+}
+
+impl From<Registration> for BracketState {
+    fn from(registration: Registration) -> Self {
+        BracketState::Registration(registration)
+    }
+}
+
+impl From<Running> for BracketState {
+    fn from(running: Running) -> Self {
+        BracketState::Running(running)
+    }
+}
+
+impl From<Finished> for BracketState {
+    fn from(finished: Finished) -> Self {
+        BracketState::Finished(finished)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        bracket::{
+            builder::fixture::{bracket, play_all},
+            validation::ValidationIssue,
+        },
+        format::Playoffs,
+        opponent::Opponent,
+    };
+
+    fn registration(n: usize) -> Registration {
+        Registration {
+            bracket: bracket(
+                Format::SingleElimination {
+                    playoffs: Playoffs::Disabled,
+                },
+                n,
+            ),
+        }
+    }
+
+    #[test]
+    fn bracket_goes_from_registration_to_finished() {
+        let (mut running, _) = registration(3).start().expect("started");
+        let running_clone = running.clone();
+        assert!(matches!(running_clone.finalize(), Err(Error::NotOver(_))));
+        while let Some(m) = running.get_bracket().matches_to_play().first().copied() {
+            let [Opponent::Player(p1), Opponent::Player(p2)] = m.get_players() else {
+                panic!("match to play without players");
+            };
+            running = running
+                .tournament_organiser_reports_result(p1, (2, 0), p2)
+                .expect("result")
+                .0;
+        }
+        let finished = running.finalize().expect("finished");
+        assert!(matches!(
+            BracketState::try_from(finished.get_bracket().clone()),
+            Ok(BracketState::Finished(_))
+        ));
+
+        let standings = finished.standings().expect("standings");
+        assert!(standings.remaining.is_empty());
+        assert_eq!(standings.placements[0].players.len(), 1);
+    }
+
+    #[test]
+    fn state_is_serialized_as_bracket() {
+        assert!(matches!(
+            BracketState::try_from(registration(0).get_bracket().clone()),
+            Ok(BracketState::Registration(_))
+        ));
+        let state = BracketState::from(registration(3));
+        let json = serde_json::to_string(&state).expect("serialized");
+        let bracket: Bracket = serde_json::from_str(&json).expect("bracket");
+        assert!(matches!(
+            BracketState::try_from(bracket),
+            Ok(BracketState::Registration(_))
+        ));
+
+        let (running, _) = registration(3).start().expect("started");
+        let json = serde_json::to_string(&BracketState::from(running)).expect("serialized");
+        let state: BracketState = serde_json::from_str(&json).expect("deserialized");
+        assert!(matches!(state, BracketState::Running(_)));
+        assert_eq!(
+            state.get_bracket().get_participants().len(),
+            3,
+            "participants are kept"
+        );
+
+        // stage does not depend on the event log
+        let mut json: serde_json::Value = serde_json::from_str(&json).expect("json");
+        json.as_object_mut().expect("bracket").remove("events");
+        let state: BracketState = serde_json::from_value(json).expect("deserialized");
+        assert!(matches!(state, BracketState::Running(_)));
+    }
+
+    #[test]
+    fn stage_must_agree_with_matches() {
+        let (running, _) = registration(3).start().expect("started");
+        let bracket = play_all(running.get_bracket().clone());
+        let json = serde_json::to_value(&bracket).expect("serialized");
+        let state: BracketState = serde_json::from_value(json.clone()).expect("deserialized");
+        assert!(matches!(state, BracketState::Finished(_)));
+
+        // every match was played in a bracket that never started
+        let mut tampered = json.clone();
+        tampered["is_closed"] = serde_json::json!(false);
+        assert!(serde_json::from_value::<BracketState>(tampered).is_err());
+
+        let mut tampered = json;
+        tampered["accept_match_results"] = serde_json::json!(true);
+        tampered["is_closed"] = serde_json::json!(false);
+        let bracket: Bracket = serde_json::from_value(tampered).expect("bracket");
+        assert!(matches!(
+            BracketState::try_from(bracket),
+            Err(Error::Malformed(_, issues)) if issues == [ValidationIssue::AcceptsResultsWhenOpen]
+        ));
+
+        let bracket = registration(3).get_bracket().clone();
+        let mut json = serde_json::to_value(&bracket).expect("serialized");
+        json["matches"][0]["reported_results"] = serde_json::json!([[2, 0], [0, 2]]);
+        let tampered: Bracket = serde_json::from_value(json).expect("bracket");
+        assert!(matches!(
+            BracketState::try_from(tampered),
+            Err(Error::Malformed(_, issues))
+                if issues == [ValidationIssue::ResultBeforeStart(bracket.get_matches()[0].get_id())]
+        ));
+    }
+}
@@ -0,0 +1,215 @@
+//! Call players to their match and start it so tournament organisers can
+//! tell matches waiting for a setup from matches being played
+
+use crate::{
+    bracket::{events::BracketEvent, matches::index::Matches, Bracket, Error},
+    matches::{Id as MatchId, Match, State},
+};
+use chrono::{DateTime, Utc};
+
+impl Bracket {
+    /// Call players of ready match `match_id`
+    ///
+    /// # Errors
+    /// thrown when match is unknown or not ready
+    pub fn call_match(self, match_id: MatchId) -> Result<Bracket, Error> {
+        let at = Utc::now();
+        let bracket = self.update_lifecycle(match_id, |m| m.call(at))?;
+        Ok(bracket.log_at(BracketEvent::MatchCalled { match_id }, at))
+    }
+
+    /// Start ready or called match `match_id`
+    ///
+    /// # Errors
+    /// thrown when match is unknown, waiting for an opponent or already
+    /// started
+    pub fn start_match(self, match_id: MatchId) -> Result<Bracket, Error> {
+        let at = Utc::now();
+        let bracket = self.update_lifecycle(match_id, |m| m.start(at))?;
+        Ok(bracket.log_at(BracketEvent::MatchStarted { match_id }, at))
+    }
+
+    /// Returns matches that can be played out and are in `state`
+    #[must_use]
+    pub fn matches_to_play_in_state(&self, state: State) -> Vec<Match> {
+        self.matches_to_play()
+            .into_iter()
+            .filter(|m| m.get_state() == state)
+            .collect()
+    }
+
+    /// Returns when `match_id` went through each state
+    ///
+    /// # Errors
+    /// thrown when match is unknown
+    pub fn get_match_timestamps(
+        &self,
+        match_id: MatchId,
+    ) -> Result<Vec<(State, DateTime<Utc>)>, Error> {
+        let Some(m) = self.matches.find(match_id) else {
+            return Err(Error::UnknownMatch(self.bracket_id, match_id));
+        };
+        let lifecycle = m.get_lifecycle();
+        Ok([
+            (State::Ready, lifecycle.ready_at),
+            (State::Called, lifecycle.called_at),
+            (State::InProgress, lifecycle.started_at),
+            (State::Completed, lifecycle.completed_at),
+        ]
+        .into_iter()
+        .filter_map(|(state, at)| at.map(|at| (state, at)))
+        .collect())
+    }
+
+    /// Replace `match_id` with the result of `transition`
+    fn update_lifecycle(
+        self,
+        match_id: MatchId,
+        transition: impl Fn(Match) -> Result<Match, crate::matches::Error>,
+    ) -> Result<Bracket, Error> {
+        let Some(index) = self.matches.position(match_id) else {
+            return Err(Error::UnknownMatch(self.bracket_id, match_id));
+        };
+        let m =
+            transition(self.matches[index]).map_err(|e| Error::MatchUpdate(self.bracket_id, e))?;
+        let mut matches = self.matches.clone();
+        matches.replace(index, m);
+        Ok(Self { matches, ..self })
+    }
+}
+
+/// Timestamp with `at` the states entered by matches of `after` that differ
+/// from the match at the same position in `before`. Generated matches have no
+/// counterpart in `before` and are timestamped as well. Updates journaled in
+/// `after` are forgotten
+pub(crate) fn sync_lifecycles(before: &[Match], mut after: Matches, at: DateTime<Utc>) -> Matches {
+    for i in 0..after.len() {
+        if before.get(i) != Some(&after[i]) {
+            let m = after[i].sync_lifecycle(at);
+            after.set(i, m);
+        }
+    }
+    after.clear_journal();
+    after
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        bracket::{builder::fixture::started_bracket, Bracket, Error},
+        format::{Format, Playoffs},
+        matches::{Error as MatchError, State},
+        player::Id as PlayerId,
+    };
+
+    fn bracket() -> (Bracket, Vec<PlayerId>) {
+        started_bracket(
+            Format::SingleElimination {
+                playoffs: Playoffs::Disabled,
+            },
+            4,
+        )
+    }
+
+    fn state_of(bracket: &Bracket, match_id: crate::matches::Id) -> State {
+        bracket
+            .get_matches()
+            .iter()
+            .find(|m| m.get_id() == match_id)
+            .expect("match")
+            .get_state()
+    }
+
+    #[test]
+    fn match_goes_through_every_state() {
+        let (bracket, seeding) = bracket();
+        let finals = bracket.get_matches()[2].get_id();
+        assert_eq!(state_of(&bracket, finals), State::Pending);
+        let m = bracket.matches_to_play_in_state(State::Ready)[0];
+        let match_id = m.get_id();
+
+        let bracket = bracket.call_match(match_id).expect("called");
+        assert_eq!(state_of(&bracket, match_id), State::Called);
+        assert_eq!(bracket.matches_to_play_in_state(State::Ready).len(), 1);
+        let bracket = bracket.start_match(match_id).expect("started");
+        assert_eq!(
+            bracket.matches_to_play_in_state(State::InProgress)[0].get_id(),
+            match_id
+        );
+        let (bracket, _, _) = bracket
+            .tournament_organiser_reports_result(seeding[0], (2, 0), seeding[3])
+            .expect("reported");
+        assert_eq!(state_of(&bracket, match_id), State::Completed);
+
+        let timestamps = bracket.get_match_timestamps(match_id).expect("match");
+        let states = timestamps.iter().map(|(s, _)| *s).collect::<Vec<_>>();
+        assert_eq!(
+            states,
+            vec![
+                State::Ready,
+                State::Called,
+                State::InProgress,
+                State::Completed
+            ]
+        );
+        assert!(timestamps.windows(2).all(|w| w[0].1 <= w[1].1));
+
+        let replayed = Bracket::replay(&bracket.get_events()).expect("replay");
+        assert_eq!(replayed.get_matches(), bracket.get_matches());
+    }
+
+    #[test]
+    fn only_ready_matches_can_be_called() {
+        let (bracket, _) = bracket();
+        let finals = bracket.get_matches()[2].get_id();
+        assert!(matches!(
+            bracket.clone().call_match(finals),
+            Err(Error::MatchUpdate(
+                _,
+                MatchError::InvalidTransition(_, State::Pending, State::Called)
+            ))
+        ));
+
+        let match_id = bracket.matches_to_play()[0].get_id();
+        let bracket = bracket.start_match(match_id).expect("started");
+        assert!(matches!(
+            bracket.call_match(match_id),
+            Err(Error::MatchUpdate(
+                _,
+                MatchError::InvalidTransition(_, State::InProgress, State::Called)
+            ))
+        ));
+    }
+
+    #[test]
+    fn reopened_match_is_ready_again() {
+        let (bracket, seeding) = bracket();
+        let match_id = bracket.matches_to_play()[0].get_id();
+        let bracket = bracket.call_match(match_id).expect("called");
+        let (bracket, _, _) = bracket
+            .tournament_organiser_reports_result(seeding[0], (2, 0), seeding[3])
+            .expect("reported");
+
+        let (bracket, _) = bracket.reopen_match(match_id, false).expect("reopen");
+
+        assert_eq!(state_of(&bracket, match_id), State::Ready);
+    }
+
+    #[test]
+    fn only_updated_matches_are_timestamped_without_event_log() {
+        let (bracket, seeding) = bracket();
+        let bracket = Bracket {
+            events: vec![],
+            ..bracket
+        };
+        let other = bracket.get_matches()[1];
+
+        let (bracket, match_id, _) = bracket
+            .tournament_organiser_reports_result(seeding[0], (2, 0), seeding[3])
+            .expect("reported");
+
+        let timestamps = bracket.get_match_timestamps(match_id).expect("match");
+        assert_eq!(timestamps.last().map(|(s, _)| *s), Some(State::Completed));
+        assert_eq!(bracket.get_matches()[1], other);
+    }
+}
@@ -0,0 +1,336 @@
+//! Set length of matches depending on how far into the bracket they are
+//! played. For instance, best of 3 until top 8, then best of 5 with grand
+//! finals as first to 3
+
+use crate::{
+    bracket::{
+        events::BracketEvent,
+        matches::single_elimination_format::playoffs::trees,
+        standings::{double_elimination_stages, placements_of_stages},
+        Bracket, Error,
+    },
+    format::Format,
+    matches::{Id as MatchId, ReportedResult, SetLength},
+};
+use serde::{Deserialize, Serialize};
+
+/// Set length of matches of a bracket
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MatchRules {
+    /// Set length of matches no other rule applies to
+    default: SetLength,
+    /// Set length of matches in top N. A match is in top N when its loser
+    /// places N or better in the worst case
+    top: Vec<(usize, SetLength)>,
+    /// Set length of grand finals (and its reset)
+    grand_finals: Option<SetLength>,
+}
+
+impl MatchRules {
+    /// Play all matches with `default` set length
+    #[must_use]
+    pub fn new(default: SetLength) -> Self {
+        Self {
+            default,
+            top: vec![],
+            grand_finals: None,
+        }
+    }
+
+    /// Play matches in top `n` with `set_length`. When a match is in many
+    /// tops, the smallest top applies
+    #[must_use]
+    pub fn set_top(self, n: usize, set_length: SetLength) -> Self {
+        let mut top = self.top;
+        top.retain(|(t, _)| *t != n);
+        top.push((n, set_length));
+        top.sort_by_key(|(t, _)| *t);
+        Self { top, ..self }
+    }
+
+    /// Play grand finals with `set_length`
+    #[must_use]
+    pub fn set_grand_finals(self, set_length: SetLength) -> Self {
+        Self {
+            grand_finals: Some(set_length),
+            ..self
+        }
+    }
+
+    /// Returns every set length used by these rules
+    fn set_lengths(&self) -> impl Iterator<Item = SetLength> + '_ {
+        std::iter::once(self.default)
+            .chain(self.top.iter().map(|(_, set_length)| *set_length))
+            .chain(self.grand_finals)
+    }
+
+    /// Returns set length of a match where loser places `worst_placement` in
+    /// the worst case
+    fn set_length(&self, worst_placement: Option<usize>, is_grand_finals: bool) -> SetLength {
+        if is_grand_finals {
+            if let Some(set_length) = self.grand_finals {
+                return set_length;
+            }
+        }
+        let Some(placement) = worst_placement else {
+            return self.default;
+        };
+        self.top
+            .iter()
+            .find(|(n, _)| placement <= *n)
+            .map_or(self.default, |(_, set_length)| *set_length)
+    }
+}
+
+impl Bracket {
+    /// Use `rules` for set length of matches. Results already reported are
+    /// not checked again
+    ///
+    /// # Errors
+    /// thrown when a set length of `rules` cannot be won
+    pub fn set_match_rules(self, rules: MatchRules) -> Result<Bracket, Error> {
+        if let Some(set_length) = rules.set_lengths().find(|s| !s.is_valid()) {
+            return Err(Error::InvalidSetLength(self.bracket_id, set_length));
+        }
+        Ok(Self {
+            match_rules: Some(rules.clone()),
+            ..self
+        }
+        .log(BracketEvent::MatchRulesUpdated { rules }))
+    }
+
+    /// Returns set length rules of this bracket, if any
+    #[must_use]
+    pub fn get_match_rules(&self) -> Option<&MatchRules> {
+        self.match_rules.as_ref()
+    }
+
+    /// Returns set length of `match_id` if this bracket has match rules
+    ///
+    /// # Errors
+    /// thrown when match is unknown
+    pub fn set_length_of(&self, match_id: MatchId) -> Result<Option<SetLength>, Error> {
+        let Some(rules) = &self.match_rules else {
+            return Ok(None);
+        };
+        let Some(index) = self.matches.position(match_id) else {
+            return Err(Error::UnknownMatch(self.bracket_id, match_id));
+        };
+        let (worst_placement, is_grand_finals) = self.worst_placement_of_loser(index);
+        Ok(Some(rules.set_length(worst_placement, is_grand_finals)))
+    }
+
+    /// Check reported `result` is a valid final score for `match_id`
+    ///
+    /// # Errors
+    /// thrown when score does not match set length of match
+    pub(crate) fn check_score(&self, match_id: MatchId, result: (i8, i8)) -> Result<(), Error> {
+        let Some(set_length) = self.set_length_of(match_id)? else {
+            return Ok(());
+        };
+        set_length
+            .validate(ReportedResult(result))
+            .map_err(|e| Error::MatchUpdate(self.bracket_id, e))
+    }
+
+    /// Returns worst placement of the loser of match at `index` (if format
+    /// has placements) and whether this match is grand finals
+    fn worst_placement_of_loser(&self, index: usize) -> (Option<usize>, bool) {
+        match self.format {
+            Format::SingleElimination { playoffs } => {
+                let trees = trees(self.participants.len(), playoffs);
+                for (i, tree) in trees.iter().enumerate() {
+                    if let Some(round) = tree.round_of(index) {
+                        let is_grand_finals = i == 0 && round + 1 == tree.number_of_rounds();
+                        return (Some(tree.worst_placement_of_loser(round)), is_grand_finals);
+                    }
+                }
+                (None, false)
+            }
+            Format::DoubleElimination { .. } => {
+                let stages = double_elimination_stages(&self.matches, self.participants.len());
+                let placements = placements_of_stages(&stages);
+                // loser of winner bracket match drops in loser bracket
+                let m = self.matches[index];
+                let stage_of = |id| stages.iter().position(|s| s.contains(&id));
+                let stage = stage_of(m.get_id())
+                    .or_else(|| m.get_links().loser.and_then(|link| stage_of(link.match_id)));
+                match stage {
+                    Some(i) => (Some(placements[i].last), i + 1 == stages.len()),
+                    None => (None, false),
+                }
+            }
+            Format::RoundRobin | Format::Swiss { .. } => (None, false),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        bracket::{
+            builder::fixture::{self, play_all},
+            match_rules::MatchRules,
+            Bracket, Error,
+        },
+        format::{Format, GrandFinals, LoserDrops, Playoffs},
+        matches::{Error as MatchError, SetLength},
+        opponent::Opponent,
+        player::Id as PlayerId,
+    };
+
+    fn bracket(format: Format, n: usize) -> (Bracket, Vec<PlayerId>) {
+        let bracket = fixture::bracket(format, n)
+            .set_match_rules(
+                MatchRules::new(SetLength::BestOf(3))
+                    .set_top(8, SetLength::BestOf(5))
+                    .set_grand_finals(SetLength::FirstTo(3)),
+            )
+            .expect("match rules");
+        let seeding = bracket.get_participants().get_seeding();
+        (bracket.start().expect("start").0, seeding)
+    }
+
+    fn set_lengths(bracket: &Bracket) -> Vec<SetLength> {
+        bracket
+            .get_matches()
+            .iter()
+            .map(|m| {
+                bracket
+                    .set_length_of(m.get_id())
+                    .expect("match")
+                    .expect("set length")
+            })
+            .collect()
+    }
+
+    #[test]
+    fn set_length_depends_on_placement_of_loser() {
+        let (bracket, _) = bracket(
+            Format::SingleElimination {
+                playoffs: Playoffs::Disabled,
+            },
+            16,
+        );
+        let set_lengths = set_lengths(&bracket);
+        assert_eq!(set_lengths[..8], [SetLength::BestOf(3); 8]);
+        assert_eq!(set_lengths[8..14], [SetLength::BestOf(5); 6]);
+        assert_eq!(set_lengths[14], SetLength::FirstTo(3));
+    }
+
+    #[test]
+    fn double_elimination_winner_bracket_matches_use_placement_of_loser_bracket() {
+        let (small, _) = bracket(
+            Format::DoubleElimination {
+                loser_drops: LoserDrops::Standard,
+                grand_finals: GrandFinals::Reset,
+            },
+            8,
+        );
+        let small = set_lengths(&small);
+        // every player of an 8 man bracket is in top 8
+        assert!(small[..13].iter().all(|s| *s == SetLength::BestOf(5)));
+        assert_eq!(small[13..], [SetLength::FirstTo(3); 2]);
+
+        let (big, _) = bracket(
+            Format::DoubleElimination {
+                loser_drops: LoserDrops::Standard,
+                grand_finals: GrandFinals::Reset,
+            },
+            16,
+        );
+        let big = set_lengths(&big);
+        // losers of first round of winner bracket get 13th-16th in the worst case
+        assert_eq!(big[..8], [SetLength::BestOf(3); 8]);
+        // losers of second round drop in loser bracket round 2 (9th-12th)
+        assert_eq!(big[8..12], [SetLength::BestOf(3); 4]);
+        assert_eq!(big[12], SetLength::BestOf(5));
+    }
+
+    #[test]
+    fn reported_score_must_match_set_length() {
+        let (bracket, seeding) = bracket(
+            Format::SingleElimination {
+                playoffs: Playoffs::Disabled,
+            },
+            16,
+        );
+        for invalid in [(3, 3), (-1, 2), (0, 0), (3, 0), (1, 1)] {
+            assert!(
+                matches!(
+                    bracket
+                        .clone()
+                        .tournament_organiser_reports_result(seeding[7], invalid, seeding[8]),
+                    Err(Error::MatchUpdate(
+                        _,
+                        MatchError::InvalidScore(_, SetLength::BestOf(3))
+                    ))
+                ),
+                "{invalid:?} should be invalid"
+            );
+        }
+        let (bracket, _, _) = bracket
+            .tournament_organiser_reports_result(seeding[7], (2, 1), seeding[8])
+            .expect("valid score");
+        assert!(matches!(
+            bracket.report_result(seeding[15], (0, 3)),
+            Err(Error::MatchUpdate(_, MatchError::InvalidScore(_, _)))
+        ));
+    }
+
+    #[test]
+    fn set_length_that_cannot_be_won_is_rejected() {
+        let bracket = fixture::bracket(Format::default(), 4);
+        for invalid in [
+            SetLength::BestOf(0),
+            SetLength::BestOf(4),
+            SetLength::FirstTo(0),
+            SetLength::FirstTo(128),
+        ] {
+            assert!(
+                matches!(
+                    bracket
+                        .clone()
+                        .set_match_rules(MatchRules::new(SetLength::BestOf(3)).set_top(
+                            8,
+                            invalid
+                        )),
+                    Err(Error::InvalidSetLength(_, s)) if s == invalid
+                ),
+                "{invalid} should be invalid"
+            );
+        }
+        assert!(bracket
+            .set_match_rules(
+                MatchRules::new(SetLength::BestOf(1)).set_grand_finals(SetLength::FirstTo(1))
+            )
+            .is_ok());
+    }
+
+    #[test]
+    fn loser_placements_agree_with_standings() {
+        for loser_drops in [LoserDrops::Standard, LoserDrops::Alternating] {
+            for n in [5, 7, 12] {
+                let format = Format::DoubleElimination {
+                    loser_drops,
+                    grand_finals: GrandFinals::Reset,
+                };
+                let bracket = play_all(fixture::started_bracket(format, n).0);
+
+                for group in bracket.standings().expect("standings").placements {
+                    for p in group.players {
+                        let last_match = *bracket.matches.matches_of(p).last().expect("match");
+                        if bracket.matches[last_match].get_winner() == Opponent::Player(p) {
+                            continue;
+                        }
+                        assert_eq!(
+                            bracket.worst_placement_of_loser(last_match).0,
+                            Some(group.placement.last),
+                            "{format} with {n} players"
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
@@ -175,7 +175,7 @@ mod tests {
         );
     }
 
-    fn initial_step(n: usize, auto: bool) -> (Step, Participants, Vec<Player>) {
+    fn initial_step(n: usize, auto: bool) -> (Step<'static>, Participants, Vec<Player>) {
         let mut p = vec![Player::new("don't use".into())];
         let mut participants = Participants::default();
         for i in 1..=n {
@@ -5,41 +5,39 @@ mod next_opponent;
 mod query_state;
 
 use super::{
-    assert_disqualified_at_most_once, assert_match_is_well_formed, update_bracket_with, Error,
+    assert_disqualified_at_most_once, assert_match_is_well_formed, index::Matches, update, Error,
     Progression,
 };
 use crate::{
-    bracket::{
-        disqualification::get_new_matches,
-        progression::{new_matches, winner_of_bracket},
-    },
-    format::Format,
+    bracket::progression::winner_of_bracket,
+    format::{Format, GrandFinals, LoserDrops},
     matches::{
-        double_elimination_matches_from_partition as dem_partition,
-        partition_double_elimination_matches, Error as MatchError, Id as MatchId, Match,
+        partition_double_elimination_matches, Error as MatchError, Id as MatchId, Link, Match,
         ReportedResult,
     },
     opponent::Opponent,
     player::Id as PlayerId,
-    seeding::{
-        double_elimination_seeded_bracket::get_loser_bracket_matches_top_seed_favored,
-        single_elimination_seeded_bracket::get_balanced_round_matches_top_seed_favored,
-    },
 };
+use std::{borrow::Cow, ops::Range};
 
 /// Computes the next step of a double elimination tournament
 #[derive(Clone, Debug)]
-pub(crate) struct Step {
+pub(crate) struct Step<'a> {
     /// True when matches do not need to be validated by the tournament
     /// organiser
     auto: bool,
     /// All matches of double-elimination bracket
-    matches: Vec<Match>,
+    matches: Cow<'a, Matches>,
     /// Seeding used for this bracket
     seeding: Vec<PlayerId>,
+    /// Placement of players dropping into loser bracket
+    loser_drops: LoserDrops,
+    /// How grand finals are played
+    grand_finals: GrandFinals,
 }
 
-impl Step {
+#[cfg(test)]
+impl Step<'static> {
     /// Generate double elimination matches using `seeding`
     ///
     /// # Errors
@@ -49,81 +47,358 @@ impl Step {
         seeding: Vec<PlayerId>,
         automatic_progression: bool,
     ) -> Result<Self, Error> {
-        let Some(matches) = matches else {
-            let mut matches = vec![];
-            let mut winner_bracket_matches = get_balanced_round_matches_top_seed_favored(&seeding)?;
-            matches.append(&mut winner_bracket_matches);
-            let mut loser_bracket_matches = get_loser_bracket_matches_top_seed_favored(&seeding)?;
-            matches.append(&mut loser_bracket_matches);
-            let grand_finals: Match = Match::new([Opponent::Unknown, Opponent::Unknown], [1, 2])?;
-            matches.push(grand_finals);
-            let grand_finals_reset: Match =
-                Match::new([Opponent::Unknown, Opponent::Unknown], [1, 2])?;
-            matches.push(grand_finals_reset);
-
-            return Ok(Self {
-                seeding,
-                matches,
-                auto: automatic_progression,
-            });
+        let matches = match matches {
+            Some(matches) => matches,
+            None => Format::DoubleElimination {
+                loser_drops: LoserDrops::default(),
+                grand_finals: GrandFinals::default(),
+            }
+            .generate_matches(&seeding)?,
         };
-        Ok(Self {
+        Ok(Step::from_matches(
+            Cow::Owned(matches.into()),
+            seeding,
+            automatic_progression,
+        ))
+    }
+}
+
+impl<'a> Step<'a> {
+    /// Double elimination bracket of `seeding` with `matches`
+    pub fn from_matches(
+        matches: Cow<'a, Matches>,
+        seeding: Vec<PlayerId>,
+        automatic_progression: bool,
+    ) -> Self {
+        Self {
             seeding,
             matches,
             auto: automatic_progression,
-        })
+            loser_drops: LoserDrops::default(),
+            grand_finals: GrandFinals::default(),
+        }
+    }
+
+    /// Use `loser_drops` for this bracket. Matches are expected to be
+    /// generated with the same placement
+    pub fn with_loser_drops(self, loser_drops: LoserDrops) -> Self {
+        Self {
+            loser_drops,
+            ..self
+        }
+    }
+
+    /// Play grand finals as `grand_finals`. Matches are expected to be
+    /// generated with the same grand finals
+    pub fn with_grand_finals(self, grand_finals: GrandFinals) -> Self {
+        Self {
+            grand_finals,
+            ..self
+        }
+    }
+
+    /// Positions of winner bracket, loser bracket, grand finals and grand
+    /// finals reset
+    fn sections(&self) -> (Range<usize>, Range<usize>, usize, Option<usize>) {
+        let n = self.seeding.len();
+        let len = self.matches.len();
+        assert!(
+            len == 2 * n - 1 || len == 2 * n - 2,
+            "expected (2 * n) - 1 matches (or (2 * n) - 2 without grand finals reset), where n is the number of players but got: {len}"
+        );
+        let reset = (len == 2 * n - 1).then_some(len - 1);
+        let grand_finals = len - 1 - usize::from(reset.is_some());
+        (0..n - 1, n - 1..grand_finals, grand_finals, reset)
+    }
+
+    /// Returns true if `player_id` takes part in this bracket
+    fn is_participant(&self, player_id: PlayerId) -> bool {
+        if self.matches.is_empty() {
+            return self.seeding.contains(&player_id);
+        }
+        self.matches.seed_of(player_id).is_some()
+    }
+
+    /// Position of first match of `player_id` that needs a winner
+    fn next_match_of(&self, player_id: PlayerId) -> Option<usize> {
+        self.matches
+            .matches_of(player_id)
+            .iter()
+            .copied()
+            .find(|i| self.matches[*i].get_winner() == Opponent::Unknown)
+    }
+
+    /// Run `update` and undo what it did to matches when it fails
+    fn attempt<T>(
+        &mut self,
+        update: impl FnOnce(&mut Self) -> Result<T, Error>,
+    ) -> Result<T, Error> {
+        let mark = self.matches.mark();
+        let result = update(self);
+        if result.is_err() {
+            self.matches.to_mut().rollback(mark);
+        }
+        result
     }
 
     /// Clear previous reported result for `player_id`
-    fn clear_reported_result(self, player_id: PlayerId) -> Result<Self, Error> {
-        let match_to_update = self
+    fn clear_reported_result(&mut self, player_id: PlayerId) -> Result<(), Error> {
+        let Some(i) = self.next_match_of(player_id) else {
+            return Err(Error::NoMatchToPlay(player_id));
+        };
+        let m_to_clear = self.matches[i].clear_reported_result(player_id);
+        self.matches.to_mut().set(i, m_to_clear);
+        Ok(())
+    }
+
+    /// Check `result` reported by `player_id` for `m` includes the game the
+    /// player coming from winner bracket starts grand finals with
+    fn check_advantage(
+        &self,
+        m: &Match,
+        result: (i8, i8),
+        player_id: PlayerId,
+    ) -> Result<(), Error> {
+        if self.grand_finals != GrandFinals::WinnerSideAdvantage {
+            return Ok(());
+        }
+        let (_, _, gf, _) = self.sections();
+        if self.matches[gf].get_id() != m.get_id() {
+            return Ok(());
+        }
+        let winner_side_score = match m.get_players()[0] {
+            Opponent::Player(p) if p == player_id => result.0,
+            _ => result.1,
+        };
+        if winner_side_score < 1 {
+            return Err(MatchError::MissingAdvantage(m.get_id(), ReportedResult(result)).into());
+        }
+        Ok(())
+    }
+
+    /// Disqualify `player_id` from their current match and move them as far as
+    /// possible in the bracket
+    fn disqualify_in_place(&mut self, player_id: PlayerId) -> Result<(), Error> {
+        let (_, losers, grand_finals, _) = self.sections();
+        let Some(i) = self
             .matches
+            .matches_of(player_id)
             .iter()
-            .find(|m| m.contains(player_id) && m.get_winner() == Opponent::Unknown);
-        match match_to_update {
-            Some(m_to_clear) => {
-                let m_to_clear = (*m_to_clear).clear_reported_result(player_id);
-                let matches = update_bracket_with(&self.matches, &m_to_clear);
-                Ok(Self { matches, ..self })
+            .rev()
+            .copied()
+            .find(|i| {
+                let m = self.matches[*i];
+                m.get_winner() == Opponent::Unknown && m.get_automatic_loser() == Opponent::Unknown
+            })
+        else {
+            return Err(Error::ForbiddenDisqualified(player_id));
+        };
+        let m = self.matches[i];
+        // disqualify player then validate match result to update double elimination bracket
+        let current_match_to_play = m.set_automatic_loser(player_id)?;
+        let matches = self.matches.to_mut();
+        matches.set(i, current_match_to_play);
+        // don't send to loser if the disqualified player is in gf or already
+        // in loser bracket
+        if !matches[grand_finals].contains(player_id)
+            && !matches
+                .matches_of(player_id)
+                .iter()
+                .any(|j| losers.contains(j))
+        {
+            send_to_losers(matches, &losers, player_id, m.get_id(), m.get_links().loser)?;
+        }
+        // move DQ'ed played as far as possible
+        match self.attempt(|p| p.validate_in_place(current_match_to_play.get_id())) {
+            Ok(()) => {
+                let Some(j) = self.next_match_of(player_id) else {
+                    return Ok(());
+                };
+                // DQ them in loser bracket and validate result again
+                let match_in_losers = self.matches[j].set_automatic_loser(player_id)?;
+                self.matches.to_mut().set(j, match_in_losers);
+                // loser bracket stays as is when its match cannot be updated
+                let _ = self.attempt(|p| p.validate_in_place(match_in_losers.get_id()));
+                Ok(())
+            }
+            // if no winner can be declared because there is a missing
+            // player, then don't throw an error. Set player as disqualified
+            // in their latest match
+            Err(Error::MatchUpdate(MatchError::MissingOpponent(_))) => {
+                let j = *self
+                    .matches
+                    .matches_of(player_id)
+                    .iter()
+                    .rev()
+                    .find(|j| self.matches[**j].get_winner() == Opponent::Unknown)
+                    .expect("match in losers to update");
+                let match_to_set_dq = self.matches[j]
+                    .set_automatic_loser(player_id)
+                    .expect("disqualified player in loser");
+                self.matches.to_mut().set(j, match_to_set_dq);
+                Ok(())
             }
-            None => Err(Error::NoMatchToPlay(player_id)),
+            // Can't update match in losers where where DQ'ed player is in.
+            // Set DQ'ed player as loser and update
+            Err(Error::MatchUpdate(MatchError::PlayersReportedDifferentMatchOutcome(_, _))) => {
+                let j = self
+                    .next_match_of(player_id)
+                    .expect("match in losers to update");
+                let match_to_set_dq = self.matches[j]
+                    .set_automatic_loser(player_id)
+                    .expect("updated match");
+                self.matches.to_mut().set(j, match_to_set_dq);
+                self.validate_in_place(match_to_set_dq.get_id())
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    // NOTE: w_bracket -> winner bracket
+    //       l_bracket -> loser bracket
+    /// First look if match is in winners, then loosers, then GF, then GF reset
+    /// If found in winners, update winners, send loser to losers and update
+    /// losers as well
+    fn validate_in_place(&mut self, match_id: MatchId) -> Result<(), Error> {
+        let (winners, losers, grand_finals, reset) = self.sections();
+        let matches = self.matches.to_mut();
+        match update(matches, winners.clone(), match_id) {
+            Ok((loser, loser_link, is_disqualified_from_winners)) => {
+                update_loser_bracket_after_updating_winners_bracket(
+                    matches,
+                    &losers,
+                    loser,
+                    is_disqualified_from_winners,
+                    match_id,
+                    loser_link,
+                )?;
+
+                let mut gf = matches[grand_finals];
+                let winner_of_winner_bracket = winner_of_bracket(&matches[winners.clone()]);
+                if let Some(winner_of_winner_bracket) = winner_of_winner_bracket {
+                    gf = gf.insert_player(winner_of_winner_bracket, true)?;
+                }
+                // when loser of winners finals is disqualified, grand finals can be updated
+                if let Some(winner_of_loser_bracket) = winner_of_bracket(&matches[losers]) {
+                    gf = gf.insert_player(winner_of_loser_bracket, false)?;
+                    if matches.matches_of(winner_of_loser_bracket).iter().any(|i| {
+                        winners.contains(i)
+                            && matches[*i]
+                                .is_automatic_loser_by_disqualification(winner_of_loser_bracket)
+                    }) {
+                        gf = gf
+                            .set_automatic_loser(winner_of_loser_bracket)?
+                            .update_outcome()?
+                            .0;
+                    }
+                }
+                // when the winner of winner bracket is disqualified, then reset match should be validated also
+                let gf_reset = match (
+                    gf.get_automatic_loser(),
+                    winner_of_winner_bracket,
+                    gf.is_over(),
+                    reset,
+                ) {
+                    (
+                        Opponent::Player(disqualified),
+                        Some(winner_of_winner_bracket),
+                        true,
+                        Some(reset),
+                    ) if disqualified == winner_of_winner_bracket => Some((
+                        reset,
+                        Match::new(gf.get_players(), [1, 2])?
+                            .set_automatic_loser(winner_of_winner_bracket)?
+                            .update_outcome()?
+                            .0,
+                    )),
+                    _ => None,
+                };
+                matches.set(grand_finals, gf);
+                if let Some((reset, gf_reset)) = gf_reset {
+                    matches.set(reset, gf_reset);
+                }
+                Ok(())
+            }
+            Err(Error::UnknownMatch(_bad_winner_match)) => {
+                match update(matches, losers.clone(), match_id) {
+                    Ok(_) => {
+                        // send winner of loser bracket to grand finals if
+                        // possible
+                        let gf = match winner_of_bracket(&matches[losers]) {
+                            Some(winner_of_loser_bracket) => {
+                                matches[grand_finals].set_player(winner_of_loser_bracket, false)
+                            }
+                            None => matches[grand_finals],
+                        };
+                        matches.set(grand_finals, gf);
+                        if let ([Opponent::Player(_), Opponent::Player(_)], Opponent::Player(_)) =
+                            (gf.get_players(), gf.get_automatic_loser())
+                        {
+                            update_grand_finals_or_reset(
+                                matches,
+                                gf.get_id(),
+                                grand_finals,
+                                reset,
+                            )?;
+                        }
+                        Ok(())
+                    }
+                    Err(Error::UnknownMatch(_bad_loser_match)) => {
+                        update_grand_finals_or_reset(matches, match_id, grand_finals, reset)
+                    }
+                    Err(e) => Err(e),
+                }
+            }
+            Err(e) => Err(e),
         }
     }
 }
 
-/// Place loser from winner's bracket into loser bracket using seed of
-/// `expected_loser_seed`. Returns updated loser bracket
+/// Place loser from winner's bracket into `losers` following `loser_link` of
+/// their match `from`
+///
+/// # Errors
+/// thrown when `from` is not linked to a loser bracket match
 fn send_to_losers(
-    loser_bracket: &[Match],
+    matches: &mut Matches,
+    losers: &Range<usize>,
     loser: PlayerId,
-    expected_loser_seed: usize,
-) -> Result<Vec<Match>, Error> {
-    let loser_match = loser_bracket
-        .iter()
-        .find(|m| m.is_first_loser_match(expected_loser_seed))
-        .expect("match");
-    let is_player_1 = expected_loser_seed == loser_match.get_seeds()[0];
-    let loser_match = (*loser_match).insert_player(loser, is_player_1)?;
-
-    Ok(update_bracket_with(loser_bracket, &loser_match))
+    from: MatchId,
+    loser_link: Option<Link>,
+) -> Result<(), Error> {
+    let Some(link) = loser_link else {
+        return Err(Error::MissingLink(from));
+    };
+    let Some(i) = matches
+        .position(link.match_id)
+        .filter(|i| losers.contains(i))
+    else {
+        return Err(Error::UnknownMatch(link.match_id));
+    };
+    let loser_match = matches[i].insert_player(loser, link.is_player_1)?;
+    matches.set(i, loser_match);
+    Ok(())
 }
 
 /// Update grand finals or reset
 fn update_grand_finals_or_reset(
+    matches: &mut Matches,
     match_id: MatchId,
-    winner_bracket: Vec<Match>,
-    loser_bracket: Vec<Match>,
-    gf: Match,
-    gf_reset: Match,
-) -> Result<Vec<Match>, Error> {
+    grand_finals: usize,
+    reset: Option<usize>,
+) -> Result<(), Error> {
+    let gf = matches[grand_finals];
+    let gf_reset = reset.map(|reset| matches[reset]);
     match match_id {
         id if id == gf.get_id() => {
             let (gf, _, _) = gf.update_outcome()?;
             // when a reset happens in grand finals
-            let gf_reset = match (gf.get_winner(), gf.get_players()[1]) {
-                (Opponent::Player(gf_winner), Opponent::Player(player_from_losers))
-                    if gf_winner == player_from_losers =>
-                {
+            let gf_reset = match (gf.get_winner(), gf.get_players()[1], gf_reset) {
+                (
+                    Opponent::Player(gf_winner),
+                    Opponent::Player(player_from_losers),
+                    Some(gf_reset),
+                ) if gf_winner == player_from_losers => {
                     // Set players of gf reset
                     let gf_reset = match gf.get_players() {
                         [Opponent::Player(p1), Opponent::Player(p2)] => {
@@ -136,7 +411,7 @@ fn update_grand_finals_or_reset(
                     };
 
                     // if player is disqualified in grand finals, update gf reset
-                    match (gf.get_automatic_loser(), gf.get_players()[0]) {
+                    let gf_reset = match (gf.get_automatic_loser(), gf.get_players()[0]) {
                         (
                             Opponent::Player(grand_finals_loser),
                             Opponent::Player(winner_of_winner_bracket),
@@ -147,129 +422,65 @@ fn update_grand_finals_or_reset(
                                 .0
                         }
                         (_, _) => gf_reset,
-                    }
+                    };
+                    Some(gf_reset)
                 }
-                _ => gf_reset,
+                (_, _, gf_reset) => gf_reset,
             };
 
-            Ok(dem_partition(&winner_bracket, &loser_bracket, gf, gf_reset))
+            matches.set(grand_finals, gf);
+            if let (Some(reset), Some(gf_reset)) = (reset, gf_reset) {
+                matches.set(reset, gf_reset);
+            }
+            Ok(())
         }
-        id if id == gf_reset.get_id() => {
-            let (gf_reset, _, _) = gf_reset.update_outcome()?;
-            Ok([winner_bracket, loser_bracket, vec![gf, gf_reset]].concat())
+        id if gf_reset.is_some_and(|m| m.get_id() == id) => {
+            let (gf_reset, _, _) = gf_reset.expect("grand finals reset").update_outcome()?;
+            matches.set(reset.expect("grand finals reset"), gf_reset);
+            Ok(())
         }
-        _ => panic!("expected GF or GF reset but got other match: {match_id}"),
+        _ => Err(Error::UnknownMatch(match_id)),
     }
 }
 
-impl Progression for Step {
-    fn disqualify_participant(
-        &self,
-        player_id: crate::player::Id,
-    ) -> Result<(Vec<Match>, Vec<Match>), Error> {
+impl Progression for Step<'_> {
+    fn boxed(&self) -> Box<dyn Progression + '_> {
+        Box::new(self.clone())
+    }
+
+    fn into_matches(self: Box<Self>) -> Matches {
+        self.matches.into_owned()
+    }
+
+    fn disqualify(&mut self, player_id: PlayerId) -> Result<Vec<Match>, Error> {
         if self.is_over() {
             return Err(Error::TournamentIsOver);
         }
-        if !self.seeding.contains(&player_id) {
+        if !self.is_participant(player_id) {
             return Err(Error::UnknownPlayer(player_id, self.seeding.clone()));
         };
-        let disqualified = player_id;
-
-        let old_matches_to_play = self.matches_to_play();
-
-        let Some(m) = self.matches.iter().rev().find(|m| {
-            m.contains(player_id)
-                && m.get_winner() == Opponent::Unknown
-                && m.get_automatic_loser() == Opponent::Unknown
-        }) else {
-            if !self.seeding.contains(&player_id) {
-                return Err(Error::UnknownPlayer(player_id, self.seeding.clone()));
-            };
-            return Err(Error::ForbiddenDisqualified(player_id));
-        };
-        // disqualify player then validate match result to update double elimination bracket
-        let current_match_to_play = (*m).set_automatic_loser(player_id)?;
-        let matches = update_bracket_with(&self.matches, &current_match_to_play);
-        let expected_loser_seed = m.get_seeds()[1];
-        let (w_bracket, l_bracket, gf, gf_reset) =
-            partition_double_elimination_matches(&matches, self.seeding.len());
-        // don't send to loser if the disqualified player is in gf or gf_reset
-        let l_bracket = if gf.contains(disqualified)
-            || gf.contains(disqualified)
-            || l_bracket.iter().any(|m| m.contains(disqualified))
-        {
-            l_bracket
-        } else {
-            send_to_losers(&l_bracket, disqualified, expected_loser_seed)?
-        };
-        let matches = dem_partition(&w_bracket, &l_bracket, gf, gf_reset);
-        let p = Step::new(Some(matches), self.seeding.clone(), self.auto)?;
-        // move DQ'ed played as far as possible
-        match p.validate_match_result(current_match_to_play.get_id()) {
-            Ok((bracket, _)) => {
-                let Some(m) = bracket
-                    .iter()
-                    .find(|m| m.contains(player_id) && m.get_winner() == Opponent::Unknown)
-                else {
-                    let p = Step::new(Some(bracket.clone()), self.seeding.clone(), self.auto)?;
-                    let new_matches = get_new_matches(&old_matches_to_play, &p.matches_to_play());
-                    return Ok((bracket, new_matches));
-                };
-                // DQ them in loser bracket and validate result again
-                let match_in_losers = (*m).set_automatic_loser(player_id)?;
-                let bracket = update_bracket_with(&bracket, &match_in_losers);
-                let p = Step::new(Some(bracket), self.seeding.clone(), self.auto)?;
-
-                let Ok((bracket, _)) = p.validate_match_result(match_in_losers.get_id()) else {
-                    let new_matches = get_new_matches(&old_matches_to_play, &p.matches_to_play());
-                    return Ok((p.matches, new_matches));
-                };
-
-                let p = Step::new(Some(bracket), self.seeding.clone(), self.auto)?;
-                let new_matches = get_new_matches(&old_matches_to_play, &p.matches_to_play());
-                Ok((p.matches, new_matches))
-            }
-            Err(bracket_e) => {
-                // if no winner can be declared because there is a
-                // missing player, then don't throw an error
-                let Error::MatchUpdate(ref e) = bracket_e else {
-                    return Err(bracket_e);
-                };
-                match e {
-                    MatchError::MissingOpponent(_) => {
-                        disqualify_player(&p, player_id, &old_matches_to_play)
-                    }
-                    MatchError::PlayersReportedDifferentMatchOutcome(_, _) => {
-                        // Can't update match in losers where where DQ'ed player is in.
-                        // Set DQ'ed player as loser and update
-                        disqualify_player_and_update_bracket(
-                            &p,
-                            player_id,
-                            &self.seeding,
-                            self.auto,
-                            &old_matches_to_play,
-                        )
-                    }
-                    _ => Err(bracket_e),
-                }
-            }
-        }
+        let mark = self.matches.mark();
+        self.attempt(|p| p.disqualify_in_place(player_id))?;
+        Ok(self.matches.newly_playable(mark))
     }
 
     fn get_format(&self) -> Format {
-        Format::DoubleElimination
+        Format::DoubleElimination {
+            loser_drops: self.loser_drops,
+            grand_finals: self.grand_finals,
+        }
     }
 
     fn is_over(&self) -> bool {
-        let (winner_bracket, loser_bracket, gf, gfr) =
-            partition_double_elimination_matches(&self.matches, self.seeding.len());
-        let Some(stronger_seed_wins) = gf.stronger_seed_wins() else {
+        let (_, _, gf, gfr) = self.sections();
+        let Some(stronger_seed_wins) = self.matches[gf].stronger_seed_wins() else {
             return false;
         };
-        super::bracket_is_over(&winner_bracket)
-            && super::bracket_is_over(&loser_bracket)
-            && gf.is_over()
-            && (stronger_seed_wins || gfr.is_over())
+        let gfr_is_over = gfr.is_some_and(|i| self.matches[i].is_over());
+        // every match but the reset is over
+        self.matches.over() - usize::from(gfr_is_over)
+            == self.matches.len() - usize::from(gfr.is_some())
+            && (stronger_seed_wins || gfr.is_none() || gfr_is_over)
     }
 
     fn matches_to_play(&self) -> Vec<Match> {
@@ -284,7 +495,7 @@ impl Progression for Step {
         &self,
         player_id: crate::player::Id,
     ) -> Result<(Opponent, crate::matches::Id), Error> {
-        if !self.seeding.contains(&player_id) {
+        if !self.is_participant(player_id) {
             return Err(Error::PlayerIsNotParticipant(player_id));
         };
 
@@ -296,11 +507,7 @@ impl Progression for Step {
             return Err(Error::Disqualified(player_id));
         }
 
-        let next_match = self
-            .matches
-            .iter()
-            .find(|m| m.contains(player_id) && m.get_winner() == Opponent::Unknown);
-        let Some(relevant_match) = next_match else {
+        let Some(relevant_match) = self.next_match_of(player_id).map(|i| self.matches[i]) else {
             let last_match = self.matches.iter().last().expect("last match");
             return match last_match.get_winner() {
                 Opponent::Player(p) if p == player_id => Err(Error::NoNextMatch(player_id)),
@@ -317,227 +524,114 @@ impl Progression for Step {
         Ok((opponent, relevant_match.get_id()))
     }
 
-    fn report_result(
-        &self,
+    fn report(
+        &mut self,
         player_id: crate::player::Id,
         result: (i8, i8),
-    ) -> Result<(Vec<Match>, crate::matches::Id, Vec<Match>), Error> {
-        if !self.seeding.contains(&player_id) {
+    ) -> Result<(crate::matches::Id, Vec<Match>), Error> {
+        if !self.is_participant(player_id) {
             return Err(Error::PlayerIsNotParticipant(player_id));
         };
-        if super::is_disqualified(player_id, &self.matches) {
+        if self.matches.is_disqualified(player_id) {
             return Err(Error::ForbiddenDisqualified(player_id));
         }
 
-        let old_matches = self.matches_to_play();
-        let Some(m) = self
-            .matches
-            .iter()
-            .find(|m| m.contains(player_id) && m.get_winner() == Opponent::Unknown)
-        else {
+        let Some(i) = self.next_match_of(player_id) else {
             return Err(Error::NoMatchToPlay(player_id));
         };
-        let affected_match_id = m.get_id();
-        let matches = self.clone().update_player_reported_match_result(
-            affected_match_id,
-            result,
-            player_id,
-        )?;
-        let bracket = Step::new(Some(matches), self.seeding.clone(), self.auto)?;
-
-        let matches = if self.auto {
-            match bracket.clone().validate_match_result(affected_match_id) {
-                Ok((m, _)) => m,
-                Err(Error::MatchUpdate(MatchError::PlayersReportedDifferentMatchOutcome(_, _))) => {
-                    bracket.matches
+        let affected_match_id = self.matches[i].get_id();
+        let mark = self.matches.mark();
+        self.attempt(|p| {
+            p.record_reported_result(affected_match_id, result, player_id)?;
+            if p.auto {
+                match p.attempt(|p| p.validate_in_place(affected_match_id)) {
+                    Ok(())
+                    | Err(Error::MatchUpdate(MatchError::PlayersReportedDifferentMatchOutcome(
+                        _,
+                        _,
+                    ))) => {}
+                    Err(e) => return Err(e),
                 }
-                Err(e) => return Err(e),
             }
-        } else {
-            bracket.matches
-        };
-        let p = Step::new(Some(matches), self.seeding.clone(), self.auto)?;
+            Ok(())
+        })?;
 
-        let new_matches = new_matches(&old_matches, &p.matches_to_play());
-        Ok((p.matches, affected_match_id, new_matches))
+        Ok((affected_match_id, self.matches.newly_playable(mark)))
     }
 
-    fn tournament_organiser_reports_result(
-        &self,
+    fn organiser_reports(
+        &mut self,
         player1: crate::player::Id,
         result: (i8, i8),
         player2: crate::player::Id,
-    ) -> Result<(Vec<Match>, crate::matches::Id, Vec<Match>), Error> {
-        // clear reported results
-        let bracket = self.clone().clear_reported_result(player1)?;
-        let bracket = bracket.clear_reported_result(player2)?;
-
-        // report score as p1
-        let result_player_1 = ReportedResult(result);
-        let (matches, first_affected_match, _new_matches) =
-            bracket.report_result(player1, result_player_1.0)?;
-
-        // report same score as p2
-        let bracket = Step::new(Some(matches), self.seeding.clone(), self.auto)?;
+    ) -> Result<(crate::matches::Id, Vec<Match>), Error> {
+        let Some(i) = self
+            .next_match_of(player1)
+            .filter(|i| self.matches[*i].contains(player2))
+        else {
+            return Err(Error::NoMatchToPlay(player1));
+        };
+        let affected_match_id = self.matches[i].get_id();
+        self.attempt(|p| {
+            // clear reported results
+            p.clear_reported_result(player1)?;
+            p.clear_reported_result(player2)?;
 
-        let (matches, second_affected_match, new_matches) =
-            bracket.report_result(player2, result_player_1.reverse().0)?;
+            // report score as p1
+            let result_player_1 = ReportedResult(result);
+            p.report(player1, result_player_1.0)?;
 
-        assert_eq!(first_affected_match, second_affected_match);
+            // report same score as p2
+            let (_, new_matches) = p.report(player2, result_player_1.reverse().0)?;
 
-        Ok((matches, first_affected_match, new_matches))
+            Ok((affected_match_id, new_matches))
+        })
     }
 
-    fn update_player_reported_match_result(
-        &self,
+    fn record_reported_result(
+        &mut self,
         match_id: crate::matches::Id,
         result: (i8, i8),
         player_id: crate::player::Id,
-    ) -> Result<Vec<Match>, Error> {
-        let Some(m) = self.matches.iter().find(|m| m.get_id() == match_id) else {
+    ) -> Result<(), Error> {
+        let Some(i) = self.matches.position(match_id) else {
             return Err(Error::UnknownMatch(match_id));
         };
+        let m = self.matches[i];
+        self.check_advantage(&m, result, player_id)?;
 
-        let updated_match = (*m).update_reported_result(player_id, ReportedResult(result))?;
-        let matches = self
-            .matches
-            .clone()
-            .iter()
-            .map(|m| {
-                if m.get_id() == updated_match.get_id() {
-                    updated_match
-                } else {
-                    *m
-                }
-            })
-            .collect();
-        Ok(matches)
+        let updated_match = m.update_reported_result(player_id, ReportedResult(result))?;
+        self.matches.to_mut().set(i, updated_match);
+        Ok(())
     }
 
-    // NOTE: w_bracket -> winner bracket
-    //       l_bracket -> loser bracket
-    /// First look if match is in winners, then loosers, then GF, then GF reset
-    /// If found in winners, update winners, send loser to losers and update
-    /// losers as well
-    fn validate_match_result(
-        &self,
-        match_id: crate::matches::Id,
-    ) -> Result<(Vec<Match>, Vec<Match>), Error> {
-        let old_matches = self.matches_to_play();
-        let (w_bracket, l_bracket, gf, gf_reset) =
-            partition_double_elimination_matches(&self.matches, self.seeding.len());
-        match super::update(&w_bracket, match_id) {
-            Ok((w_bracket, l_bracket_elements)) => {
-                let l_bracket = match l_bracket_elements {
-                    Some((loser, expected_loser_seed, is_disqualified_from_winners)) => {
-                        update_loser_bracket_after_updating_winners_bracket(
-                            &l_bracket,
-                            loser,
-                            is_disqualified_from_winners,
-                            expected_loser_seed,
-                        )?
-                    }
-                    None => l_bracket,
-                };
-
-                let gf = match winner_of_bracket(&w_bracket) {
-                    Some(winner_of_winner_bracket) => {
-                        gf.insert_player(winner_of_winner_bracket, true)?
-                    }
-                    None => gf,
-                };
-                // when loser of winners finals is disqualified, grand finals can be updated
-                let gf = match winner_of_bracket(&l_bracket) {
-                    Some(winner_of_loser_bracket) => {
-                        let gf = gf.insert_player(winner_of_loser_bracket, false)?;
-
-                        if w_bracket.iter().any(|m| {
-                            m.is_automatic_loser_by_disqualification(winner_of_loser_bracket)
-                        }) {
-                            gf.set_automatic_loser(winner_of_loser_bracket)?
-                                .update_outcome()?
-                                .0
-                        } else {
-                            gf
-                        }
-                    }
-                    None => gf,
-                };
-                // when the winner of winner bracket is disqualified, then reset match should be validated also
-                let gf_reset = match (
-                    gf.get_automatic_loser(),
-                    winner_of_bracket(&w_bracket),
-                    gf.is_over(),
-                ) {
-                    (Opponent::Player(disqualified), Some(winner_of_winner_bracket), true)
-                        if disqualified == winner_of_winner_bracket =>
-                    {
-                        Match::new(gf.get_players(), [1, 2])?
-                            .set_automatic_loser(winner_of_winner_bracket)?
-                            .update_outcome()?
-                            .0
-                    }
-                    _ => gf_reset,
-                };
-
-                let matches = dem_partition(&w_bracket, &l_bracket, gf, gf_reset);
-                let bracket = Step::new(Some(matches.clone()), self.seeding.clone(), self.auto)?;
-                let new_matches = new_matches(&old_matches, &bracket.matches_to_play());
-                Ok((matches, new_matches))
-            }
-            Err(Error::UnknownMatch(_bad_winner_match)) => {
-                match super::update(&l_bracket, match_id) {
-                    Ok((l_bracket, _elements)) => {
-                        // send winner of loser bracket to grand finals if
-                        // possible
-                        let gf = match winner_of_bracket(&l_bracket) {
-                            Some(winner_of_loser_bracket) => {
-                                gf.set_player(winner_of_loser_bracket, false)
-                            }
-                            None => gf,
-                        };
-                        let matches = match (gf.get_players(), gf.get_automatic_loser()) {
-                            ([Opponent::Player(_), Opponent::Player(_)], Opponent::Player(_)) => {
-                                update_grand_finals_or_reset(
-                                    gf.get_id(),
-                                    w_bracket,
-                                    l_bracket,
-                                    gf,
-                                    gf_reset,
-                                )
-                                .expect("grand finals updated")
-                            }
-                            _ => dem_partition(&w_bracket, &l_bracket, gf, gf_reset),
-                        };
-                        let bracket = Step::new(Some(matches), self.seeding.clone(), self.auto)?;
-                        let new_matches = new_matches(&old_matches, &bracket.matches_to_play());
-                        Ok((bracket.matches, new_matches))
-                    }
-                    Err(Error::UnknownMatch(_bad_loser_match)) => {
-                        let matches = update_grand_finals_or_reset(
-                            match_id, w_bracket, l_bracket, gf, gf_reset,
-                        )?;
-                        let bracket = Step::new(Some(matches), self.seeding.clone(), self.auto)?;
-                        let new_m = new_matches(&old_matches, &bracket.matches_to_play());
-                        Ok((bracket.matches, new_m))
-                    }
-                    Err(e) => Err(e),
-                }
-            }
-            Err(e) => Err(e),
-        }
+    fn validate(&mut self, match_id: crate::matches::Id) -> Result<Vec<Match>, Error> {
+        let mark = self.matches.mark();
+        self.attempt(|p| p.validate_in_place(match_id))?;
+        Ok(self.matches.newly_playable(mark))
     }
 
     fn is_disqualified(&self, player_id: PlayerId) -> bool {
-        super::is_disqualified(player_id, &self.matches)
+        self.matches.is_disqualified(player_id)
+    }
+
+    fn reopen(&mut self, match_id: MatchId, cascade: bool) -> Result<Vec<Match>, Error> {
+        let (_, _, grand_finals, reset) = self.sections();
+        let grand_finals = reset.map(|reset| {
+            (
+                self.matches[grand_finals].get_id(),
+                self.matches[reset].get_id(),
+            )
+        });
+        super::reopen_elimination_match(self.matches.to_mut(), match_id, cascade, grand_finals)?;
+        Ok(self.matches_to_play())
     }
 
     fn check_all_assertions(&self) {
-        let (w_bracket, l_bracket, _gf, _gf_reset) =
-            partition_double_elimination_matches(&self.matches, self.seeding.len());
-        assert_disqualified_at_most_once(&w_bracket, &self.seeding);
-        assert_disqualified_at_most_once(&l_bracket, &self.seeding);
-        for m in &self.matches {
+        let (winners, losers, _, _) = self.sections();
+        assert_disqualified_at_most_once(&self.matches[winners], &self.seeding);
+        assert_disqualified_at_most_once(&self.matches[losers], &self.seeding);
+        for m in self.matches.iter() {
             assert_match_is_well_formed(m);
         }
     }
@@ -545,14 +639,14 @@ impl Progression for Step {
     fn matches_progress(&self) -> (usize, usize) {
         let (winner_bracket, loser_bracket, gf, gfr) =
             partition_double_elimination_matches(&self.matches, self.seeding.len());
-        let right = winner_bracket.len() + loser_bracket.len() + 2;
+        let right = winner_bracket.len() + loser_bracket.len() + 1 + usize::from(gfr.is_some());
         let mut left = 0;
         left += winner_bracket.iter().filter(|m| m.is_over()).count();
         left += loser_bracket.iter().filter(|m| m.is_over()).count();
         if gf.is_over() {
             left += 1;
         }
-        if gfr.is_over() {
+        if gfr.is_some_and(|m| m.is_over()) {
             left += 1;
         }
 
@@ -560,106 +654,140 @@ impl Progression for Step {
     }
 }
 
-/// Set player as disqualified. Used when there is no need for further updates
-fn disqualify_player(
-    p: &Step,
-    player_id: PlayerId,
-    old_matches_to_play: &[Match],
-) -> Result<(Vec<Match>, Vec<Match>), Error> {
-    // Look in late matches to disqualify player
-    let new_matches = get_new_matches(old_matches_to_play, &p.matches_to_play());
-    let match_to_set_dq = (*p
-        .matches
-        .iter()
-        .rev()
-        .find(|m| m.contains(player_id) && m.get_winner() == Opponent::Unknown)
-        .expect("match in losers to update"))
-    .set_automatic_loser(player_id)
-    .expect("disqualified player in loser");
-    let matches = update_bracket_with(&p.matches, &match_to_set_dq);
-    let p = Step::new(Some(matches), p.seeding.clone(), p.auto)?;
-    Ok((p.matches, new_matches))
-}
-
-/// Set player as disqualified and update bracket
-fn disqualify_player_and_update_bracket(
-    p: &Step,
-    player_id: PlayerId,
-    seeding: &[PlayerId],
-    auto: bool,
-    old_bracket: &[Match],
-) -> Result<(Vec<Match>, Vec<Match>), Error> {
-    let match_to_set_dq = (*p
-        .matches
-        .iter()
-        .find(|m| m.contains(player_id) && m.get_winner() == Opponent::Unknown)
-        .expect("match in losers to update"))
-    .set_automatic_loser(player_id)
-    .expect("updated match");
-    let bracket = update_bracket_with(&p.matches, &match_to_set_dq);
-    let p = Step::new(Some(bracket), seeding.to_vec(), auto)?;
-    let (bracket, _) = p.validate_match_result(match_to_set_dq.get_id())?;
-    let p = Step::new(Some(bracket), p.seeding, auto)?;
-    let new_matches = get_new_matches(old_bracket, &p.matches_to_play());
-    Ok((p.matches, new_matches))
-}
-
 /// when disqualifying a player and updating winner bracket, you can then
 /// update loser bracket.
 ///
 /// First you send disqualified player to loser, move him if he was not
 /// disqualified, then set him as automatic loser in his current loser bracket
-/// match.
+/// match. Loser bracket matches that cannot be updated yet are left as is
 fn update_loser_bracket_after_updating_winners_bracket(
-    l_bracket: &[Match],
+    matches: &mut Matches,
+    losers: &Range<usize>,
     loser: PlayerId,
     is_disqualified_from_winners: bool,
-    expected_loser_seed: usize,
-) -> Result<Vec<Match>, Error> {
-    let l_bracket = send_to_losers(l_bracket, loser, expected_loser_seed)?;
-    let l_match = l_bracket
+    from: MatchId,
+    loser_link: Option<Link>,
+) -> Result<(), Error> {
+    send_to_losers(matches, losers, loser, from, loser_link)?;
+    let l_match = matches
+        .matches_of(loser)
         .iter()
-        .find(|m| m.contains(loser))
+        .find(|i| losers.contains(i))
+        .map(|i| matches[*i].get_id())
         .expect("loser match");
-    if is_disqualified_from_winners {
-        let l_bracket = match super::update(&l_bracket, l_match.get_id()) {
-            Ok((matches, _)) => matches,
-            Err(_) => l_bracket.clone(),
-        };
-        let l_bracket = match l_bracket
-            .iter()
-            .find(|m| m.contains(loser) && m.get_winner() == Opponent::Unknown)
-        {
-            Some(match_to_set_dq) => {
-                let match_to_set_dq = (*match_to_set_dq)
-                    .set_automatic_loser(loser)
-                    .expect("match with disqualified player");
-                let l_bracket = update_bracket_with(&l_bracket, &match_to_set_dq);
-                match super::update(&l_bracket, l_match.get_id()) {
-                    Ok((matches, _)) => matches,
-                    Err(_) => l_bracket,
-                }
-            }
-            // loser finishes in GF
-            None => l_bracket,
-        };
-        Ok(l_bracket)
-    } else {
-        let l_bracket = match super::update(&l_bracket.clone(), l_match.get_id()) {
-            Ok((l_bracket_matches, _)) => l_bracket_matches,
-            Err(_) => l_bracket,
-        };
-        Ok(l_bracket)
+    let _ = update(matches, losers.clone(), l_match);
+    if !is_disqualified_from_winners {
+        return Ok(());
     }
+    let match_to_set_dq = matches
+        .matches_of(loser)
+        .iter()
+        .copied()
+        .find(|i| losers.contains(i) && matches[*i].get_winner() == Opponent::Unknown);
+    // otherwise loser finishes in GF
+    if let Some(i) = match_to_set_dq {
+        let m = matches[i]
+            .set_automatic_loser(loser)
+            .expect("match with disqualified player");
+        matches.set(i, m);
+        let _ = update(matches, losers.clone(), l_match);
+    }
+    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
     use crate::{
-        bracket::matches::{double_elimination_format::Step, Progression},
-        matches::{partition_double_elimination_matches, Id as MatchId},
+        bracket::matches::{double_elimination_format::Step, Error, Progression},
+        format::{Format, GrandFinals, LoserDrops},
+        matches::{partition_double_elimination_matches, Error as MatchError, Id as MatchId},
         player::{Id as PlayerId, Participants, Player},
     };
+    use std::borrow::Cow;
+
+    /// Returns 3 man bracket where grand finals are played as `grand_finals`
+    /// and the player coming from loser bracket reaches grand finals
+    fn grand_finals_of_3_man_bracket(grand_finals: GrandFinals) -> (Step<'static>, Vec<PlayerId>) {
+        let player_ids = (0..=3).map(|_| PlayerId::new_v4()).collect::<Vec<_>>();
+        let matches = Format::DoubleElimination {
+            loser_drops: LoserDrops::Standard,
+            grand_finals,
+        }
+        .generate_matches(&player_ids[1..])
+        .expect("matches");
+        let mut p = Step::new(Some(matches), player_ids[1..].to_vec(), true)
+            .expect("progression")
+            .with_grand_finals(grand_finals);
+        for (p1, result, p2) in [(2, (2, 0), 3), (1, (0, 2), 2), (1, (0, 2), 3)] {
+            let (matches, _, _) = p
+                .tournament_organiser_reports_result(player_ids[p1], result, player_ids[p2])
+                .expect("result");
+            p = Step {
+                matches: Cow::Owned(matches.into()),
+                ..p
+            };
+        }
+        (p, player_ids)
+    }
+
+    #[test]
+    fn grand_finals_can_be_played_once() {
+        let (p, player_ids) = grand_finals_of_3_man_bracket(GrandFinals::Reset);
+        assert_eq!(p.matches.len(), 5);
+        let (matches, _, _) = p
+            .tournament_organiser_reports_result(player_ids[2], (0, 2), player_ids[3])
+            .expect("result");
+        let p = Step {
+            matches: Cow::Owned(matches.into()),
+            ..p
+        };
+        assert!(!p.is_over(), "grand finals are reset");
+
+        let (p, player_ids) = grand_finals_of_3_man_bracket(GrandFinals::Single);
+        assert_eq!(p.matches.len(), 4);
+        let (_, _, gf, gf_reset) = partition_double_elimination_matches(&p.matches, 3);
+        assert!(gf_reset.is_none());
+        assert_eq!(p.matches_progress(), (3, 4));
+        let (matches, _, _) = p
+            .tournament_organiser_reports_result(player_ids[2], (0, 2), player_ids[3])
+            .expect("result");
+        let p = Step {
+            matches: Cow::Owned(matches.into()),
+            ..p
+        };
+        assert!(p.is_over());
+        assert_eq!(p.matches_progress(), (4, 4));
+        assert_eq!(
+            p.matches.last().expect("grand finals").get_id(),
+            gf.get_id()
+        );
+    }
+
+    #[test]
+    fn winner_side_starts_grand_finals_with_one_game_lead() {
+        let (p, player_ids) = grand_finals_of_3_man_bracket(GrandFinals::WinnerSideAdvantage);
+        assert_eq!(p.matches.len(), 4);
+        let gf = p.matches[3].get_id();
+
+        // result of player coming from winner bracket counts the game they
+        // start with, whoever reports it
+        assert!(matches!(
+            p.report_result(player_ids[2], (0, 2)),
+            Err(Error::MatchUpdate(MatchError::MissingAdvantage(id, _))) if id == gf
+        ));
+        assert!(matches!(
+            p.report_result(player_ids[3], (2, 0)),
+            Err(Error::MatchUpdate(MatchError::MissingAdvantage(id, _))) if id == gf
+        ));
+        let (matches, _, _) = p
+            .tournament_organiser_reports_result(player_ids[3], (2, 1), player_ids[2])
+            .expect("result");
+        let p = Step {
+            matches: Cow::Owned(matches.into()),
+            ..p
+        };
+        assert!(p.is_over());
+    }
 
     #[test]
     fn run_3_man_bracket() {
@@ -857,7 +985,7 @@ mod tests {
         result: (i8, i8),
         player2: PlayerId,
         automatic_progression: bool,
-    ) -> (Step, MatchId) {
+    ) -> (Step<'static>, MatchId) {
         let (matches, m_id, _new_matches) = p
             .tournament_organiser_reports_result(player1, result, player2)
             .expect("bracket");
@@ -868,7 +996,7 @@ mod tests {
         )
     }
 
-    fn validate(p: &Step, match_id: MatchId, automatic_progression: bool) -> Step {
+    fn validate(p: &Step, match_id: MatchId, automatic_progression: bool) -> Step<'static> {
         let (matches, _new_matches) = p.validate_match_result(match_id).expect("bracket");
         Step::new(Some(matches), p.seeding.clone(), automatic_progression).expect("progression")
     }
@@ -898,7 +1026,7 @@ mod tests {
         let p = validate(&p, winner_4vs5, auto);
         let (p, loser_4vs8) = report(&p, player_ids[4], (2, 0), player_ids[8], auto);
         let p = validate(&p, loser_4vs8, auto);
-        let p = Step::new(Some(p.matches), p.seeding, auto).expect("progression");
+        let p = Step::new(Some(p.matches.to_vec()), p.seeding, auto).expect("progression");
         let (matches, loser_2vs6, _new_matches) = p
             .tournament_organiser_reports_result(player_ids[2], (0, 2), player_ids[6])
             .expect("bracket");
@@ -0,0 +1,412 @@
+//! Matches of a bracket indexed by identifier, by player and by seed
+//!
+//! Brackets with thousands of participants have thousands of matches. Looking
+//! matches up through an index instead of scanning all of them keeps
+//! progression linear in the number of matches. The index is stored with the
+//! matches and follows them as they are updated in place, so it is built once
+//! per bracket rather than once per update.
+
+use crate::{
+    matches::{Id as MatchId, Match},
+    opponent::Opponent,
+    player::Id as PlayerId,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, HashSet},
+    ops::Deref,
+};
+
+/// Position of matches in a slice of matches
+#[derive(Clone, Debug, Default)]
+struct Index {
+    /// Position of match with identifier
+    by_id: HashMap<MatchId, usize>,
+    /// Positions of matches of player, in bracket order
+    by_player: HashMap<PlayerId, Vec<usize>>,
+    /// Position of the match where each seed enters the bracket. Seeds are
+    /// small integers so the table is a vector indexed by seed
+    slots: Vec<Option<usize>>,
+    /// Number of matches that are over
+    over: usize,
+}
+
+impl Index {
+    /// Index `matches`
+    fn new(matches: &[Match]) -> Self {
+        let mut index = Self {
+            by_id: HashMap::with_capacity(matches.len()),
+            by_player: HashMap::new(),
+            slots: first_match_of_seeds(matches, 0..matches.len()),
+            over: 0,
+        };
+        for (i, m) in matches.iter().enumerate() {
+            index.add(i, m);
+        }
+        index
+    }
+
+    /// Index match `m` at position `i`
+    fn add(&mut self, i: usize, m: &Match) {
+        self.by_id.insert(m.get_id(), i);
+        for player in players_of(m) {
+            let positions = self.by_player.entry(player).or_default();
+            if let Err(at) = positions.binary_search(&i) {
+                positions.insert(at, i);
+            }
+        }
+        self.over += usize::from(m.is_over());
+    }
+
+    /// Forget match `m` at position `i`
+    fn remove(&mut self, i: usize, m: &Match) {
+        if self.by_id.get(&m.get_id()) == Some(&i) {
+            self.by_id.remove(&m.get_id());
+        }
+        for player in players_of(m) {
+            if let Some(positions) = self.by_player.get_mut(&player) {
+                positions.retain(|p| *p != i);
+                if positions.is_empty() {
+                    self.by_player.remove(&player);
+                }
+            }
+        }
+        self.over -= usize::from(m.is_over());
+    }
+}
+
+/// Players of `m`
+pub(crate) fn players_of(m: &Match) -> impl Iterator<Item = PlayerId> {
+    m.get_players().into_iter().filter_map(|p| match p {
+        Opponent::Player(p) => Some(p),
+        Opponent::Unknown | Opponent::Bye => None,
+    })
+}
+
+/// Matches of a bracket along with their index. Matches are updated in place
+/// and every update is journaled, so callers can tell which matches changed
+/// and undo updates that could not go through
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(from = "Vec<Match>", into = "Vec<Match>")]
+pub struct Matches {
+    /// Matches in bracket order
+    ordered: Vec<Match>,
+    /// Where matches are
+    index: Index,
+    /// Position and previous value of every updated match, oldest first.
+    /// Appended matches have no previous value
+    journal: Vec<(usize, Option<Match>)>,
+}
+
+impl From<Vec<Match>> for Matches {
+    fn from(matches: Vec<Match>) -> Self {
+        Self {
+            index: Index::new(&matches),
+            ordered: matches,
+            journal: vec![],
+        }
+    }
+}
+
+impl FromIterator<Match> for Matches {
+    fn from_iter<I: IntoIterator<Item = Match>>(iter: I) -> Self {
+        iter.into_iter().collect::<Vec<_>>().into()
+    }
+}
+
+impl From<Matches> for Vec<Match> {
+    fn from(matches: Matches) -> Self {
+        matches.ordered
+    }
+}
+
+impl<'a> IntoIterator for &'a Matches {
+    type Item = &'a Match;
+    type IntoIter = std::slice::Iter<'a, Match>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.ordered.iter()
+    }
+}
+
+impl Deref for Matches {
+    type Target = [Match];
+
+    fn deref(&self) -> &Self::Target {
+        &self.ordered
+    }
+}
+
+impl Matches {
+    /// Returns position of `match_id`
+    pub(crate) fn position(&self, match_id: MatchId) -> Option<usize> {
+        self.index.by_id.get(&match_id).copied()
+    }
+
+    /// Returns match with `match_id`
+    pub(crate) fn find(&self, match_id: MatchId) -> Option<Match> {
+        self.position(match_id).map(|i| self.ordered[i])
+    }
+
+    /// Returns positions of matches of `player_id`, in bracket order
+    pub(crate) fn matches_of(&self, player_id: PlayerId) -> &[usize] {
+        self.index
+            .by_player
+            .get(&player_id)
+            .map_or(&[], Vec::as_slice)
+    }
+
+    /// Returns seed of `player_id` when they sit where their seed enters the
+    /// bracket. Players reach their other matches from there, so every
+    /// participant of an elimination bracket has one
+    pub(crate) fn seed_of(&self, player_id: PlayerId) -> Option<usize> {
+        let i = *self.matches_of(player_id).first()?;
+        let m = self.ordered[i];
+        let side = usize::from(m.get_players()[0] != Opponent::Player(player_id));
+        let seed = m.get_seeds()[side];
+        (self.index.slots.get(seed) == Some(&Some(i))).then_some(seed)
+    }
+
+    /// Number of matches that are over
+    pub(crate) fn over(&self) -> usize {
+        self.index.over
+    }
+
+    /// Returns true when `player_id` has been disqualified in any match
+    pub(crate) fn is_disqualified(&self, player_id: PlayerId) -> bool {
+        self.matches_of(player_id)
+            .iter()
+            .any(|i| self.ordered[*i].is_automatic_loser_by_disqualification(player_id))
+    }
+
+    /// Replace match at position `i` with `m`
+    pub(crate) fn set(&mut self, i: usize, m: Match) {
+        let previous = self.ordered[i];
+        if previous == m {
+            return;
+        }
+        self.replace(i, m);
+        self.journal.push((i, Some(previous)));
+    }
+
+    /// Append `m`
+    pub(crate) fn push(&mut self, m: Match) {
+        let i = self.ordered.len();
+        self.ordered.push(m);
+        self.index.add(i, &m);
+        self.index_seeds(i, &m);
+        self.journal.push((i, None));
+    }
+
+    /// Replace all matches with `matches`, journaling matches that differ
+    /// position by position. Matches dropped from the end are not journaled,
+    /// so replaced matches cannot be rolled back
+    pub(crate) fn replace_all(&mut self, matches: Vec<Match>) {
+        for (i, m) in matches.iter().enumerate() {
+            match self.ordered.get(i) {
+                Some(previous) if previous == m => {}
+                previous => self.journal.push((i, previous.copied())),
+            }
+        }
+        self.journal.retain(|(i, _)| *i < matches.len());
+        self.index = Index::new(&matches);
+        self.ordered = matches;
+    }
+
+    /// Current position in the journal, to roll back to or compare with
+    pub(crate) fn mark(&self) -> usize {
+        self.journal.len()
+    }
+
+    /// Undo updates journaled since `mark`
+    pub(crate) fn rollback(&mut self, mark: usize) {
+        while self.journal.len() > mark {
+            match self.journal.pop().expect("journaled update") {
+                (i, Some(previous)) => self.replace(i, previous),
+                (i, None) => {
+                    let m = self.ordered.pop().expect("appended match");
+                    self.index.remove(i, &m);
+                    self.index.slots = first_match_of_seeds(&self.ordered, 0..self.ordered.len());
+                }
+            }
+        }
+    }
+
+    /// Run `update` and undo what it did when it fails
+    ///
+    /// # Errors
+    /// thrown when `update` fails
+    pub(crate) fn attempt<T, E>(
+        &mut self,
+        update: impl FnOnce(&mut Self) -> Result<T, E>,
+    ) -> Result<T, E> {
+        let mark = self.mark();
+        let result = update(self);
+        if result.is_err() {
+            self.rollback(mark);
+        }
+        result
+    }
+
+    /// Returns position and value before `mark` of matches that differ since
+    /// then, in bracket order. Appended matches have no previous value
+    fn changes_since(&self, mark: usize) -> Vec<(usize, Option<Match>)> {
+        let mut originals = HashMap::new();
+        for (i, previous) in &self.journal[mark..] {
+            originals.entry(*i).or_insert(*previous);
+        }
+        let mut changes = originals
+            .into_iter()
+            .filter(|(i, previous)| *previous != Some(self.ordered[*i]))
+            .collect::<Vec<_>>();
+        changes.sort_unstable_by_key(|(i, _)| *i);
+        changes
+    }
+
+    /// Returns positions of matches updated since `mark`, whether or not they
+    /// still differ, in bracket order
+    pub(crate) fn touched_since(&self, mark: usize) -> Vec<usize> {
+        let mut touched = self.journal[mark..]
+            .iter()
+            .map(|(i, _)| *i)
+            .collect::<Vec<_>>();
+        touched.sort_unstable();
+        touched.dedup();
+        touched
+    }
+
+    /// Returns matches updated since `mark` that can now be played out while
+    /// they could not before
+    pub(crate) fn newly_playable(&self, mark: usize) -> Vec<Match> {
+        self.changes_since(mark)
+            .into_iter()
+            .filter(|(i, previous)| {
+                let m = self.ordered[*i];
+                m.needs_playing()
+                    && !previous.is_some_and(|p| p.needs_playing() && p.get_id() == m.get_id())
+            })
+            .map(|(i, _)| self.ordered[i])
+            .collect()
+    }
+
+    /// Forget journaled updates
+    pub(crate) fn clear_journal(&mut self) {
+        self.journal.clear();
+    }
+
+    /// Replace match at position `i` with `m` without journaling it
+    pub(crate) fn replace(&mut self, i: usize, m: Match) {
+        let previous = self.ordered[i];
+        self.index.remove(i, &previous);
+        self.ordered[i] = m;
+        self.index.add(i, &m);
+        if previous.get_seeds() != m.get_seeds() {
+            self.index.slots = first_match_of_seeds(&self.ordered, 0..self.ordered.len());
+        }
+    }
+
+    /// Add seeds of `m` appended at position `i` to the seed table
+    fn index_seeds(&mut self, i: usize, m: &Match) {
+        for seed in m.get_seeds() {
+            if self.index.slots.len() <= seed {
+                self.index.slots.resize(seed + 1, None);
+            }
+            self.index.slots[seed].get_or_insert(i);
+        }
+    }
+}
+
+/// Returns matches of `after` that are not in `before`
+pub(crate) fn added_matches(before: &[Match], after: &[Match]) -> Vec<Match> {
+    // matches keep their position when new matches are appended
+    if after.len() >= before.len()
+        && before
+            .iter()
+            .zip(after)
+            .all(|(b, a)| b.get_id() == a.get_id())
+    {
+        return after[before.len()..].to_vec();
+    }
+    let before = before.iter().map(Match::get_id).collect::<HashSet<_>>();
+    after
+        .iter()
+        .filter(|m| !before.contains(&m.get_id()))
+        .copied()
+        .collect()
+}
+
+/// Returns for every seed the position of the first of `positions` whose
+/// match contains that seed. Seeds are small integers so the table is a
+/// vector indexed by seed
+pub(crate) fn first_match_of_seeds(
+    matches: &[Match],
+    positions: impl Iterator<Item = usize>,
+) -> Vec<Option<usize>> {
+    let mut table = vec![];
+    for i in positions {
+        for seed in matches[i].get_seeds() {
+            if table.len() <= seed {
+                table.resize(seed + 1, None);
+            }
+            table[seed].get_or_insert(i);
+        }
+    }
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{added_matches, first_match_of_seeds, Matches};
+    use crate::{format::Format, opponent::Opponent, player::Id as PlayerId};
+
+    #[test]
+    fn matches_are_found_by_id_and_player() {
+        let seeding = (0..5).map(|_| PlayerId::new_v4()).collect::<Vec<_>>();
+        let matches = Format::default()
+            .generate_matches(&seeding)
+            .expect("matches");
+        let index = Matches::from(matches.clone());
+
+        for (i, m) in matches.iter().enumerate() {
+            assert_eq!(index.position(m.get_id()), Some(i));
+        }
+        // top seed waits for the winner of 4-5
+        assert_eq!(index.matches_of(seeding[0]).len(), 1);
+        let m = matches[index.matches_of(seeding[0])[0]];
+        assert_eq!(m.get_players()[0], Opponent::Player(seeding[0]));
+        assert!(index.matches_of(PlayerId::new_v4()).is_empty());
+        for (i, player) in seeding.iter().enumerate() {
+            assert_eq!(index.seed_of(*player), Some(i + 1));
+        }
+        assert_eq!(index.seed_of(PlayerId::new_v4()), None);
+    }
+
+    #[test]
+    fn only_matches_absent_before_are_added() {
+        let seeding = (0..4).map(|_| PlayerId::new_v4()).collect::<Vec<_>>();
+        let before = Format::default()
+            .generate_matches(&seeding)
+            .expect("matches");
+        let after = Format::default()
+            .generate_matches(&seeding)
+            .expect("matches");
+        let after = [&before[..2], &after[..1]].concat();
+
+        assert_eq!(added_matches(&before, &after), vec![after[2]]);
+    }
+
+    #[test]
+    fn seed_table_points_to_first_match_of_seed() {
+        let seeding = (0..4).map(|_| PlayerId::new_v4()).collect::<Vec<_>>();
+        let matches = Format::default()
+            .generate_matches(&seeding)
+            .expect("matches");
+
+        // 1-4, 2-3 then 1-2 in winner bracket
+        let table = first_match_of_seeds(&matches, 0..3);
+
+        assert_eq!(table, vec![None, Some(0), Some(1), Some(1), Some(0)]);
+        let table = first_match_of_seeds(&matches, (0..3).rev());
+        assert_eq!(table, vec![None, Some(2), Some(2), Some(1), Some(0)]);
+    }
+}
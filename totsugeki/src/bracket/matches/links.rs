@@ -0,0 +1,243 @@
+//! Link every match of elimination brackets to the matches its winner and
+//! loser play next
+//!
+//! Links are built once when matches are generated so progression never has
+//! to search where players go.
+
+use crate::{
+    bracket::matches::{index::first_match_of_seeds, single_elimination_format::playoffs::trees},
+    format::Format,
+    matches::{partition_double_elimination_matches, Link, Links, Match},
+};
+
+/// Returns `matches` of a bracket with `n` participants with links to where
+/// players are sent next
+///
+/// In every elimination track, the winner goes to the next match containing
+/// the top seed of their match and the loser to the match of their track
+/// containing the bottom seed. Winners of winner and loser bracket finals
+/// meet in grand finals. Grand finals reset is played only when the player
+/// coming from losers wins grand finals, so grand finals has no link.
+#[must_use]
+pub(crate) fn link(format: Format, n: usize, matches: Vec<Match>) -> Vec<Match> {
+    let mut links = vec![Links::default(); matches.len()];
+    match format {
+        Format::SingleElimination { playoffs } => {
+            let trees = trees(n, playoffs);
+            for tree in &trees {
+                link_winners(&matches, &mut links, &tree.range().collect::<Vec<_>>());
+                for round in 0..tree.number_of_rounds() {
+                    let Some(playoff) = tree.playoff_of(round) else {
+                        continue;
+                    };
+                    let playoff = first_match_of_seeds(&matches, trees[playoff].range());
+                    for from in tree.round_range(round) {
+                        let seed = matches[from].get_seeds()[1];
+                        links[from].loser = playoff
+                            .get(seed)
+                            .copied()
+                            .flatten()
+                            .map(|to| link_to(&matches[to], seed));
+                    }
+                }
+            }
+        }
+        Format::DoubleElimination { .. } if !matches.is_empty() => {
+            let (wb, lb, _, _) = partition_double_elimination_matches(&matches, n);
+            let wb = 0..wb.len();
+            let lb = wb.end..wb.end + lb.len();
+            let gf = lb.end;
+            link_winners(&matches, &mut links, &wb.clone().collect::<Vec<_>>());
+            link_winners(&matches, &mut links, &lb.clone().collect::<Vec<_>>());
+            let first_loser_match = first_match_of_seeds(&matches, lb.clone());
+            for from in wb.clone() {
+                let seed = matches[from].get_seeds()[1];
+                links[from].loser = first_loser_match
+                    .get(seed)
+                    .copied()
+                    .flatten()
+                    .map(|to| link_to(&matches[to], seed));
+            }
+            for finals in [wb.last(), lb.last()].into_iter().flatten() {
+                let seed = matches[finals].get_seeds()[0];
+                links[finals].winner = Some(link_to(&matches[gf], seed));
+            }
+        }
+        Format::DoubleElimination { .. } | Format::RoundRobin | Format::Swiss { .. } => {}
+    }
+    matches
+        .into_iter()
+        .zip(links)
+        .map(|(m, links)| m.with_links(links))
+        .collect()
+}
+
+/// Returns true when `matches` of an elimination bracket have no links at
+/// all, like brackets saved before matches were linked. They are linked from
+/// their seeds when they progress
+pub(crate) fn is_unlinked(format: Format, matches: &[Match]) -> bool {
+    matches!(
+        format,
+        Format::SingleElimination { .. } | Format::DoubleElimination { .. }
+    ) && matches.iter().all(|m| m.get_links() == Links::default())
+}
+
+/// Link winners of `track` matches to the next match containing the top seed
+/// of their match
+fn link_winners(matches: &[Match], links: &mut [Links], track: &[usize]) {
+    // next match of every seed, filled from the end of the track
+    let mut next_match_of_seed: Vec<Option<usize>> = vec![];
+    for from in track.iter().rev() {
+        let seeds = matches[*from].get_seeds();
+        links[*from].winner = next_match_of_seed
+            .get(seeds[0])
+            .copied()
+            .flatten()
+            .map(|to| link_to(&matches[to], seeds[0]));
+        for seed in seeds {
+            if next_match_of_seed.len() <= seed {
+                next_match_of_seed.resize(seed + 1, None);
+            }
+            next_match_of_seed[seed] = Some(*from);
+        }
+    }
+}
+
+/// Link to the slot of `seed` in `to`
+fn link_to(to: &Match, seed: usize) -> Link {
+    Link {
+        match_id: to.get_id(),
+        is_player_1: to.get_seeds()[0] == seed,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        bracket::{Bracket, Error},
+        format::{Format, GrandFinals, LoserDrops, Playoffs},
+        matches::partition_double_elimination_matches,
+        player::Id as PlayerId,
+        seeding::Method as SeedingMethod,
+    };
+    use chrono::Utc;
+
+    fn seeding(n: usize) -> Vec<PlayerId> {
+        (0..n).map(|_| PlayerId::new_v4()).collect()
+    }
+
+    #[test]
+    fn every_double_elimination_match_but_grand_finals_sends_its_winner() {
+        let matches = Format::DoubleElimination {
+            loser_drops: LoserDrops::Standard,
+            grand_finals: GrandFinals::Reset,
+        }
+        .generate_matches(&seeding(8))
+        .expect("matches");
+        let (wb, lb, gf, gf_reset) = partition_double_elimination_matches(&matches, 8);
+
+        for m in wb.iter().chain(lb.iter()) {
+            assert!(m.get_links().winner.is_some(), "{m}");
+        }
+        assert!(wb.iter().all(|m| m.get_links().loser.is_some()));
+        assert!(lb.iter().all(|m| m.get_links().loser.is_none()));
+        let winner_finals = wb.last().expect("winner finals").get_links().winner;
+        let loser_finals = lb.last().expect("loser finals").get_links().winner;
+        assert_eq!(
+            winner_finals.map(|l| (l.match_id, l.is_player_1)),
+            Some((gf.get_id(), true))
+        );
+        assert_eq!(
+            loser_finals.map(|l| (l.match_id, l.is_player_1)),
+            Some((gf.get_id(), false))
+        );
+        assert_eq!(gf.get_links(), gf_reset.expect("reset").get_links());
+        assert!(gf.get_links().winner.is_none());
+    }
+
+    #[test]
+    fn semi_finals_losers_go_to_third_place_match() {
+        let matches = Format::SingleElimination {
+            playoffs: Playoffs::ThirdPlaceMatch,
+        }
+        .generate_matches(&seeding(4))
+        .expect("matches");
+
+        let third_place_match = matches[3].get_id();
+        let semi_finals_losers = matches[..2]
+            .iter()
+            .map(|m| m.get_links().loser.map(|l| (l.match_id, l.is_player_1)))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            semi_finals_losers,
+            vec![
+                Some((third_place_match, false)),
+                Some((third_place_match, true))
+            ]
+        );
+        assert_eq!(
+            matches[0].get_links().winner.map(|l| l.match_id),
+            Some(matches[2].get_id())
+        );
+        assert!(matches[2].get_links().winner.is_none());
+    }
+
+    fn started_double_elimination_bracket(n: usize) -> (Bracket, Vec<PlayerId>) {
+        let mut bracket = Bracket::new(
+            "bracket",
+            Format::default(),
+            SeedingMethod::Strict,
+            Utc::now(),
+            true,
+        );
+        for i in 1..=n {
+            bracket = bracket
+                .add_participant(format!("p{i}").as_str())
+                .expect("participant");
+        }
+        let seeding = bracket.get_participants().get_seeding();
+        (bracket.start().expect("start").0, seeding)
+    }
+
+    /// Returns `bracket` saved then loaded again without the links of matches
+    /// selected by `strip`
+    fn without_links(bracket: &Bracket, strip: impl Fn(usize) -> bool) -> Bracket {
+        let mut json = serde_json::to_value(bracket).expect("serialized");
+        let matches = json["matches"].as_array_mut().expect("matches");
+        for (i, m) in matches.iter_mut().enumerate() {
+            if strip(i) {
+                m.as_object_mut().expect("match").remove("links");
+            }
+        }
+        serde_json::from_value(json).expect("deserialized")
+    }
+
+    #[test]
+    fn brackets_saved_without_links_are_linked_again() {
+        let (bracket, seeding) = started_double_elimination_bracket(4);
+        let bracket = without_links(&bracket, |_| true);
+
+        let (bracket, _, _) = bracket
+            .tournament_organiser_reports_result(seeding[0], (2, 0), seeding[3])
+            .expect("winner sent without links");
+        let (bracket, _, _) = bracket
+            .tournament_organiser_reports_result(seeding[1], (2, 0), seeding[2])
+            .expect("reported");
+
+        let loser_bracket = bracket.get_matches()[3];
+        assert!(loser_bracket.contains(seeding[3]));
+        assert!(loser_bracket.contains(seeding[2]));
+    }
+
+    #[test]
+    fn match_without_link_cannot_send_its_players() {
+        let (bracket, seeding) = started_double_elimination_bracket(4);
+        let first_match = bracket.get_matches()[0].get_id();
+        let bracket = without_links(&bracket, |i| i == 0);
+
+        assert!(matches!(
+            bracket.tournament_organiser_reports_result(seeding[0], (2, 0), seeding[3]),
+            Err(Error::MissingLink(_, match_id)) if match_id == first_match
+        ));
+    }
+}
@@ -2,18 +2,24 @@
 
 use crate::{
     format::Format,
-    matches::{Error as MatchError, Id as MatchId, Match},
+    matches::{Error as MatchError, Id as MatchId, Link, Match},
     opponent::Opponent,
     player::Id as PlayerId,
     seeding::Error as SeedingError,
 };
+use index::Matches;
+use std::{collections::HashSet, ops::Range};
 use thiserror::Error;
 
 #[cfg(test)]
 use crate::player::Player;
 
 pub mod double_elimination_format;
+pub mod index;
+pub(crate) mod links;
+pub mod round_robin_format;
 pub mod single_elimination_format;
+pub mod swiss_format;
 
 /// Error while managing matches of bracket
 #[derive(Error, Debug)]
@@ -57,148 +63,201 @@ pub enum Error {
     /// Fordidden action because player has been disqualified
     #[error("{0} is disqualified")]
     ForbiddenDisqualified(PlayerId),
+    /// Match cannot be reopened without reopening a match that follows it
+    #[error("Match {0} cannot be reopened because match {1} depends on its result. Reopen match {1} first or cascade")]
+    DownstreamMatchHasResult(MatchId, MatchId),
+    /// Match where a player has a bye cannot be reopened
+    #[error("Match {0} is a bye and cannot be reopened")]
+    ByeCannotBeReopened(MatchId),
+    /// Match does not say where its players go next
+    #[error("Match {0} is not linked to the match where its players go next")]
+    MissingLink(MatchId),
+    /// Number of matches does not fit format and participants
+    #[error("Found {0} matches where {1} are expected for this format and participants")]
+    UnexpectedMatchCount(usize, usize),
 }
 
-/// Returns true if bracket is over
-fn bracket_is_over(bracket_matches: &[Match]) -> bool {
-    !bracket_matches.iter().any(|m| !m.is_over())
-}
+/// Where the loser of an updated match goes: the loser, where they are sent
+/// next (if anywhere) and whether or not they are disqualified
+type Loser = (PlayerId, Option<Link>, bool);
 
-/// Returns true when `player_id` has been disqualified by looking into all
-/// `matches` in the bracket
-pub(crate) fn is_disqualified(player_id: PlayerId, matches: &[Match]) -> bool {
-    matches
-        .iter()
-        .any(|m| m.is_automatic_loser_by_disqualification(player_id))
-}
+/// Validate `match_id` among matches of `section` and move its winner forward
+/// inside `section`. Returns the loser of `match_id`, where they are sent next
+/// and whether or not this player is disqualified. Matches are left untouched
+/// when the update fails
+///
+/// Assuming bracket is always updated after any player is disqualified, the
+/// only matches that can be resolved because of a disqualification are the
+/// ones the winner is sent to
+///
+/// # Errors
+/// thrown when `match_id` is not in `section` or cannot be updated
+fn update(matches: &mut Matches, section: Range<usize>, match_id: MatchId) -> Result<Loser, Error> {
+    let Some(index) = matches.position(match_id).filter(|i| section.contains(i)) else {
+        return Err(Error::UnknownMatch(match_id));
+    };
+    matches.attempt(|matches| {
+        let m = matches[index];
+        // declare winner if there is one
+        let is_disqualified = m.get_automatic_loser() != Opponent::Unknown;
+        let (updated_m, winner, loser) = m.update_outcome()?;
+        let loser = (loser, updated_m.get_links().loser, is_disqualified);
+        matches.set(index, updated_m);
+        if index + 1 == section.end {
+            return Ok(loser);
+        }
 
-/// Update `bracket` with `updated_match` using a readable one-liner
-pub(crate) fn update_bracket_with(bracket: &[Match], updated_match: &Match) -> Vec<Match> {
-    bracket
-        .iter()
-        .copied()
-        .map(|m| {
-            if m.get_id() == updated_match.get_id() {
-                *updated_match
-            } else {
-                m
+        // winner moves forward in bracket. They may be moved up several times
+        // because each next match contains a disqualified player
+        let mut next = send_winner(matches, &updated_m, winner)?;
+        while matches[next].needs_update_because_of_disqualified_participant() {
+            let (updated_match, winner, _) = matches[next].update_outcome()?;
+            matches.set(next, updated_match);
+            if next + 1 == section.end {
+                break;
             }
-        })
-        .collect()
+            next = send_winner(matches, &updated_match, winner)?;
+        }
+        Ok(loser)
+    })
 }
 
-/// Updated matches for bracket
+/// Send `winner` of `m` to the match it is linked to. Returns the position of
+/// that match
 ///
-/// if applicable, the loser of updated match, the expected loser seed that
-/// should be used when sending them in lower bracket and a boolean to indicate
-/// if the they are disqualified
-type BracketUpdate = (Vec<Match>, Option<(PlayerId, usize, bool)>);
-
-/// Takes matches in bracket, validate `match_id` and returns updated winner
-/// bracket, id of loser (if there is one), the seed to use when placing them
-/// in loser's bracket (if there is one) and whether or not this player is
-/// disqualified
+/// # Errors
+/// thrown when winner cannot be placed in their next match
+fn send_winner(matches: &mut Matches, m: &Match, winner: PlayerId) -> Result<usize, Error> {
+    let Some(link) = m.get_links().winner else {
+        return Err(Error::MissingLink(m.get_id()));
+    };
+    let Some(next) = matches.position(link.match_id) else {
+        return Err(Error::UnknownMatch(link.match_id));
+    };
+    let next_match = matches[next].insert_player(winner, link.is_player_1)?;
+    matches.set(next, next_match);
+    Ok(next)
+}
+
+/// Reopen `match_id` in an elimination bracket: both players are removed from
+/// the next match they were sent to. If that next match has a result, it is
+/// reopened first when `cascade` is set. Players follow the links of their
+/// match, except players of grand finals who all move to its reset, paired
+/// with grand finals in `grand_finals`
 ///
-/// Assuming bracket is always updated after any player is disqualified, there
-/// is at most one disqualified player that can drop from winners into loser
+/// A following match resolved by a disqualification is reopened like any
+/// other: only its winner is pulled out and the disqualified player waits for
+/// a new opponent
 ///
 /// # Errors
-/// thrown when attempting an update for winner/loser bracket match in
-/// loser/winner bracket
-fn update(bracket: &[Match], match_id: MatchId) -> Result<BracketUpdate, Error> {
-    let Some(m) = bracket.iter().find(|m| m.get_id() == match_id) else {
+/// thrown when `match_id` is unknown, involves a disqualified player, a
+/// following match has a result and `cascade` is not set or a player pulled
+/// out of a following match is disqualified in it
+pub(crate) fn reopen_elimination_match(
+    matches: &mut Matches,
+    match_id: MatchId,
+    cascade: bool,
+    grand_finals: Option<(MatchId, MatchId)>,
+) -> Result<(), Error> {
+    let Some(m) = matches.find(match_id) else {
         return Err(Error::UnknownMatch(match_id));
     };
-    // declare winner if there is one
-    let is_disqualified = m.get_automatic_loser() != Opponent::Unknown;
-    let (updated_m, winner, loser) = (*m).update_outcome()?;
-    let seed_of_expected_winner = updated_m.get_seeds()[0];
-    let expected_loser_seed = updated_m.get_seeds()[1];
-    let bracket = update_bracket_with(bracket, &updated_m);
-
-    let last_match = bracket.last().expect("last match in bracket");
-    match (last_match.get_id(), last_match.get_winner()) {
-        (id, Opponent::Player(_)) if id == match_id => {
-            return Ok((bracket, Some((loser, expected_loser_seed, is_disqualified))))
-        }
-        (id, Opponent::Unknown) if id == match_id => {
-            panic!("No winner of bracket declared when updating bracket finalists match")
-        }
-        _ => {}
+    if let Opponent::Player(disqualified) = m.get_automatic_loser() {
+        return Err(Error::ForbiddenDisqualified(disqualified));
     }
+    matches.attempt(|matches| reopen_linked_matches(matches, match_id, cascade, grand_finals))
+}
 
-    // winner moves forward in bracket
-    let index = bracket
-        .iter()
-        .position(|m| m.get_id() == updated_m.get_id())
-        .expect("reference to updated match");
-    let mut iter = bracket.iter().skip(index + 1);
-    let m = iter
-        .find(|m| m.get_seeds().contains(&seed_of_expected_winner))
-        .expect("match where winner of updated match plays next");
-    let updated_match = (*m).insert_player(winner, m.get_seeds()[0] == seed_of_expected_winner)?;
-    let mut bracket = update_bracket_with(&bracket, &updated_match);
-
-    // looser drops to loser bracket in double elimination format
-
-    // Set winner to all matches were a player is disqualified
-    // while loop is needed because there can be a scenario where a player
-    // is moved up several times because each next match contains a
-    // disqualified player
-    while bracket
-        .iter()
-        .any(Match::needs_update_because_of_disqualified_participant)
-    {
-        let to_update = bracket
-            .iter()
-            .find(|m| m.needs_update_because_of_disqualified_participant())
-            .expect("match with disqualified player");
-        let match_id = to_update.get_id();
-        let m = bracket
-            .iter()
-            .find(|m| m.get_id() == match_id)
-            .expect("match in bracket");
-        let (updated_match, _, _) = (*m).update_outcome()?;
-        bracket = update_bracket_with(&bracket, &updated_match);
-
-        if bracket.last().expect("last match").get_id() == match_id {
-            return Ok((bracket, Some((loser, expected_loser_seed, is_disqualified))));
+/// Reopen `match_id` and pull its players out of the matches they were sent
+/// to. Disqualified player of `match_id` stays where they are
+///
+/// # Errors
+/// thrown when a following match has a result and `cascade` is not set or a
+/// player pulled out of a following match is disqualified in it
+fn reopen_linked_matches(
+    matches: &mut Matches,
+    match_id: MatchId,
+    cascade: bool,
+    grand_finals: Option<(MatchId, MatchId)>,
+) -> Result<(), Error> {
+    let Some(index) = matches.position(match_id) else {
+        return Err(Error::UnknownMatch(match_id));
+    };
+    let links = matches[index].get_links();
+    let next_matches = match grand_finals {
+        Some((grand_finals, reset)) if grand_finals == match_id => vec![reset],
+        _ => [links.winner, links.loser]
+            .into_iter()
+            .flatten()
+            .map(|link| link.match_id)
+            .collect(),
+    };
+    let disqualified = matches[index].get_automatic_loser();
+    for player in matches[index].get_players() {
+        let Opponent::Player(player) = player else {
+            continue;
+        };
+        if Opponent::Player(player) == disqualified {
+            continue;
         }
-
-        // winner moves forward in bracket
-        let index = bracket
+        let Some(next) = next_matches
             .iter()
-            .position(|m| m.get_id() == updated_match.get_id())
-            .expect("reference to updated match");
-        let mut iter = bracket.iter().skip(index + 1);
-        let seed_of_expected_winner = updated_match.get_seeds()[0];
-        let Opponent::Player(winner) = updated_match.get_winner() else {
-            panic!("no winner in updated match");
+            .filter_map(|id| matches.position(*id))
+            .filter(|i| matches[*i].contains(player))
+            .min()
+        else {
+            continue;
         };
-        let m = iter
-            .find(|m| m.get_seeds().contains(&seed_of_expected_winner))
-            .expect("match where winner of updated match plays next");
-        let updated_match =
-            (*m).insert_player(winner, m.get_seeds()[0] == seed_of_expected_winner)?;
-        bracket = update_bracket_with(&bracket, &updated_match);
+        if matches[next].is_automatic_loser_by_disqualification(player) {
+            return Err(Error::ForbiddenDisqualified(player));
+        }
+        if matches[next].has_result() {
+            if !cascade {
+                return Err(Error::DownstreamMatchHasResult(
+                    match_id,
+                    matches[next].get_id(),
+                ));
+            }
+            let next_id = matches[next].get_id();
+            reopen_linked_matches(matches, next_id, cascade, grand_finals)?;
+        }
+        matches.set(next, matches[next].remove_player(player));
     }
-
-    Ok((bracket, Some((loser, expected_loser_seed, is_disqualified))))
+    matches.set(index, matches[index].reopen());
+    Ok(())
 }
 
 /// Assert any players set as disqualified at most once
 pub(crate) fn assert_disqualified_at_most_once(matches: &[Match], seeding: &[PlayerId]) {
-    for player in seeding {
-        assert!(
-            matches
-                .iter()
-                .filter(
-                    |m| matches!(m.get_automatic_loser(), Opponent::Player(id) if id == *player)
-                )
-                .count()
-                < 2
-        );
+    assert!(disqualified_more_than_once(matches, seeding).is_empty());
+}
+
+/// Returns players of `seeding` disqualified in more than one of `matches`
+pub(crate) fn disqualified_more_than_once(
+    matches: &[Match],
+    seeding: &[PlayerId],
+) -> Vec<PlayerId> {
+    let mut disqualifications = matches
+        .iter()
+        .filter_map(|m| match m.get_automatic_loser() {
+            Opponent::Player(id) => Some(id),
+            Opponent::Unknown | Opponent::Bye => None,
+        })
+        .collect::<Vec<_>>();
+    disqualifications.sort_unstable();
+    let twice = disqualifications
+        .windows(2)
+        .filter(|w| w[0] == w[1])
+        .map(|w| w[0])
+        .collect::<HashSet<_>>();
+    if twice.is_empty() {
+        return vec![];
     }
+    seeding
+        .iter()
+        .filter(|player| twice.contains(player))
+        .copied()
+        .collect()
 }
 
 /// Assert if both opponent are not the same player
@@ -210,10 +269,26 @@ pub(crate) fn assert_match_is_well_formed(m: &Match) {
 }
 
 /// Computes the next state of a tournament
+///
+/// Matches are updated in place. The methods returning updated matches work
+/// on a copy and leave this progression untouched
 pub trait Progression {
     /// Get format of this bracket
     fn get_format(&self) -> Format;
 
+    /// Copy of this progression to update
+    fn boxed(&self) -> Box<dyn Progression + '_>;
+
+    /// Matches of this bracket
+    fn into_matches(self: Box<Self>) -> Matches;
+
+    /// Disqualify participant from bracket and update matches in place.
+    /// Returns new matches to play. Matches are left untouched on error
+    ///
+    /// # Errors
+    /// thrown when participant does not belong in tournament
+    fn disqualify(&mut self, player_id: PlayerId) -> Result<Vec<Match>, Error>;
+
     /// Disqualify participant from bracket and update matches. Returns updated
     /// matches and matches to play
     ///
@@ -222,7 +297,11 @@ pub trait Progression {
     fn disqualify_participant(
         &self,
         player_id: PlayerId,
-    ) -> Result<(Vec<Match>, Vec<Match>), Error>;
+    ) -> Result<(Vec<Match>, Vec<Match>), Error> {
+        let mut p = self.boxed();
+        let new_matches = p.disqualify(player_id)?;
+        Ok((p.into_matches().into(), new_matches))
+    }
 
     /// Returns true if bracket is over (all matches are played)
     #[must_use]
@@ -245,6 +324,17 @@ pub trait Progression {
     /// Returns true if player is disqualified
     fn is_disqualified(&self, player_id: PlayerId) -> bool;
 
+    /// Report result of match in place. Returns affected match and new
+    /// matches to play. Matches are left untouched on error
+    ///
+    /// # Errors
+    /// thrown when player does not belong in bracket
+    fn report(
+        &mut self,
+        player_id: PlayerId,
+        result: (i8, i8),
+    ) -> Result<(MatchId, Vec<Match>), Error>;
+
     /// Report result of match. Returns updated matches, affected match and new
     /// matches to play
     /// # Errors
@@ -253,7 +343,23 @@ pub trait Progression {
         &self,
         player_id: PlayerId,
         result: (i8, i8),
-    ) -> Result<(Vec<Match>, MatchId, Vec<Match>), Error>;
+    ) -> Result<(Vec<Match>, MatchId, Vec<Match>), Error> {
+        let mut p = self.boxed();
+        let (affected_match_id, new_matches) = p.report(player_id, result)?;
+        Ok((p.into_matches().into(), affected_match_id, new_matches))
+    }
+
+    /// Tournament organiser reports result in place. Returns affected match
+    /// and new matches to play. Matches are left untouched on error
+    ///
+    /// # Errors
+    /// thrown when player does not belong in bracket
+    fn organiser_reports(
+        &mut self,
+        player1: PlayerId,
+        result: (i8, i8),
+        player2: PlayerId,
+    ) -> Result<(MatchId, Vec<Match>), Error>;
 
     /// Tournament organiser reports result
     /// # Errors
@@ -263,7 +369,22 @@ pub trait Progression {
         player1: PlayerId,
         result: (i8, i8),
         player2: PlayerId,
-    ) -> Result<(Vec<Match>, MatchId, Vec<Match>), Error>;
+    ) -> Result<(Vec<Match>, MatchId, Vec<Match>), Error> {
+        let mut p = self.boxed();
+        let (affected_match_id, new_matches) = p.organiser_reports(player1, result, player2)?;
+        Ok((p.into_matches().into(), affected_match_id, new_matches))
+    }
+
+    /// Record `result` reported by `player_id` for `match_id` in place
+    ///
+    /// # Errors
+    /// thrown when `match_id` matches no existing match
+    fn record_reported_result(
+        &mut self,
+        match_id: MatchId,
+        result: (i8, i8),
+        player_id: PlayerId,
+    ) -> Result<(), Error>;
 
     /// Update `match_id` with reported `result` of `player`
     ///
@@ -274,14 +395,55 @@ pub trait Progression {
         match_id: MatchId,
         result: (i8, i8),
         player_id: PlayerId,
-    ) -> Result<Vec<Match>, Error>;
+    ) -> Result<Vec<Match>, Error> {
+        let mut p = self.boxed();
+        p.record_reported_result(match_id, result, player_id)?;
+        Ok(p.into_matches().into())
+    }
+
+    /// Validate `match_id` in place and returns matches to play. Matches are
+    /// left untouched on error
+    ///
+    /// # Errors
+    /// thrown when `match_id` matches no existing match
+    fn validate(&mut self, match_id: MatchId) -> Result<Vec<Match>, Error>;
 
     /// Returns updated matches and matches to play. Uses `match_id` as the
     /// first match to start updating before looking deeper into the bracket
     ///
     /// # Errors
     /// thrown when `match_id` matches no existing match
-    fn validate_match_result(&self, match_id: MatchId) -> Result<(Vec<Match>, Vec<Match>), Error>;
+    fn validate_match_result(&self, match_id: MatchId) -> Result<(Vec<Match>, Vec<Match>), Error> {
+        let mut p = self.boxed();
+        let new_matches = p.validate(match_id)?;
+        Ok((p.into_matches().into(), new_matches))
+    }
+
+    /// Reopen `match_id` in place like `reopen_match`. Returns matches to
+    /// play. Matches are left untouched on error
+    ///
+    /// # Errors
+    /// thrown when `match_id` matches no existing match or a following match
+    /// has a result and `cascade` is not set
+    fn reopen(&mut self, match_id: MatchId, cascade: bool) -> Result<Vec<Match>, Error>;
+
+    /// Remove result of `match_id` and pull its players out of the matches
+    /// that followed from it. Matches that followed from it and already have
+    /// a result are reopened too when `cascade` is set. Returns updated
+    /// matches and matches to play
+    ///
+    /// # Errors
+    /// thrown when `match_id` matches no existing match or a following match
+    /// has a result and `cascade` is not set
+    fn reopen_match(
+        &self,
+        match_id: MatchId,
+        cascade: bool,
+    ) -> Result<(Vec<Match>, Vec<Match>), Error> {
+        let mut p = self.boxed();
+        let matches_to_play = p.reopen(match_id, cascade)?;
+        Ok((p.into_matches().into(), matches_to_play))
+    }
 
     /// Checks all assertions after updating matches
     fn check_all_assertions(&self);
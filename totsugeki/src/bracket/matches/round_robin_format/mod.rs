@@ -0,0 +1,551 @@
+//! Manage matches of round robin pool
+
+use super::{assert_match_is_well_formed, index::Matches, Error, Progression};
+use crate::{
+    format::Format,
+    matches::{Error as MatchError, Id as MatchId, Match, ReportedResult},
+    opponent::Opponent,
+    player::Id as PlayerId,
+    seeding::round_robin_seeded_bracket::get_round_robin_matches,
+};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+
+/// Computes the next step of a round robin pool
+#[derive(Clone, Debug)]
+pub(crate) struct Step<'a> {
+    /// Seeding for this pool
+    seeding: Vec<PlayerId>,
+    /// All matches of round robin pool, sorted by rounds
+    matches: Cow<'a, Matches>,
+    /// True when matches do not need to be validated by the tournament
+    /// organiser
+    automatic_progression: bool,
+}
+
+/// Line of a round robin pool standings
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Standing {
+    /// Player of this line
+    pub player_id: PlayerId,
+    /// Number of matches won
+    pub wins: usize,
+    /// Number of matches lost
+    pub losses: usize,
+    /// Games won minus games lost over all matches
+    pub game_differential: i32,
+}
+
+impl Step<'static> {
+    /// Create new matches for round robin pool. If no matches are provided,
+    /// generates matches with `seeding`
+    ///
+    /// # Errors
+    /// thrown when initial matches cannot be generated
+    pub fn new(
+        matches: Option<Vec<Match>>,
+        seeding: &[PlayerId],
+        automatic_progression: bool,
+    ) -> Result<Self, Error> {
+        let matches = match matches {
+            Some(matches) => matches,
+            None => get_round_robin_matches(seeding)?,
+        };
+
+        Ok(Step::from_matches(
+            Cow::Owned(matches.into()),
+            seeding.to_vec(),
+            automatic_progression,
+        ))
+    }
+}
+
+impl<'a> Step<'a> {
+    /// Round robin pool of `seeding` with `matches`
+    pub fn from_matches(
+        matches: Cow<'a, Matches>,
+        seeding: Vec<PlayerId>,
+        automatic_progression: bool,
+    ) -> Self {
+        Self {
+            seeding,
+            matches,
+            automatic_progression,
+        }
+    }
+
+    /// Returns standings of pool sorted from first to last place. Players are
+    /// ranked by match wins, then game differential, then matches won against
+    /// the players they are tied with. Remaining ties are broken by seeding.
+    #[must_use]
+    pub fn standings(&self) -> Vec<Standing> {
+        let mut standings = self
+            .seeding
+            .iter()
+            .map(|p| standing_of(*p, &self.matches))
+            .collect::<Vec<_>>();
+        // stable sort: seeding order breaks remaining ties
+        standings.sort_by(|a, b| {
+            b.wins
+                .cmp(&a.wins)
+                .then(b.game_differential.cmp(&a.game_differential))
+        });
+
+        let mut sorted = vec![];
+        let mut remaining = standings.as_slice();
+        while let Some(first) = remaining.first() {
+            let tied = remaining
+                .iter()
+                .take_while(|s| {
+                    s.wins == first.wins && s.game_differential == first.game_differential
+                })
+                .count();
+            let (group, rest) = remaining.split_at(tied);
+            let mut group = group.to_vec();
+            if group.len() > 1 {
+                let tied_players = group.iter().map(|s| s.player_id).collect::<Vec<_>>();
+                group.sort_by_key(|s| {
+                    std::cmp::Reverse(head_to_head_wins(s.player_id, &tied_players, &self.matches))
+                });
+            }
+            sorted.append(&mut group);
+            remaining = rest;
+        }
+
+        sorted
+    }
+
+    /// Returns number of matches `player_id` has left to play
+    pub(crate) fn matches_left(&self, player_id: PlayerId) -> usize {
+        self.matches
+            .matches_of(player_id)
+            .iter()
+            .filter(|i| self.matches[**i].get_winner() == Opponent::Unknown)
+            .count()
+    }
+
+    /// Returns position of the first match left to play between `player1`
+    /// and `player2`
+    fn match_between(&self, player1: PlayerId, player2: PlayerId) -> Option<usize> {
+        self.matches.matches_of(player1).iter().copied().find(|i| {
+            let m = self.matches[*i];
+            m.contains(player2) && m.get_winner() == Opponent::Unknown
+        })
+    }
+
+    /// Position of first match of `player_id` that needs a winner
+    fn next_match_of(&self, player_id: PlayerId) -> Option<usize> {
+        self.matches
+            .matches_of(player_id)
+            .iter()
+            .copied()
+            .find(|i| self.matches[*i].get_winner() == Opponent::Unknown)
+    }
+
+    /// Run `update` and undo what it did to matches when it fails
+    fn attempt<T>(
+        &mut self,
+        update: impl FnOnce(&mut Self) -> Result<T, Error>,
+    ) -> Result<T, Error> {
+        let mark = self.matches.mark();
+        let result = update(self);
+        if result.is_err() {
+            self.matches.to_mut().rollback(mark);
+        }
+        result
+    }
+
+    /// Validate `match_id`
+    fn validate_in_place(&mut self, match_id: MatchId) -> Result<(), Error> {
+        let Some(i) = self.matches.position(match_id) else {
+            return Err(Error::UnknownMatch(match_id));
+        };
+        let (updated_match, _, _) = self.matches[i].update_outcome()?;
+        self.matches.to_mut().set(i, updated_match);
+        Ok(())
+    }
+}
+
+/// Compute the standing of `player_id` using validated `matches`
+fn standing_of(player_id: PlayerId, matches: &[Match]) -> Standing {
+    let mut standing = Standing {
+        player_id,
+        wins: 0,
+        losses: 0,
+        game_differential: 0,
+    };
+    for m in matches.iter().filter(|m| m.contains(player_id)) {
+        let Opponent::Player(winner) = m.get_winner() else {
+            continue;
+        };
+        if winner == player_id {
+            standing.wins += 1;
+        } else {
+            standing.losses += 1;
+        }
+        let (s1, s2) = m.get_score();
+        let differential = i32::from(s1) - i32::from(s2);
+        standing.game_differential += match m.get_players() {
+            [Opponent::Player(p1), _] if p1 == player_id => differential,
+            _ => -differential,
+        };
+    }
+    standing
+}
+
+/// Number of matches won by `player_id` against any of the `tied_players`
+fn head_to_head_wins(player_id: PlayerId, tied_players: &[PlayerId], matches: &[Match]) -> usize {
+    matches
+        .iter()
+        .filter(|m| matches!(m.get_winner(), Opponent::Player(w) if w == player_id))
+        .filter(|m| {
+            tied_players
+                .iter()
+                .any(|other| *other != player_id && m.contains(*other))
+        })
+        .count()
+}
+
+impl Progression for Step<'_> {
+    fn boxed(&self) -> Box<dyn Progression + '_> {
+        Box::new(self.clone())
+    }
+
+    fn into_matches(self: Box<Self>) -> Matches {
+        self.matches.into_owned()
+    }
+
+    fn disqualify(&mut self, player_id: PlayerId) -> Result<Vec<Match>, Error> {
+        if self.is_over() {
+            return Err(Error::TournamentIsOver);
+        }
+        if !self.seeding.contains(&player_id) {
+            return Err(Error::UnknownPlayer(player_id, self.seeding.clone()));
+        }
+        if self.is_disqualified(player_id) {
+            return Err(Error::ForbiddenDisqualified(player_id));
+        }
+
+        let mark = self.matches.mark();
+        // disqualified player forfeits all their remaining matches
+        self.attempt(|p| {
+            while let Some(i) = p.next_match_of(player_id) {
+                let (updated_match, _, _) = p.matches[i]
+                    .set_automatic_loser(player_id)?
+                    .update_outcome()?;
+                p.matches.to_mut().set(i, updated_match);
+            }
+            Ok(())
+        })?;
+        Ok(self.matches.newly_playable(mark))
+    }
+
+    fn get_format(&self) -> Format {
+        Format::RoundRobin
+    }
+
+    fn is_over(&self) -> bool {
+        self.matches.over() == self.matches.len()
+    }
+
+    fn matches_progress(&self) -> (usize, usize) {
+        (self.matches.over(), self.matches.len())
+    }
+
+    fn matches_to_play(&self) -> Vec<Match> {
+        self.matches
+            .iter()
+            .copied()
+            .filter(Match::needs_playing)
+            .collect()
+    }
+
+    fn next_opponent(&self, player_id: PlayerId) -> Result<(Opponent, MatchId), Error> {
+        if !self.seeding.contains(&player_id) {
+            return Err(Error::PlayerIsNotParticipant(player_id));
+        }
+
+        if self.matches.is_empty() {
+            return Err(Error::NoGeneratedMatches);
+        }
+
+        if self.is_disqualified(player_id) {
+            return Err(Error::Disqualified(player_id));
+        }
+
+        let Some(relevant_match) = self.next_match_of(player_id).map(|i| self.matches[i]) else {
+            if !self.is_over() {
+                return Err(Error::NoMatchToPlay(player_id));
+            }
+            return match self.standings().first() {
+                Some(s) if s.player_id == player_id => Err(Error::NoNextMatch(player_id)),
+                _ => Err(Error::Eliminated(player_id)),
+            };
+        };
+
+        let opponent = match relevant_match.get_players() {
+            [Opponent::Player(p1), Opponent::Player(p2)] if p1 == player_id => Opponent::Player(p2),
+            [Opponent::Player(p1), Opponent::Player(p2)] if p2 == player_id => Opponent::Player(p1),
+            _ => Opponent::Unknown,
+        };
+        Ok((opponent, relevant_match.get_id()))
+    }
+
+    fn is_disqualified(&self, player_id: PlayerId) -> bool {
+        self.matches.is_disqualified(player_id)
+    }
+
+    fn report(
+        &mut self,
+        player_id: PlayerId,
+        result: (i8, i8),
+    ) -> Result<(MatchId, Vec<Match>), Error> {
+        let Ok((Opponent::Player(opponent), _)) = self.next_opponent(player_id) else {
+            return match self.next_opponent(player_id) {
+                Err(Error::Disqualified(p)) => Err(Error::ForbiddenDisqualified(p)),
+                Err(Error::PlayerIsNotParticipant(p)) => {
+                    Err(Error::UnknownPlayer(p, self.seeding.clone()))
+                }
+                Err(Error::NoNextMatch(_) | Error::Eliminated(_)) => Err(Error::TournamentIsOver),
+                Err(e) => Err(e),
+                Ok(_) => Err(Error::NoMatchToPlay(player_id)),
+            };
+        };
+        self.report_against(player_id, result, opponent)
+    }
+
+    fn organiser_reports(
+        &mut self,
+        player1: PlayerId,
+        result: (i8, i8),
+        player2: PlayerId,
+    ) -> Result<(MatchId, Vec<Match>), Error> {
+        let result_player_1 = ReportedResult(result);
+        self.attempt(|p| {
+            let (first_affected_match, _) =
+                p.report_against(player1, result_player_1.0, player2)?;
+            let (second_affected_match, new_matches) =
+                p.report_against(player2, result_player_1.reverse().0, player1)?;
+            assert_eq!(first_affected_match, second_affected_match);
+            Ok((first_affected_match, new_matches))
+        })
+    }
+
+    fn record_reported_result(
+        &mut self,
+        match_id: MatchId,
+        result: (i8, i8),
+        player_id: PlayerId,
+    ) -> Result<(), Error> {
+        let Some(i) = self.matches.position(match_id) else {
+            return Err(Error::UnknownMatch(match_id));
+        };
+
+        let updated_match =
+            self.matches[i].update_reported_result(player_id, ReportedResult(result))?;
+        self.matches.to_mut().set(i, updated_match);
+        Ok(())
+    }
+
+    fn validate(&mut self, match_id: MatchId) -> Result<Vec<Match>, Error> {
+        let mark = self.matches.mark();
+        self.validate_in_place(match_id)?;
+        Ok(self.matches.newly_playable(mark))
+    }
+
+    fn reopen(&mut self, match_id: MatchId, _cascade: bool) -> Result<Vec<Match>, Error> {
+        // no match depends on the result of another match in a round robin
+        let Some(i) = self.matches.position(match_id) else {
+            return Err(Error::UnknownMatch(match_id));
+        };
+        if let Opponent::Player(disqualified) = self.matches[i].get_automatic_loser() {
+            return Err(Error::ForbiddenDisqualified(disqualified));
+        }
+        let mark = self.matches.mark();
+        let reopened = self.matches[i].reopen();
+        self.matches.to_mut().set(i, reopened);
+        Ok(self.matches.newly_playable(mark))
+    }
+
+    fn check_all_assertions(&self) {
+        // a disqualified player forfeits all of their remaining matches, so
+        // they can be the automatic loser of more than one match
+        for m in self.matches.iter() {
+            assert_match_is_well_formed(m);
+        }
+    }
+}
+
+impl Step<'_> {
+    /// Report result of `player_id` for their match against `opponent`.
+    /// Returns affected match and new matches to play
+    fn report_against(
+        &mut self,
+        player_id: PlayerId,
+        result: (i8, i8),
+        opponent: PlayerId,
+    ) -> Result<(MatchId, Vec<Match>), Error> {
+        if !self.seeding.contains(&player_id) {
+            return Err(Error::UnknownPlayer(player_id, self.seeding.clone()));
+        }
+        if self.is_over() {
+            return Err(Error::TournamentIsOver);
+        }
+        if self.is_disqualified(player_id) {
+            return Err(Error::ForbiddenDisqualified(player_id));
+        }
+        let Some(i) = self.match_between(player_id, opponent) else {
+            return Err(Error::NoMatchToPlay(player_id));
+        };
+        let affected_match_id = self.matches[i].get_id();
+        let mark = self.matches.mark();
+        self.attempt(|p| {
+            p.record_reported_result(affected_match_id, result, player_id)?;
+            if p.automatic_progression {
+                match p.validate_in_place(affected_match_id) {
+                    Ok(())
+                    | Err(Error::MatchUpdate(MatchError::PlayersReportedDifferentMatchOutcome(
+                        _,
+                        _,
+                    ))) => {}
+                    Err(e) => return Err(e),
+                }
+            }
+            Ok(())
+        })?;
+
+        Ok((affected_match_id, self.matches.newly_playable(mark)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Step;
+    use crate::{
+        bracket::matches::{Error, Progression},
+        opponent::Opponent,
+        player::{Id as PlayerId, Player},
+    };
+
+    fn players(n: usize) -> (Vec<PlayerId>, Vec<PlayerId>) {
+        let mut player_ids = vec![PlayerId::new_v4()]; // padding for readability
+        let mut seeding = vec![];
+        for i in 1..=n {
+            let player = Player::new(format!("p{i}"));
+            player_ids.push(player.get_id());
+            seeding.push(player.get_id());
+        }
+        (player_ids, seeding)
+    }
+
+    fn report(s: &Step, p1: PlayerId, result: (i8, i8), p2: PlayerId) -> Step<'static> {
+        let (matches, _, _) = s
+            .tournament_organiser_reports_result(p1, result, p2)
+            .expect("matches");
+        Step::new(Some(matches), &s.seeding, s.automatic_progression).expect("step")
+    }
+
+    #[test]
+    fn run_4_man_pool() {
+        let (p, seeding) = players(4);
+        let s = Step::new(None, &seeding, true).expect("step");
+        assert_eq!(s.matches.len(), 6);
+        assert_eq!(s.matches_to_play().len(), 6);
+
+        let s = report(&s, p[1], (2, 0), p[2]);
+        let s = report(&s, p[1], (2, 1), p[3]);
+        let s = report(&s, p[1], (2, 0), p[4]);
+        let s = report(&s, p[2], (2, 0), p[3]);
+        let s = report(&s, p[2], (0, 2), p[4]);
+        assert!(!s.is_over());
+        let s = report(&s, p[3], (2, 0), p[4]);
+        assert!(s.is_over());
+        assert_eq!(s.matches_progress(), (6, 6));
+
+        let standings = s.standings();
+        assert_eq!(standings[0].player_id, p[1]);
+        assert_eq!(standings[0].wins, 3);
+        assert_eq!(standings[0].game_differential, 5);
+        // p2, p3 and p4 all have 1 win. Game differential: p2 = -2, p3 = -1,
+        // p4 = -2. p4 beat p2 head-to-head
+        assert_eq!(standings[1].player_id, p[3]);
+        assert_eq!(standings[2].player_id, p[4]);
+        assert_eq!(standings[3].player_id, p[2]);
+
+        match s.next_opponent(p[1]) {
+            Err(Error::NoNextMatch(winner)) => assert_eq!(winner, p[1]),
+            other => panic!("expected NoNextMatch but got {other:?}"),
+        }
+        match s.next_opponent(p[4]) {
+            Err(Error::Eliminated(loser)) => assert_eq!(loser, p[4]),
+            other => panic!("expected Eliminated but got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn head_to_head_breaks_ties() {
+        let (p, seeding) = players(3);
+        let s = Step::new(None, &seeding, true).expect("step");
+
+        // everyone wins one match 2-0: only head-to-head cannot break the tie
+        // so seeding does
+        let s = report(&s, p[3], (2, 0), p[1]);
+        let s = report(&s, p[1], (2, 0), p[2]);
+        let s = report(&s, p[2], (2, 0), p[3]);
+        let standings = s.standings();
+        assert_eq!(
+            standings.iter().map(|s| s.player_id).collect::<Vec<_>>(),
+            vec![p[1], p[2], p[3]]
+        );
+
+        let (p, seeding) = players(4);
+        let s = Step::new(None, &seeding, true).expect("step");
+        // p1 and p2 are tied on wins and game differential but p2 beat p1
+        let s = report(&s, p[2], (2, 0), p[1]);
+        let s = report(&s, p[1], (2, 0), p[3]);
+        let s = report(&s, p[1], (2, 0), p[4]);
+        let s = report(&s, p[4], (2, 0), p[2]);
+        let s = report(&s, p[2], (2, 0), p[3]);
+        let s = report(&s, p[3], (2, 0), p[4]);
+        let standings = s.standings();
+        assert_eq!(
+            standings.iter().map(|s| s.player_id).collect::<Vec<_>>(),
+            vec![p[2], p[1], p[3], p[4]]
+        );
+    }
+
+    #[test]
+    fn players_can_report_matches_in_any_order() {
+        let (p, seeding) = players(4);
+        let s = Step::new(None, &seeding, true).expect("step");
+        let (opponent, _) = s.next_opponent(p[3]).expect("next opponent");
+        let Opponent::Player(opponent) = opponent else {
+            panic!("expected player");
+        };
+        let (matches, _, _) = s.report_result(p[3], (2, 0)).expect("matches");
+        let s = Step::new(Some(matches), &seeding, true).expect("step");
+        let (matches, _, _) = s.report_result(opponent, (0, 2)).expect("matches");
+        let s = Step::new(Some(matches), &seeding, true).expect("step");
+        assert_eq!(s.matches_progress(), (1, 6));
+        assert_eq!(s.standings()[0].player_id, p[3]);
+    }
+
+    #[test]
+    fn disqualified_player_forfeits_all_remaining_matches() {
+        let (p, seeding) = players(4);
+        let s = Step::new(None, &seeding, true).expect("step");
+        let s = report(&s, p[2], (2, 0), p[4]);
+        let (matches, _) = s.disqualify_participant(p[2]).expect("matches");
+        let s = Step::new(Some(matches), &seeding, true).expect("step");
+        s.check_all_assertions();
+
+        assert!(s.is_disqualified(p[2]));
+        assert_eq!(s.matches_progress(), (3, 6));
+        let standings = s.standings();
+        let p2 = standings
+            .iter()
+            .find(|s| s.player_id == p[2])
+            .expect("p2 standing");
+        assert_eq!((p2.wins, p2.losses), (1, 2));
+    }
+}
@@ -2,34 +2,38 @@
 
 mod disqualification;
 mod next_opponent;
+pub(crate) mod playoffs;
 mod query_state;
 
 use super::{
-    assert_disqualified_at_most_once, assert_match_is_well_formed, update_bracket_with, Error,
+    assert_disqualified_at_most_once, assert_match_is_well_formed, index::Matches, Error,
     Progression,
 };
 use crate::{
-    bracket::{disqualification::get_new_matches, progression::new_matches},
-    format::Format,
+    format::{Format, Playoffs},
     matches::{Error as MatchError, Id as MatchId, Match, ReportedResult},
     opponent::Opponent,
     player::Id as PlayerId,
-    seeding::single_elimination_seeded_bracket::get_balanced_round_matches_top_seed_favored,
 };
+use playoffs::{settle, trees, update_in_tree, Tree};
+use std::borrow::Cow;
 
 /// Computes the next step of a single-elimination tournament
 #[derive(Clone, Debug)]
-pub(crate) struct Step {
+pub(crate) struct Step<'a> {
     /// Seeding for this bracket
     seeding: Vec<PlayerId>,
     /// All matches of single-elimination bracket
-    matches: Vec<Match>,
+    matches: Cow<'a, Matches>,
     /// True when matches do not need to be validated by the tournament
     /// organiser
     automatic_progression: bool,
+    /// Extra matches deciding placements of eliminated players
+    playoffs: Playoffs,
 }
 
-impl Step {
+#[cfg(test)]
+impl Step<'static> {
     /// Create new matches for single elimination bracket. If no matches are
     /// provided, generates matches with `seeding`
     ///
@@ -42,115 +46,162 @@ impl Step {
     ) -> Result<Self, Error> {
         let matches = match matches {
             Some(matches) => matches,
-            None => get_balanced_round_matches_top_seed_favored(seeding)?,
+            None => Format::SingleElimination {
+                playoffs: Playoffs::Disabled,
+            }
+            .generate_matches(seeding)?,
         };
 
-        Ok(Self {
+        Ok(Step::from_matches(
+            Cow::Owned(matches.into()),
+            seeding.to_vec(),
+            automatic_progression,
+        ))
+    }
+}
+
+impl<'a> Step<'a> {
+    /// Single elimination bracket of `seeding` with `matches`
+    pub fn from_matches(
+        matches: Cow<'a, Matches>,
+        seeding: Vec<PlayerId>,
+        automatic_progression: bool,
+    ) -> Self {
+        Self {
             matches,
-            seeding: seeding.to_vec(),
+            seeding,
             automatic_progression,
-        })
+            playoffs: Playoffs::Disabled,
+        }
     }
 
-    /// Returns true if `player_id` is disqualified
-    fn is_disqualified(&self, player_id: PlayerId) -> bool {
+    /// Use `playoffs` for this bracket. Playoff matches are expected to
+    /// follow main bracket matches
+    pub fn with_playoffs(self, playoffs: Playoffs) -> Self {
+        Self { playoffs, ..self }
+    }
+
+    /// Main bracket followed by playoffs of this bracket
+    fn trees(&self) -> Vec<Tree> {
+        trees(self.seeding.len(), self.playoffs)
+    }
+
+    /// Returns true if `player_id` takes part in this bracket
+    fn is_participant(&self, player_id: PlayerId) -> bool {
+        if self.matches.is_empty() {
+            return self.seeding.contains(&player_id);
+        }
+        self.matches.seed_of(player_id).is_some()
+    }
+
+    /// Position of first match of `player_id` that needs a winner
+    fn next_match_of(&self, player_id: PlayerId) -> Option<usize> {
         self.matches
+            .matches_of(player_id)
             .iter()
-            .any(|m| m.is_automatic_loser_by_disqualification(player_id))
+            .copied()
+            .find(|i| self.matches[*i].get_winner() == Opponent::Unknown)
+    }
+
+    /// Run `update` and undo what it did to matches when it fails
+    fn attempt<T>(
+        &mut self,
+        update: impl FnOnce(&mut Self) -> Result<T, Error>,
+    ) -> Result<T, Error> {
+        let mark = self.matches.mark();
+        let result = update(self);
+        if result.is_err() {
+            self.matches.to_mut().rollback(mark);
+        }
+        result
     }
 
     /// Clear previous reported result for `player_id`
-    fn clear_reported_result(self, player_id: PlayerId) -> Result<Self, Error> {
-        let match_to_update = self
-            .matches
-            .iter()
-            .find(|m| m.contains(player_id) && m.get_winner() == Opponent::Unknown);
-        match match_to_update {
-            Some(m_to_clear) => {
-                let m_to_clear = (*m_to_clear).clear_reported_result(player_id);
-
-                let matches = self
-                    .matches
-                    .into_iter()
-                    .map(|m| {
-                        if m.get_id() == m_to_clear.get_id() {
-                            m_to_clear
-                        } else {
-                            m
-                        }
-                    })
-                    .collect();
-                Ok(Self { matches, ..self })
+    fn clear_reported_result(&mut self, player_id: PlayerId) -> Result<(), Error> {
+        let Some(i) = self.next_match_of(player_id) else {
+            return Err(Error::NoMatchToPlay(player_id));
+        };
+        let m_to_clear = self.matches[i].clear_reported_result(player_id);
+        self.matches.to_mut().set(i, m_to_clear);
+        Ok(())
+    }
+
+    /// Validate `match_id` then settle playoffs
+    fn validate_in_place(&mut self, match_id: MatchId) -> Result<(), Error> {
+        let trees = self.trees();
+        let matches = self.matches.to_mut();
+        let mark = matches.mark();
+        update_in_tree(matches, &trees, match_id)?;
+        settle(matches, &trees, mark)
+    }
+
+    /// Disqualify `player_id` from match at `i` and move them as far as
+    /// possible in the bracket
+    fn disqualify_in_place(&mut self, player_id: PlayerId, i: usize) -> Result<(), Error> {
+        let mark = self.matches.mark();
+        let current_match_to_play = self.matches[i].set_automatic_loser(player_id)?;
+        self.matches.to_mut().set(i, current_match_to_play);
+        match self.attempt(|p| p.validate_in_place(current_match_to_play.get_id())) {
+            Ok(()) => {
+                if let Some(j) = self.next_match_of(player_id) {
+                    let match_in_losers = self.matches[j].set_automatic_loser(player_id)?;
+                    self.matches.to_mut().set(j, match_in_losers);
+                }
+                let trees = self.trees();
+                settle(self.matches.to_mut(), &trees, mark)
             }
-            None => Err(Error::NoMatchToPlay(player_id)),
+            // if no winner can be declared because there is a
+            // missing player, then don't throw an error
+            Err(Error::MatchUpdate(MatchError::MissingOpponent(_))) => Ok(()),
+            Err(e) => Err(e),
         }
     }
 }
 
-impl Progression for Step {
-    fn disqualify_participant(
-        &self,
-        player_id: PlayerId,
-    ) -> Result<(Vec<Match>, Vec<Match>), Error> {
+impl Progression for Step<'_> {
+    fn boxed(&self) -> Box<dyn Progression + '_> {
+        Box::new(self.clone())
+    }
+
+    fn into_matches(self: Box<Self>) -> Matches {
+        self.matches.into_owned()
+    }
+
+    fn disqualify(&mut self, player_id: PlayerId) -> Result<Vec<Match>, Error> {
         if self.is_over() {
             return Err(Error::TournamentIsOver);
         }
 
-        let old_matches_to_play = self.matches_to_play();
-
-        let Some(match_of_player_to_disqualify) = self.matches.iter().rev().find(|m| {
-            m.contains(player_id)
-                && m.get_winner() == Opponent::Unknown
-                && m.get_automatic_loser() == Opponent::Unknown
-        }) else {
-            return if self.seeding.contains(&player_id) {
+        let Some(i) = self
+            .matches
+            .matches_of(player_id)
+            .iter()
+            .rev()
+            .copied()
+            .find(|i| {
+                let m = self.matches[*i];
+                m.get_winner() == Opponent::Unknown && m.get_automatic_loser() == Opponent::Unknown
+            })
+        else {
+            return if self.is_participant(player_id) {
                 Err(Error::ForbiddenDisqualified(player_id))
             } else {
                 Err(Error::UnknownPlayer(player_id, self.seeding.clone()))
             };
         };
-        let current_match_to_play =
-            (*match_of_player_to_disqualify).set_automatic_loser(player_id)?;
-        let bracket = update_bracket_with(&self.matches, &current_match_to_play);
-        let bracket = Step::new(Some(bracket), &self.seeding, self.automatic_progression)?;
-        let new_matches = get_new_matches(&old_matches_to_play, &bracket.matches_to_play());
-        match bracket
-            .clone()
-            .validate_match_result(current_match_to_play.get_id())
-        {
-            Ok((bracket, _)) => {
-                let bracket = match bracket
-                    .iter()
-                    .copied()
-                    .find(|m| m.contains(player_id) && m.get_winner() == Opponent::Unknown)
-                {
-                    Some(next_match_of_disqualified_player) => {
-                        let match_in_losers =
-                            next_match_of_disqualified_player.set_automatic_loser(player_id)?;
-                        update_bracket_with(&bracket, &match_in_losers)
-                    }
-                    None => bracket,
-                };
-                let bracket = Step::new(Some(bracket), &self.seeding, self.automatic_progression)?;
-
-                let new_matches = get_new_matches(&old_matches_to_play, &bracket.matches_to_play());
-                Ok((bracket.matches, new_matches))
-            }
-            // if no winner can be declared because there is a
-            // missing player, then don't throw an error
-            Err(Error::MatchUpdate(MatchError::MissingOpponent(_))) => {
-                Ok((bracket.matches, new_matches))
-            }
-            Err(e) => Err(e),
-        }
+        let mark = self.matches.mark();
+        self.attempt(|p| p.disqualify_in_place(player_id, i))?;
+        Ok(self.matches.newly_playable(mark))
     }
 
     fn get_format(&self) -> Format {
-        Format::SingleElimination
+        Format::SingleElimination {
+            playoffs: self.playoffs,
+        }
     }
 
     fn is_over(&self) -> bool {
-        super::bracket_is_over(&self.matches)
+        self.matches.over() == self.matches.len()
     }
 
     fn matches_to_play(&self) -> Vec<Match> {
@@ -165,7 +216,7 @@ impl Progression for Step {
         &self,
         player_id: PlayerId,
     ) -> Result<(crate::opponent::Opponent, crate::matches::Id), Error> {
-        if !self.seeding.contains(&player_id) {
+        if !self.is_participant(player_id) {
             return Err(Error::PlayerIsNotParticipant(player_id));
         };
 
@@ -173,17 +224,15 @@ impl Progression for Step {
             return Err(Error::NoGeneratedMatches);
         }
 
-        if super::is_disqualified(player_id, &self.matches) {
+        if self.matches.is_disqualified(player_id) {
             return Err(Error::Disqualified(player_id));
         }
 
-        let next_match = self
-            .matches
-            .iter()
-            .find(|m| m.contains(player_id) && m.get_winner() == Opponent::Unknown);
-        let Some(relevant_match) = next_match else {
-            let last_match = self.matches.iter().last().expect("last match");
-            return match last_match.get_winner() {
+        let Some(relevant_match) = self.next_match_of(player_id).map(|i| self.matches[i]) else {
+            let finals = self.matches[self.trees()[0].range()]
+                .last()
+                .expect("last match");
+            return match finals.get_winner() {
                 Opponent::Player(p) if p == player_id => Err(Error::NoNextMatch(player_id)),
                 _ => Err(Error::Eliminated(player_id)),
             };
@@ -201,132 +250,108 @@ impl Progression for Step {
         Ok((opponent, relevant_match.get_id()))
     }
 
-    fn report_result(
-        &self,
+    fn report(
+        &mut self,
         player_id: PlayerId,
         result: (i8, i8),
-    ) -> Result<(Vec<Match>, crate::matches::Id, Vec<Match>), Error> {
-        if !self.seeding.contains(&player_id) {
+    ) -> Result<(crate::matches::Id, Vec<Match>), Error> {
+        if !self.is_participant(player_id) {
             return Err(Error::UnknownPlayer(player_id, self.seeding.clone()));
         };
         if self.is_over() {
             return Err(Error::TournamentIsOver);
         }
-        if self.is_disqualified(player_id) {
+        if self.matches.is_disqualified(player_id) {
             return Err(Error::ForbiddenDisqualified(player_id));
         }
-        let old_matches = self.matches_to_play();
-        let match_to_update = self
-            .matches
-            .iter()
-            .find(|m| m.contains(player_id) && m.get_winner() == Opponent::Unknown);
-        match match_to_update {
-            Some(m) => {
-                let affected_match_id = m.get_id();
-                let matches =
-                    self.update_player_reported_match_result(affected_match_id, result, player_id)?;
-                let p = Step::new(Some(matches), &self.seeding, self.automatic_progression)?;
-
-                let matches = if self.automatic_progression {
-                    match p.clone().validate_match_result(affected_match_id) {
-                        Ok((b, _)) => b,
-                        Err(e) => match e {
-                            Error::MatchUpdate(
-                                MatchError::PlayersReportedDifferentMatchOutcome(_, _),
-                            ) => p.matches,
-                            _ => return Err(e),
-                        },
-                    }
-                } else {
-                    p.matches
-                };
-
-                let p = Step::new(Some(matches), &self.seeding, self.automatic_progression)?;
-
-                let new_matches = p
-                    .matches_to_play()
-                    .iter()
-                    .filter(|m| !old_matches.iter().any(|old_m| old_m.get_id() == m.get_id()))
-                    .map(std::clone::Clone::clone)
-                    .collect();
-                Ok((p.matches, affected_match_id, new_matches))
+        let Some(i) = self.next_match_of(player_id) else {
+            return Err(Error::NoMatchToPlay(player_id));
+        };
+        let affected_match_id = self.matches[i].get_id();
+        let mark = self.matches.mark();
+        self.attempt(|p| {
+            p.record_reported_result(affected_match_id, result, player_id)?;
+            if p.automatic_progression {
+                match p.attempt(|p| p.validate_in_place(affected_match_id)) {
+                    Ok(())
+                    | Err(Error::MatchUpdate(MatchError::PlayersReportedDifferentMatchOutcome(
+                        _,
+                        _,
+                    ))) => {}
+                    Err(e) => return Err(e),
+                }
             }
-            None => Err(Error::NoMatchToPlay(player_id)),
-        }
+            Ok(())
+        })?;
+
+        Ok((affected_match_id, self.matches.newly_playable(mark)))
     }
 
-    fn tournament_organiser_reports_result(
-        &self,
+    fn organiser_reports(
+        &mut self,
         player1: PlayerId,
         result: (i8, i8),
         player2: PlayerId,
-    ) -> Result<(Vec<Match>, crate::matches::Id, Vec<Match>), Error> {
-        let result_player_1 = ReportedResult(result);
-        let bracket = self.clone().clear_reported_result(player1)?;
-        let bracket = bracket.clear_reported_result(player2)?;
-        let (bracket, first_affected_match, _new_matches) =
-            bracket.report_result(player1, result_player_1.0)?;
-        let bracket = Step::new(Some(bracket), &self.seeding, self.automatic_progression)?;
-        let (bracket, second_affected_match, new_matches_2) =
-            bracket.report_result(player2, result_player_1.reverse().0)?;
-        assert_eq!(first_affected_match, second_affected_match);
-        Ok((bracket, first_affected_match, new_matches_2))
+    ) -> Result<(crate::matches::Id, Vec<Match>), Error> {
+        let Some(i) = self
+            .next_match_of(player1)
+            .filter(|i| self.matches[*i].contains(player2))
+        else {
+            return Err(Error::NoMatchToPlay(player1));
+        };
+        let affected_match_id = self.matches[i].get_id();
+        self.attempt(|p| {
+            let result_player_1 = ReportedResult(result);
+            p.clear_reported_result(player1)?;
+            p.clear_reported_result(player2)?;
+            p.report(player1, result_player_1.0)?;
+            let (_, new_matches) = p.report(player2, result_player_1.reverse().0)?;
+            Ok((affected_match_id, new_matches))
+        })
     }
 
-    fn update_player_reported_match_result(
-        &self,
+    fn record_reported_result(
+        &mut self,
         match_id: MatchId,
         result: (i8, i8),
         player_id: PlayerId,
-    ) -> Result<Vec<Match>, Error> {
-        let Some(m) = self.matches.iter().find(|m| m.get_id() == match_id) else {
+    ) -> Result<(), Error> {
+        let Some(i) = self.matches.position(match_id) else {
             return Err(Error::UnknownMatch(match_id));
         };
 
-        let updated_match = (*m).update_reported_result(player_id, ReportedResult(result))?;
-        let matches = self
-            .matches
-            .clone()
-            .iter()
-            .map(|m| {
-                if m.get_id() == updated_match.get_id() {
-                    updated_match
-                } else {
-                    *m
-                }
-            })
-            .collect();
-        Ok(matches)
+        let updated_match =
+            self.matches[i].update_reported_result(player_id, ReportedResult(result))?;
+        self.matches.to_mut().set(i, updated_match);
+        Ok(())
     }
 
-    fn validate_match_result(&self, match_id: MatchId) -> Result<(Vec<Match>, Vec<Match>), Error> {
-        let old_matches = self.matches_to_play();
-        let (matches, _) = super::update(&self.matches, match_id)?;
-        let p = Step::new(
-            Some(matches.clone()),
-            &self.seeding,
-            self.automatic_progression,
-        )?;
-        let new_matches = new_matches(&old_matches, &p.matches_to_play());
-        Ok((matches, new_matches))
+    fn validate(&mut self, match_id: MatchId) -> Result<Vec<Match>, Error> {
+        let mark = self.matches.mark();
+        self.attempt(|p| p.validate_in_place(match_id))?;
+        Ok(self.matches.newly_playable(mark))
     }
 
     fn is_disqualified(&self, player_id: PlayerId) -> bool {
-        super::is_disqualified(player_id, &self.matches)
+        self.matches.is_disqualified(player_id)
+    }
+
+    fn reopen(&mut self, match_id: MatchId, cascade: bool) -> Result<Vec<Match>, Error> {
+        super::reopen_elimination_match(self.matches.to_mut(), match_id, cascade, None)?;
+        Ok(self.matches_to_play())
     }
 
     fn check_all_assertions(&self) {
-        assert_disqualified_at_most_once(&self.matches, &self.seeding);
-        for m in &self.matches {
+        for tree in self.trees() {
+            assert_disqualified_at_most_once(&self.matches[tree.range()], &self.seeding);
+        }
+        for m in self.matches.iter() {
             assert_match_is_well_formed(m);
         }
     }
 
     fn matches_progress(&self) -> (usize, usize) {
-        let right = self.matches.len();
-        let left = self.matches.iter().filter(|m| m.is_over()).count();
-
-        (left, right)
+        (self.matches.over(), self.matches.len())
     }
 }
 
@@ -499,7 +524,7 @@ mod tests {
             .expect("bracket");
         let p = Step::new(Some(matches), &p.seeding, automatic_progression).expect("progress");
         if !p.is_over() {
-            for m in p.matches {
+            for m in p.matches.iter() {
                 println!("{m}");
             }
             panic!("expected bracket to be over")
@@ -0,0 +1,258 @@
+//! Playoffs of single elimination bracket, where players eliminated in the
+//! same round play each other to decide their placement
+//!
+//! Matches of a single elimination bracket with playoffs are made of trees:
+//! the main bracket followed by playoff brackets. Each tree is a single
+//! elimination bracket stored contiguously whose seeds are the placements
+//! it decides.
+
+use std::ops::Range;
+
+use super::super::{index::Matches, update, Error};
+use crate::{format::Playoffs, matches::Id as MatchId, opponent::Opponent};
+
+/// Single elimination bracket deciding placements from `first_seed` to
+/// `first_seed + size - 1`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Tree {
+    /// Best placement decided by this tree
+    pub(crate) first_seed: usize,
+    /// Number of players in this tree
+    pub(crate) size: usize,
+    /// Index of first match of this tree in bracket matches
+    start: usize,
+    /// Number of matches of each round
+    rounds: Vec<usize>,
+    /// Index of tree where losers of each round go, if any
+    playoffs: Vec<Option<usize>>,
+}
+
+impl Tree {
+    /// Indices of all matches of this tree in bracket matches
+    pub(crate) fn range(&self) -> Range<usize> {
+        self.start..self.start + self.size.saturating_sub(1)
+    }
+
+    /// Indices of matches of round `round` in bracket matches
+    pub(crate) fn round_range(&self, round: usize) -> Range<usize> {
+        let start = self.start + self.rounds[..round].iter().sum::<usize>();
+        start..start + self.rounds[round]
+    }
+
+    /// Number of rounds of this tree
+    pub(crate) fn number_of_rounds(&self) -> usize {
+        self.rounds.len()
+    }
+
+    /// Number of players left in this tree after `round` is played
+    pub(crate) fn survivors(&self, round: usize) -> usize {
+        1 + self.rounds[round + 1..].iter().sum::<usize>()
+    }
+
+    /// Round of match at `index` in bracket matches, if it belongs to this
+    /// tree
+    pub(crate) fn round_of(&self, index: usize) -> Option<usize> {
+        (0..self.rounds.len()).find(|r| self.round_range(*r).contains(&index))
+    }
+
+    /// Worst placement of a player losing a match of `round`
+    pub(crate) fn worst_placement_of_loser(&self, round: usize) -> usize {
+        self.first_seed + self.survivors(round) + self.rounds[round] - 1
+    }
+
+    /// Index of tree where losers of `round` go, if any
+    pub(crate) fn playoff_of(&self, round: usize) -> Option<usize> {
+        self.playoffs[round]
+    }
+}
+
+/// Number of matches per round of a single elimination bracket of `n` players
+fn round_sizes(n: usize) -> Vec<usize> {
+    if n < 2 {
+        return vec![];
+    }
+    let npo2 = n.next_power_of_two();
+    let byes = npo2 - n;
+    let mut rounds = vec![(n - byes) / 2];
+    let mut matches = npo2 / 4;
+    while matches >= 1 {
+        rounds.push(matches);
+        matches /= 2;
+    }
+    rounds
+}
+
+/// Returns trees of a single elimination bracket of `n` players. Main bracket
+/// comes first
+pub(crate) fn trees(n: usize, playoffs: Playoffs) -> Vec<Tree> {
+    let mut trees = vec![];
+    let mut next_match_index = 0;
+    add_tree(1, n, playoffs, true, &mut trees, &mut next_match_index);
+    trees
+}
+
+/// Add tree of `size` players and all of its playoffs to `trees`
+fn add_tree(
+    first_seed: usize,
+    size: usize,
+    playoffs: Playoffs,
+    is_main_bracket: bool,
+    trees: &mut Vec<Tree>,
+    next_match_index: &mut usize,
+) {
+    let rounds = round_sizes(size);
+    let index = trees.len();
+    trees.push(Tree {
+        first_seed,
+        size,
+        start: *next_match_index,
+        rounds: rounds.clone(),
+        playoffs: vec![None; rounds.len()],
+    });
+    *next_match_index += size.saturating_sub(1);
+
+    // best placements first
+    for round in (0..rounds.len()).rev() {
+        if rounds[round] < 2 {
+            continue;
+        }
+        let has_playoff = match playoffs {
+            Playoffs::Disabled => false,
+            Playoffs::ThirdPlaceMatch => is_main_bracket && round + 2 == rounds.len(),
+            Playoffs::Consolation => true,
+        };
+        if !has_playoff {
+            continue;
+        }
+        let survivors = trees[index].survivors(round);
+        trees[index].playoffs[round] = Some(trees.len());
+        add_tree(
+            first_seed + survivors,
+            rounds[round],
+            playoffs,
+            false,
+            trees,
+            next_match_index,
+        );
+    }
+}
+
+/// Returns the tree containing match at `index`
+pub(crate) fn tree_of(trees: &[Tree], index: usize) -> &Tree {
+    // trees are stored one after the other
+    let tree = trees.partition_point(|t| t.range().end <= index);
+    trees
+        .get(tree)
+        .filter(|t| t.range().contains(&index))
+        .expect("tree of match")
+}
+
+/// Validate `match_id` inside its tree
+///
+/// # Errors
+/// thrown when match is unknown or cannot be updated
+pub(crate) fn update_in_tree(
+    matches: &mut Matches,
+    trees: &[Tree],
+    match_id: MatchId,
+) -> Result<(), Error> {
+    let Some(index) = matches.position(match_id) else {
+        return Err(Error::UnknownMatch(match_id));
+    };
+    update(matches, tree_of(trees, index).range(), match_id)?;
+    Ok(())
+}
+
+/// Send losers of matches updated since `mark` to their playoff and resolve
+/// matches involving disqualified players until nothing changes
+///
+/// # Errors
+/// thrown when a playoff match cannot be updated
+pub(crate) fn settle(matches: &mut Matches, trees: &[Tree], mark: usize) -> Result<(), Error> {
+    let mut settled = mark;
+    loop {
+        let touched = matches.touched_since(settled);
+        if touched.is_empty() {
+            return Ok(());
+        }
+        settled = matches.mark();
+        for i in touched {
+            settle_match(matches, trees, i)?;
+        }
+    }
+}
+
+/// Send loser of match at `index` to its playoff and resolve it if it
+/// involves a disqualified player
+///
+/// # Errors
+/// thrown when a playoff match cannot be updated
+fn settle_match(matches: &mut Matches, trees: &[Tree], index: usize) -> Result<(), Error> {
+    let m = matches[index];
+    let tree = tree_of(trees, index);
+    let playoff = tree
+        .round_of(index)
+        .and_then(|round| tree.playoff_of(round));
+    if let (Some(playoff), Opponent::Player(winner)) = (playoff, m.get_winner()) {
+        let loser = m.get_other_player(winner)?;
+        let Some(link) = m.get_links().loser else {
+            return Err(Error::MissingLink(m.get_id()));
+        };
+        let Some(target) = matches
+            .position(link.match_id)
+            .filter(|j| trees[playoff].range().contains(j))
+        else {
+            return Err(Error::UnknownMatch(link.match_id));
+        };
+        if !matches[target].contains(loser) {
+            let mut playoff_match = matches[target].insert_player(loser, link.is_player_1)?;
+            if matches.is_disqualified(loser) {
+                playoff_match = playoff_match.set_automatic_loser(loser)?;
+            }
+            matches.set(target, playoff_match);
+        }
+    }
+    if matches[index].needs_update_because_of_disqualified_participant() {
+        update_in_tree(matches, trees, matches[index].get_id())?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::trees;
+    use crate::format::Playoffs;
+
+    fn placements(n: usize, playoffs: Playoffs) -> Vec<(usize, usize)> {
+        trees(n, playoffs)
+            .iter()
+            .map(|t| (t.first_seed, t.first_seed + t.size - 1))
+            .collect()
+    }
+
+    #[test]
+    fn third_place_match_is_played_by_semi_finals_losers() {
+        assert_eq!(
+            placements(8, Playoffs::ThirdPlaceMatch),
+            vec![(1, 8), (3, 4)]
+        );
+        assert_eq!(placements(3, Playoffs::ThirdPlaceMatch), vec![(1, 3)]);
+    }
+
+    #[test]
+    fn consolation_decides_every_placement() {
+        assert_eq!(
+            placements(8, Playoffs::Consolation),
+            vec![(1, 8), (3, 4), (5, 8), (7, 8)]
+        );
+        assert_eq!(
+            placements(6, Playoffs::Consolation),
+            vec![(1, 6), (3, 4), (5, 6)]
+        );
+        let matches: usize = trees(8, Playoffs::Consolation)
+            .iter()
+            .map(|t| t.range().len())
+            .sum();
+        assert_eq!(matches, 12);
+    }
+}
@@ -0,0 +1,783 @@
+//! Manage matches of swiss tournament. Unlike other formats, matches of a
+//! round are only generated once the previous round is over.
+
+use super::{assert_match_is_well_formed, index::Matches, Error, Progression};
+use crate::{
+    format::Format,
+    matches::{Error as MatchError, Id as MatchId, Match, ReportedResult},
+    opponent::Opponent,
+    player::Id as PlayerId,
+    seeding::swiss_seeded_bracket::{default_number_of_rounds, get_swiss_round_matches, wins_of},
+};
+use serde::{Deserialize, Serialize};
+use std::{borrow::Cow, num::NonZeroUsize};
+
+/// Computes the next step of a swiss tournament
+#[derive(Clone, Debug)]
+pub(crate) struct Step<'a> {
+    /// Seeding for this tournament
+    seeding: Vec<PlayerId>,
+    /// All matches generated so far, sorted by rounds
+    matches: Cow<'a, Matches>,
+    /// True when matches do not need to be validated by the tournament
+    /// organiser
+    automatic_progression: bool,
+    /// Number of rounds to play
+    rounds: usize,
+    /// Players disqualified while waiting for the next round. No match
+    /// records their disqualification and they are not paired again
+    disqualified_between_rounds: Vec<PlayerId>,
+}
+
+/// Line of swiss tournament standings
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Standing {
+    /// Player of this line
+    pub player_id: PlayerId,
+    /// Number of matches won, including byes
+    pub wins: usize,
+    /// Number of matches lost
+    pub losses: usize,
+    /// Sum of wins of all opponents faced (Buchholz score)
+    pub buchholz: usize,
+    /// Buchholz score without the best and worst opponent (median Buchholz)
+    pub median_buchholz: usize,
+    /// Games won minus games lost over all matches
+    pub game_differential: i32,
+}
+
+impl Step<'static> {
+    /// Create swiss tournament for `seeding`. If no matches are provided,
+    /// generates the first round. When `rounds` is not provided, play enough
+    /// rounds to have a single undefeated player.
+    ///
+    /// # Errors
+    /// thrown when first round cannot be generated
+    pub fn new(
+        matches: Option<Vec<Match>>,
+        seeding: &[PlayerId],
+        automatic_progression: bool,
+        rounds: Option<NonZeroUsize>,
+    ) -> Result<Self, Error> {
+        let matches = match matches {
+            Some(matches) => matches,
+            None => get_swiss_round_matches(seeding, &[], &[])?,
+        };
+
+        Ok(Step::from_matches(
+            Cow::Owned(matches.into()),
+            seeding.to_vec(),
+            automatic_progression,
+            rounds,
+        ))
+    }
+}
+
+impl<'a> Step<'a> {
+    /// Swiss tournament of `seeding` with `matches` generated so far. When
+    /// `rounds` is not provided, play enough rounds to have a single
+    /// undefeated player.
+    pub fn from_matches(
+        matches: Cow<'a, Matches>,
+        seeding: Vec<PlayerId>,
+        automatic_progression: bool,
+        rounds: Option<NonZeroUsize>,
+    ) -> Self {
+        Self {
+            rounds: rounds.map_or_else(
+                || default_number_of_rounds(seeding.len()),
+                NonZeroUsize::get,
+            ),
+            matches,
+            seeding,
+            automatic_progression,
+            disqualified_between_rounds: vec![],
+        }
+    }
+
+    /// Leave out `players` disqualified while waiting for the next round
+    pub fn with_disqualified_between_rounds(self, players: Vec<PlayerId>) -> Self {
+        Self {
+            disqualified_between_rounds: players,
+            ..self
+        }
+    }
+
+    /// Returns standings sorted from first to last place. Players are ranked
+    /// by wins, Buchholz score, median Buchholz score then game differential.
+    /// Remaining ties are broken by seeding.
+    #[must_use]
+    pub fn standings(&self) -> Vec<Standing> {
+        let mut standings = self
+            .seeding
+            .iter()
+            .map(|p| self.standing_of(*p))
+            .collect::<Vec<_>>();
+        // stable sort: seeding order breaks remaining ties
+        standings.sort_by(|a, b| {
+            b.wins
+                .cmp(&a.wins)
+                .then(b.buchholz.cmp(&a.buchholz))
+                .then(b.median_buchholz.cmp(&a.median_buchholz))
+                .then(b.game_differential.cmp(&a.game_differential))
+        });
+        standings
+    }
+
+    /// Returns number of matches `player_id` may still play, including
+    /// rounds not generated yet
+    pub(crate) fn matches_left(&self, player_id: PlayerId) -> usize {
+        if self.is_disqualified(player_id) {
+            return 0;
+        }
+        let generated = self
+            .matches
+            .matches_of(player_id)
+            .iter()
+            .filter(|i| self.matches[**i].get_winner() == Opponent::Unknown)
+            .count();
+        generated + self.rounds.saturating_sub(self.rounds_generated())
+    }
+
+    /// Returns matches partitionned by rounds
+    #[must_use]
+    pub fn rounds(&self) -> Vec<Vec<Match>> {
+        partition_by_round(&self.matches)
+    }
+
+    /// Compute standing of `player_id`
+    fn standing_of(&self, player_id: PlayerId) -> Standing {
+        let mut losses = 0;
+        let mut game_differential = 0;
+        let mut opponent_wins = vec![];
+        for m in self
+            .matches
+            .iter()
+            .filter(|m| m.contains(player_id) && !m.is_bye())
+        {
+            let Opponent::Player(winner) = m.get_winner() else {
+                continue;
+            };
+            let (s1, s2) = m.get_score();
+            let differential = i32::from(s1) - i32::from(s2);
+            let (differential, opponent) = match m.get_players() {
+                [Opponent::Player(p1), Opponent::Player(p2)] if p1 == player_id => {
+                    (differential, p2)
+                }
+                [Opponent::Player(p1), Opponent::Player(p2)] if p2 == player_id => {
+                    (-differential, p1)
+                }
+                // opponent was removed from match: there is no one to count
+                _ => continue,
+            };
+            if winner != player_id {
+                losses += 1;
+            }
+            game_differential += differential;
+            opponent_wins.push(wins_of(opponent, &self.matches));
+        }
+        opponent_wins.sort_unstable();
+        let buchholz = opponent_wins.iter().sum();
+        let median_buchholz = if opponent_wins.len() > 2 {
+            opponent_wins[1..opponent_wins.len() - 1].iter().sum()
+        } else {
+            0
+        };
+
+        Standing {
+            player_id,
+            wins: wins_of(player_id, &self.matches),
+            losses,
+            buchholz,
+            median_buchholz,
+            game_differential,
+        }
+    }
+
+    /// Returns disqualified players
+    fn disqualified_players(&self) -> Vec<PlayerId> {
+        self.seeding
+            .iter()
+            .copied()
+            .filter(|p| self.is_disqualified(*p))
+            .collect()
+    }
+
+    /// Returns true if all matches of current round are over
+    fn current_round_is_over(&self) -> bool {
+        self.matches.iter().all(|m| m.is_over() || m.is_bye())
+    }
+
+    /// Generate next round if current round is over and there are rounds
+    /// left to play
+    fn generate_next_round_if_needed(&mut self) -> Result<(), Error> {
+        let disqualified = self.disqualified_players();
+        if !self.current_round_is_over()
+            || self.rounds_generated() >= self.rounds
+            || self.seeding.len() - disqualified.len() < 2
+        {
+            return Ok(());
+        }
+        let next_round = get_swiss_round_matches(&self.seeding, &self.matches, &disqualified)?;
+        let matches = self.matches.to_mut();
+        for m in next_round {
+            matches.push(m);
+        }
+        Ok(())
+    }
+
+    /// Returns position of match of `player_id` that needs a winner
+    fn match_to_play_of(&self, player_id: PlayerId) -> Option<usize> {
+        self.matches
+            .matches_of(player_id)
+            .iter()
+            .copied()
+            .find(|i| self.matches[*i].get_winner() == Opponent::Unknown)
+    }
+
+    /// Returns position of match left to play between `player1` and `player2`
+    fn match_between(&self, player1: PlayerId, player2: PlayerId) -> Option<usize> {
+        self.match_to_play_of(player1)
+            .filter(|i| self.matches[*i].contains(player2))
+    }
+
+    /// Returns number of rounds generated so far
+    fn rounds_generated(&self) -> usize {
+        match self.matches.iter().map(Match::get_round).max() {
+            None => 0,
+            // brackets saved before matches recorded their round
+            Some(0) => self.rounds().len(),
+            Some(round) => round,
+        }
+    }
+
+    /// Clear previous reported result for `player_id`
+    fn clear_reported_result(&mut self, player_id: PlayerId) -> Result<(), Error> {
+        let Some(i) = self.match_to_play_of(player_id) else {
+            return Err(Error::NoMatchToPlay(player_id));
+        };
+        let m_to_clear = self.matches[i].clear_reported_result(player_id);
+        self.matches.to_mut().set(i, m_to_clear);
+        Ok(())
+    }
+
+    /// Run `update` and undo what it did to matches when it fails
+    fn attempt<T>(
+        &mut self,
+        update: impl FnOnce(&mut Self) -> Result<T, Error>,
+    ) -> Result<T, Error> {
+        let mark = self.matches.mark();
+        let result = update(self);
+        if result.is_err() {
+            self.matches.to_mut().rollback(mark);
+        }
+        result
+    }
+
+    /// Validate `match_id` and pair next round when it was the last match of
+    /// current round
+    fn validate_in_place(&mut self, match_id: MatchId) -> Result<(), Error> {
+        let Some(i) = self.matches.position(match_id) else {
+            return Err(Error::UnknownMatch(match_id));
+        };
+        let (updated_match, _, _) = self.matches[i].update_outcome()?;
+        self.attempt(|p| {
+            p.matches.to_mut().set(i, updated_match);
+            p.generate_next_round_if_needed()
+        })
+    }
+}
+
+/// Partition swiss `matches` into the rounds they record
+pub(crate) fn partition_by_round(matches: &[Match]) -> Vec<Vec<Match>> {
+    // brackets saved before matches recorded their round
+    let (unnumbered, numbered): (Vec<Match>, Vec<Match>) =
+        matches.iter().partition(|m| m.get_round() == 0);
+    let mut rounds = partition_by_players_showing_up_again(&unnumbered);
+    let mut numbered_rounds: Vec<Vec<Match>> = vec![];
+    for m in numbered {
+        match numbered_rounds
+            .iter_mut()
+            .find(|r| r[0].get_round() == m.get_round())
+        {
+            Some(round) => round.push(m),
+            None => numbered_rounds.push(vec![m]),
+        }
+    }
+    numbered_rounds.sort_by_key(|r| r[0].get_round());
+    rounds.append(&mut numbered_rounds);
+    rounds
+}
+
+/// Partition swiss `matches` that do not record their round. A new round
+/// starts when a player shows up a second time.
+fn partition_by_players_showing_up_again(matches: &[Match]) -> Vec<Vec<Match>> {
+    let mut rounds: Vec<Vec<Match>> = vec![];
+    let mut round: Vec<Match> = vec![];
+    for m in matches {
+        let player_already_in_round = m.get_players().iter().any(|o| match o {
+            Opponent::Player(p) => round.iter().any(|other| other.contains(*p)),
+            Opponent::Unknown | Opponent::Bye => false,
+        });
+        if player_already_in_round {
+            rounds.push(std::mem::take(&mut round));
+        }
+        round.push(*m);
+    }
+    if !round.is_empty() {
+        rounds.push(round);
+    }
+    rounds
+}
+
+impl Progression for Step<'_> {
+    fn boxed(&self) -> Box<dyn Progression + '_> {
+        Box::new(self.clone())
+    }
+
+    fn into_matches(self: Box<Self>) -> Matches {
+        self.matches.into_owned()
+    }
+
+    fn disqualify(&mut self, player_id: PlayerId) -> Result<Vec<Match>, Error> {
+        if self.is_over() {
+            return Err(Error::TournamentIsOver);
+        }
+        if !self.seeding.contains(&player_id) {
+            return Err(Error::UnknownPlayer(player_id, self.seeding.clone()));
+        }
+        if self.is_disqualified(player_id) {
+            return Err(Error::ForbiddenDisqualified(player_id));
+        }
+        let mark = self.matches.mark();
+
+        if let Some(i) = self.match_to_play_of(player_id) {
+            let updated_match = self.matches[i].set_automatic_loser(player_id)?;
+            let updated_match = match updated_match.update_outcome() {
+                Ok((updated_match, _, _)) => updated_match,
+                Err(MatchError::MissingOpponent(_)) => updated_match,
+                Err(e) => return Err(e.into()),
+            };
+            self.attempt(|p| {
+                p.matches.to_mut().set(i, updated_match);
+                p.generate_next_round_if_needed()
+            })?;
+        } else {
+            // player is waiting for the next round: matches they played are
+            // left untouched and they are left out of next pairings
+            self.disqualified_between_rounds.push(player_id);
+            if let Err(e) = self.generate_next_round_if_needed() {
+                self.disqualified_between_rounds.pop();
+                return Err(e);
+            }
+        }
+        Ok(self.matches.newly_playable(mark))
+    }
+
+    fn get_format(&self) -> Format {
+        Format::Swiss {
+            rounds: NonZeroUsize::new(self.rounds),
+        }
+    }
+
+    fn is_over(&self) -> bool {
+        self.current_round_is_over()
+            && (self.rounds_generated() >= self.rounds
+                || self.seeding.len() - self.disqualified_players().len() < 2)
+    }
+
+    fn matches_progress(&self) -> (usize, usize) {
+        let left = self
+            .matches
+            .iter()
+            .filter(|m| m.is_over() || m.is_bye())
+            .count();
+        let active_players = self.seeding.len() - self.disqualified_players().len();
+        let remaining_rounds = self.rounds.saturating_sub(self.rounds_generated());
+        let right = self.matches.len() + remaining_rounds * active_players.div_ceil(2);
+
+        (left, right)
+    }
+
+    fn matches_to_play(&self) -> Vec<Match> {
+        self.matches
+            .iter()
+            .copied()
+            .filter(Match::needs_playing)
+            .collect()
+    }
+
+    fn next_opponent(&self, player_id: PlayerId) -> Result<(Opponent, MatchId), Error> {
+        if !self.seeding.contains(&player_id) {
+            return Err(Error::PlayerIsNotParticipant(player_id));
+        }
+
+        if self.matches.is_empty() {
+            return Err(Error::NoGeneratedMatches);
+        }
+
+        if self.is_disqualified(player_id) {
+            return Err(Error::Disqualified(player_id));
+        }
+
+        let Some(relevant_match) = self.match_to_play_of(player_id).map(|i| self.matches[i]) else {
+            if !self.is_over() {
+                return Err(Error::NoMatchToPlay(player_id));
+            }
+            return match self.standings().first() {
+                Some(s) if s.player_id == player_id => Err(Error::NoNextMatch(player_id)),
+                _ => Err(Error::Eliminated(player_id)),
+            };
+        };
+
+        let opponent = match relevant_match.get_players() {
+            [Opponent::Player(p1), Opponent::Player(p2)] if p1 == player_id => Opponent::Player(p2),
+            [Opponent::Player(p1), Opponent::Player(p2)] if p2 == player_id => Opponent::Player(p1),
+            _ => Opponent::Unknown,
+        };
+        Ok((opponent, relevant_match.get_id()))
+    }
+
+    fn is_disqualified(&self, player_id: PlayerId) -> bool {
+        self.disqualified_between_rounds.contains(&player_id)
+            || self.matches.is_disqualified(player_id)
+    }
+
+    fn report(
+        &mut self,
+        player_id: PlayerId,
+        result: (i8, i8),
+    ) -> Result<(MatchId, Vec<Match>), Error> {
+        if !self.seeding.contains(&player_id) {
+            return Err(Error::UnknownPlayer(player_id, self.seeding.clone()));
+        }
+        if self.is_over() {
+            return Err(Error::TournamentIsOver);
+        }
+        if self.is_disqualified(player_id) {
+            return Err(Error::ForbiddenDisqualified(player_id));
+        }
+        let Some(i) = self.match_to_play_of(player_id) else {
+            return Err(Error::NoMatchToPlay(player_id));
+        };
+        let affected_match_id = self.matches[i].get_id();
+        let mark = self.matches.mark();
+        self.attempt(|p| {
+            p.record_reported_result(affected_match_id, result, player_id)?;
+            if p.automatic_progression {
+                match p.validate_in_place(affected_match_id) {
+                    Ok(())
+                    | Err(Error::MatchUpdate(MatchError::PlayersReportedDifferentMatchOutcome(
+                        _,
+                        _,
+                    ))) => {}
+                    Err(e) => return Err(e),
+                }
+            }
+            Ok(())
+        })?;
+
+        Ok((affected_match_id, self.matches.newly_playable(mark)))
+    }
+
+    fn organiser_reports(
+        &mut self,
+        player1: PlayerId,
+        result: (i8, i8),
+        player2: PlayerId,
+    ) -> Result<(MatchId, Vec<Match>), Error> {
+        let Some(i) = self.match_between(player1, player2) else {
+            return Err(Error::NoMatchToPlay(player1));
+        };
+        let affected_match_id = self.matches[i].get_id();
+        let result_player_1 = ReportedResult(result);
+        self.attempt(|p| {
+            p.clear_reported_result(player1)?;
+            p.clear_reported_result(player2)?;
+            p.report(player1, result_player_1.0)?;
+            let (_, new_matches) = p.report(player2, result_player_1.reverse().0)?;
+            Ok((affected_match_id, new_matches))
+        })
+    }
+
+    fn record_reported_result(
+        &mut self,
+        match_id: MatchId,
+        result: (i8, i8),
+        player_id: PlayerId,
+    ) -> Result<(), Error> {
+        let Some(i) = self.matches.position(match_id) else {
+            return Err(Error::UnknownMatch(match_id));
+        };
+
+        let updated_match =
+            self.matches[i].update_reported_result(player_id, ReportedResult(result))?;
+        self.matches.to_mut().set(i, updated_match);
+        Ok(())
+    }
+
+    fn validate(&mut self, match_id: MatchId) -> Result<Vec<Match>, Error> {
+        let mark = self.matches.mark();
+        self.validate_in_place(match_id)?;
+        Ok(self.matches.newly_playable(mark))
+    }
+
+    fn reopen(&mut self, match_id: MatchId, cascade: bool) -> Result<Vec<Match>, Error> {
+        let Some(m) = self.matches.find(match_id) else {
+            return Err(Error::UnknownMatch(match_id));
+        };
+        if m.is_bye() {
+            return Err(Error::ByeCannotBeReopened(match_id));
+        }
+        if let Opponent::Player(disqualified) = m.get_automatic_loser() {
+            return Err(Error::ForbiddenDisqualified(disqualified));
+        }
+        // later rounds were paired using this result: they are dropped and
+        // paired again once the round of reopened match is over
+        let rounds = partition_by_round(&self.matches);
+        let Some(round) = rounds
+            .iter()
+            .position(|r| r.iter().any(|other| other.get_id() == match_id))
+        else {
+            return Err(Error::UnknownMatch(match_id));
+        };
+        if !cascade {
+            if let Some(downstream) = rounds
+                .iter()
+                .skip(round + 1)
+                .flatten()
+                .find(|other| !other.is_bye() && other.has_result())
+            {
+                return Err(Error::DownstreamMatchHasResult(
+                    match_id,
+                    downstream.get_id(),
+                ));
+            }
+        }
+        let matches = rounds
+            .into_iter()
+            .take(round + 1)
+            .flatten()
+            .map(|other| {
+                if other.get_id() == match_id {
+                    m.reopen()
+                } else {
+                    other
+                }
+            })
+            .collect::<Vec<_>>();
+        self.matches.to_mut().replace_all(matches);
+        Ok(self.matches_to_play())
+    }
+
+    fn check_all_assertions(&self) {
+        super::assert_disqualified_at_most_once(&self.matches, &self.seeding);
+        for m in self.matches.iter() {
+            assert_match_is_well_formed(m);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Step;
+    use crate::{
+        bracket::matches::{Error, Progression},
+        opponent::Opponent,
+        player::{Id as PlayerId, Player},
+    };
+    use std::{borrow::Cow, num::NonZeroUsize};
+
+    fn seeding(n: usize) -> Vec<PlayerId> {
+        (1..=n)
+            .map(|i| Player::new(format!("p{i}")).get_id())
+            .collect()
+    }
+
+    /// Stronger seed wins every match to play
+    fn play_round(s: Step) -> Step {
+        let mut s = s;
+        for m in s.matches_to_play() {
+            let [Opponent::Player(p1), Opponent::Player(p2)] = m.get_players() else {
+                panic!("expected players");
+            };
+            s.organiser_reports(p1, (2, 1), p2).expect("matches");
+        }
+        s
+    }
+
+    #[test]
+    fn run_8_man_swiss() {
+        let seeding = seeding(8);
+        let mut s = Step::new(None, &seeding, true, None).expect("step");
+        assert_eq!(s.rounds, 3);
+        assert_eq!(s.matches_to_play().len(), 4);
+        for round in 1..=3 {
+            assert!(!s.is_over(), "over before round {round}");
+            s = play_round(s);
+            s.check_all_assertions();
+        }
+        assert!(s.is_over());
+        assert_eq!(s.rounds().len(), 3);
+        assert_eq!(s.matches_progress(), (12, 12));
+
+        // no rematches
+        for (i, p1) in seeding.iter().enumerate() {
+            for p2 in seeding.iter().skip(i + 1) {
+                assert!(
+                    s.matches
+                        .iter()
+                        .filter(|m| m.contains(*p1) && m.contains(*p2))
+                        .count()
+                        < 2
+                );
+            }
+        }
+
+        let standings = s.standings();
+        assert_eq!(standings[0].player_id, seeding[0]);
+        assert_eq!(standings[0].wins, 3);
+        assert_eq!(standings[7].wins, 0);
+    }
+
+    #[test]
+    fn odd_players_get_at_most_one_bye() {
+        let seeding = seeding(5);
+        let mut s = Step::new(None, &seeding, true, NonZeroUsize::new(4)).expect("step");
+        while !s.is_over() {
+            s = play_round(s);
+        }
+        assert_eq!(s.rounds().len(), 4);
+        for p in &seeding {
+            assert!(
+                s.matches
+                    .iter()
+                    .filter(|m| m.is_bye() && m.contains(*p))
+                    .count()
+                    < 2,
+                "{p} got more than one bye"
+            );
+        }
+    }
+
+    #[test]
+    fn disqualified_player_is_not_paired_again() {
+        let seeding = seeding(4);
+        let mut s = Step::new(None, &seeding, true, NonZeroUsize::new(3)).expect("step");
+        s.disqualify(seeding[3]).expect("matches");
+        assert!(s.is_disqualified(seeding[3]));
+        while !s.is_over() {
+            s = play_round(s);
+        }
+        let rounds = s.rounds();
+        assert_eq!(rounds.len(), 3);
+        for round in &rounds[1..] {
+            assert!(!round.iter().any(|m| m.contains(seeding[3])));
+            assert!(round.iter().any(super::Match::is_bye));
+        }
+        // p1 and p3 played in round 1, so p1 gets the bye in round 3
+        assert!(rounds[2]
+            .iter()
+            .any(|m| m.is_bye() && m.contains(seeding[0])));
+        s.check_all_assertions();
+    }
+
+    #[test]
+    fn standings_use_buchholz_score() {
+        let seeding = seeding(4);
+        let mut s = Step::new(None, &seeding, true, NonZeroUsize::new(2)).expect("step");
+        // round 1: 1 beats 3, 2 beats 4
+        s = play_round(s);
+        // round 2: 1 beats 2, 3 beats 4
+        s = play_round(s);
+        assert!(s.is_over());
+        let standings = s.standings();
+        // p2 and p3 have 1 win each. p2 played p1 (2 wins) and p4 (0 wins),
+        // p3 played p1 (2 wins) and p4 (0 wins): seeding breaks the tie
+        assert_eq!(standings[0].player_id, seeding[0]);
+        assert_eq!(standings[0].buchholz, 2);
+        assert_eq!(standings[1].player_id, seeding[1]);
+        assert_eq!(standings[1].buchholz, 2);
+        assert_eq!(standings[3].player_id, seeding[3]);
+        assert_eq!(standings[3].buchholz, 2);
+    }
+
+    #[test]
+    fn match_without_opponent_does_not_count_towards_buchholz_score() {
+        let seeding = seeding(4);
+        let mut s = Step::new(None, &seeding, true, NonZeroUsize::new(2)).expect("step");
+        // round 1: 1 beats 3, 2 beats 4
+        s = play_round(s);
+        // 3 is removed from their match against 1
+        let i = s
+            .matches
+            .iter()
+            .position(|m| m.contains(seeding[0]))
+            .expect("match of 1");
+        let m = s.matches[i].remove_player(seeding[2]);
+        s.matches.to_mut().set(i, m);
+
+        let standing = s.standing_of(seeding[0]);
+        assert_eq!(standing.wins, 1);
+        assert_eq!(standing.buchholz, 0);
+        assert_eq!(standing.game_differential, 0);
+    }
+
+    #[test]
+    fn rounds_do_not_depend_on_match_order() {
+        let seeding = seeding(8);
+        let mut s = Step::new(None, &seeding, true, None).expect("step");
+        s = play_round(s);
+        s = play_round(s);
+        let rounds = s.rounds();
+        assert_eq!(rounds.len(), 3);
+
+        s.matches = Cow::Owned(s.matches.iter().rev().copied().collect());
+        assert_eq!(s.rounds().len(), 3);
+        for (round, reordered) in rounds.iter().zip(s.rounds()) {
+            assert_eq!(round.len(), reordered.len());
+            assert!(round.iter().all(|m| reordered.contains(m)));
+        }
+    }
+
+    #[test]
+    fn matches_saved_without_their_round_are_partitioned_by_players() {
+        let seeding = seeding(8);
+        let mut s = Step::new(None, &seeding, true, None).expect("step");
+        s = play_round(s);
+        s = play_round(s);
+        s.matches = Cow::Owned(
+            s.matches
+                .iter()
+                .map(|m| {
+                    let mut json = serde_json::to_value(m).expect("serialized");
+                    json.as_object_mut().expect("match").remove("round");
+                    serde_json::from_value(json).expect("deserialized")
+                })
+                .collect(),
+        );
+
+        assert_eq!(
+            s.rounds().iter().map(Vec::len).collect::<Vec<_>>(),
+            vec![4, 4, 4]
+        );
+    }
+
+    #[test]
+    fn organiser_cannot_report_result_between_players_not_paired_together() {
+        let seeding = seeding(4);
+        let mut s = Step::new(None, &seeding, true, None).expect("step");
+        // round 1: 1 plays 3, 2 plays 4
+        let e = s
+            .organiser_reports(seeding[0], (2, 0), seeding[1])
+            .expect_err("players are not paired together");
+        assert!(
+            matches!(e, Error::NoMatchToPlay(p) if p == seeding[0]),
+            "got {e:?}"
+        );
+        assert!(s
+            .matches
+            .iter()
+            .all(|m| m.get_winner() == Opponent::Unknown));
+    }
+}
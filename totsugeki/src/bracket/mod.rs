@@ -2,21 +2,45 @@
 
 mod assertions;
 pub mod builder;
+mod byes;
+pub mod disputes;
 mod disqualification;
 pub mod double_elimination_variant;
+pub mod events;
+mod games;
 mod getter_setter;
+pub mod lifecycle;
+mod match_lifecycle;
+pub mod match_rules;
 pub mod matches;
+mod overrides;
 mod participants;
 mod progression;
 mod query_state;
+mod reopen;
+pub mod round_robin_variant;
+pub mod schedule;
 mod seeding;
 pub mod single_elimination_variant;
+pub mod standings;
+pub mod stations;
+pub mod swiss_variant;
+pub mod validation;
 mod winner_bracket;
 
 use crate::{
-    bracket::{matches::Error as ProgressError, Id as BracketId},
+    bracket::{
+        events::{generated_match_ids, BracketEvent, Entry},
+        match_lifecycle::sync_lifecycles,
+        match_rules::MatchRules,
+        matches::{index::Matches, Error as ProgressError},
+        stations::{Id as StationId, Station},
+        validation::ValidationIssue,
+        Id as BracketId,
+    },
     format::{Format, ParsingError as FormatParsingError},
-    matches::{Error as MatchError, Id as MatchId, Match, MatchParsingError},
+    game::{Error as GameError, GameResult},
+    matches::{Error as MatchError, Id as MatchId, Match, MatchParsingError, SetLength},
     player::{Error as PlayerError, Id as PlayerId, Participants, Player},
     seeding::{
         Error as SeedingError, Method as SeedingMethod, ParsingError as SeedingParsingError,
@@ -25,7 +49,10 @@ use crate::{
 };
 use chrono::prelude::*;
 use serde::{Deserialize, Serialize};
-use std::collections::{HashMap, HashSet};
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+};
 use thiserror::Error;
 use uuid::Uuid;
 
@@ -79,12 +106,60 @@ pub enum Error {
     /// Cannot update match
     #[error("{1}\nBracket: {0}")]
     MatchUpdate(BracketId, MatchError),
+    /// Match rules use a set length that cannot be won
+    #[error("{1} cannot be won. Best of N needs an odd N and at most 127 games can be needed to win\nBracket: {0}")]
+    InvalidSetLength(BracketId, SetLength),
+    /// Cannot derive set score from reported games
+    #[error("{1}\nBracket: {0}")]
+    GameUpdate(BracketId, GameError),
     /// Referred match is unknown
     #[error("Match {1} is unknown\nBracket: {0}")]
     UnknownMatch(BracketId, MatchId),
     /// Update to match could not happen
     #[error("There is no match to update\nBracket: {0}")]
     NoMatchToUpdate(BracketId, Vec<Match>, MatchId),
+    /// Match cannot be reopened without reopening a match that follows it
+    #[error("Match {1} cannot be reopened because match {2} depends on its result. Reopen match {2} first or cascade\nBracket: {0}")]
+    DownstreamMatchHasResult(BracketId, MatchId, MatchId),
+    /// Match where a player has a bye cannot be reopened
+    #[error("Match {1} is a bye and cannot be reopened\nBracket: {0}")]
+    ByeCannotBeReopened(BracketId, MatchId),
+    /// Match does not say where its players go next
+    #[error("Match {1} is not linked to the match where its players go next\nBracket: {0}")]
+    MissingLink(BracketId, MatchId),
+    /// Number of matches does not fit format and participants
+    #[error(
+        "Found {1} matches where {2} are expected for this format and participants\nBracket: {0}"
+    )]
+    UnexpectedMatchCount(BracketId, usize, usize),
+    /// Only a match where players reported conflicting results can have its
+    /// dispute resolved
+    #[error("Match {1} is not disputed\nBracket: {0}")]
+    MatchIsNotDisputed(BracketId, MatchId),
+    /// Referred station is unknown
+    #[error("Station {1} is unknown\nBracket: {0}")]
+    UnknownStation(BracketId, StationId),
+    /// Matches can only be pinned to stream stations
+    #[error("Station {1} is not streamed\nBracket: {0}")]
+    NotAStreamStation(BracketId, StationId),
+    /// Bracket cannot be replayed from events without its creation
+    #[error("Event log does not start with the creation of the bracket")]
+    MissingCreationEvent,
+    /// Match already has a winner
+    #[error("Match {1} is over. Reopen it first\nBracket: {0}")]
+    MatchIsOver(BracketId, MatchId),
+    /// Player has reported a result or played a match
+    #[error("{1} has already played\nBracket: {0}")]
+    AlreadyPlayed(BracketId, Player),
+    /// Player cannot take their own place
+    #[error("{1} cannot be swapped with themselves\nBracket: {0}")]
+    SwappedWithThemselves(BracketId, Player),
+    /// Bracket cannot be finished while matches remain to be played
+    #[error("Bracket {0} is not over yet")]
+    NotOver(BracketId),
+    /// Bracket breaks some of its invariants
+    #[error("Bracket is malformed: {}\nBracket: {0}", .1.iter().map(ToString::to_string).collect::<Vec<_>>().join(", "))]
+    Malformed(BracketId, Vec<ValidationIssue>),
 }
 
 /// Bracket identifier
@@ -111,7 +186,7 @@ pub struct Bracket {
     /// Players of this bracket
     participants: Participants,
     /// Matches from this bracket, sorted by rounds
-    matches: Vec<Match>,
+    matches: Matches,
     /// Bracket format
     format: Format,
     /// Seeding method used for this bracket
@@ -124,6 +199,24 @@ pub struct Bracket {
     automatic_match_progression: bool,
     /// When set to `true`, bars new participants from entering bracket
     is_closed: bool,
+    /// Everything that happened to this bracket, from oldest to newest
+    #[serde(default)]
+    events: Vec<Entry>,
+    /// Set length of matches. When not set, any score is accepted
+    #[serde(default)]
+    match_rules: Option<MatchRules>,
+    /// Games of matches reported game by game, with who reported them
+    #[serde(default)]
+    games: HashMap<MatchId, Vec<(PlayerId, Vec<GameResult>)>>,
+    /// Stations where matches are played
+    #[serde(default)]
+    stations: Vec<Station>,
+    /// Value seeding the random draw of participants, when drawn randomly
+    #[serde(default)]
+    random_seed: Option<u64>,
+    /// Swiss players disqualified while waiting for the next round
+    #[serde(default)]
+    disqualified_between_rounds: Vec<PlayerId>,
 }
 
 impl Bracket {
@@ -136,18 +229,33 @@ impl Bracket {
         start_time: DateTime<Utc>,
         automatic_match_validation: bool,
     ) -> Self {
+        let bracket_id = BracketId::new_v4();
         Self {
-            bracket_id: BracketId::new_v4(),
+            bracket_id,
             bracket_name: name.to_string(),
             participants: Participants::default(),
-            matches: vec![],
+            matches: Matches::default(),
             format,
             seeding_method,
             start_time,
             accept_match_results: false,
             automatic_match_progression: automatic_match_validation,
             is_closed: false,
+            events: vec![],
+            match_rules: None,
+            games: HashMap::new(),
+            stations: vec![],
+            random_seed: None,
+            disqualified_between_rounds: vec![],
         }
+        .log(BracketEvent::Created {
+            bracket_id,
+            name: name.to_string(),
+            format,
+            seeding_method,
+            start_time,
+            automatic_match_validation,
+        })
     }
 
     /// Add participant to bracket
@@ -161,12 +269,10 @@ impl Bracket {
                 "Bracket has started. You may not enter at this time.".into(),
             ));
         }
-        let participants = self
-            .participants
-            .clone()
-            .add_participant(Player::new(name.into()))?;
+        let player = Player::new(name.into());
+        let participants = self.participants.clone().add_participant(player.clone())?;
         let bracket = self.clone().regenerate_matches(participants)?;
-        Ok(bracket)
+        Ok(bracket.log(BracketEvent::ParticipantAdded { player }))
     }
 
     /// Report result for a match in this bracket. Returns updated bracket,
@@ -186,16 +292,35 @@ impl Bracket {
                 ". Match results are not yet accepted".into(),
             ));
         }
-        let p = self.format.get_progression(
-            self.matches.clone(),
-            &self.participants,
-            self.automatic_match_progression,
-        );
-        let (matches, affected_match_id, new_matches) = match p.report_result(player_id, result) {
+        let mut p = self
+            .format
+            .get_progression(
+                Cow::Borrowed(&self.matches),
+                &self.participants,
+                self.automatic_match_progression,
+                &self.disqualified_between_rounds,
+            )
+            .map_err(|e| self.get_from_progression_error(e))?;
+        // result is reported for the next match of player
+        if let Ok((_, match_id)) = p.next_opponent(player_id) {
+            self.check_score(match_id, result)?;
+        }
+        let (affected_match_id, new_matches) = match p.report(player_id, result) {
             Ok(el) => el,
             Err(e) => return Err(self.get_from_progression_error(e)),
         };
-        let bracket = Self { matches, ..self };
+        let matches = p.into_matches();
+        let generated_match_ids = generated_match_ids(&self.matches, &matches);
+        let at = Utc::now();
+        let matches = sync_lifecycles(&self.matches, matches, at);
+        let bracket = Self { matches, ..self }.free_stations().log_at(
+            BracketEvent::ResultReported {
+                player_id,
+                result,
+                generated_match_ids,
+            },
+            at,
+        );
         bracket.check_all_assertions();
         Ok((bracket, affected_match_id, new_matches))
     }
@@ -214,17 +339,40 @@ impl Bracket {
         result_player1: (i8, i8),
         player2: PlayerId,
     ) -> Result<(Bracket, MatchId, Vec<Match>), Error> {
-        let p = self.format.get_progression(
-            self.get_matches(),
-            &self.get_participants(),
-            self.automatic_match_progression,
-        );
-        let (matches, affected_match_id, new_matches) =
-            match p.tournament_organiser_reports_result(player1, result_player1, player2) {
+        let mut p = self
+            .format
+            .get_progression(
+                Cow::Borrowed(&self.matches),
+                &self.participants,
+                self.automatic_match_progression,
+                &self.disqualified_between_rounds,
+            )
+            .map_err(|e| self.get_from_progression_error(e))?;
+        if let Some(m) = self
+            .matches
+            .iter()
+            .find(|m| m.contains(player1) && m.contains(player2) && !m.is_over())
+        {
+            self.check_score(m.get_id(), result_player1)?;
+        }
+        let (affected_match_id, new_matches) =
+            match p.organiser_reports(player1, result_player1, player2) {
                 Ok(el) => el,
                 Err(e) => return Err(self.get_from_progression_error(e)),
             };
-        let bracket = Self { matches, ..self };
+        let matches = p.into_matches();
+        let generated_match_ids = generated_match_ids(&self.matches, &matches);
+        let at = Utc::now();
+        let matches = sync_lifecycles(&self.matches, matches, at);
+        let bracket = Self { matches, ..self }.free_stations().log_at(
+            BracketEvent::TournamentOrganiserReportedResult {
+                player1,
+                result: result_player1,
+                player2,
+                generated_match_ids,
+            },
+            at,
+        );
         bracket.check_all_assertions();
         Ok((bracket, affected_match_id, new_matches))
     }
@@ -242,25 +390,32 @@ impl Bracket {
             ));
         }
         let matches = self.matches_to_play();
+        let match_ids = self.matches.iter().map(Match::get_id).collect();
+        let at = Utc::now();
         let bracket = Self {
+            matches: sync_lifecycles(&[], self.matches, at),
             is_closed: true,
             accept_match_results: true,
             ..self
-        };
+        }
+        .log_at(BracketEvent::Started { match_ids }, at);
         bracket.check_all_assertions();
         Ok((bracket, matches))
     }
 
-    /// Returns all matches that can be played out
+    /// Returns all matches that can be played out. There are none in a
+    /// bracket whose matches cannot be read
     #[must_use]
     pub fn matches_to_play(&self) -> Vec<Match> {
         self.format
             .get_progression(
-                self.get_matches(),
-                &self.get_participants(),
+                Cow::Borrowed(&self.matches),
+                &self.participants,
                 self.automatic_match_progression,
+                &self.disqualified_between_rounds,
             )
-            .matches_to_play()
+            .map(|p| p.matches_to_play())
+            .unwrap_or_default()
     }
 
     /// Summarise bracket state
@@ -328,6 +483,16 @@ impl Bracket {
             ProgressError::NoMatchToUpdate(matches, m) => {
                 Error::NoMatchToUpdate(self.bracket_id, matches, m)
             }
+            ProgressError::DownstreamMatchHasResult(match_id, downstream) => {
+                Error::DownstreamMatchHasResult(self.bracket_id, match_id, downstream)
+            }
+            ProgressError::ByeCannotBeReopened(match_id) => {
+                Error::ByeCannotBeReopened(self.bracket_id, match_id)
+            }
+            ProgressError::MissingLink(match_id) => Error::MissingLink(self.bracket_id, match_id),
+            ProgressError::UnexpectedMatchCount(found, expected) => {
+                Error::UnexpectedMatchCount(self.bracket_id, found, expected)
+            }
         }
     }
 }
@@ -409,6 +574,6 @@ impl Default for Bracket {
 /// Errors while manipulating bracket
 #[derive(Debug)]
 pub enum PartitionError {
-    /// You need at least 3 players to perform this operation
+    /// Bracket does not have enough players to perform this operation
     NotEnoughPlayersInBracket,
 }
@@ -0,0 +1,325 @@
+//! Tournament organiser overrides of the normal flow of a bracket
+//!
+//! When reports cannot settle a match (for instance, a player leaves
+//! mid-set), the tournament organiser forces its winner. When a seeding
+//! mistake is noticed after the bracket started, two players who have yet to
+//! play swap places in the bracket.
+
+use crate::{
+    bracket::{
+        events::{generated_match_ids, BracketEvent},
+        match_lifecycle::sync_lifecycles,
+        matches::index::added_matches,
+        Bracket, Error,
+    },
+    matches::{Error as MatchError, Id as MatchId, Match, SetLength},
+    opponent::Opponent,
+    player::{Id as PlayerId, Participants},
+};
+use chrono::Utc;
+use std::borrow::Cow;
+
+impl Bracket {
+    /// Tournament organiser declares `winner` of `match_id` regardless of
+    /// reported results. Winner is credited with the number of games needed
+    /// to win the match. Returns updated bracket and new matches to play
+    ///
+    /// # Errors
+    /// thrown when bracket has not started, match is unknown, over or missing
+    /// an opponent or when `winner` does not play in this match
+    pub fn force_winner(
+        self,
+        match_id: MatchId,
+        winner: PlayerId,
+    ) -> Result<(Bracket, Vec<Match>), Error> {
+        if !self.accept_match_results {
+            return Err(Error::NotStarted(
+                self.bracket_id,
+                ". Cannot declare winner at this time.".into(),
+            ));
+        }
+        let Some(index) = self.matches.position(match_id) else {
+            return Err(Error::UnknownMatch(self.bracket_id, match_id));
+        };
+        let m = self.matches[index];
+        if m.get_winner() != Opponent::Unknown {
+            return Err(Error::MatchIsOver(self.bracket_id, match_id));
+        }
+        let [Opponent::Player(_), Opponent::Player(_)] = m.get_players() else {
+            return Err(Error::MatchUpdate(
+                self.bracket_id,
+                MatchError::MissingOpponent(m.get_players()),
+            ));
+        };
+        if !m.contains(winner) {
+            return Err(Error::MatchUpdate(
+                self.bracket_id,
+                MatchError::UnknownPlayer(winner, m.get_players()),
+            ));
+        }
+        let wins = self
+            .set_length_of(match_id)?
+            .map_or(1, SetLength::wins_needed);
+        let wins = i8::try_from(wins).unwrap_or(i8::MAX);
+
+        let old_matches = self.matches_to_play();
+        let mut matches = self.matches.clone();
+        matches.set(index, m.with_forced_result(winner, wins));
+        let mut p = self
+            .format
+            .get_progression(
+                Cow::Owned(matches),
+                &self.participants,
+                self.automatic_match_progression,
+                &self.disqualified_between_rounds,
+            )
+            .map_err(|e| self.get_from_progression_error(e))?;
+        if let Err(e) = p.validate(match_id) {
+            return Err(self.get_from_progression_error(e));
+        }
+        let matches = p.into_matches();
+        let generated_match_ids = generated_match_ids(&self.matches, &matches);
+        let at = Utc::now();
+        let matches = sync_lifecycles(&self.matches, matches, at);
+        let bracket = Self { matches, ..self }.free_stations().log_at(
+            BracketEvent::WinnerForced {
+                match_id,
+                winner,
+                generated_match_ids,
+            },
+            at,
+        );
+        let bracket = Self {
+            accept_match_results: !bracket.is_over(),
+            ..bracket
+        };
+        bracket.check_all_assertions();
+        let new_matches = added_matches(&old_matches, &bracket.matches_to_play());
+        Ok((bracket, new_matches))
+    }
+
+    /// Tournament organiser swaps `player1` and `player2` in the bracket,
+    /// including their seeding position. Neither player may have played or
+    /// reported a result. Matches of swapped players are new pairings: they
+    /// leave their station, are unpinned and need to be called again. Returns
+    /// updated bracket and matches to play of swapped players
+    ///
+    /// Before the bracket starts, use [`Bracket::update_seeding`] instead.
+    ///
+    /// # Errors
+    /// thrown when bracket has not started, one of the players is not a
+    /// participant, has already played or is swapped with themselves
+    pub fn swap_players(
+        self,
+        player1: PlayerId,
+        player2: PlayerId,
+    ) -> Result<(Bracket, Vec<Match>), Error> {
+        if !self.accept_match_results {
+            return Err(Error::NotStarted(
+                self.bracket_id,
+                ". Update seeding instead.".into(),
+            ));
+        }
+        let mut swapped = vec![];
+        for player_id in [player2, player1] {
+            let Some(player) = self.participants.get(player_id) else {
+                return Err(Error::UnknownPlayer(
+                    player_id,
+                    self.participants.clone(),
+                    self.bracket_id,
+                ));
+            };
+            if self.has_played(player_id) {
+                return Err(Error::AlreadyPlayed(self.bracket_id, player));
+            }
+            swapped.push(player);
+        }
+        if player1 == player2 {
+            return Err(Error::SwappedWithThemselves(
+                self.bracket_id,
+                swapped.swap_remove(0),
+            ));
+        }
+
+        let seeding = self
+            .participants
+            .get_players_list()
+            .into_iter()
+            .map(|p| match p.get_id() {
+                id if id == player1 => swapped[0].clone(),
+                id if id == player2 => swapped[1].clone(),
+                _ => p,
+            })
+            .collect::<Vec<_>>();
+        let participants = Participants::try_from(seeding)?;
+        let matches = self
+            .matches
+            .iter()
+            .map(|m| m.with_players_swapped(player1, player2))
+            .collect::<Vec<_>>();
+        let swapped_matches = matches
+            .iter()
+            .filter(|m| m.contains(player1) || m.contains(player2))
+            .map(Match::get_id)
+            .collect::<Vec<_>>();
+        let bracket = Self {
+            participants,
+            matches: matches.into(),
+            ..self
+        }
+        .unpin(&swapped_matches)
+        .free_stations()
+        .forget_games_of_reopened_matches()
+        .log(BracketEvent::PlayersSwapped { player1, player2 });
+        bracket.check_all_assertions();
+        let matches_to_play = bracket
+            .matches_to_play()
+            .into_iter()
+            .filter(|m| m.contains(player1) || m.contains(player2))
+            .collect();
+        Ok((bracket, matches_to_play))
+    }
+
+    /// Returns true if `player_id` has a match with a result or was
+    /// disqualified
+    pub(crate) fn has_played(&self, player_id: PlayerId) -> bool {
+        self.matches.iter().any(|m| {
+            m.contains(player_id)
+                && (m.has_result() || m.get_automatic_loser() != Opponent::Unknown)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        bracket::match_rules::MatchRules,
+        bracket::{builder::fixture, Bracket, Error},
+        format::Format,
+        matches::{SetLength, State},
+        opponent::Opponent,
+        player::Id as PlayerId,
+    };
+
+    fn started_bracket(n: usize) -> (Bracket, Vec<PlayerId>) {
+        fixture::started_bracket(Format::default(), n)
+    }
+
+    #[test]
+    fn tournament_organiser_forces_winner_despite_conflicting_reports() {
+        let (bracket, seeding) = started_bracket(4);
+        let bracket = bracket
+            .set_match_rules(MatchRules::new(SetLength::BestOf(3)))
+            .expect("match rules");
+        let (bracket, match_id, _) = bracket.report_result(seeding[0], (2, 1)).expect("report");
+        let (bracket, _, _) = bracket.report_result(seeding[3], (2, 0)).expect("report");
+
+        let (bracket, new_matches) = bracket.force_winner(match_id, seeding[3]).expect("forced");
+
+        let m = bracket
+            .get_matches()
+            .into_iter()
+            .find(|m| m.get_id() == match_id)
+            .expect("match");
+        assert_eq!(m.get_winner(), Opponent::Player(seeding[3]));
+        assert_eq!(m.get_score(), (0, 2));
+        assert!(
+            new_matches.is_empty(),
+            "other winner bracket match is pending"
+        );
+        assert!(matches!(
+            bracket.clone().force_winner(match_id, seeding[0]),
+            Err(Error::MatchIsOver(_, _))
+        ));
+
+        // 4th seed meets 2nd or 3rd seed in winner finals
+        let other = bracket
+            .matches_to_play()
+            .into_iter()
+            .find(|m| m.contains(seeding[1]))
+            .expect("match");
+        let (bracket, new_matches) = bracket
+            .force_winner(other.get_id(), seeding[2])
+            .expect("forced");
+        assert!(new_matches
+            .iter()
+            .any(|m| m.contains(seeding[3]) && m.contains(seeding[2])));
+        let replayed = Bracket::replay(&bracket.get_events()).expect("replayed");
+        assert_eq!(replayed.get_matches(), bracket.get_matches());
+    }
+
+    #[test]
+    fn players_who_have_not_played_can_be_swapped() {
+        let (bracket, seeding) = started_bracket(8);
+        let (bracket, _, _) = bracket.report_result(seeding[0], (2, 0)).expect("report");
+
+        assert!(matches!(
+            bracket.clone().swap_players(seeding[7], seeding[1]),
+            Err(Error::AlreadyPlayed(_, _))
+        ));
+        // 2nd seed was meant to be 4th seed
+        let (bracket, matches_to_play) = bracket
+            .swap_players(seeding[1], seeding[3])
+            .expect("swapped");
+
+        let mut expected = seeding.clone();
+        expected.swap(1, 3);
+        assert_eq!(bracket.get_participants().get_seeding(), expected);
+        assert_eq!(matches_to_play.len(), 2);
+        // swapped players play from their new seeding position
+        for m in matches_to_play {
+            let [top_seed, bottom_seed] = m.get_seeds();
+            assert_eq!(
+                m.get_players(),
+                [top_seed, bottom_seed].map(|seed| Opponent::Player(expected[seed - 1]))
+            );
+        }
+        let replayed = Bracket::replay(&bracket.get_events()).expect("replayed");
+        assert_eq!(replayed.get_matches(), bracket.get_matches());
+    }
+
+    #[test]
+    fn player_cannot_be_swapped_with_themselves() {
+        let (bracket, seeding) = started_bracket(8);
+
+        assert!(matches!(
+            bracket.swap_players(seeding[1], seeding[1]),
+            Err(Error::SwappedWithThemselves(_, _))
+        ));
+    }
+
+    #[test]
+    fn swapped_matches_leave_their_station_and_are_called_again() {
+        let (bracket, seeding) = started_bracket(8);
+        let (bracket, stream) = bracket.add_station("stream", true);
+        let match_id = bracket
+            .matches_to_play()
+            .into_iter()
+            .find(|m| m.contains(seeding[1]))
+            .expect("match")
+            .get_id();
+        let bracket = bracket
+            .pin_match_to_station(match_id, stream)
+            .expect("pinned");
+        let (bracket, assignments) = bracket.assign_stations();
+        assert_eq!(assignments, vec![(stream, match_id)]);
+
+        let (bracket, matches_to_play) = bracket
+            .swap_players(seeding[1], seeding[3])
+            .expect("swapped");
+
+        assert!(matches_to_play
+            .iter()
+            .all(|m| m.get_state() == State::Ready));
+        let station = bracket
+            .get_stations()
+            .into_iter()
+            .find(|s| s.get_id() == stream)
+            .expect("station");
+        assert_eq!(station.get_current_match(), None);
+        assert!(station.get_pinned_matches().is_empty());
+        let replayed = Bracket::replay(&bracket.get_events()).expect("replayed");
+        assert_eq!(replayed.get_matches(), bracket.get_matches());
+        assert_eq!(replayed.get_stations(), bracket.get_stations());
+    }
+}
@@ -1,7 +1,7 @@
 //! Add and remove or disqualify participants from bracket. Let them
 //! join/forfeit
 
-use super::{Bracket, Error};
+use super::{events::BracketEvent, Bracket, Error};
 use crate::player::{Id as PlayerId, Participants, Player};
 
 impl Bracket {
@@ -14,7 +14,7 @@ impl Bracket {
         self,
         updated_participants: Participants,
     ) -> Result<Self, Error> {
-        let matches = if updated_participants.len() < 3 {
+        let matches = if updated_participants.len() < self.format.minimum_number_of_participants() {
             vec![]
         } else {
             self.format.generate_matches(
@@ -27,7 +27,7 @@ impl Bracket {
         };
         Ok(Self {
             participants: updated_participants,
-            matches,
+            matches: matches.into(),
             ..self
         })
     }
@@ -45,7 +45,7 @@ impl Bracket {
     /// # Errors
     /// thrown when math overflow happens
     pub fn generate_matches(self) -> Result<Self, Error> {
-        let matches = if self.participants.len() < 3 {
+        let matches = if self.participants.len() < self.format.minimum_number_of_participants() {
             vec![]
         } else {
             self.format.generate_matches(
@@ -57,7 +57,10 @@ impl Bracket {
                     .collect::<Vec<_>>(),
             )?
         };
-        Ok(Self { matches, ..self })
+        Ok(Self {
+            matches: matches.into(),
+            ..self
+        })
     }
 
     /// Let `player` join participants and returns an updated version of the
@@ -70,13 +73,18 @@ impl Bracket {
             return Err(Error::BarredFromEntering(player.get_id(), self.get_id()));
         }
         let bracket = Self {
-            participants: self.participants.clone().add_participant(player)?,
+            participants: self.participants.clone().add_participant(player.clone())?,
             ..self
         };
-        bracket.clone().regenerate_matches(bracket.participants)
+        Ok(bracket
+            .clone()
+            .regenerate_matches(bracket.participants)?
+            .log(BracketEvent::ParticipantAdded { player }))
     }
 
-    /// Let `player` join participants and returns an updated version of the bracket
+    /// Let `player` join participants and returns an updated version of the bracket.
+    /// Consecutive unchecked joins are logged as a single event, which
+    /// replays them without checks either
     ///
     /// # Errors
     /// Thrown when bracket has already started
@@ -85,9 +93,10 @@ impl Bracket {
             return Err(Error::BarredFromEntering(player.get_id(), self.get_id()));
         }
         Ok(Self {
-            participants: self.participants.unchecked_add_participant(player),
+            participants: self.participants.unchecked_add_participant(player.clone()),
             ..self
-        })
+        }
+        .log_unchecked_join(player))
     }
 
     /// Remove participant, regenerate matches and return updated bracket
@@ -103,14 +112,76 @@ impl Bracket {
             ));
         }
         let updated_participants = self.participants.clone().remove(participant_id);
-        self.regenerate_matches(updated_participants)
+        Ok(self
+            .regenerate_matches(updated_participants)?
+            .log(BracketEvent::ParticipantRemoved {
+                player_id: participant_id,
+            }))
+    }
+
+    /// Replace `participant_id` with `substitute` at the same seeding
+    /// position and in every match where they play. Unlike removing a
+    /// participant, matches are kept, so it is allowed after the bracket
+    /// started as long as the replaced participant has not played
+    ///
+    /// # Errors
+    /// thrown if participant does not belong in bracket or has already
+    /// played or if substitute is already a participant
+    pub fn substitute_participant(
+        self,
+        participant_id: PlayerId,
+        substitute: Player,
+    ) -> Result<Self, Error> {
+        let Some(player) = self.participants.get(participant_id) else {
+            return Err(Error::UnknownPlayer(
+                participant_id,
+                self.participants.clone(),
+                self.bracket_id,
+            ));
+        };
+        if self.has_played(participant_id) {
+            return Err(Error::AlreadyPlayed(self.bracket_id, player));
+        }
+        let participants = self
+            .participants
+            .clone()
+            .replace(participant_id, substitute.clone())?;
+        let substituted_matches = self
+            .matches
+            .matches_of(participant_id)
+            .iter()
+            .map(|i| self.matches[*i].get_id())
+            .collect::<Vec<_>>();
+        // substitute does not play in any match yet
+        let matches = self
+            .matches
+            .iter()
+            .map(|m| m.with_players_swapped(participant_id, substitute.get_id()))
+            .collect();
+        let bracket = Self {
+            participants,
+            matches,
+            ..self
+        }
+        .unpin(&substituted_matches)
+        .free_stations()
+        .log(BracketEvent::ParticipantSubstituted {
+            player_id: participant_id,
+            substitute,
+        });
+        if !bracket.matches.is_empty() {
+            bracket.check_all_assertions();
+        }
+        Ok(bracket)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{bracket::Format, seeding::Method as SeedingMethod};
+    use crate::{
+        bracket::Format, format::Playoffs, matches::State, seeding::Method as SeedingMethod,
+    };
     use chrono::prelude::*;
 
     #[test]
@@ -132,7 +203,9 @@ mod tests {
     #[test]
     fn closing_bracket_will_deny_new_participants_from_entering() {
         let mut bracket = Bracket {
-            format: Format::SingleElimination,
+            format: Format::SingleElimination {
+                playoffs: Playoffs::Disabled,
+            },
             ..Bracket::default()
         };
         for i in 1..=3 {
@@ -159,7 +232,9 @@ mod tests {
     #[test]
     fn starting_bracket_will_deny_new_participants_from_entering() {
         let mut bracket = Bracket {
-            format: Format::SingleElimination,
+            format: Format::SingleElimination {
+                playoffs: Playoffs::Disabled,
+            },
             ..Bracket::default()
         };
         for i in 1..=3 {
@@ -180,4 +255,106 @@ mod tests {
         assert_eq!(id, player_id);
         assert_eq!(b_id, bracket_id);
     }
+
+    #[test]
+    fn participant_is_substituted_until_they_play() {
+        let mut bracket = Bracket::new(
+            "name",
+            Format::default(),
+            SeedingMethod::default(),
+            Utc.with_ymd_and_hms(2000, 1, 1, 0, 0, 0).unwrap(),
+            true,
+        );
+        for i in 1..=4 {
+            bracket = bracket
+                .add_participant(format!("p{i}").as_str())
+                .expect("participant");
+        }
+        let seeding = bracket.get_participants().get_seeding();
+        let (bracket, _) = bracket.start().expect("start");
+        let (bracket, _, _) = bracket.report_result(seeding[0], (2, 0)).expect("report");
+
+        let substitute = Player::new("substitute".into());
+        assert!(matches!(
+            bracket
+                .clone()
+                .substitute_participant(seeding[3], substitute.clone()),
+            Err(Error::AlreadyPlayed(_, _))
+        ));
+        let bracket = bracket
+            .substitute_participant(seeding[2], substitute.clone())
+            .expect("substituted");
+
+        // substitute takes the 3rd seed and their match against the 2nd seed
+        let mut expected = seeding.clone();
+        expected[2] = substitute.get_id();
+        assert_eq!(bracket.get_participants().get_seeding(), expected);
+        assert!(bracket
+            .matches_to_play()
+            .iter()
+            .any(|m| m.contains(seeding[1]) && m.contains(substitute.get_id())));
+        assert!(bracket
+            .get_matches()
+            .iter()
+            .all(|m| !m.contains(seeding[2])));
+        assert!(matches!(
+            bracket
+                .clone()
+                .substitute_participant(seeding[1], substitute.clone()),
+            Err(Error::PlayerUpdate(_))
+        ));
+        let replayed = Bracket::replay(&bracket.get_events()).expect("replayed");
+        assert_eq!(replayed.get_matches(), bracket.get_matches());
+    }
+
+    #[test]
+    fn substituted_matches_leave_their_station_and_are_called_again() {
+        let mut bracket = Bracket::new(
+            "name",
+            Format::default(),
+            SeedingMethod::default(),
+            Utc.with_ymd_and_hms(2000, 1, 1, 0, 0, 0).unwrap(),
+            true,
+        );
+        for i in 1..=4 {
+            bracket = bracket
+                .add_participant(format!("p{i}").as_str())
+                .expect("participant");
+        }
+        let seeding = bracket.get_participants().get_seeding();
+        let (bracket, _) = bracket.start().expect("start");
+        let (bracket, stream) = bracket.add_station("stream", true);
+        let match_id = bracket
+            .matches_to_play()
+            .into_iter()
+            .find(|m| m.contains(seeding[2]))
+            .expect("match")
+            .get_id();
+        let bracket = bracket
+            .pin_match_to_station(match_id, stream)
+            .expect("pinned");
+        let (bracket, assignments) = bracket.assign_stations();
+        assert_eq!(assignments, vec![(stream, match_id)]);
+
+        let bracket = bracket
+            .substitute_participant(seeding[2], Player::new("substitute".into()))
+            .expect("substituted");
+
+        let m = bracket
+            .get_matches()
+            .into_iter()
+            .find(|m| m.get_id() == match_id)
+            .expect("match");
+        assert_eq!(m.get_state(), State::Ready);
+        let station = bracket
+            .get_stations()
+            .into_iter()
+            .find(|s| s.get_id() == stream)
+            .expect("station");
+        assert_eq!(station.get_current_match(), None);
+        assert!(station.get_pinned_matches().is_empty());
+        let replayed = Bracket::replay(&bracket.get_events()).expect("replayed");
+        assert_eq!(replayed.get_matches(), bracket.get_matches());
+        assert_eq!(replayed.get_stations(), bracket.get_stations());
+    }
 }
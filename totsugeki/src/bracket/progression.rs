@@ -1,12 +1,18 @@
 //! Upon match validation, bracket progress by moving winners forward and
 //! handling loosers
 
-use super::{Bracket, Error};
+use super::{
+    events::{generated_match_ids, BracketEvent},
+    match_lifecycle::sync_lifecycles,
+    Bracket, Error,
+};
 use crate::{
     matches::{Id as MatchId, Match},
     opponent::Opponent,
     player::Id as PlayerId,
 };
+use chrono::Utc;
+use std::borrow::Cow;
 
 impl Bracket {
     /// Validate match result and return updated bracket with new matches.
@@ -16,41 +22,47 @@ impl Bracket {
     /// # Errors
     /// Thrown when given match id is unknown or when reported results differ
     pub fn validate_match_result(self, match_id: MatchId) -> Result<(Self, Vec<Match>), Error> {
-        let p = self.format.get_progression(
-            self.matches.clone(),
-            &self.get_participants(),
-            self.automatic_match_progression,
-        );
-        let (matches, new_matches) = match p.validate_match_result(match_id) {
+        let mut p = self
+            .format
+            .get_progression(
+                Cow::Borrowed(&self.matches),
+                &self.participants,
+                self.automatic_match_progression,
+                &self.disqualified_between_rounds,
+            )
+            .map_err(|e| self.get_from_progression_error(e))?;
+        let new_matches = match p.validate(match_id) {
             Ok(el) => el,
             Err(e) => return Err(self.get_from_progression_error(e)),
         };
-
-        let bracket = Self { matches, ..self };
+        let matches = p.into_matches();
+
+        let generated_match_ids = generated_match_ids(&self.matches, &matches);
+        let at = Utc::now();
+        let matches = sync_lifecycles(&self.matches, matches, at);
+        let bracket = Self { matches, ..self }.free_stations().log_at(
+            BracketEvent::MatchResultValidated {
+                match_id,
+                generated_match_ids,
+            },
+            at,
+        );
         let bracket = Self {
             accept_match_results: !bracket.is_over(),
             ..bracket
         };
+        bracket.check_all_assertions();
 
         Ok((bracket, new_matches))
     }
 }
 
-/// Get new matches using `old_matches` to play and new matches to play
-pub(crate) fn new_matches(old_matches: &[Match], new_matches: &[Match]) -> Vec<Match> {
-    new_matches
-        .iter()
-        .filter(|m| !old_matches.iter().any(|old_m| old_m.get_id() == m.get_id()))
-        .map(std::clone::Clone::clone)
-        .collect()
-}
-
 /// Returns winner of bracket
 pub(crate) fn winner_of_bracket(bracket: &[Match]) -> Option<PlayerId> {
     match bracket.last() {
         Some(m) => match m.get_winner() {
             Opponent::Player(p) => Some(p),
-            Opponent::Unknown => None,
+            Opponent::Unknown | Opponent::Bye => None,
         },
         None => None,
     }
@@ -61,7 +73,7 @@ mod tests {
 
     use crate::{
         bracket::Bracket,
-        format::Format,
+        format::{Format, GrandFinals, LoserDrops, Playoffs},
         matches::partition_double_elimination_matches,
         opponent::Opponent,
         player::{Id as PlayerId, Player},
@@ -102,7 +114,9 @@ mod tests {
         // player 2 reports before TO does
         let mut bracket = Bracket::new(
             "",
-            Format::SingleElimination,
+            Format::SingleElimination {
+                playoffs: Playoffs::Disabled,
+            },
             Method::Strict,
             Utc.with_ymd_and_hms(2000, 1, 1, 0, 0, 0).unwrap(),
             true,
@@ -126,7 +140,9 @@ mod tests {
         // player 3 reports before TO does
         let mut bracket = Bracket::new(
             "",
-            Format::SingleElimination,
+            Format::SingleElimination {
+                playoffs: Playoffs::Disabled,
+            },
             Method::Strict,
             Utc.with_ymd_and_hms(2000, 1, 1, 0, 0, 0).unwrap(),
             true,
@@ -152,7 +168,10 @@ mod tests {
     fn partition_matches_for_3_man_bracket() {
         let mut bracket = Bracket::new(
             "",
-            Format::DoubleElimination,
+            Format::DoubleElimination {
+                loser_drops: LoserDrops::Standard,
+                grand_finals: GrandFinals::Reset,
+            },
             Method::Strict,
             Utc.with_ymd_and_hms(2000, 1, 1, 0, 0, 0).unwrap(),
             true,
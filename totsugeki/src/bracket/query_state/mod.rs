@@ -6,30 +6,35 @@ use crate::{
     opponent::Opponent,
     player::Id as PlayerId,
 };
+use std::borrow::Cow;
 
 impl Bracket {
-    /// Returns true if bracket is over (all matches are played)
+    /// Returns true if bracket is over (all matches are played). A bracket
+    /// whose matches cannot be read is not over
     #[must_use]
     pub fn is_over(&self) -> bool {
         self.format
             .get_progression(
-                self.get_matches(),
-                &self.get_participants(),
+                Cow::Borrowed(&self.matches),
+                &self.participants,
                 self.automatic_match_progression,
+                &self.disqualified_between_rounds,
             )
-            .is_over()
+            .is_ok_and(|p| p.is_over())
     }
 
-    /// Returns the number of matches done and total remaining
+    /// Returns the number of matches done and total remaining. Nothing is
+    /// done in a bracket whose matches cannot be read
     #[must_use]
     pub fn matches_progress(&self) -> (usize, usize) {
         self.format
             .get_progression(
-                self.get_matches(),
-                &self.get_participants(),
+                Cow::Borrowed(&self.matches),
+                &self.participants,
                 self.automatic_match_progression,
+                &self.disqualified_between_rounds,
             )
-            .matches_progress()
+            .map_or((0, self.matches.len()), |p| p.matches_progress())
     }
 
     /// Return next opponent for `player_id`, relevant match and player name
@@ -44,11 +49,12 @@ impl Bracket {
         match self
             .format
             .get_progression(
-                self.get_matches(),
-                &self.get_participants(),
+                Cow::Borrowed(&self.matches),
+                &self.participants,
                 self.automatic_match_progression,
+                &self.disqualified_between_rounds,
             )
-            .next_opponent(player_id)
+            .and_then(|p| p.next_opponent(player_id))
         {
             Ok((opponent, match_id)) => Ok((
                 opponent,
@@ -0,0 +1,314 @@
+//! Reopen match to correct a wrong result, rolling back matches that followed
+
+use crate::{
+    bracket::{events::BracketEvent, match_lifecycle::sync_lifecycles, Bracket, Error},
+    matches::{Id as MatchId, Match},
+};
+use chrono::Utc;
+use std::borrow::Cow;
+
+impl Bracket {
+    /// Remove result of `match_id` and pull its players out of all matches
+    /// they were sent to because of it. When one of those matches already
+    /// has a result, reopening is refused unless `cascade` is set, in which
+    /// case those matches are reopened too. Returns updated bracket and
+    /// matches to play
+    ///
+    /// # Errors
+    /// thrown when bracket has not started, match is unknown or a following
+    /// match has a result and `cascade` is not set
+    pub fn reopen_match(
+        self,
+        match_id: MatchId,
+        cascade: bool,
+    ) -> Result<(Bracket, Vec<Match>), Error> {
+        if self.matches.is_empty() {
+            return Err(Error::NoGeneratedMatches(self.bracket_id));
+        }
+        if !self.accept_match_results && !self.is_over() {
+            return Err(Error::NotStarted(
+                self.bracket_id,
+                ". Cannot reopen match at this time.".into(),
+            ));
+        }
+
+        let mut p = self
+            .format
+            .get_progression(
+                Cow::Borrowed(&self.matches),
+                &self.participants,
+                self.automatic_match_progression,
+                &self.disqualified_between_rounds,
+            )
+            .map_err(|e| self.get_from_progression_error(e))?;
+        let matches_to_play = match p.reopen(match_id, cascade) {
+            Ok(v) => v,
+            Err(e) => return Err(self.get_from_progression_error(e)),
+        };
+        let matches = p.into_matches();
+        let at = Utc::now();
+        let bracket = Self {
+            matches: sync_lifecycles(&self.matches, matches, at),
+            accept_match_results: true,
+            ..self
+        }
+        .forget_games_of_reopened_matches()
+        .free_stations()
+        .log_at(BracketEvent::MatchReopened { match_id, cascade }, at);
+        bracket.check_all_assertions();
+        Ok((bracket, matches_to_play))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        bracket::{builder::fixture::started_bracket as bracket, Bracket, Error},
+        format::{Format, GrandFinals, LoserDrops, Playoffs},
+        matches::Match,
+        opponent::Opponent,
+        player::Id as PlayerId,
+    };
+
+    fn match_between(bracket: &Bracket, p1: PlayerId, p2: PlayerId) -> Match {
+        *bracket
+            .get_matches()
+            .iter()
+            .find(|m| m.contains(p1) && m.contains(p2))
+            .expect("match")
+    }
+
+    #[test]
+    fn reopening_match_pulls_winner_out_of_next_match() {
+        let (bracket, seeding) = bracket(
+            Format::SingleElimination {
+                playoffs: Playoffs::Disabled,
+            },
+            4,
+        );
+        let (bracket, _, _) = bracket
+            .tournament_organiser_reports_result(seeding[0], (2, 0), seeding[3])
+            .expect("result");
+        let m = match_between(&bracket, seeding[0], seeding[3]);
+
+        let (bracket, matches_to_play) = bracket.reopen_match(m.get_id(), false).expect("reopen");
+
+        let m = match_between(&bracket, seeding[0], seeding[3]);
+        assert_eq!(m.get_winner(), Opponent::Unknown);
+        assert_eq!(m.get_score(), (0, 0));
+        let finals = bracket.get_matches()[2];
+        assert!(!finals.contains(seeding[0]));
+        assert_eq!(matches_to_play.len(), 2);
+    }
+
+    #[test]
+    fn reopening_match_with_played_downstream_match_requires_cascade() {
+        let (bracket, seeding) = bracket(
+            Format::SingleElimination {
+                playoffs: Playoffs::Disabled,
+            },
+            4,
+        );
+        let (bracket, _, _) = bracket
+            .tournament_organiser_reports_result(seeding[0], (2, 0), seeding[3])
+            .expect("result");
+        let (bracket, _, _) = bracket
+            .tournament_organiser_reports_result(seeding[1], (2, 0), seeding[2])
+            .expect("result");
+        let (bracket, _, _) = bracket
+            .tournament_organiser_reports_result(seeding[0], (2, 1), seeding[1])
+            .expect("result");
+        assert!(bracket.is_over());
+        let first_match = match_between(&bracket, seeding[0], seeding[3]);
+        let finals = bracket.get_matches()[2];
+
+        match bracket.clone().reopen_match(first_match.get_id(), false) {
+            Err(Error::DownstreamMatchHasResult(_, reopened, downstream)) => {
+                assert_eq!(reopened, first_match.get_id());
+                assert_eq!(downstream, finals.get_id());
+            }
+            other => panic!("expected DownstreamMatchHasResult but got {other:?}"),
+        }
+
+        let (bracket, matches_to_play) = bracket
+            .reopen_match(first_match.get_id(), true)
+            .expect("reopen");
+        let finals = bracket.get_matches()[2];
+        assert_eq!(
+            finals.get_players(),
+            [Opponent::Unknown, Opponent::Player(seeding[1])]
+        );
+        assert_eq!(finals.get_winner(), Opponent::Unknown);
+        assert_eq!(matches_to_play.len(), 1);
+        assert!(!bracket.is_over());
+
+        // correct result can be reported
+        let (bracket, _, _) = bracket
+            .tournament_organiser_reports_result(seeding[3], (2, 0), seeding[0])
+            .expect("corrected result");
+        assert!(bracket.get_matches()[2].contains(seeding[3]));
+    }
+
+    #[test]
+    fn reopening_winner_bracket_match_pulls_loser_out_of_loser_bracket() {
+        let (bracket, seeding) = bracket(
+            Format::DoubleElimination {
+                loser_drops: LoserDrops::Standard,
+                grand_finals: GrandFinals::Reset,
+            },
+            4,
+        );
+        let (bracket, _, _) = bracket
+            .tournament_organiser_reports_result(seeding[0], (2, 0), seeding[3])
+            .expect("result");
+        assert!(bracket
+            .get_matches()
+            .iter()
+            .skip(3)
+            .any(|m| m.contains(seeding[3])));
+        let m = match_between(&bracket, seeding[0], seeding[3]);
+
+        let (bracket, _) = bracket.reopen_match(m.get_id(), false).expect("reopen");
+
+        for m in bracket.get_matches().iter().skip(2) {
+            assert!(!m.contains(seeding[0]));
+            assert!(!m.contains(seeding[3]));
+        }
+    }
+
+    #[test]
+    fn reopening_swiss_match_drops_unplayed_later_rounds() {
+        let (bracket, seeding) = bracket(Format::Swiss { rounds: None }, 4);
+        let (bracket, _, _) = bracket
+            .tournament_organiser_reports_result(seeding[0], (2, 0), seeding[2])
+            .expect("result");
+        let (bracket, _, _) = bracket
+            .tournament_organiser_reports_result(seeding[1], (2, 0), seeding[3])
+            .expect("result");
+        assert_eq!(bracket.get_matches().len(), 4, "second round generated");
+        let m = match_between(&bracket, seeding[0], seeding[2]);
+
+        let (bracket, matches_to_play) = bracket.reopen_match(m.get_id(), false).expect("reopen");
+
+        assert_eq!(bracket.get_matches().len(), 2);
+        assert_eq!(matches_to_play.len(), 1);
+        assert_eq!(matches_to_play[0].get_id(), m.get_id());
+    }
+
+    #[test]
+    fn reopening_round_robin_match_only_returns_reopened_match() {
+        let (bracket, seeding) = bracket(Format::RoundRobin, 4);
+        let (bracket, _, _) = bracket
+            .tournament_organiser_reports_result(seeding[0], (2, 0), seeding[3])
+            .expect("result");
+        let m = match_between(&bracket, seeding[0], seeding[3]);
+
+        let (bracket, matches_to_play) = bracket.reopen_match(m.get_id(), false).expect("reopen");
+
+        assert_eq!(
+            match_between(&bracket, seeding[0], seeding[3]).get_score(),
+            (0, 0)
+        );
+        assert_eq!(matches_to_play.len(), 1);
+        assert_eq!(matches_to_play[0].get_id(), m.get_id());
+    }
+
+    #[test]
+    fn cascading_reopen_clears_match_resolved_by_disqualification() {
+        let (bracket, seeding) = bracket(
+            Format::SingleElimination {
+                playoffs: Playoffs::Disabled,
+            },
+            4,
+        );
+        let (bracket, _, _) = bracket
+            .tournament_organiser_reports_result(seeding[0], (2, 0), seeding[3])
+            .expect("result");
+        let (bracket, _, _) = bracket
+            .tournament_organiser_reports_result(seeding[1], (2, 0), seeding[2])
+            .expect("result");
+        let (bracket, _) = bracket
+            .disqualify_participant(seeding[1])
+            .expect("disqualified");
+        assert!(bracket.is_over());
+        let first_match = match_between(&bracket, seeding[0], seeding[3]);
+        let finals = bracket.get_matches()[2];
+
+        match bracket.clone().reopen_match(first_match.get_id(), false) {
+            Err(Error::DownstreamMatchHasResult(_, _, downstream)) => {
+                assert_eq!(downstream, finals.get_id());
+            }
+            other => panic!("expected DownstreamMatchHasResult but got {other:?}"),
+        }
+        let (bracket, _) = bracket
+            .reopen_match(first_match.get_id(), true)
+            .expect("reopen");
+        let finals = bracket.get_matches()[2];
+        assert_eq!(
+            finals.get_players(),
+            [Opponent::Unknown, Opponent::Player(seeding[1])]
+        );
+        assert_eq!(finals.get_winner(), Opponent::Unknown);
+        assert_eq!(
+            finals.get_automatic_loser(),
+            Opponent::Player(seeding[1]),
+            "disqualification is kept"
+        );
+
+        // corrected winner wins finals against disqualified player
+        let (bracket, _, _) = bracket
+            .tournament_organiser_reports_result(seeding[3], (2, 0), seeding[0])
+            .expect("corrected result");
+        assert_eq!(
+            bracket.get_matches()[2].get_winner(),
+            Opponent::Player(seeding[3])
+        );
+        assert!(bracket.is_over());
+    }
+
+    #[test]
+    fn reopening_grand_finals_requires_cascade_when_reset_was_played() {
+        let (mut bracket, _) = bracket(
+            Format::DoubleElimination {
+                loser_drops: LoserDrops::Standard,
+                grand_finals: GrandFinals::Reset,
+            },
+            4,
+        );
+        let n = bracket.get_matches().len();
+        while let Some(m) = bracket.matches_to_play().first().copied() {
+            let [Opponent::Player(p1), Opponent::Player(p2)] = m.get_players() else {
+                panic!("match to play without players");
+            };
+            // player from losers wins grand finals
+            let result = if m.get_id() == bracket.get_matches()[n - 2].get_id() {
+                (0, 2)
+            } else {
+                (2, 0)
+            };
+            bracket = bracket
+                .tournament_organiser_reports_result(p1, result, p2)
+                .expect("result")
+                .0;
+        }
+        assert!(bracket.is_over());
+        let grand_finals = bracket.get_matches()[n - 2];
+        let reset = bracket.get_matches()[n - 1];
+
+        match bracket.clone().reopen_match(grand_finals.get_id(), false) {
+            Err(Error::DownstreamMatchHasResult(_, reopened, downstream)) => {
+                assert_eq!(reopened, grand_finals.get_id());
+                assert_eq!(downstream, reset.get_id());
+            }
+            other => panic!("expected DownstreamMatchHasResult but got {other:?}"),
+        }
+        let (bracket, matches_to_play) = bracket
+            .reopen_match(grand_finals.get_id(), true)
+            .expect("reopen");
+        let reset = bracket.get_matches()[n - 1];
+        assert_eq!(reset.get_players(), [Opponent::Unknown, Opponent::Unknown]);
+        assert_eq!(reset.get_winner(), Opponent::Unknown);
+        assert_eq!(matches_to_play.len(), 1);
+        assert_eq!(matches_to_play[0].get_id(), grand_finals.get_id());
+    }
+}
@@ -0,0 +1,114 @@
+//! Round robin pool
+
+use crate::bracket::matches::round_robin_format::{Standing, Step};
+use crate::bracket::Bracket;
+use crate::bracket::PartitionError;
+use crate::format::Format;
+use crate::matches::Match;
+use crate::seeding::round_robin_seeded_bracket::matches_per_round;
+
+/// Round robin bracket variant
+#[derive(Debug)]
+pub struct Variant {
+    /// Some bracket
+    bracket: Bracket,
+}
+
+/// Error with round robin brackets
+#[derive(Debug)]
+pub enum TryIntoError {
+    /// Expected format to be round-robin
+    ExpectedRoundRobinFormat,
+}
+
+impl TryFrom<Bracket> for Variant {
+    type Error = TryIntoError;
+
+    fn try_from(bracket: Bracket) -> Result<Self, Self::Error> {
+        if bracket.format != Format::RoundRobin {
+            return Err(TryIntoError::ExpectedRoundRobinFormat);
+        }
+
+        Ok(Variant { bracket })
+    }
+}
+
+impl Variant {
+    /// Returns pool matches partitionned by round
+    ///
+    /// # Errors
+    /// Returns an error when there is less than 2 players in the bracket
+    pub fn partition_by_round(&self) -> Result<Vec<Vec<Match>>, PartitionError> {
+        if self.bracket.participants.len() < Format::RoundRobin.minimum_number_of_participants() {
+            return Err(PartitionError::NotEnoughPlayersInBracket);
+        }
+        Ok(self
+            .bracket
+            .matches
+            .chunks(matches_per_round(self.bracket.participants.len()))
+            .map(<[Match]>::to_vec)
+            .collect())
+    }
+
+    /// Returns standings of pool sorted from first to last place
+    ///
+    /// # Panics
+    /// When round robin matches cannot be read
+    #[must_use]
+    pub fn standings(&self) -> Vec<Standing> {
+        Step::new(
+            Some(self.bracket.matches.to_vec()),
+            &self.bracket.participants.get_seeding(),
+            self.bracket.automatic_match_progression,
+        )
+        .expect("round robin bracket state")
+        .standings()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Variant;
+    use crate::{bracket::builder::Builder, format::Format};
+
+    #[test]
+    fn run_round_robin_bracket() {
+        let bracket = Builder::default()
+            .set_format(Format::RoundRobin)
+            .set_new_players(5)
+            .build()
+            .expect("bracket");
+        let players = bracket.get_participants().get_seeding();
+        let (mut bracket, matches_to_play) = bracket.start().expect("start");
+        assert_eq!(matches_to_play.len(), 10);
+
+        // lower seed always wins
+        for m in matches_to_play {
+            let [p1, p2] = m.get_players().map(|o| match o {
+                crate::opponent::Opponent::Player(p) => p,
+                crate::opponent::Opponent::Unknown | crate::opponent::Opponent::Bye => {
+                    panic!("expected player")
+                }
+            });
+            bracket = bracket
+                .tournament_organiser_reports_result(p1, (2, 0), p2)
+                .expect("result")
+                .0;
+        }
+        assert!(bracket.is_over());
+
+        let rr = Variant::try_from(bracket).expect("round robin");
+        let rounds = rr.partition_by_round().expect("rounds");
+        assert_eq!(rounds.len(), 5);
+        assert!(rounds.iter().all(|r| r.len() == 2));
+        let standings = rr.standings();
+        assert_eq!(
+            standings.iter().map(|s| s.player_id).collect::<Vec<_>>(),
+            players
+        );
+        assert_eq!(
+            standings.iter().map(|s| s.wins).collect::<Vec<_>>(),
+            vec![4, 3, 2, 1, 0]
+        );
+    }
+}
@@ -0,0 +1,480 @@
+//! Project when matches will be played from the start time of the bracket,
+//! the duration of sets and the number of stations
+//!
+//! A match cannot start before the matches sending it its players are over
+//! nor before a station is free. Matches already played use their recorded
+//! timestamps.
+
+use crate::{
+    bracket::{
+        double_elimination_variant::loser_bracket,
+        matches::{single_elimination_format::playoffs::trees, swiss_format::partition_by_round},
+        winner_bracket::winner_bracket,
+        Bracket,
+    },
+    format::Format,
+    matches::{partition_double_elimination_matches, Id as MatchId, Match, SetLength, State},
+    opponent::Opponent,
+    seeding::round_robin_seeded_bracket::matches_per_round,
+};
+use chrono::{DateTime, Duration, Utc};
+use std::collections::HashMap;
+
+/// Part of a bracket a round belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Section {
+    /// Winner bracket of elimination brackets or rounds of pools
+    Main,
+    /// Loser bracket of double elimination brackets
+    Losers,
+    /// Playoff of single elimination bracket deciding placements from
+    /// `placement`
+    Playoff {
+        /// Best placement decided by this playoff
+        placement: usize,
+    },
+    /// Grand finals and its reset
+    GrandFinals,
+}
+
+/// Round of a bracket
+#[derive(Debug, Clone)]
+pub(crate) struct Round {
+    /// Part of the bracket
+    pub(crate) section: Section,
+    /// Round number inside its section, starting at 0
+    pub(crate) number: usize,
+    /// Round of the main section this round is played alongside
+    pub(crate) wave: usize,
+    /// Matches of this round
+    pub(crate) matches: Vec<Match>,
+}
+
+/// Duration of sets and number of stations used to project a schedule
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScheduleSettings {
+    /// Number of stations where matches are played at the same time
+    stations: usize,
+    /// Average duration of a set without specific duration
+    default_set_duration: Duration,
+    /// Average duration of sets of a given length
+    set_durations: Vec<(SetLength, Duration)>,
+}
+
+impl ScheduleSettings {
+    /// Play on `stations` stations (at least one) with sets lasting
+    /// `default_set_duration` on average
+    #[must_use]
+    pub fn new(stations: usize, default_set_duration: Duration) -> Self {
+        Self {
+            stations: stations.max(1),
+            default_set_duration,
+            set_durations: vec![],
+        }
+    }
+
+    /// Sets of `set_length` last `duration` on average
+    #[must_use]
+    pub fn set_duration(self, set_length: SetLength, duration: Duration) -> Self {
+        let mut set_durations = self.set_durations;
+        set_durations.retain(|(l, _)| *l != set_length);
+        set_durations.push((set_length, duration));
+        Self {
+            set_durations,
+            ..self
+        }
+    }
+
+    /// Returns average duration of a set of `set_length`
+    fn duration_of(&self, set_length: Option<SetLength>) -> Duration {
+        set_length
+            .and_then(|set_length| {
+                self.set_durations
+                    .iter()
+                    .find(|(l, _)| *l == set_length)
+                    .map(|(_, d)| *d)
+            })
+            .unwrap_or(self.default_set_duration)
+    }
+}
+
+/// Estimated start and end of a round
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RoundSchedule {
+    /// Part of the bracket
+    pub section: Section,
+    /// Round number inside its section, starting at 0
+    pub round: usize,
+    /// When first match of round starts
+    pub start: DateTime<Utc>,
+    /// When last match of round ends
+    pub end: DateTime<Utc>,
+}
+
+/// Projected schedule of a bracket
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Schedule {
+    /// Estimated start of every match, in bracket order
+    pub matches: Vec<(MatchId, DateTime<Utc>)>,
+    /// Estimated start and end of every round
+    pub rounds: Vec<RoundSchedule>,
+    /// Estimated start of grand finals (or finals of single elimination
+    /// bracket)
+    pub grand_finals: Option<DateTime<Utc>>,
+    /// When last match ends
+    pub end: DateTime<Utc>,
+}
+
+impl Schedule {
+    /// Returns by how much bracket ends after `deadline`, if it does
+    #[must_use]
+    pub fn overrun(&self, deadline: DateTime<Utc>) -> Option<Duration> {
+        (self.end > deadline).then(|| self.end - deadline)
+    }
+}
+
+impl Bracket {
+    /// Project when every match, round and grand finals will start using
+    /// `settings`. Bracket is expected to start at its start time
+    #[must_use]
+    pub fn project_schedule(&self, settings: &ScheduleSettings) -> Schedule {
+        let feeders = self.feeders();
+        let priorities = self.priorities();
+        let mut starts = HashMap::new();
+        let mut ends = HashMap::new();
+        let mut stations = vec![self.start_time; settings.stations];
+        let mut remaining = vec![];
+        for m in &self.matches {
+            let lifecycle = m.get_lifecycle();
+            let duration = settings.duration_of(self.set_length_of(m.get_id()).ok().flatten());
+            match (m.get_state(), lifecycle.completed_at, lifecycle.started_at) {
+                (State::Completed, Some(completed_at), started_at) => {
+                    let start = started_at.or(lifecycle.called_at).unwrap_or(completed_at);
+                    starts.insert(m.get_id(), start);
+                    ends.insert(m.get_id(), completed_at);
+                }
+                (State::InProgress, _, Some(started_at)) => {
+                    starts.insert(m.get_id(), started_at);
+                    ends.insert(m.get_id(), started_at + duration);
+                    let station = earliest_free(&stations);
+                    stations[station] = stations[station].max(started_at + duration);
+                }
+                _ => remaining.push((*m, duration)),
+            }
+        }
+
+        while !remaining.is_empty() {
+            let next = remaining
+                .iter()
+                .enumerate()
+                .filter_map(|(i, (m, _))| {
+                    let ready = feeders
+                        .get(&m.get_id())
+                        .into_iter()
+                        .flatten()
+                        .map(|f| ends.get(f).copied())
+                        .try_fold(self.start_time, |ready, end| end.map(|e| ready.max(e)))?;
+                    let start = if is_played(m) {
+                        ready.max(stations[earliest_free(&stations)])
+                    } else {
+                        ready
+                    };
+                    Some((start, priorities.get(&m.get_id()).copied(), i))
+                })
+                .min();
+            // unreachable matches are left out of the schedule
+            let Some((start, _, i)) = next else {
+                break;
+            };
+            let (m, duration) = remaining.remove(i);
+            let duration = if is_played(&m) {
+                let station = earliest_free(&stations);
+                stations[station] = start + duration;
+                duration
+            } else {
+                Duration::zero()
+            };
+            starts.insert(m.get_id(), start);
+            ends.insert(m.get_id(), start + duration);
+        }
+
+        let rounds = self
+            .rounds()
+            .into_iter()
+            .filter_map(|round| {
+                let ids = round.matches.iter().map(Match::get_id).collect::<Vec<_>>();
+                Some(RoundSchedule {
+                    section: round.section,
+                    round: round.number,
+                    start: ids.iter().filter_map(|id| starts.get(id)).min().copied()?,
+                    end: ids.iter().filter_map(|id| ends.get(id)).max().copied()?,
+                })
+            })
+            .collect::<Vec<_>>();
+        let grand_finals = match self.format {
+            Format::SingleElimination { playoffs } => trees(self.participants.len(), playoffs)
+                .first()
+                .and_then(|main| main.range().last())
+                .and_then(|i| starts.get(&self.matches[i].get_id()).copied()),
+            Format::DoubleElimination { .. } => rounds
+                .iter()
+                .find(|r| r.section == Section::GrandFinals)
+                .map(|r| r.start),
+            Format::RoundRobin | Format::Swiss { .. } => None,
+        };
+        Schedule {
+            matches: self
+                .matches
+                .iter()
+                .filter_map(|m| starts.get(&m.get_id()).map(|s| (m.get_id(), *s)))
+                .collect(),
+            rounds,
+            grand_finals,
+            end: ends.values().max().copied().unwrap_or(self.start_time),
+        }
+    }
+
+    /// Returns rounds of this bracket
+    pub(crate) fn rounds(&self) -> Vec<Round> {
+        if self.matches.is_empty() {
+            return vec![];
+        }
+        let section_rounds = |section, rounds: Vec<Vec<Match>>, wave: &dyn Fn(usize) -> usize| {
+            rounds
+                .into_iter()
+                .enumerate()
+                .map(|(number, matches)| Round {
+                    section,
+                    number,
+                    wave: wave(number),
+                    matches,
+                })
+                .collect::<Vec<_>>()
+        };
+        match self.format {
+            Format::SingleElimination { playoffs } => trees(self.participants.len(), playoffs)
+                .iter()
+                .enumerate()
+                .flat_map(|(i, tree)| {
+                    let rounds = (0..tree.number_of_rounds())
+                        .map(|r| self.matches[tree.round_range(r)].to_vec())
+                        .collect();
+                    let section = if i == 0 {
+                        Section::Main
+                    } else {
+                        Section::Playoff {
+                            placement: tree.first_seed,
+                        }
+                    };
+                    section_rounds(section, rounds, &|r| r)
+                })
+                .collect(),
+            Format::DoubleElimination { .. } => {
+                let (wb, lb, gf, gf_reset) =
+                    partition_double_elimination_matches(&self.matches, self.participants.len());
+                let wb = winner_bracket(wb, &self.participants);
+                let wb_rounds = wb.len();
+                let mut rounds = section_rounds(Section::Main, wb, &|r| r);
+                // loser bracket round is played after the winner bracket
+                // round its players drop from
+                rounds.extend(section_rounds(Section::Losers, loser_bracket(lb), &|r| {
+                    r.div_ceil(2) + 1
+                }));
+                rounds.extend(section_rounds(
+                    Section::GrandFinals,
+                    [Some(gf), gf_reset]
+                        .into_iter()
+                        .flatten()
+                        .map(|m| vec![m])
+                        .collect(),
+                    &|r| wb_rounds + 1 + r,
+                ));
+                rounds
+            }
+            Format::RoundRobin => section_rounds(
+                Section::Main,
+                self.matches
+                    .chunks(matches_per_round(self.participants.len()).max(1))
+                    .map(<[Match]>::to_vec)
+                    .collect(),
+                &|r| r,
+            ),
+            Format::Swiss { .. } => {
+                section_rounds(Section::Main, partition_by_round(&self.matches), &|r| r)
+            }
+        }
+    }
+
+    /// Priority of every match: lowest is played first. A match has the
+    /// priority of the round of the main section it is played alongside,
+    /// then loser bracket and playoff matches go first
+    pub(crate) fn priorities(&self) -> HashMap<MatchId, (usize, bool, usize)> {
+        let mut priorities = HashMap::new();
+        for round in self.rounds() {
+            let is_main = matches!(round.section, Section::Main | Section::GrandFinals);
+            for m in round.matches {
+                let index = self.matches.position(m.get_id()).expect("match of bracket");
+                priorities.insert(m.get_id(), (round.wave, is_main, index));
+            }
+        }
+        priorities
+    }
+
+    /// Returns matches sending a player to each match
+    fn feeders(&self) -> HashMap<MatchId, Vec<MatchId>> {
+        let mut feeders: HashMap<MatchId, Vec<MatchId>> = HashMap::new();
+        for (i, m) in self.matches.iter().enumerate() {
+            let links = m.get_links();
+            for link in [links.winner, links.loser].into_iter().flatten() {
+                feeders.entry(link.match_id).or_default().push(m.get_id());
+            }
+            match self.format {
+                // players of grand finals play its reset
+                Format::DoubleElimination { .. } if i + 2 == self.matches.len() => {
+                    feeders
+                        .entry(self.matches[i + 1].get_id())
+                        .or_default()
+                        .push(m.get_id());
+                }
+                // players play their matches one after the other
+                Format::RoundRobin | Format::Swiss { .. } => {
+                    for player in m.get_players() {
+                        let Opponent::Player(player) = player else {
+                            continue;
+                        };
+                        let history = self.matches.matches_of(player);
+                        let before = history.partition_point(|p| *p < i);
+                        if let Some(previous) = before.checked_sub(1).map(|p| history[p]) {
+                            feeders
+                                .entry(m.get_id())
+                                .or_default()
+                                .push(self.matches[previous].get_id());
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        feeders
+    }
+}
+
+/// Returns true if match is played on a station rather than resolved
+/// automatically (bye or disqualification)
+fn is_played(m: &Match) -> bool {
+    m.get_automatic_loser() == Opponent::Unknown && m.get_state() != State::Completed
+}
+
+/// Returns index of station that is free the earliest
+fn earliest_free(stations: &[DateTime<Utc>]) -> usize {
+    stations
+        .iter()
+        .enumerate()
+        .min_by_key(|(_, free_at)| **free_at)
+        .map_or(0, |(i, _)| i)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ScheduleSettings, Section};
+    use crate::{
+        bracket::{match_rules::MatchRules, Bracket},
+        format::{Format, GrandFinals, LoserDrops, Playoffs},
+        matches::SetLength,
+        seeding::Method as SeedingMethod,
+    };
+    use chrono::{Duration, TimeZone, Utc};
+
+    fn bracket(format: Format, n: usize) -> Bracket {
+        let mut bracket = Bracket::new(
+            "bracket",
+            format,
+            SeedingMethod::Strict,
+            Utc.with_ymd_and_hms(2000, 1, 1, 18, 0, 0).unwrap(),
+            true,
+        );
+        for i in 1..=n {
+            bracket = bracket
+                .add_participant(format!("p{i}").as_str())
+                .expect("participant");
+        }
+        bracket.start().expect("start").0
+    }
+
+    #[test]
+    fn rounds_are_played_one_after_the_other() {
+        let bracket = bracket(
+            Format::SingleElimination {
+                playoffs: Playoffs::Disabled,
+            },
+            8,
+        );
+        let start = Utc.with_ymd_and_hms(2000, 1, 1, 18, 0, 0).unwrap();
+
+        let schedule = bracket.project_schedule(&ScheduleSettings::new(4, Duration::minutes(10)));
+
+        let round_starts = schedule
+            .rounds
+            .iter()
+            .map(|r| (r.section, r.round, r.start))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            round_starts,
+            vec![
+                (Section::Main, 0, start),
+                (Section::Main, 1, start + Duration::minutes(10)),
+                (Section::Main, 2, start + Duration::minutes(20)),
+            ]
+        );
+        assert_eq!(schedule.grand_finals, Some(start + Duration::minutes(20)));
+        assert_eq!(schedule.end, start + Duration::minutes(30));
+    }
+
+    #[test]
+    fn fewer_stations_take_longer() {
+        let bracket = bracket(
+            Format::DoubleElimination {
+                loser_drops: LoserDrops::Standard,
+                grand_finals: GrandFinals::Reset,
+            },
+            8,
+        );
+        let start = Utc.with_ymd_and_hms(2000, 1, 1, 18, 0, 0).unwrap();
+
+        let plenty = bracket.project_schedule(&ScheduleSettings::new(8, Duration::minutes(10)));
+        let one = bracket.project_schedule(&ScheduleSettings::new(1, Duration::minutes(10)));
+
+        // 7 rounds: 3 in winners, then losers semis, finals, grand finals and reset
+        assert_eq!(plenty.end, start + Duration::minutes(70));
+        // 15 matches one after the other
+        assert_eq!(one.end, start + Duration::minutes(150));
+        assert_eq!(
+            one.overrun(start + Duration::minutes(120)),
+            Some(Duration::minutes(30))
+        );
+        assert_eq!(plenty.overrun(start + Duration::minutes(120)), None);
+        assert!(plenty.grand_finals < one.grand_finals);
+    }
+
+    #[test]
+    fn set_length_changes_duration_of_matches() {
+        let bracket = bracket(
+            Format::SingleElimination {
+                playoffs: Playoffs::Disabled,
+            },
+            4,
+        )
+        .set_match_rules(
+            MatchRules::new(SetLength::BestOf(3)).set_grand_finals(SetLength::BestOf(5)),
+        )
+        .expect("match rules");
+        let start = Utc.with_ymd_and_hms(2000, 1, 1, 18, 0, 0).unwrap();
+        let settings = ScheduleSettings::new(2, Duration::minutes(10))
+            .set_duration(SetLength::BestOf(5), Duration::minutes(20));
+
+        let schedule = bracket.project_schedule(&settings);
+
+        assert_eq!(schedule.grand_finals, Some(start + Duration::minutes(10)));
+        assert_eq!(schedule.end, start + Duration::minutes(30));
+    }
+}
@@ -1,9 +1,10 @@
 //! Update seeding of bracket
 
 use crate::{
-    bracket::{Bracket, Error},
+    bracket::{events::BracketEvent, Bracket, Error},
     player::{Id as PlayerId, Participants, Player},
-    seeding::seed,
+    rating::Ratings,
+    seeding::{self, draw_rng, seed, separation::Separation, Method as SeedingMethod},
 };
 
 impl Bracket {
@@ -13,6 +14,27 @@ impl Bracket {
     /// # Errors
     /// thrown when provided players do not match current players in bracket
     pub fn update_seeding(self, players: &[PlayerId]) -> Result<Self, Error> {
+        self.update_seeding_with(players, None, None, None)
+    }
+
+    /// Update seeding using seeding method of bracket and generate matches.
+    /// Players are ordered by seeding position with `Strict` method and by
+    /// `ratings` with `ByRating` method. With `Random` method, players are
+    /// drawn using `random_seed`, or a value drawn from the OS when not
+    /// provided. The value used is recorded so the draw can be reproduced.
+    /// When provided, `separation` then moves players sharing a tag away from
+    /// each other
+    ///
+    /// # Errors
+    /// thrown when provided players do not match current players in bracket,
+    /// ratings are missing or no random value can be drawn
+    pub fn update_seeding_with(
+        self,
+        players: &[PlayerId],
+        ratings: Option<&Ratings>,
+        separation: Option<&Separation>,
+        random_seed: Option<u64>,
+    ) -> Result<Self, Error> {
         if self.accept_match_results {
             return Err(Error::Started(self.bracket_id, String::new()));
         }
@@ -29,7 +51,19 @@ impl Bracket {
             };
             player_group = player_group.add_participant(player.clone())?;
         }
-        let participants = seed(&self.seeding_method, player_group, self.participants)?;
+        let random_seed = match (self.seeding_method, random_seed) {
+            (SeedingMethod::Random, Some(random_seed)) => Some(random_seed),
+            (SeedingMethod::Random, None) => Some(seeding::random_seed()?),
+            (SeedingMethod::Strict | SeedingMethod::ByRating, _) => None,
+        };
+        let participants = seed(
+            &self.seeding_method,
+            player_group,
+            self.participants,
+            ratings,
+            separation,
+            &mut draw_rng(random_seed.unwrap_or_default()),
+        )?;
         let matches = self.format.generate_matches(
             &participants
                 .get_players_list()
@@ -37,11 +71,17 @@ impl Bracket {
                 .map(Player::get_id)
                 .collect::<Vec<_>>(),
         )?;
+        let seeding = participants.get_seeding();
         Ok(Self {
             participants,
-            matches,
+            matches: matches.into(),
+            random_seed,
             ..self
-        })
+        }
+        .log(BracketEvent::SeedingUpdated {
+            seeding,
+            random_seed,
+        }))
     }
 }
 #[cfg(test)]
@@ -49,17 +89,31 @@ mod tests {
     use super::*;
     use crate::{
         bracket::builder::Builder,
-        format::Format,
-        matches::{Id as MatchId, Match},
+        format::{Format, GrandFinals, LoserDrops, Playoffs},
+        matches::{Id as MatchId, Lifecycle, Link, Links, Match},
         opponent::Opponent,
         player::Error as PlayerError,
-        seeding::Error as SeedingError,
+        rating::Rating,
+        seeding::{Error as SeedingError, Method as SeedingMethod},
     };
+    use chrono::{TimeZone, Utc};
+
+    fn winner_to(match_id: MatchId, is_player_1: bool) -> Links {
+        Links {
+            winner: Some(Link {
+                match_id,
+                is_player_1,
+            }),
+            loser: None,
+        }
+    }
 
     #[test]
     fn cannot_seed_bracket_after_it_started() {
         let bracket = Builder::default()
-            .set_format(Format::SingleElimination)
+            .set_format(Format::SingleElimination {
+                playoffs: Playoffs::Disabled,
+            })
             .set_new_players(3)
             .build()
             .expect("bracket");
@@ -81,7 +135,9 @@ mod tests {
     fn seeding_single_elimination_bracket_with_wrong_players_fails() {
         let unknown_player = PlayerId::new_v4();
         let bracket = Builder::default()
-            .set_format(Format::SingleElimination)
+            .set_format(Format::SingleElimination {
+                playoffs: Playoffs::Disabled,
+            })
             .set_new_players(3)
             .build()
             .expect("bracket");
@@ -128,7 +184,9 @@ mod tests {
     #[test]
     fn updating_seeding_changes_matches_of_3_man_bracket() {
         let bracket = Builder::default()
-            .set_format(Format::SingleElimination)
+            .set_format(Format::SingleElimination {
+                playoffs: Playoffs::Disabled,
+            })
             .set_new_players(3)
             .build()
             .expect("bracket");
@@ -145,6 +203,7 @@ mod tests {
             .iter()
             .map(Match::get_id)
             .collect();
+        let ids = match_ids.clone();
         match_ids.reverse();
         let p1 = Opponent::Player(players[0].get_id());
         let p2 = Opponent::Player(players[1].get_id());
@@ -158,7 +217,10 @@ mod tests {
                     seeds: [2, 3],
                     winner: Opponent::Unknown,
                     automatic_loser: Opponent::Unknown,
-                    reported_results: [(0, 0), (0, 0)]
+                    reported_results: [(0, 0), (0, 0)],
+                    lifecycle: Lifecycle::default(),
+                    links: winner_to(ids[1], false),
+                    round: 0,
                 },
                 Match {
                     id: match_ids.pop().expect("match id"),
@@ -166,7 +228,10 @@ mod tests {
                     seeds: [1, 2],
                     winner: Opponent::Unknown,
                     automatic_loser: Opponent::Unknown,
-                    reported_results: [(0, 0), (0, 0)]
+                    reported_results: [(0, 0), (0, 0)],
+                    lifecycle: Lifecycle::default(),
+                    links: Links::default(),
+                    round: 0,
                 }
             ]
         );
@@ -175,7 +240,9 @@ mod tests {
     #[test]
     fn updating_seeding_changes_matches_of_5_man_bracket() {
         let bracket = Builder::default()
-            .set_format(Format::SingleElimination)
+            .set_format(Format::SingleElimination {
+                playoffs: Playoffs::Disabled,
+            })
             .set_new_players(5)
             .build()
             .expect("bracket");
@@ -194,6 +261,7 @@ mod tests {
             .iter()
             .map(Match::get_id)
             .collect();
+        let ids = match_ids.clone();
         match_ids.reverse();
         let p1 = Opponent::Player(players[0].get_id());
         let p2 = Opponent::Player(players[1].get_id());
@@ -209,7 +277,10 @@ mod tests {
                     seeds: [4, 5],
                     winner: Opponent::Unknown,
                     automatic_loser: Opponent::Unknown,
-                    reported_results: [(0, 0), (0, 0)]
+                    reported_results: [(0, 0), (0, 0)],
+                    lifecycle: Lifecycle::default(),
+                    links: winner_to(ids[1], false),
+                    round: 0,
                 },
                 Match {
                     id: match_ids.pop().expect("match id"),
@@ -217,7 +288,10 @@ mod tests {
                     seeds: [1, 4],
                     winner: Opponent::Unknown,
                     automatic_loser: Opponent::Unknown,
-                    reported_results: [(0, 0), (0, 0)]
+                    reported_results: [(0, 0), (0, 0)],
+                    lifecycle: Lifecycle::default(),
+                    links: winner_to(ids[3], true),
+                    round: 0,
                 },
                 Match {
                     id: match_ids.pop().expect("match id"),
@@ -225,7 +299,10 @@ mod tests {
                     seeds: [2, 3],
                     winner: Opponent::Unknown,
                     automatic_loser: Opponent::Unknown,
-                    reported_results: [(0, 0), (0, 0)]
+                    reported_results: [(0, 0), (0, 0)],
+                    lifecycle: Lifecycle::default(),
+                    links: winner_to(ids[3], false),
+                    round: 0,
                 },
                 Match {
                     id: match_ids.pop().expect("match id"),
@@ -233,9 +310,102 @@ mod tests {
                     seeds: [1, 2],
                     winner: Opponent::Unknown,
                     automatic_loser: Opponent::Unknown,
-                    reported_results: [(0, 0), (0, 0)]
+                    reported_results: [(0, 0), (0, 0)],
+                    lifecycle: Lifecycle::default(),
+                    links: Links::default(),
+                    round: 0,
                 },
             ]
         );
     }
+
+    #[test]
+    fn bracket_seeded_by_rating_generates_matches_from_ratings() {
+        let mut bracket = Bracket::new(
+            "bracket",
+            Format::SingleElimination {
+                playoffs: Playoffs::Disabled,
+            },
+            SeedingMethod::ByRating,
+            Utc.with_ymd_and_hms(2000, 1, 1, 0, 0, 0).unwrap(),
+            true,
+        );
+        for i in 1..=3 {
+            bracket = bracket
+                .add_participant(format!("p{i}").as_str())
+                .expect("participant");
+        }
+        let seeding = bracket.get_participants().get_seeding();
+        let ratings = Ratings::default().set_rating(
+            seeding[2],
+            Rating {
+                rating: 1600.0,
+                ..Rating::default()
+            },
+        );
+
+        assert!(matches!(
+            bracket.clone().update_seeding(&seeding),
+            Err(Error::Seeding(SeedingError::MissingRatings))
+        ));
+        let bracket = bracket
+            .update_seeding_with(&seeding, Some(&ratings), None, None)
+            .expect("seeding update");
+
+        assert_eq!(
+            bracket.get_participants().get_seeding(),
+            vec![seeding[2], seeding[0], seeding[1]]
+        );
+        assert_eq!(
+            bracket.get_matches()[1].get_players()[0],
+            Opponent::Player(seeding[2])
+        );
+    }
+
+    #[test]
+    fn random_draw_is_recorded_and_reproducible() {
+        let mut bracket = Bracket::new(
+            "bracket",
+            Format::DoubleElimination {
+                loser_drops: LoserDrops::Standard,
+                grand_finals: GrandFinals::Reset,
+            },
+            SeedingMethod::Random,
+            Utc.with_ymd_and_hms(2000, 1, 1, 0, 0, 0).unwrap(),
+            true,
+        );
+        for i in 1..=16 {
+            bracket = bracket
+                .add_participant(format!("p{i}").as_str())
+                .expect("participant");
+        }
+        let seeding = bracket.get_participants().get_seeding();
+        let draw = |random_seed| {
+            bracket
+                .clone()
+                .update_seeding_with(&seeding, None, None, random_seed)
+                .expect("seeding update")
+        };
+
+        let drawn = draw(Some(42));
+        assert_eq!(drawn.get_random_seed(), Some(42));
+        assert_eq!(
+            drawn.get_participants().get_seeding(),
+            draw(Some(42)).get_participants().get_seeding()
+        );
+        assert_ne!(
+            drawn.get_participants().get_seeding(),
+            draw(Some(43)).get_participants().get_seeding()
+        );
+        let replayed = Bracket::replay(&drawn.get_events()).expect("replay");
+        assert_eq!(replayed.get_random_seed(), Some(42));
+        // draw from the OS can be reproduced with recorded value
+        let drawn = draw(None);
+        assert_eq!(
+            drawn.get_participants().get_seeding(),
+            draw(drawn.get_random_seed())
+                .get_participants()
+                .get_seeding()
+        );
+    }
 }
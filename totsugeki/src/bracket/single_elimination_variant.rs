@@ -1,7 +1,9 @@
 //! Single elimination bracket
 
+use crate::bracket::matches::single_elimination_format::playoffs::{trees, Tree};
+use crate::bracket::standings::Placement;
 use crate::bracket::Bracket;
-use crate::format::Format;
+use crate::format::{Format, Playoffs};
 use crate::matches::Match;
 
 use super::winner_bracket::winner_bracket;
@@ -14,6 +16,16 @@ pub struct Variant {
     bracket: Bracket,
 }
 
+/// Playoff bracket where players eliminated in the same round of the main
+/// bracket play each other to decide their placement
+#[derive(Debug, Clone)]
+pub struct PlayoffBracket {
+    /// Placements decided by this playoff bracket
+    pub placement: Placement,
+    /// Matches of this playoff bracket partitionned by round
+    pub rounds: Vec<Vec<Match>>,
+}
+
 /// Error with double elimination brackets
 #[derive(Debug)]
 pub enum TryIntoError {
@@ -28,7 +40,7 @@ impl TryFrom<Bracket> for Variant {
     // trying to coerce smth into smth else is a sign that a fallible process
     // should be made not fallible
     fn try_from(bracket: Bracket) -> Result<Self, Self::Error> {
-        if bracket.format != Format::SingleElimination {
+        if !matches!(bracket.format, Format::SingleElimination { .. }) {
             return Err(TryIntoError::ExpectedSingleEliminationFormat);
         }
 
@@ -42,8 +54,38 @@ impl Variant {
     /// # Errors
     /// Returns an error when there is less than 3 players in the bracket
     pub fn partition_by_round(&self) -> Result<Vec<Vec<Match>>, PartitionError> {
-        let wb = winner_bracket(self.bracket.matches.clone(), &self.bracket.participants);
+        let main_bracket = self.trees()[0].range();
+        let wb = winner_bracket(
+            self.bracket.matches[main_bracket].to_vec(),
+            &self.bracket.participants,
+        );
 
         Ok(wb)
     }
+
+    /// Returns playoff brackets from best to worst placements they decide
+    #[must_use]
+    pub fn playoffs(&self) -> Vec<PlayoffBracket> {
+        self.trees()
+            .iter()
+            .skip(1)
+            .map(|tree| PlayoffBracket {
+                placement: Placement {
+                    first: tree.first_seed,
+                    last: tree.first_seed + tree.size - 1,
+                },
+                rounds: (0..tree.number_of_rounds())
+                    .map(|round| self.bracket.matches[tree.round_range(round)].to_vec())
+                    .collect(),
+            })
+            .collect()
+    }
+
+    /// Main bracket followed by its playoffs
+    fn trees(&self) -> Vec<Tree> {
+        let Format::SingleElimination { playoffs } = self.bracket.format else {
+            return trees(self.bracket.participants.len(), Playoffs::Disabled);
+        };
+        trees(self.bracket.participants.len(), playoffs)
+    }
 }
@@ -0,0 +1,689 @@
+//! Final placements of players in a bracket
+
+use crate::{
+    bracket::{
+        double_elimination_variant::loser_bracket,
+        matches::{
+            index::Matches, links::link, round_robin_format::Step as RoundRobinStep,
+            single_elimination_format::playoffs::trees, swiss_format::Step as SwissStep,
+        },
+        Bracket, Error,
+    },
+    format::Format,
+    matches::{partition_double_elimination_matches, Id as MatchId, Links, Match},
+    opponent::Opponent,
+    player::Id as PlayerId,
+};
+use serde::{Deserialize, Serialize};
+use std::{borrow::Cow, collections::HashMap};
+
+/// Range of places shared by players eliminated at the same time (for
+/// instance 5th-6th)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Placement {
+    /// Best place of range
+    pub first: usize,
+    /// Worst place of range
+    pub last: usize,
+}
+
+impl std::fmt::Display for Placement {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.first == self.last {
+            write!(f, "{}", self.first)
+        } else {
+            write!(f, "{}-{}", self.first, self.last)
+        }
+    }
+}
+
+/// Players sharing the same placement
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlacementGroup {
+    /// Placement of players
+    pub placement: Placement,
+    /// Players with this placement, sorted by seed
+    pub players: Vec<PlayerId>,
+}
+
+/// Player still in the running with the worst placement they can get
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GuaranteedPlacement {
+    /// Player still in the running
+    pub player_id: PlayerId,
+    /// Placement of player if they lose all their remaining matches
+    pub placement: Placement,
+}
+
+/// Placements of players of a bracket
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Standings {
+    /// Placements of eliminated players (and winner once bracket is over),
+    /// from best to worst
+    pub placements: Vec<PlacementGroup>,
+    /// Players still in the running, sorted by seed
+    pub remaining: Vec<GuaranteedPlacement>,
+}
+
+impl Bracket {
+    /// Returns placements of eliminated players grouped by placement (1, 2,
+    /// 3, 4, 5-6, 7-8...) and the guaranteed placement of players still in
+    /// the running.
+    ///
+    /// Round robin and swiss brackets do not share placements: every player
+    /// gets their own placement according to their standings once bracket is
+    /// over. Until then, a player losing all their remaining matches is only
+    /// guaranteed to finish ahead of players who cannot reach their wins.
+    ///
+    /// # Errors
+    /// thrown when matches have not been generated or cannot be read
+    pub fn standings(&self) -> Result<Standings, Error> {
+        if self.matches.is_empty() {
+            return Err(Error::NoGeneratedMatches(self.bracket_id));
+        }
+        // players from first to last place with their wins and the most
+        // wins they can get
+        let ranking = match self.format {
+            Format::SingleElimination { .. } | Format::DoubleElimination { .. } => {
+                return Ok(self.elimination_standings())
+            }
+            Format::RoundRobin => {
+                let step = RoundRobinStep::new(
+                    Some(self.matches.to_vec()),
+                    &self.participants.get_seeding(),
+                    self.automatic_match_progression,
+                )
+                .map_err(|e| self.get_from_progression_error(e))?;
+                step.standings()
+                    .iter()
+                    .map(|s| (s.player_id, s.wins, s.wins + step.matches_left(s.player_id)))
+                    .collect::<Vec<_>>()
+            }
+            Format::Swiss { rounds } => {
+                let step = SwissStep::new(
+                    Some(self.matches.to_vec()),
+                    &self.participants.get_seeding(),
+                    self.automatic_match_progression,
+                    rounds,
+                )
+                .map_err(|e| self.get_from_progression_error(e))?
+                .with_disqualified_between_rounds(self.disqualified_between_rounds.clone());
+                step.standings()
+                    .iter()
+                    .map(|s| (s.player_id, s.wins, s.wins + step.matches_left(s.player_id)))
+                    .collect::<Vec<_>>()
+            }
+        };
+        if !self.is_over() {
+            return Ok(Standings {
+                placements: vec![],
+                remaining: guaranteed_placements(&ranking, &self.participants.get_seeding()),
+            });
+        }
+        Ok(Standings {
+            placements: ranking
+                .into_iter()
+                .enumerate()
+                .map(|(i, (p, _, _))| PlacementGroup {
+                    placement: Placement {
+                        first: i + 1,
+                        last: i + 1,
+                    },
+                    players: vec![p],
+                })
+                .collect(),
+            remaining: vec![],
+        })
+    }
+
+    /// Returns standings of single or double elimination bracket
+    fn elimination_standings(&self) -> Standings {
+        let stages = self.elimination_stages();
+        // brackets saved before matches were linked have no links
+        let matches = if self
+            .matches
+            .iter()
+            .all(|m| m.get_links() == Links::default())
+        {
+            Cow::Owned(link(self.format, self.participants.len(), self.matches.to_vec()).into())
+        } else {
+            Cow::Borrowed(&self.matches)
+        };
+        // player whose next matches cannot be followed can only count on last
+        // place
+        let last_place = Placement {
+            first: self.participants.len(),
+            last: self.participants.len(),
+        };
+
+        let stage_of = stages
+            .iter()
+            .flat_map(|s| s.matches.iter().map(move |id| (*id, s)))
+            .collect::<HashMap<_, _>>();
+
+        let mut placements: Vec<PlacementGroup> = vec![];
+        let mut remaining = vec![];
+        for p in &self.participants.get_seeding() {
+            let Some(placement) = placement_of(*p, &self.matches, &stage_of) else {
+                let placement = worst_case_of(*p, &matches, &stage_of).unwrap_or(last_place);
+                remaining.push(GuaranteedPlacement {
+                    player_id: *p,
+                    placement,
+                });
+                continue;
+            };
+            match placements.iter_mut().find(|g| g.placement == placement) {
+                Some(group) => group.players.push(*p),
+                None => placements.push(PlacementGroup {
+                    placement,
+                    players: vec![*p],
+                }),
+            }
+        }
+        placements.sort_by_key(|g| g.placement.first);
+
+        Standings {
+            placements,
+            remaining,
+        }
+    }
+
+    /// Returns matches where players get their placement, grouped by round
+    /// from first to last round
+    fn elimination_stages(&self) -> Vec<Stage> {
+        let ids = |round: &[Match]| round.iter().map(Match::get_id).collect::<Vec<_>>();
+        if let Format::SingleElimination { playoffs } = self.format {
+            let mut stages = vec![];
+            for tree in trees(self.participants.len(), playoffs) {
+                for round in 0..tree.number_of_rounds() {
+                    if tree.playoff_of(round).is_some() {
+                        continue;
+                    }
+                    let matches = ids(&self.matches[tree.round_range(round)]);
+                    let first = tree.first_seed + tree.survivors(round);
+                    stages.push(Stage {
+                        loser: Placement {
+                            first,
+                            last: first + matches.len() - 1,
+                        },
+                        winner: (round + 1 == tree.number_of_rounds()).then_some(Placement {
+                            first: tree.first_seed,
+                            last: tree.first_seed,
+                        }),
+                        matches,
+                    });
+                }
+            }
+            return stages;
+        }
+        let stages = double_elimination_stages(&self.matches, self.participants.len());
+        let placements = placements_of_stages(&stages);
+        let last = stages.len() - 1;
+        stages
+            .into_iter()
+            .zip(placements)
+            .enumerate()
+            .map(|(i, (matches, loser))| Stage {
+                matches,
+                loser,
+                winner: (i == last).then_some(Placement { first: 1, last: 1 }),
+            })
+            .collect()
+    }
+}
+
+/// Returns matches of a double elimination bracket of `n` players where
+/// players get their placement: rounds of loser bracket then grand finals
+/// with its reset
+pub(super) fn double_elimination_stages(matches: &[Match], n: usize) -> Vec<Vec<MatchId>> {
+    let ids = |round: &[Match]| round.iter().map(Match::get_id).collect::<Vec<_>>();
+    let (_, lb, gf, gf_reset) = partition_double_elimination_matches(matches, n);
+    let mut stages = loser_bracket(lb)
+        .iter()
+        .map(|round| ids(round))
+        .collect::<Vec<_>>();
+    stages.push(ids(&[Some(gf), gf_reset]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>()));
+    stages
+}
+
+/// Returns placement of players eliminated in each stage. Every match of a
+/// stage eliminates one player, except grand finals and its reset which
+/// eliminate only one player together
+pub(super) fn placements_of_stages(stages: &[Vec<MatchId>]) -> Vec<Placement> {
+    let mut placements = vec![];
+    let mut players_eliminated_later = 0;
+    for (i, stage) in stages.iter().enumerate().rev() {
+        let eliminated = if i + 1 == stages.len() {
+            1
+        } else {
+            stage.len()
+        };
+        placements.push(Placement {
+            first: players_eliminated_later + 2,
+            last: players_eliminated_later + 1 + eliminated,
+        });
+        players_eliminated_later += eliminated;
+    }
+    placements.reverse();
+    placements
+}
+
+/// Matches of an elimination bracket where players get their placement
+struct Stage {
+    /// Matches of this stage
+    matches: Vec<MatchId>,
+    /// Placement of players losing their last match in this stage
+    loser: Placement,
+    /// Placement of players winning their last match in this stage, if
+    /// they have nothing left to play
+    winner: Option<Placement>,
+}
+
+/// Returns placement of `player_id` once their last match in `matches` is
+/// decided, if it is
+fn placement_of(
+    player_id: PlayerId,
+    matches: &Matches,
+    stage_of: &HashMap<MatchId, &Stage>,
+) -> Option<Placement> {
+    let last_match = matches[*matches.matches_of(player_id).last()?];
+    let stage = stage_of.get(&last_match.get_id())?;
+    match last_match.get_winner() {
+        Opponent::Player(winner) if winner == player_id => stage.winner,
+        Opponent::Player(_) => Some(stage.loser),
+        Opponent::Unknown | Opponent::Bye => None,
+    }
+}
+
+/// Returns placement of `player_id` if they lose all their remaining
+/// matches. Losing sends them where losers of their current match go, until
+/// they lose a match of one of the elimination stages. There is none when
+/// loser links of an unvalidated bracket go round in circles
+fn worst_case_of(
+    player_id: PlayerId,
+    matches: &Matches,
+    stage_of: &HashMap<MatchId, &Stage>,
+) -> Option<Placement> {
+    let mut m = matches[*matches.matches_of(player_id).last()?];
+    for _ in 0..matches.len() {
+        let Some(link) = m.get_links().loser else {
+            return stage_of.get(&m.get_id()).map(|s| s.loser);
+        };
+        m = matches.find(link.match_id)?;
+    }
+    None
+}
+
+/// Returns guaranteed placement of players of a round robin or swiss
+/// bracket in progress, sorted by seed. A player losing all their remaining
+/// matches can be caught by anyone able to reach their number of wins.
+/// `ranking` holds players with their wins and the most wins they can get
+fn guaranteed_placements(
+    ranking: &[(PlayerId, usize, usize)],
+    seeding: &[PlayerId],
+) -> Vec<GuaranteedPlacement> {
+    let wins = ranking
+        .iter()
+        .map(|(p, wins, _)| (*p, *wins))
+        .collect::<HashMap<_, _>>();
+    let mut most_wins = ranking.iter().map(|(_, _, most)| *most).collect::<Vec<_>>();
+    most_wins.sort_unstable();
+    seeding
+        .iter()
+        .map(|p| {
+            let wins = wins.get(p).copied().unwrap_or_default();
+            // counts the player too since they keep their wins
+            let worst = most_wins.len() - most_wins.partition_point(|most| *most < wins);
+            GuaranteedPlacement {
+                player_id: *p,
+                placement: Placement {
+                    first: worst,
+                    last: worst,
+                },
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        bracket::{
+            builder::fixture::{play_all, started_bracket as bracket},
+            single_elimination_variant::Variant as SingleEliminationVariant,
+        },
+        format::{GrandFinals, LoserDrops, Playoffs},
+        player::{Participants, Player},
+    };
+
+    fn placement(first: usize, last: usize) -> Placement {
+        Placement { first, last }
+    }
+
+    #[test]
+    fn single_elimination_placements_are_tied_by_round() {
+        let (bracket, seeding) = bracket(
+            Format::SingleElimination {
+                playoffs: Playoffs::Disabled,
+            },
+            8,
+        );
+        let standings = play_all(bracket).standings().expect("standings");
+
+        assert!(standings.remaining.is_empty());
+        assert_eq!(
+            standings.placements,
+            vec![
+                PlacementGroup {
+                    placement: placement(1, 1),
+                    players: vec![seeding[0]]
+                },
+                PlacementGroup {
+                    placement: placement(2, 2),
+                    players: vec![seeding[1]]
+                },
+                PlacementGroup {
+                    placement: placement(3, 4),
+                    players: vec![seeding[2], seeding[3]]
+                },
+                PlacementGroup {
+                    placement: placement(5, 8),
+                    players: seeding[4..].to_vec()
+                },
+            ]
+        );
+        assert_eq!(standings.placements[3].placement.to_string(), "5-8");
+    }
+
+    #[test]
+    fn double_elimination_placements_follow_loser_bracket_rounds() {
+        let (bracket, seeding) = bracket(
+            Format::DoubleElimination {
+                loser_drops: LoserDrops::Standard,
+                grand_finals: GrandFinals::Reset,
+            },
+            8,
+        );
+        let standings = play_all(bracket).standings().expect("standings");
+
+        let placements = standings
+            .placements
+            .iter()
+            .map(|g| (g.placement.to_string(), g.players.clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            placements,
+            vec![
+                ("1".to_string(), vec![seeding[0]]),
+                ("2".to_string(), vec![seeding[1]]),
+                ("3".to_string(), vec![seeding[2]]),
+                ("4".to_string(), vec![seeding[3]]),
+                ("5-6".to_string(), vec![seeding[4], seeding[5]]),
+                ("7-8".to_string(), vec![seeding[6], seeding[7]]),
+            ]
+        );
+    }
+
+    #[test]
+    fn in_progress_bracket_returns_guaranteed_placements() {
+        let (bracket, seeding) = bracket(
+            Format::DoubleElimination {
+                loser_drops: LoserDrops::Standard,
+                grand_finals: GrandFinals::Reset,
+            },
+            8,
+        );
+        // 1 beats 8 in winner bracket
+        let bracket = bracket
+            .tournament_organiser_reports_result(seeding[0], (2, 0), seeding[7])
+            .expect("result")
+            .0;
+        let standings = bracket.standings().expect("standings");
+
+        assert!(standings.placements.is_empty());
+        let guaranteed = |p: PlayerId| {
+            standings
+                .remaining
+                .iter()
+                .find(|g| g.player_id == p)
+                .expect("remaining player")
+                .placement
+        };
+        // 1 can drop at the latest in loser bracket round 2
+        assert_eq!(guaranteed(seeding[0]), placement(5, 6));
+        assert_eq!(guaranteed(seeding[7]), placement(7, 8));
+        assert_eq!(guaranteed(seeding[1]), placement(7, 8));
+    }
+
+    #[test]
+    fn uneven_single_elimination_bracket() {
+        let (bracket, seeding) = bracket(
+            Format::SingleElimination {
+                playoffs: Playoffs::Disabled,
+            },
+            5,
+        );
+        let standings = play_all(bracket).standings().expect("standings");
+        let placements = standings
+            .placements
+            .iter()
+            .map(|g| (g.placement.to_string(), g.players.clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            placements,
+            vec![
+                ("1".to_string(), vec![seeding[0]]),
+                ("2".to_string(), vec![seeding[1]]),
+                ("3-4".to_string(), vec![seeding[2], seeding[3]]),
+                ("5".to_string(), vec![seeding[4]]),
+            ]
+        );
+    }
+
+    #[test]
+    fn third_place_match_separates_semi_finals_losers() {
+        let (bracket, seeding) = bracket(
+            Format::SingleElimination {
+                playoffs: Playoffs::ThirdPlaceMatch,
+            },
+            8,
+        );
+        assert_eq!(bracket.get_matches().len(), 8);
+        let bracket = play_all(bracket);
+        let placements = bracket
+            .standings()
+            .expect("standings")
+            .placements
+            .iter()
+            .map(|g| (g.placement.to_string(), g.players.clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            placements,
+            vec![
+                ("1".to_string(), vec![seeding[0]]),
+                ("2".to_string(), vec![seeding[1]]),
+                ("3".to_string(), vec![seeding[2]]),
+                ("4".to_string(), vec![seeding[3]]),
+                ("5-8".to_string(), seeding[4..].to_vec()),
+            ]
+        );
+    }
+
+    #[test]
+    fn consolation_decides_every_placement() {
+        for n in [6, 8] {
+            let (bracket, seeding) = bracket(
+                Format::SingleElimination {
+                    playoffs: Playoffs::Consolation,
+                },
+                n,
+            );
+            let standings = play_all(bracket).standings().expect("standings");
+            assert!(standings.remaining.is_empty());
+            for (i, group) in standings.placements.iter().enumerate() {
+                assert_eq!(group.placement, placement(i + 1, i + 1));
+                assert_eq!(group.players, vec![seeding[i]]);
+            }
+            assert_eq!(standings.placements.len(), n);
+        }
+    }
+
+    #[test]
+    fn disqualified_player_forfeits_playoff_matches() {
+        let (bracket, seeding) = bracket(
+            Format::SingleElimination {
+                playoffs: Playoffs::Consolation,
+            },
+            8,
+        );
+        let (bracket, _) = bracket
+            .disqualify_participant(seeding[7])
+            .expect("disqualified");
+        let bracket = play_all(bracket);
+
+        let standings = bracket.standings().expect("standings");
+        let last = standings.placements.last().expect("last placement");
+        assert_eq!(last.placement, placement(8, 8));
+        assert_eq!(last.players, vec![seeding[7]]);
+        let playoffs = SingleEliminationVariant::try_from(bracket)
+            .expect("single elimination")
+            .playoffs();
+        assert_eq!(
+            playoffs
+                .iter()
+                .map(|p| p.placement.to_string())
+                .collect::<Vec<_>>(),
+            vec!["3-4", "5-8", "7-8"]
+        );
+    }
+
+    #[test]
+    fn standings_need_generated_matches() {
+        let bracket = Bracket::default();
+        let bracket = Bracket {
+            participants: Participants::try_from(vec![Player::new("p1".into())])
+                .expect("participants"),
+            ..bracket
+        };
+        assert!(matches!(
+            bracket.standings(),
+            Err(Error::NoGeneratedMatches(_))
+        ));
+    }
+
+    #[test]
+    fn player_whose_next_matches_cannot_be_followed_is_guaranteed_last_place() {
+        let (bracket, seeding) = bracket(
+            Format::DoubleElimination {
+                loser_drops: LoserDrops::Standard,
+                grand_finals: GrandFinals::Reset,
+            },
+            8,
+        );
+        // loser of first match is sent to a match that does not exist
+        let mut json = serde_json::to_value(&bracket).expect("serialized");
+        json["matches"][0]["links"]["loser"]["match_id"] =
+            serde_json::to_value(MatchId::new_v4()).expect("match id");
+        let bracket: Bracket = serde_json::from_value(json).expect("deserialized");
+        let standings = bracket.standings().expect("standings");
+
+        let guaranteed = |p: PlayerId| {
+            standings
+                .remaining
+                .iter()
+                .find(|g| g.player_id == p)
+                .expect("remaining player")
+                .placement
+        };
+        assert_eq!(guaranteed(seeding[0]), placement(8, 8));
+        assert_eq!(guaranteed(seeding[1]), placement(7, 8));
+    }
+
+    #[test]
+    fn player_whose_loser_links_go_round_in_circles_is_guaranteed_last_place() {
+        let (bracket, seeding) = bracket(
+            Format::DoubleElimination {
+                loser_drops: LoserDrops::Standard,
+                grand_finals: GrandFinals::Reset,
+            },
+            8,
+        );
+        // loser of first match is sent back to first match
+        let mut json = serde_json::to_value(&bracket).expect("serialized");
+        json["matches"][0]["links"]["loser"]["match_id"] = json["matches"][0]["id"].clone();
+        let bracket: Bracket = serde_json::from_value(json).expect("deserialized");
+        let standings = bracket.standings().expect("standings");
+
+        let guaranteed = standings
+            .remaining
+            .iter()
+            .find(|g| g.player_id == seeding[0])
+            .expect("remaining player")
+            .placement;
+        assert_eq!(guaranteed, placement(8, 8));
+    }
+
+    #[test]
+    fn semi_finalist_with_third_place_match_is_guaranteed_fourth() {
+        let (bracket, seeding) = bracket(
+            Format::SingleElimination {
+                playoffs: Playoffs::ThirdPlaceMatch,
+            },
+            8,
+        );
+        // 1 beats 8 in quarter-finals
+        let bracket = bracket
+            .tournament_organiser_reports_result(seeding[0], (2, 0), seeding[7])
+            .expect("result")
+            .0;
+        let standings = bracket.standings().expect("standings");
+
+        let guaranteed = |p: PlayerId| {
+            standings
+                .remaining
+                .iter()
+                .find(|g| g.player_id == p)
+                .expect("remaining player")
+                .placement
+        };
+        // losing in semi-finals sends 1 to the third place match
+        assert_eq!(guaranteed(seeding[0]), placement(4, 4));
+        assert_eq!(guaranteed(seeding[1]), placement(5, 8));
+        assert!(standings
+            .placements
+            .iter()
+            .any(|g| g.placement == placement(5, 8) && g.players == vec![seeding[7]]));
+    }
+
+    #[test]
+    fn round_robin_player_out_of_reach_is_guaranteed_their_placement() {
+        let (mut bracket, seeding) = bracket(Format::RoundRobin, 4);
+        for opponent in &seeding[1..] {
+            bracket = bracket
+                .tournament_organiser_reports_result(seeding[0], (2, 0), *opponent)
+                .expect("result")
+                .0;
+        }
+        let standings = bracket.standings().expect("standings");
+
+        assert!(standings.placements.is_empty());
+        // nobody else can win 3 matches
+        assert_eq!(
+            standings
+                .remaining
+                .iter()
+                .map(|g| (g.player_id, g.placement.to_string()))
+                .collect::<Vec<_>>(),
+            vec![
+                (seeding[0], "1".to_string()),
+                (seeding[1], "4".to_string()),
+                (seeding[2], "4".to_string()),
+                (seeding[3], "4".to_string()),
+            ]
+        );
+    }
+}
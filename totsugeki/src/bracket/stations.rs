@@ -0,0 +1,432 @@
+//! Stations (setups) where matches are played. Ready matches are assigned
+//! to free stations so the bracket keeps moving and stations are freed once
+//! their match is over.
+//!
+//! Matches from earlier rounds are assigned first. For a given round, loser
+//! bracket matches go first since they are the bottleneck of double
+//! elimination brackets. Marquee matches can be pinned to a stream station
+//! and are only ever assigned to it.
+
+use crate::{
+    bracket::{events::BracketEvent, matches::index::players_of, Bracket, Error},
+    matches::{Id as MatchId, Match, State},
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use uuid::Uuid;
+
+/// Station identifier
+pub type Id = Uuid;
+
+/// Place where a match is played
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Station {
+    /// Identifier of station
+    id: Id,
+    /// Name of station (for instance, "Setup 3")
+    name: String,
+    /// Station is streamed
+    is_stream: bool,
+    /// Match played on this station
+    current_match: Option<MatchId>,
+    /// Matches that can only be played on this station
+    pinned_matches: Vec<MatchId>,
+}
+
+impl Station {
+    /// Returns identifier of station
+    #[must_use]
+    pub fn get_id(&self) -> Id {
+        self.id
+    }
+
+    /// Returns name of station
+    #[must_use]
+    pub fn get_name(&self) -> String {
+        self.name.clone()
+    }
+
+    /// Returns true if station is streamed
+    #[must_use]
+    pub fn is_stream(&self) -> bool {
+        self.is_stream
+    }
+
+    /// Returns match played on this station, if any
+    #[must_use]
+    pub fn get_current_match(&self) -> Option<MatchId> {
+        self.current_match
+    }
+
+    /// Returns matches pinned to this station
+    #[must_use]
+    pub fn get_pinned_matches(&self) -> Vec<MatchId> {
+        self.pinned_matches.clone()
+    }
+}
+
+impl Bracket {
+    /// Add station named `name`. Returns updated bracket and identifier of
+    /// new station
+    #[must_use]
+    pub fn add_station(self, name: &str, is_stream: bool) -> (Bracket, Id) {
+        let station_id = Id::new_v4();
+        (self.insert_station(station_id, name, is_stream), station_id)
+    }
+
+    /// Add station with identifier `station_id`
+    pub(crate) fn insert_station(self, station_id: Id, name: &str, is_stream: bool) -> Bracket {
+        let mut stations = self.stations.clone();
+        stations.push(Station {
+            id: station_id,
+            name: name.to_string(),
+            is_stream,
+            current_match: None,
+            pinned_matches: vec![],
+        });
+        Self { stations, ..self }.log(BracketEvent::StationAdded {
+            station_id,
+            name: name.to_string(),
+            is_stream,
+        })
+    }
+
+    /// Remove station `station_id`. Its match, if any, stays called
+    ///
+    /// # Errors
+    /// thrown when station is unknown
+    pub fn remove_station(self, station_id: Id) -> Result<Bracket, Error> {
+        self.get_station(station_id)?;
+        let mut stations = self.stations.clone();
+        stations.retain(|s| s.id != station_id);
+        Ok(Self { stations, ..self }.log(BracketEvent::StationRemoved { station_id }))
+    }
+
+    /// Returns all stations of this bracket
+    #[must_use]
+    pub fn get_stations(&self) -> Vec<Station> {
+        self.stations.clone()
+    }
+
+    /// Pin `match_id` to stream station `station_id`. This match will only
+    /// be assigned to this station
+    ///
+    /// # Errors
+    /// thrown when match or station is unknown or when station is not
+    /// streamed
+    pub fn pin_match_to_station(self, match_id: MatchId, station_id: Id) -> Result<Bracket, Error> {
+        if !self.matches.iter().any(|m| m.get_id() == match_id) {
+            return Err(Error::UnknownMatch(self.bracket_id, match_id));
+        }
+        if !self.get_station(station_id)?.is_stream {
+            return Err(Error::NotAStreamStation(self.bracket_id, station_id));
+        }
+        let stations = self
+            .stations
+            .iter()
+            .cloned()
+            .map(|mut s| {
+                s.pinned_matches.retain(|m| *m != match_id);
+                if s.id == station_id {
+                    s.pinned_matches.push(match_id);
+                }
+                s
+            })
+            .collect();
+        Ok(Self { stations, ..self }.log(BracketEvent::MatchPinned {
+            match_id,
+            station_id,
+        }))
+    }
+
+    /// Assign matches to play to free stations and call their players.
+    /// Players are only ever on one station: matches of players who are
+    /// called or playing elsewhere wait. Returns updated bracket and new
+    /// assignments
+    #[must_use]
+    pub fn assign_stations(self) -> (Bracket, Vec<(Id, MatchId)>) {
+        let is_busy_elsewhere = |m: &Match| {
+            players_of(m).any(|player_id| {
+                self.matches.matches_of(player_id).iter().any(|i| {
+                    let other = self.matches[*i];
+                    other.get_id() != m.get_id()
+                        && matches!(other.get_state(), State::Called | State::InProgress)
+                })
+            })
+        };
+        let mut candidates = self
+            .matches_to_play()
+            .into_iter()
+            .filter(|m| matches!(m.get_state(), State::Ready | State::Called))
+            .filter(|m| {
+                !self
+                    .stations
+                    .iter()
+                    .any(|s| s.current_match == Some(m.get_id()))
+            })
+            .filter(|m| !is_busy_elsewhere(m))
+            .collect::<Vec<_>>();
+        let priorities = self.priorities();
+        candidates.sort_by_key(|m| priorities.get(&m.get_id()).copied());
+
+        let mut assignments = vec![];
+        let mut seated = HashSet::new();
+        for station in self.stations.iter().filter(|s| s.current_match.is_none()) {
+            let is_pinned_elsewhere = |m: &Match| {
+                self.stations
+                    .iter()
+                    .any(|s| s.id != station.id && s.pinned_matches.contains(&m.get_id()))
+            };
+            let is_free = |m: &Match| players_of(m).all(|p| !seated.contains(&p));
+            let pinned = candidates
+                .iter()
+                .position(|m| station.pinned_matches.contains(&m.get_id()) && is_free(m));
+            let next = pinned.or_else(|| {
+                candidates
+                    .iter()
+                    .position(|m| !is_pinned_elsewhere(m) && is_free(m))
+            });
+            if let Some(i) = next {
+                let m = candidates.remove(i);
+                seated.extend(players_of(&m));
+                assignments.push((station.id, m.get_id()));
+            }
+        }
+
+        let at = Utc::now();
+        let bracket = self.apply_assignments(&assignments, at).log_at(
+            BracketEvent::StationsAssigned {
+                assignments: assignments.clone(),
+            },
+            at,
+        );
+        (bracket, assignments)
+    }
+
+    /// Put matches on their assigned station and call their players
+    pub(crate) fn apply_assignments(
+        self,
+        assignments: &[(Id, MatchId)],
+        at: DateTime<Utc>,
+    ) -> Self {
+        let stations = self
+            .stations
+            .iter()
+            .cloned()
+            .map(|mut s| {
+                if let Some((_, match_id)) = assignments.iter().find(|(id, _)| *id == s.id) {
+                    s.current_match = Some(*match_id);
+                }
+                s
+            })
+            .collect();
+        let matches = self
+            .matches
+            .iter()
+            .map(|m| {
+                let is_assigned = assignments.iter().any(|(_, id)| *id == m.get_id());
+                match m.call(at) {
+                    Ok(called) if is_assigned => called,
+                    _ => *m,
+                }
+            })
+            .collect();
+        Self {
+            matches,
+            stations,
+            ..self
+        }
+    }
+
+    /// Free stations whose match is not called or being played anymore
+    pub(crate) fn free_stations(self) -> Self {
+        if self.stations.is_empty() {
+            return self;
+        }
+        let stations = self
+            .stations
+            .iter()
+            .cloned()
+            .map(|mut s| {
+                let is_busy = s.current_match.is_some_and(|id| {
+                    self.matches.position(id).is_some_and(|i| {
+                        matches!(
+                            self.matches[i].get_state(),
+                            State::Called | State::InProgress
+                        )
+                    })
+                });
+                if !is_busy {
+                    s.current_match = None;
+                }
+                s
+            })
+            .collect();
+        Self { stations, ..self }
+    }
+
+    /// Unpin `match_ids` from their station
+    pub(crate) fn unpin(self, match_ids: &[MatchId]) -> Self {
+        let stations = self
+            .stations
+            .iter()
+            .cloned()
+            .map(|mut s| {
+                s.pinned_matches.retain(|m| !match_ids.contains(m));
+                s
+            })
+            .collect();
+        Self { stations, ..self }
+    }
+
+    /// Returns station `station_id`
+    ///
+    /// # Errors
+    /// thrown when station is unknown
+    fn get_station(&self, station_id: Id) -> Result<&Station, Error> {
+        self.stations
+            .iter()
+            .find(|s| s.id == station_id)
+            .ok_or(Error::UnknownStation(self.bracket_id, station_id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        bracket::{
+            builder::fixture::{play, started_bracket as bracket},
+            Bracket, Error,
+        },
+        format::{Format, GrandFinals, LoserDrops, Playoffs},
+        matches::{Match, State},
+        opponent::Opponent,
+    };
+
+    #[test]
+    fn free_stations_get_ready_matches_and_are_freed_after_result() {
+        let (bracket, _) = bracket(
+            Format::SingleElimination {
+                playoffs: Playoffs::Disabled,
+            },
+            8,
+        );
+        let (bracket, s1) = bracket.add_station("Setup 1", false);
+        let (bracket, s2) = bracket.add_station("Setup 2", false);
+
+        let (bracket, assignments) = bracket.assign_stations();
+        let first_round = bracket.get_matches()[..4]
+            .iter()
+            .map(Match::get_id)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            assignments,
+            vec![(s1, first_round[0]), (s2, first_round[1])]
+        );
+        assert_eq!(bracket.matches_to_play_in_state(State::Called).len(), 2);
+        let (bracket, assignments) = bracket.clone().assign_stations();
+        assert!(assignments.is_empty());
+
+        let bracket = play(bracket, first_round[1]);
+        assert_eq!(bracket.get_stations()[1].get_current_match(), None);
+        let (bracket, assignments) = bracket.assign_stations();
+        assert_eq!(assignments, vec![(s2, first_round[2])]);
+        let Opponent::Player(player_id) = bracket.get_matches()[0].get_players()[0] else {
+            panic!("expected player");
+        };
+        let (bracket, _) = bracket
+            .disqualify_participant(player_id)
+            .expect("disqualified");
+        assert_eq!(bracket.get_stations()[0].get_current_match(), None);
+
+        let replayed = Bracket::replay(&bracket.get_events()).expect("replay");
+        assert_eq!(replayed.get_stations(), bracket.get_stations());
+        assert_eq!(replayed.get_matches(), bracket.get_matches());
+    }
+
+    #[test]
+    fn loser_bracket_matches_go_first() {
+        let (mut bracket, _) = bracket(
+            Format::DoubleElimination {
+                loser_drops: LoserDrops::Standard,
+                grand_finals: GrandFinals::Reset,
+            },
+            8,
+        );
+        for m in bracket.get_matches()[..4].to_vec() {
+            bracket = play(bracket, m.get_id());
+        }
+        let (bracket, _) = bracket.add_station("Setup 1", false);
+
+        let (_, assignments) = bracket.clone().assign_stations();
+
+        let (_, lb, _, _) = crate::bracket::double_elimination_variant::Variant::try_from(bracket)
+            .expect("double elimination")
+            .partition_matches()
+            .expect("partition");
+        assert!(lb.iter().any(|m| m.get_id() == assignments[0].1));
+    }
+
+    #[test]
+    fn pinned_match_waits_for_its_stream_station() {
+        let (bracket, _) = bracket(
+            Format::SingleElimination {
+                playoffs: Playoffs::Disabled,
+            },
+            4,
+        );
+        let marquee = bracket.get_matches()[0].get_id();
+        let (bracket, setup) = bracket.add_station("Setup 1", false);
+        assert!(matches!(
+            bracket.clone().pin_match_to_station(marquee, setup),
+            Err(Error::NotAStreamStation(_, id)) if id == setup
+        ));
+        let (bracket, stream) = bracket.add_station("Stream", true);
+        let bracket = bracket
+            .pin_match_to_station(marquee, stream)
+            .expect("pinned");
+
+        let (_, assignments) = bracket.assign_stations();
+
+        assert_eq!(assignments.len(), 2);
+        assert!(assignments.contains(&(stream, marquee)));
+        assert!(!assignments.contains(&(setup, marquee)));
+    }
+
+    #[test]
+    fn players_are_never_on_two_stations() {
+        let (bracket, _) = bracket(Format::RoundRobin, 4);
+        let (bracket, _) = bracket.add_station("Setup 1", false);
+        let (bracket, _) = bracket.add_station("Setup 2", false);
+        let (bracket, _) = bracket.add_station("Setup 3", false);
+
+        let (bracket, assignments) = bracket.assign_stations();
+
+        assert_eq!(assignments.len(), 2);
+        let players = assignments
+            .iter()
+            .flat_map(|(_, match_id)| {
+                bracket
+                    .get_matches()
+                    .into_iter()
+                    .find(|m| m.get_id() == *match_id)
+                    .expect("match")
+                    .get_players()
+            })
+            .collect::<Vec<_>>();
+        for p in &players {
+            assert_eq!(players.iter().filter(|other| *other == p).count(), 1);
+        }
+        let (bracket, assignments) = bracket.assign_stations();
+        assert!(assignments.is_empty());
+
+        let played = bracket.get_stations()[0]
+            .get_current_match()
+            .expect("match");
+        let bracket = play(bracket, played);
+        // remaining matches all involve a player who is still seated
+        let (_, assignments) = bracket.assign_stations();
+        assert!(assignments.is_empty());
+    }
+}
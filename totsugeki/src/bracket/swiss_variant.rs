@@ -0,0 +1,121 @@
+//! Swiss tournament
+
+use crate::bracket::matches::swiss_format::{partition_by_round, Standing, Step};
+use crate::bracket::Bracket;
+use crate::format::Format;
+use crate::matches::Match;
+use std::num::NonZeroUsize;
+
+/// Swiss bracket variant
+#[derive(Debug)]
+pub struct Variant {
+    /// Some bracket
+    bracket: Bracket,
+    /// Number of rounds to play
+    rounds: Option<NonZeroUsize>,
+}
+
+/// Error with swiss brackets
+#[derive(Debug)]
+pub enum TryIntoError {
+    /// Expected format to be swiss
+    ExpectedSwissFormat,
+}
+
+impl TryFrom<Bracket> for Variant {
+    type Error = TryIntoError;
+
+    fn try_from(bracket: Bracket) -> Result<Self, Self::Error> {
+        let Format::Swiss { rounds } = bracket.format else {
+            return Err(TryIntoError::ExpectedSwissFormat);
+        };
+
+        Ok(Variant { bracket, rounds })
+    }
+}
+
+impl Variant {
+    /// Returns rounds generated so far
+    #[must_use]
+    pub fn partition_by_round(&self) -> Vec<Vec<Match>> {
+        partition_by_round(&self.bracket.matches)
+    }
+
+    /// Returns standings sorted from first to last place
+    ///
+    /// # Panics
+    /// When swiss matches cannot be read
+    #[must_use]
+    pub fn standings(&self) -> Vec<Standing> {
+        Step::new(
+            Some(self.bracket.matches.to_vec()),
+            &self.bracket.participants.get_seeding(),
+            self.bracket.automatic_match_progression,
+            self.rounds,
+        )
+        .expect("swiss bracket state")
+        .standings()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Variant;
+    use crate::{bracket::builder::Builder, format::Format, opponent::Opponent};
+    use std::num::NonZeroUsize;
+
+    #[test]
+    fn run_swiss_bracket() {
+        let bracket = Builder::default()
+            .set_format(Format::Swiss {
+                rounds: NonZeroUsize::new(3),
+            })
+            .set_new_players(6)
+            .build()
+            .expect("bracket");
+        let (mut bracket, mut matches_to_play) = bracket.start().expect("start");
+        assert_eq!(matches_to_play.len(), 3);
+
+        while !bracket.is_over() {
+            let m = matches_to_play.pop().expect("match to play");
+            let [Opponent::Player(p1), Opponent::Player(p2)] = m.get_players() else {
+                panic!("expected players");
+            };
+            let (b, _, mut new_matches) = bracket
+                .tournament_organiser_reports_result(p1, (2, 0), p2)
+                .expect("result");
+            bracket = b;
+            matches_to_play.append(&mut new_matches);
+        }
+        assert!(matches_to_play.is_empty());
+
+        let swiss = Variant::try_from(bracket).expect("swiss");
+        assert_eq!(swiss.partition_by_round().len(), 3);
+        let standings = swiss.standings();
+        assert_eq!(standings[0].wins, 3);
+        assert_eq!(standings.iter().map(|s| s.wins).sum::<usize>(), 9);
+    }
+
+    #[test]
+    fn parse_swiss_format() {
+        assert_eq!(
+            "swiss".parse::<Format>().expect("format"),
+            Format::Swiss { rounds: None }
+        );
+        assert_eq!(
+            "swiss-5".parse::<Format>().expect("format"),
+            Format::Swiss {
+                rounds: NonZeroUsize::new(5)
+            }
+        );
+        assert_eq!(
+            Format::Swiss {
+                rounds: NonZeroUsize::new(5)
+            }
+            .to_string(),
+            "swiss-5"
+        );
+        assert!("swiss-0".parse::<Format>().is_err());
+        assert!(serde_json::from_str::<Format>(r#"{"Swiss":{"rounds":0}}"#).is_err());
+    }
+}
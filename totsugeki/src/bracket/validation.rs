@@ -0,0 +1,381 @@
+//! Check structural invariants of a bracket coming from untrusted input
+//!
+//! A bracket deserialized from a client may have been tampered with. Methods
+//! of [`Bracket`] expect a well-formed bracket and panic otherwise, so
+//! validating it first lets callers reject bad input gracefully.
+
+use crate::{
+    bracket::{
+        matches::{
+            disqualified_more_than_once,
+            links::{is_unlinked, link},
+            single_elimination_format::playoffs::trees,
+        },
+        Bracket,
+    },
+    format::Format,
+    matches::{partition_double_elimination_matches, Id as MatchId, Link},
+    opponent::Opponent,
+    player::Id as PlayerId,
+};
+use std::collections::HashSet;
+use thiserror::Error;
+
+/// Broken invariant of a bracket
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ValidationIssue {
+    /// Same player entered bracket more than once
+    #[error("Player {0} is a participant more than once")]
+    DuplicatedParticipant(PlayerId),
+    /// Same match appears more than once
+    #[error("Match {0} appears more than once")]
+    DuplicatedMatch(MatchId),
+    /// Number of matches does not fit format and participants
+    #[error(
+        "Found {found} matches where {expected} are expected for this format and participants"
+    )]
+    UnexpectedMatchCount {
+        /// Number of matches of bracket
+        found: usize,
+        /// Number of matches expected (at most for swiss brackets)
+        expected: usize,
+    },
+    /// Seed of match does not belong to any participant
+    #[error("Match {0} uses seed {1} but there is not that many participants")]
+    SeedOutOfRange(MatchId, usize),
+    /// Match opponent is not a participant
+    #[error("Match {0} has player {1} who is not a participant")]
+    UnknownPlayer(MatchId, PlayerId),
+    /// Both opponents of a match are the same player
+    #[error("Player {1} plays against themselves in match {0}")]
+    SamePlayerTwice(MatchId, PlayerId),
+    /// Winner of a match does not play in it
+    #[error("Winner {1} of match {0} does not play in it")]
+    WinnerNotInMatch(MatchId, PlayerId),
+    /// Disqualified player of a match does not play in it
+    #[error("Disqualified player {1} of match {0} does not play in it")]
+    DisqualifiedPlayerNotInMatch(MatchId, PlayerId),
+    /// Player is disqualified in more than one match of the same bracket
+    #[error("Player {0} is disqualified more than once")]
+    DisqualifiedMoreThanOnce(PlayerId),
+    /// Match does not say where its players go next
+    #[error("Match {0} is not linked to the match where its players go next")]
+    MissingLink(MatchId),
+    /// Match sends its players to a match that is not in the bracket
+    #[error("Match {0} sends players to unknown match {1}")]
+    UnknownLinkedMatch(MatchId, MatchId),
+    /// Match sends its players somewhere the format does not allow
+    #[error("Match {0} cannot send players to match {1} in this format")]
+    IllegalLink(MatchId, MatchId),
+    /// Match has a result while bracket does not accept any
+    #[error("Match {0} has a result but bracket has not started")]
+    ResultBeforeStart(MatchId),
+    /// Bracket accepts results while participants can still join
+    #[error("Bracket accepts match results but it is not closed")]
+    AcceptsResultsWhenOpen,
+}
+
+impl Bracket {
+    /// Check structural invariants of this bracket: number of matches,
+    /// seeds, players and winners of matches, disqualifications, where
+    /// players go next and whether it accepts results. Use it before using a
+    /// bracket from untrusted input
+    ///
+    /// # Errors
+    /// thrown with every broken invariant
+    pub fn validate(&self) -> Result<(), Vec<ValidationIssue>> {
+        let seeding = self.participants.get_seeding();
+        let mut issues = vec![];
+
+        let mut participants = HashSet::new();
+        for player in &seeding {
+            if !participants.insert(*player) {
+                issues.push(ValidationIssue::DuplicatedParticipant(*player));
+            }
+        }
+        let mut match_ids = HashSet::new();
+        let mut seeds_in_range = true;
+        for m in &self.matches {
+            if !match_ids.insert(m.id) {
+                issues.push(ValidationIssue::DuplicatedMatch(m.id));
+            }
+            for seed in m.seeds.iter().filter(|seed| **seed > seeding.len()) {
+                seeds_in_range = false;
+                issues.push(ValidationIssue::SeedOutOfRange(m.id, *seed));
+            }
+            let players = m
+                .players
+                .iter()
+                .filter_map(|o| match o {
+                    Opponent::Player(id) => Some(*id),
+                    Opponent::Unknown | Opponent::Bye => None,
+                })
+                .collect::<Vec<_>>();
+            issues.extend(
+                players
+                    .iter()
+                    .filter(|p| !participants.contains(p))
+                    .map(|p| ValidationIssue::UnknownPlayer(m.id, *p)),
+            );
+            if let [p1, p2] = players[..] {
+                if p1 == p2 {
+                    issues.push(ValidationIssue::SamePlayerTwice(m.id, p1));
+                }
+            }
+            if let Opponent::Player(winner) = m.winner {
+                if !players.contains(&winner) {
+                    issues.push(ValidationIssue::WinnerNotInMatch(m.id, winner));
+                }
+            }
+            if let Opponent::Player(loser) = m.automatic_loser {
+                if !players.contains(&loser) {
+                    issues.push(ValidationIssue::DisqualifiedPlayerNotInMatch(m.id, loser));
+                }
+            }
+        }
+
+        let found = self.matches.len();
+        if self.format.accepts_number_of_matches(found, seeding.len()) {
+            // disqualifications are counted per bracket, which needs the
+            // expected number of matches to partition them
+            issues.extend(
+                self.disqualified_more_than_once(&seeding)
+                    .into_iter()
+                    .map(ValidationIssue::DisqualifiedMoreThanOnce),
+            );
+            // links are derived from seeds of matches
+            if seeds_in_range {
+                issues.extend(self.link_issues(seeding.len(), &match_ids));
+            }
+        } else {
+            issues.push(ValidationIssue::UnexpectedMatchCount {
+                found,
+                expected: self.format.number_of_matches(seeding.len()),
+            });
+        }
+        // progression of a malformed bracket cannot be read
+        if issues.is_empty() {
+            issues.extend(self.stage_issues());
+        }
+
+        if issues.is_empty() {
+            Ok(())
+        } else {
+            Err(issues)
+        }
+    }
+
+    /// Returns issues with flags of this bracket disagreeing with its
+    /// matches. A bracket is closed when it starts. Only a started bracket
+    /// has results, which it accepts until it is over
+    fn stage_issues(&self) -> Vec<ValidationIssue> {
+        if self.accept_match_results {
+            return if self.is_closed {
+                vec![]
+            } else {
+                vec![ValidationIssue::AcceptsResultsWhenOpen]
+            };
+        }
+        if self.is_closed && !self.matches.is_empty() && self.is_over() {
+            return vec![];
+        }
+        self.matches
+            .iter()
+            .filter(|m| m.has_result() && !m.is_bye())
+            .map(|m| ValidationIssue::ResultBeforeStart(m.id))
+            .collect()
+    }
+
+    /// Returns issues with links of matches compared to the links the format
+    /// gives to a bracket of `n` participants. Brackets without any link are
+    /// linked from their seeds when they progress and have none
+    fn link_issues(&self, n: usize, match_ids: &HashSet<MatchId>) -> Vec<ValidationIssue> {
+        if is_unlinked(self.format, &self.matches) {
+            return vec![];
+        }
+        let expected = link(self.format, n, self.matches.to_vec());
+        let mut issues = vec![];
+        for (m, expected) in self.matches.iter().zip(expected) {
+            let expected = expected.get_links();
+            for (link, expected) in [
+                (m.links.winner, expected.winner),
+                (m.links.loser, expected.loser),
+            ] {
+                match (link, expected) {
+                    (None, Some(_)) => issues.push(ValidationIssue::MissingLink(m.id)),
+                    (Some(Link { match_id, .. }), _) if !match_ids.contains(&match_id) => {
+                        issues.push(ValidationIssue::UnknownLinkedMatch(m.id, match_id));
+                    }
+                    (Some(link), expected) if Some(link) != expected => {
+                        issues.push(ValidationIssue::IllegalLink(m.id, link.match_id));
+                    }
+                    _ => {}
+                }
+            }
+        }
+        issues
+    }
+
+    /// Returns players disqualified more than once in the same bracket. Round
+    /// robin players forfeit all of their remaining matches once disqualified
+    fn disqualified_more_than_once(&self, seeding: &[PlayerId]) -> Vec<PlayerId> {
+        if self.matches.is_empty() {
+            return vec![];
+        }
+        match self.format {
+            Format::SingleElimination { playoffs } => trees(seeding.len(), playoffs)
+                .iter()
+                .flat_map(|tree| disqualified_more_than_once(&self.matches[tree.range()], seeding))
+                .collect(),
+            Format::DoubleElimination { .. } => {
+                let (w_bracket, l_bracket, _, _) =
+                    partition_double_elimination_matches(&self.matches, seeding.len());
+                let mut players = disqualified_more_than_once(&w_bracket, seeding);
+                players.extend(disqualified_more_than_once(&l_bracket, seeding));
+                players
+            }
+            Format::RoundRobin => vec![],
+            Format::Swiss { .. } => disqualified_more_than_once(&self.matches, seeding),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        bracket::builder::fixture::{bracket, started_bracket},
+        format::{GrandFinals, LoserDrops},
+    };
+
+    #[test]
+    fn generated_brackets_are_valid() {
+        for format in [
+            Format::default(),
+            Format::DoubleElimination {
+                loser_drops: LoserDrops::Alternating,
+                grand_finals: GrandFinals::Single,
+            },
+            Format::RoundRobin,
+            Format::Swiss { rounds: None },
+        ] {
+            for n in 0..10 {
+                let bracket = bracket(format, n);
+                assert_eq!(bracket.validate(), Ok(()), "{format} with {n} players");
+                if n >= 3 {
+                    let (bracket, _) = bracket.start().expect("started");
+                    assert_eq!(bracket.validate(), Ok(()), "{format} with {n} players");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn organiser_cannot_report_result_between_players_not_paired_together() {
+        for format in [
+            Format::default(),
+            Format::DoubleElimination {
+                loser_drops: LoserDrops::Alternating,
+                grand_finals: GrandFinals::Single,
+            },
+            Format::Swiss { rounds: None },
+        ] {
+            let (bracket, seeding) = started_bracket(format, 4);
+            // seed 1 plays seed 4 and seed 2 plays seed 3
+            match bracket.tournament_organiser_reports_result(seeding[0], (2, 0), seeding[1]) {
+                Err(crate::bracket::Error::NoMatchToPlay(_, player)) => {
+                    assert_eq!(player.get_id(), seeding[0], "{format}");
+                }
+                Err(e) => panic!("{format}: expected NoMatchToPlay, got {e}"),
+                Ok(_) => panic!("{format}: seeds 1 and 2 are not paired together"),
+            }
+        }
+    }
+
+    #[test]
+    fn tampered_bracket_is_rejected() {
+        let bracket = bracket(Format::default(), 5);
+        let seeding = bracket.get_participants().get_seeding();
+        let intruder = PlayerId::new_v4();
+
+        let mut matches = bracket.get_matches();
+        matches[0].players = [Opponent::Player(seeding[0]), Opponent::Player(seeding[0])];
+        matches[0].winner = Opponent::Player(intruder);
+        matches[1].seeds = [1, 6];
+        let tampered = Bracket {
+            matches: matches.into(),
+            ..bracket.clone()
+        };
+        let issues = tampered.validate().expect_err("issues");
+        assert_eq!(
+            issues,
+            vec![
+                ValidationIssue::SamePlayerTwice(tampered.matches[0].id, seeding[0]),
+                ValidationIssue::WinnerNotInMatch(tampered.matches[0].id, intruder),
+                ValidationIssue::SeedOutOfRange(tampered.matches[1].id, 6),
+            ]
+        );
+
+        let mut matches = bracket.get_matches();
+        matches.pop();
+        let tampered = Bracket {
+            matches: matches.into(),
+            ..bracket
+        };
+        assert_eq!(
+            tampered.validate(),
+            Err(vec![ValidationIssue::UnexpectedMatchCount {
+                found: 8,
+                expected: 9
+            }])
+        );
+    }
+
+    #[test]
+    fn tampered_links_are_rejected() {
+        let (bracket, _) = started_bracket(Format::default(), 4);
+        let matches = bracket.get_matches();
+        let unknown = MatchId::new_v4();
+
+        let mut json = serde_json::to_value(&bracket).expect("serialized");
+        let tampered = json["matches"].as_array_mut().expect("matches");
+        tampered[0].as_object_mut().expect("match").remove("links");
+        tampered[1]["links"]["winner"]["match_id"] = serde_json::json!(unknown);
+        tampered[2]["links"]["loser"]["match_id"] = serde_json::json!(matches[3].get_id());
+        let tampered: Bracket = serde_json::from_value(json).expect("deserialized");
+
+        assert_eq!(
+            tampered.validate(),
+            Err(vec![
+                ValidationIssue::MissingLink(matches[0].get_id()),
+                ValidationIssue::MissingLink(matches[0].get_id()),
+                ValidationIssue::UnknownLinkedMatch(matches[1].get_id(), unknown),
+                ValidationIssue::IllegalLink(matches[2].get_id(), matches[3].get_id()),
+            ])
+        );
+    }
+
+    #[test]
+    fn bracket_saved_without_links_is_accepted_and_linked() {
+        let (bracket, _) = started_bracket(Format::default(), 5);
+        let seeding = bracket.get_participants().get_seeding();
+
+        let mut json = serde_json::to_value(&bracket).expect("serialized");
+        for m in json["matches"].as_array_mut().expect("matches") {
+            m.as_object_mut().expect("match").remove("links");
+        }
+        let saved: Bracket = serde_json::from_value(json).expect("deserialized");
+
+        assert_eq!(saved.validate(), Ok(()));
+        let (saved, _, _) = saved
+            .tournament_organiser_reports_result(seeding[3], (2, 0), seeding[4])
+            .expect("result");
+        let (bracket, _, _) = bracket
+            .tournament_organiser_reports_result(seeding[3], (2, 0), seeding[4])
+            .expect("result");
+        for (saved, m) in saved.get_matches().iter().zip(bracket.get_matches()) {
+            assert_eq!(saved.get_players(), m.get_players());
+            assert_eq!(saved.get_links(), m.get_links());
+        }
+    }
+}
@@ -1,94 +1,340 @@
 //! Format of bracket
 
 use serde::{Deserialize, Serialize};
+use std::{borrow::Cow, num::NonZeroUsize};
 use thiserror::Error;
 
 use crate::{
     bracket::matches::{
-        double_elimination_format::Step as DE_Step, single_elimination_format::Step as SE_Step,
-        Progression,
+        double_elimination_format::Step as DE_Step,
+        index::Matches,
+        links::{is_unlinked, link},
+        round_robin_format::Step as RR_Step,
+        single_elimination_format::{playoffs::trees, Step as SE_Step},
+        swiss_format::Step as Swiss_Step,
+        Error as ProgressionError, Progression,
     },
     matches::Match,
-    player::{Id as PlayerId, Participants, Player},
+    player::{Id as PlayerId, Participants},
     seeding::{
-        double_elimination_seeded_bracket::get_loser_bracket_matches_top_seed_favored,
-        single_elimination_seeded_bracket::get_balanced_round_matches_top_seed_favored,
+        double_elimination_seeded_bracket::{
+            alternate_loser_drops, get_loser_bracket_matches_top_seed_favored,
+        },
+        round_robin_seeded_bracket::get_round_robin_matches,
+        single_elimination_seeded_bracket::{
+            get_balanced_round_matches_top_seed_favored, get_empty_matches_with_seed_offset,
+        },
+        swiss_seeded_bracket::{default_number_of_rounds, get_swiss_round_matches},
         Error as SeedingError,
     },
 };
 
 /// All bracket formats
 #[derive(PartialEq, Eq, Copy, Clone, Deserialize, Serialize, Debug)]
+// (de)serialization is wrapped to read formats saved without their options
+#[serde(remote = "Self")]
 pub enum Format {
+    /// Players are eliminated after their first loss
+    SingleElimination {
+        /// Extra matches played by eliminated players to decide placements
+        #[serde(default)]
+        playoffs: Playoffs,
+    },
+    /// Players are eliminated after their second loss
+    DoubleElimination {
+        /// Where players losing in winner bracket are placed in loser bracket
+        #[serde(default)]
+        loser_drops: LoserDrops,
+        /// How grand finals are played
+        #[serde(default)]
+        grand_finals: GrandFinals,
+    },
+    /// Every player plays every other player once
+    RoundRobin,
+    /// Players are paired each round against players with a similar record
+    Swiss {
+        /// Number of rounds to play. When not set, play enough rounds to have
+        /// a single undefeated player
+        rounds: Option<NonZeroUsize>,
+    },
+}
+
+/// Single and double elimination formats, as saved before they had options
+#[derive(Deserialize)]
+enum FormatWithoutOptions {
     /// Players are eliminated after their first loss
     SingleElimination,
     /// Players are eliminated after their second loss
     DoubleElimination,
 }
 
+impl From<FormatWithoutOptions> for Format {
+    fn from(format: FormatWithoutOptions) -> Self {
+        match format {
+            FormatWithoutOptions::SingleElimination => Format::SingleElimination {
+                playoffs: Playoffs::default(),
+            },
+            FormatWithoutOptions::DoubleElimination => Format::DoubleElimination {
+                loser_drops: LoserDrops::default(),
+                grand_finals: GrandFinals::default(),
+            },
+        }
+    }
+}
+
+impl Serialize for Format {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        Format::serialize(self, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Format {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum SavedFormat {
+            #[serde(deserialize_with = "Format::deserialize")]
+            WithOptions(Format),
+            WithoutOptions(FormatWithoutOptions),
+        }
+
+        Ok(match SavedFormat::deserialize(deserializer)? {
+            SavedFormat::WithOptions(format) => format,
+            SavedFormat::WithoutOptions(format) => format.into(),
+        })
+    }
+}
+
+/// Extra matches of a single elimination bracket that decide placements of
+/// eliminated players
+#[derive(PartialEq, Eq, Copy, Clone, Deserialize, Serialize, Debug, Default)]
+pub enum Playoffs {
+    /// Players eliminated in the same round share their placement
+    #[default]
+    Disabled,
+    /// Losers of semi-finals play each other for third place
+    ThirdPlaceMatch,
+    /// Players eliminated in the same round keep playing each other until
+    /// every placement is decided
+    Consolation,
+}
+
+/// Placement of players dropping from winner bracket into loser bracket
+#[derive(PartialEq, Eq, Copy, Clone, Deserialize, Serialize, Debug, Default)]
+pub enum LoserDrops {
+    /// Players drop where the seeding expects them, which often pits them
+    /// against someone they already played in winner bracket
+    #[default]
+    Standard,
+    /// Players dropping from the second winner bracket round onwards are
+    /// placed in reverse order, alternating the side they drop on to avoid
+    /// early rematches
+    Alternating,
+}
+
+/// How grand finals of a double elimination bracket are played. The player
+/// coming from winner bracket is player 1 of grand finals
+#[derive(PartialEq, Eq, Copy, Clone, Deserialize, Serialize, Debug, Default)]
+pub enum GrandFinals {
+    /// Grand finals are played again when the player coming from loser
+    /// bracket wins, so both players have lost once
+    #[default]
+    Reset,
+    /// Grand finals are played once
+    Single,
+    /// Grand finals are played once and the player coming from winner bracket
+    /// starts with a one game lead. Reported results include that game
+    WinnerSideAdvantage,
+}
+
 impl Format {
-    /// Generate matches according to the current format
+    /// Returns the number of participants needed to generate matches of this
+    /// format. A single match between two players is not worth an
+    /// elimination bracket
+    #[must_use]
+    pub fn minimum_number_of_participants(self) -> usize {
+        match self {
+            Format::SingleElimination { .. } | Format::DoubleElimination { .. } => 3,
+            Format::RoundRobin | Format::Swiss { .. } => 2,
+        }
+    }
+
+    /// Returns the number of matches of a bracket of `n` participants. Swiss
+    /// brackets pair rounds as they go: returns the number of matches once
+    /// all rounds are paired
+    #[must_use]
+    pub(crate) fn number_of_matches(self, n: usize) -> usize {
+        if n < self.minimum_number_of_participants() {
+            return 0;
+        }
+        match self {
+            Format::SingleElimination { playoffs } => trees(n, playoffs)
+                .iter()
+                .map(|tree| tree.range().len())
+                .sum(),
+            Format::DoubleElimination { grand_finals, .. } => {
+                2 * n - 2 + usize::from(grand_finals == GrandFinals::Reset)
+            }
+            Format::RoundRobin => n * (n - 1) / 2,
+            Format::Swiss { rounds } => {
+                rounds.map_or_else(|| default_number_of_rounds(n), NonZeroUsize::get)
+                    * n.div_ceil(2)
+            }
+        }
+    }
+
+    /// Returns true when a bracket of `n` participants can have `found`
+    /// matches in this format. A swiss bracket has its first round as soon
+    /// as it has enough participants
+    #[must_use]
+    pub(crate) fn accepts_number_of_matches(self, found: usize, n: usize) -> bool {
+        let expected = self.number_of_matches(n);
+        match self {
+            Format::Swiss { .. } => {
+                found <= expected && (found > 0 || n < self.minimum_number_of_participants())
+            }
+            _ => found == expected,
+        }
+    }
+
+    /// Generate matches according to the current format, linked to the
+    /// matches their players are sent to
     ///
     /// # Errors
     /// thrown when math overflow happens
     pub fn generate_matches(self, seeding: &[PlayerId]) -> Result<Vec<Match>, SeedingError> {
-        Ok(match self {
-            Format::SingleElimination => get_balanced_round_matches_top_seed_favored(seeding)?,
-            Format::DoubleElimination => {
+        let matches = match self {
+            Format::SingleElimination { playoffs } => {
+                let mut matches = get_balanced_round_matches_top_seed_favored(seeding)?;
+                for tree in trees(seeding.len(), playoffs).iter().skip(1) {
+                    let mut playoff_matches =
+                        get_empty_matches_with_seed_offset(tree.size, tree.first_seed - 1)?;
+                    matches.append(&mut playoff_matches);
+                }
+                matches
+            }
+            Format::DoubleElimination {
+                loser_drops,
+                grand_finals,
+            } => {
                 let mut matches = vec![];
                 let mut winner_bracket_matches =
                     get_balanced_round_matches_top_seed_favored(seeding)?;
-                matches.append(&mut winner_bracket_matches);
                 let mut looser_bracket_matches =
                     get_loser_bracket_matches_top_seed_favored(seeding)?;
+                if loser_drops == LoserDrops::Alternating {
+                    looser_bracket_matches = alternate_loser_drops(
+                        &winner_bracket_matches,
+                        looser_bracket_matches,
+                        seeding.len(),
+                    );
+                }
+                matches.append(&mut winner_bracket_matches);
                 matches.append(&mut looser_bracket_matches);
-                let grand_finals: Match = Match::new_empty([1, 2]);
-                matches.push(grand_finals);
-                let grand_finals_reset: Match = Match::new_empty([1, 2]);
-                matches.push(grand_finals_reset);
+                matches.push(Match::new_empty([1, 2]));
+                if grand_finals == GrandFinals::Reset {
+                    let grand_finals_reset: Match = Match::new_empty([1, 2]);
+                    matches.push(grand_finals_reset);
+                }
                 matches
             }
-        })
+            Format::RoundRobin => get_round_robin_matches(seeding)?,
+            // next rounds are generated as the tournament progresses
+            Format::Swiss { .. } => get_swiss_round_matches(seeding, &[], &[])?,
+        };
+        Ok(link(self, seeding.len(), matches))
     }
 
     // TODO remove abstraction? Putting stuff on the heap may not be necessary
-    /// Returns progression implementation for this bracket format
+    /// Returns progression implementation for this bracket format.
+    /// `disqualified` players were disqualified while no match of theirs
+    /// could record it
     ///
-    /// # Panics
-    /// Panics if match generation of given format cannot generate match
-    #[must_use]
-    pub fn get_progression(
+    /// # Errors
+    /// thrown when there are not as many `matches` as this format gives to
+    /// `seeding`
+    pub fn get_progression<'a>(
         &self,
-        matches: Vec<Match>,
+        matches: Cow<'a, Matches>,
         seeding: &Participants,
         automatic_progression: bool,
-    ) -> Box<dyn Progression> {
-        match self {
-            Format::SingleElimination => Box::new(
-                SE_Step::new(Some(matches), &seeding.get_seeding(), automatic_progression)
-                    .expect("single elimination bracket state"),
+        disqualified: &[PlayerId],
+    ) -> Result<Box<dyn Progression + 'a>, ProgressionError> {
+        // matches of a bracket are generated when it starts
+        let n = seeding.len();
+        if !matches.is_empty() && !self.accepts_number_of_matches(matches.len(), n) {
+            return Err(ProgressionError::UnexpectedMatchCount(
+                matches.len(),
+                self.number_of_matches(n),
+            ));
+        }
+        let matches = if is_unlinked(*self, &matches) {
+            Cow::Owned(link(*self, n, matches.to_vec()).into())
+        } else {
+            matches
+        };
+        Ok(match self {
+            Format::SingleElimination { playoffs } => Box::new(
+                SE_Step::from_matches(matches, seeding.get_seeding(), automatic_progression)
+                    .with_playoffs(*playoffs),
+            ),
+            Format::DoubleElimination {
+                loser_drops,
+                grand_finals,
+            } => Box::new(
+                DE_Step::from_matches(matches, seeding.get_seeding(), automatic_progression)
+                    .with_loser_drops(*loser_drops)
+                    .with_grand_finals(*grand_finals),
             ),
-            Format::DoubleElimination => Box::new(
-                DE_Step::new(
-                    Some(matches),
-                    seeding
-                        .get_players_list()
-                        .iter()
-                        .map(Player::get_id)
-                        .collect(),
+            Format::RoundRobin => Box::new(RR_Step::from_matches(
+                matches,
+                seeding.get_seeding(),
+                automatic_progression,
+            )),
+            Format::Swiss { rounds } => Box::new(
+                Swiss_Step::from_matches(
+                    matches,
+                    seeding.get_seeding(),
                     automatic_progression,
+                    *rounds,
                 )
-                .expect("double elimination bracket state"),
+                .with_disqualified_between_rounds(disqualified.to_vec()),
             ),
-        }
+        })
     }
 }
 
 impl std::fmt::Display for Format {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Format::SingleElimination => write!(f, "single-elimination"),
-            Format::DoubleElimination => write!(f, "double-elimination"),
+            Format::SingleElimination {
+                playoffs: Playoffs::Disabled,
+            } => write!(f, "single-elimination"),
+            Format::SingleElimination {
+                playoffs: Playoffs::ThirdPlaceMatch,
+            } => write!(f, "single-elimination-third-place"),
+            Format::SingleElimination {
+                playoffs: Playoffs::Consolation,
+            } => write!(f, "single-elimination-consolation"),
+            Format::DoubleElimination {
+                loser_drops,
+                grand_finals,
+            } => {
+                write!(f, "double-elimination")?;
+                if *loser_drops == LoserDrops::Alternating {
+                    write!(f, "-alternating-drops")?;
+                }
+                match grand_finals {
+                    GrandFinals::Reset => Ok(()),
+                    GrandFinals::Single => write!(f, "-no-reset"),
+                    GrandFinals::WinnerSideAdvantage => write!(f, "-winner-side-advantage"),
+                }
+            }
+            Format::RoundRobin => write!(f, "round-robin"),
+            Format::Swiss { rounds: None } => write!(f, "swiss"),
+            Format::Swiss {
+                rounds: Some(rounds),
+            } => write!(f, "swiss-{rounds}"),
         }
     }
 }
@@ -98,16 +344,51 @@ impl std::str::FromStr for Format {
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
-            "single-elimination" => Ok(Format::SingleElimination),
-            "double-elimination" => Ok(Format::DoubleElimination),
-            _ => Err(ParsingError::Unknown(s.to_string())),
+            "single-elimination" => Ok(Format::SingleElimination {
+                playoffs: Playoffs::Disabled,
+            }),
+            "single-elimination-third-place" => Ok(Format::SingleElimination {
+                playoffs: Playoffs::ThirdPlaceMatch,
+            }),
+            "single-elimination-consolation" => Ok(Format::SingleElimination {
+                playoffs: Playoffs::Consolation,
+            }),
+            "round-robin" => Ok(Format::RoundRobin),
+            "swiss" => Ok(Format::Swiss { rounds: None }),
+            _ => {
+                if let Some(options) = s.strip_prefix("double-elimination") {
+                    let (loser_drops, options) = match options.strip_prefix("-alternating-drops") {
+                        Some(options) => (LoserDrops::Alternating, options),
+                        None => (LoserDrops::Standard, options),
+                    };
+                    let grand_finals = match options {
+                        "" => GrandFinals::Reset,
+                        "-no-reset" => GrandFinals::Single,
+                        "-winner-side-advantage" => GrandFinals::WinnerSideAdvantage,
+                        _ => return Err(ParsingError::Unknown(s.to_string())),
+                    };
+                    return Ok(Format::DoubleElimination {
+                        loser_drops,
+                        grand_finals,
+                    });
+                }
+                match s.strip_prefix("swiss-").map(str::parse::<NonZeroUsize>) {
+                    Some(Ok(rounds)) => Ok(Format::Swiss {
+                        rounds: Some(rounds),
+                    }),
+                    _ => Err(ParsingError::Unknown(s.to_string())),
+                }
+            }
         }
     }
 }
 
 impl Default for Format {
     fn default() -> Self {
-        Self::DoubleElimination
+        Self::DoubleElimination {
+            loser_drops: LoserDrops::default(),
+            grand_finals: GrandFinals::default(),
+        }
     }
 }
 
@@ -121,3 +402,142 @@ pub enum ParsingError {
     )]
     Unknown(String),
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bracket::Bracket;
+
+    /// Double elimination bracket of 3 players saved before formats had
+    /// options, where p2 beat p3
+    const BRACKET_SAVED_WITHOUT_FORMAT_OPTIONS: &str = r#"{"bracket_id":"0cbc76ca-2f5f-48c0-8910-e67bc10db1f6","bracket_name":"weekly","participants":{"participants":[{"id":"d07503e8-daf5-4e99-8c0c-47b31ee8873f","name":"p1"},{"id":"a21dd48c-bcfc-490e-ae5d-44b6aec6a001","name":"p2"},{"id":"d6e2b718-58d7-46f4-9665-a612e3029081","name":"p3"}]},"matches":[{"id":"c62e83ce-1365-40b1-afe7-e76f7506e154","players":[{"Player":"a21dd48c-bcfc-490e-ae5d-44b6aec6a001"},{"Player":"d6e2b718-58d7-46f4-9665-a612e3029081"}],"seeds":[2,3],"winner":{"Player":"a21dd48c-bcfc-490e-ae5d-44b6aec6a001"},"automatic_loser":"Unknown","reported_results":[[2,0],[0,2]]},{"id":"aca922ab-7e80-4c3a-b080-d7cf86b79e81","players":[{"Player":"d07503e8-daf5-4e99-8c0c-47b31ee8873f"},{"Player":"a21dd48c-bcfc-490e-ae5d-44b6aec6a001"}],"seeds":[1,2],"winner":"Unknown","automatic_loser":"Unknown","reported_results":[[0,0],[0,0]]},{"id":"1f2748b6-06d2-4754-aa02-964403e369a6","players":["Unknown",{"Player":"d6e2b718-58d7-46f4-9665-a612e3029081"}],"seeds":[2,3],"winner":"Unknown","automatic_loser":"Unknown","reported_results":[[0,0],[0,0]]},{"id":"fdf10751-726c-40f5-9920-91810f56be0b","players":["Unknown","Unknown"],"seeds":[1,2],"winner":"Unknown","automatic_loser":"Unknown","reported_results":[[0,0],[0,0]]},{"id":"4e6a37e7-05d5-4423-8918-e8b449b3a3ff","players":["Unknown","Unknown"],"seeds":[1,2],"winner":"Unknown","automatic_loser":"Unknown","reported_results":[[0,0],[0,0]]}],"format":"DoubleElimination","seeding_method":"Strict","start_time":"2024-01-01T00:00:00Z","accept_match_results":true,"automatic_match_progression":true,"is_closed":true}"#;
+
+    #[test]
+    fn formats_saved_without_options_get_default_options() {
+        let double_elimination = Format::DoubleElimination {
+            loser_drops: LoserDrops::Standard,
+            grand_finals: GrandFinals::Reset,
+        };
+        for (json, expected) in [
+            (
+                r#""SingleElimination""#,
+                Format::SingleElimination {
+                    playoffs: Playoffs::Disabled,
+                },
+            ),
+            (r#""DoubleElimination""#, double_elimination),
+            (r#"{"DoubleElimination":{}}"#, double_elimination),
+            (
+                r#"{"DoubleElimination":{"grand_finals":"Single"}}"#,
+                Format::DoubleElimination {
+                    loser_drops: LoserDrops::Standard,
+                    grand_finals: GrandFinals::Single,
+                },
+            ),
+            (r#""RoundRobin""#, Format::RoundRobin),
+        ] {
+            let format: Format = serde_json::from_str(json).expect(json);
+            assert_eq!(format, expected, "{json}");
+        }
+        assert!(serde_json::from_str::<Format>(r#""TripleElimination""#).is_err());
+    }
+
+    #[test]
+    fn formats_are_read_back() {
+        for format in [
+            Format::SingleElimination {
+                playoffs: Playoffs::Consolation,
+            },
+            Format::DoubleElimination {
+                loser_drops: LoserDrops::Alternating,
+                grand_finals: GrandFinals::WinnerSideAdvantage,
+            },
+            Format::RoundRobin,
+            Format::Swiss {
+                rounds: NonZeroUsize::new(4),
+            },
+        ] {
+            let json = serde_json::to_string(&format).expect("serialized");
+            assert_eq!(
+                serde_json::from_str::<Format>(&json).expect("deserialized"),
+                format
+            );
+        }
+    }
+
+    #[test]
+    fn bracket_saved_without_format_options_can_be_played() {
+        let bracket: Bracket =
+            serde_json::from_str(BRACKET_SAVED_WITHOUT_FORMAT_OPTIONS).expect("saved bracket");
+        assert_eq!(bracket.get_format(), Format::default());
+        let json = serde_json::to_string(&bracket).expect("serialized");
+        let bracket: Bracket = serde_json::from_str(&json).expect("deserialized");
+        assert_eq!(bracket.get_format(), Format::default());
+
+        // p1 beats p2 in winner bracket finals: p2 drops to loser bracket
+        let seeding = bracket.get_participants().get_seeding();
+        let (bracket, _, _) = bracket
+            .tournament_organiser_reports_result(seeding[0], (2, 0), seeding[1])
+            .expect("result");
+        let loser_bracket_finals = bracket.get_matches()[2];
+        assert!(loser_bracket_finals.contains(seeding[1]));
+        assert!(loser_bracket_finals.contains(seeding[2]));
+    }
+
+    #[test]
+    fn number_of_matches_is_the_number_of_generated_matches() {
+        for format in [
+            Format::SingleElimination {
+                playoffs: Playoffs::Disabled,
+            },
+            Format::SingleElimination {
+                playoffs: Playoffs::ThirdPlaceMatch,
+            },
+            Format::SingleElimination {
+                playoffs: Playoffs::Consolation,
+            },
+            Format::DoubleElimination {
+                loser_drops: LoserDrops::Standard,
+                grand_finals: GrandFinals::Reset,
+            },
+            Format::DoubleElimination {
+                loser_drops: LoserDrops::Alternating,
+                grand_finals: GrandFinals::Single,
+            },
+            Format::RoundRobin,
+        ] {
+            for n in format.minimum_number_of_participants()..=40 {
+                let seeding = (0..n).map(|_| PlayerId::new_v4()).collect::<Vec<_>>();
+                let matches = format.generate_matches(&seeding).expect("matches");
+                assert_eq!(format.number_of_matches(n), matches.len(), "{format} {n}");
+            }
+        }
+    }
+
+    #[test]
+    fn bracket_with_missing_matches_cannot_progress() {
+        let mut bracket = Bracket::default();
+        for i in 1..=5 {
+            bracket = bracket
+                .add_participant(format!("p{i}").as_str())
+                .expect("participant");
+        }
+        let bracket = bracket.start().expect("start").0;
+        let seeding = bracket.get_participants().get_seeding();
+        // saved without links, then tampered with
+        let mut json = serde_json::to_value(&bracket).expect("serialized");
+        let matches = json["matches"].as_array_mut().expect("matches");
+        for m in matches.iter_mut() {
+            m.as_object_mut().expect("match").remove("links");
+        }
+        matches.truncate(matches.len() - 2);
+        let bracket: Bracket = serde_json::from_value(json).expect("deserialized");
+
+        assert!(bracket.matches_to_play().is_empty());
+        assert!(!bracket.is_over());
+        assert!(matches!(
+            bracket.tournament_organiser_reports_result(seeding[3], (2, 0), seeding[4]),
+            Err(crate::bracket::Error::UnexpectedMatchCount(_, 7, 9))
+        ));
+    }
+}
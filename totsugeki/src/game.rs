@@ -0,0 +1,89 @@
+//! Result of a single game inside a match (a set)
+
+use crate::player::Id as PlayerId;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use thiserror::Error;
+
+/// Set score cannot be derived from games
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// Set scores count at most 127 games for each player
+    #[error("{0} games were reported but a set score counts at most 127 games per player")]
+    TooManyGames(usize),
+}
+
+/// Result of one game of a match with what was played
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GameResult {
+    /// Winner of this game
+    pub winner: PlayerId,
+    /// Character used by players during this game
+    pub characters: Vec<(PlayerId, String)>,
+    /// Stage where this game was played
+    pub stage: Option<String>,
+    /// Any other game specific information (for instance, stocks left)
+    pub metadata: BTreeMap<String, String>,
+}
+
+impl GameResult {
+    /// Game won by `winner` without any other information
+    #[must_use]
+    pub fn new(winner: PlayerId) -> Self {
+        Self {
+            winner,
+            characters: vec![],
+            stage: None,
+            metadata: BTreeMap::new(),
+        }
+    }
+
+    /// Set character used by `player_id`
+    #[must_use]
+    pub fn set_character(self, player_id: PlayerId, character: &str) -> Self {
+        let mut characters = self.characters;
+        characters.retain(|(p, _)| *p != player_id);
+        characters.push((player_id, character.to_string()));
+        Self { characters, ..self }
+    }
+
+    /// Set stage where this game was played
+    #[must_use]
+    pub fn set_stage(self, stage: &str) -> Self {
+        Self {
+            stage: Some(stage.to_string()),
+            ..self
+        }
+    }
+
+    /// Add game specific information
+    #[must_use]
+    pub fn set_metadata(self, key: &str, value: &str) -> Self {
+        let mut metadata = self.metadata;
+        metadata.insert(key.to_string(), value.to_string());
+        Self { metadata, ..self }
+    }
+
+    /// Returns character used by `player_id`, if known
+    #[must_use]
+    pub fn get_character(&self, player_id: PlayerId) -> Option<&str> {
+        self.characters
+            .iter()
+            .find(|(p, _)| *p == player_id)
+            .map(|(_, c)| c.as_str())
+    }
+}
+
+/// Set score from the point of view of `player_id`: games they won and games
+/// they lost
+///
+/// # Errors
+/// thrown when a player won more than 127 games
+pub fn score_of(player_id: PlayerId, games: &[GameResult]) -> Result<(i8, i8), Error> {
+    let won = games.iter().filter(|g| g.winner == player_id).count();
+    let lost = games.len() - won;
+    match (i8::try_from(won), i8::try_from(lost)) {
+        (Ok(won), Ok(lost)) => Ok((won, lost)),
+        _ => Err(Error::TooManyGames(games.len())),
+    }
+}
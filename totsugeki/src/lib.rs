@@ -15,10 +15,13 @@ use uuid::Uuid;
 
 pub mod bracket;
 pub mod format;
+pub mod game;
 pub mod matches;
 pub mod opponent;
 pub mod player;
+pub mod rating;
 pub mod seeding;
+pub mod tournament;
 
 /// Discussion channel identifier
 pub type DiscussionChannelId = Uuid;
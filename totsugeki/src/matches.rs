@@ -5,6 +5,7 @@ use crate::{
     opponent::{Opponent, ParsingOpponentError},
     player::{Id as PlayerId, Participants, Player},
 };
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::{num::ParseIntError, str::FromStr};
 use thiserror::Error;
@@ -39,6 +40,73 @@ pub enum Error {
     /// Cannot insert player because another player is already present
     #[error("Player \"{0}\" cannot be set because player \"{1}\" is already there")]
     AlreadyPresent(PlayerId, PlayerId),
+    /// Reported score is not a possible final score for the set length of
+    /// this match
+    #[error("Score {0} is not a valid final score for a {1} match")]
+    InvalidScore(ReportedResult, SetLength),
+    /// Match cannot go from its current state to the requested one
+    #[error("Match {0} cannot go from {1} to {2}")]
+    InvalidTransition(MatchId, State, State),
+    /// Reported score of grand finals does not include the game the player
+    /// coming from winner bracket starts with
+    #[error("Score {1} of match {0} does not include the game the player coming from winner bracket starts with")]
+    MissingAdvantage(MatchId, ReportedResult),
+}
+
+/// Number of games to play in a match
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SetLength {
+    /// Best of N games (for instance, best of 3 is won with 2 games)
+    BestOf(u8),
+    /// First player to win N games
+    FirstTo(u8),
+}
+
+impl SetLength {
+    /// Number of games to win the match
+    #[must_use]
+    pub fn wins_needed(self) -> u8 {
+        match self {
+            SetLength::BestOf(n) => n / 2 + 1,
+            SetLength::FirstTo(n) => n,
+        }
+    }
+
+    /// Returns true if a match of this length can be won: best of N needs an
+    /// odd number of games and at least 1 and at most 127 wins are needed
+    #[must_use]
+    pub fn is_valid(self) -> bool {
+        match self {
+            SetLength::BestOf(n) => n % 2 == 1 && n / 2 < 127,
+            SetLength::FirstTo(n) => (1..=127).contains(&n),
+        }
+    }
+
+    /// Check `result` is a possible final score of a match of this length:
+    /// one player won exactly the number of games needed and the other won
+    /// less
+    ///
+    /// # Errors
+    /// thrown when score is negative, a draw or does not match set length
+    pub fn validate(self, result: ReportedResult) -> Result<(), Error> {
+        let (s1, s2) = result.0;
+        let Ok(wins_needed) = i8::try_from(self.wins_needed()) else {
+            return Err(Error::InvalidScore(result, self));
+        };
+        if s1 < 0 || s2 < 0 || s1.max(s2) != wins_needed || s1 == s2 {
+            return Err(Error::InvalidScore(result, self));
+        }
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for SetLength {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SetLength::BestOf(n) => write!(f, "Bo{n}"),
+            SetLength::FirstTo(n) => write!(f, "FT{n}"),
+        }
+    }
 }
 
 /// Seeds of players
@@ -100,6 +168,98 @@ impl FromStr for ReportedResult {
 /// Players in match
 pub type MatchPlayers = [Opponent; 2];
 
+/// Where a match is in its lifecycle
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum State {
+    /// Waiting for at least one opponent
+    Pending,
+    /// Both opponents are known and match can be called
+    Ready,
+    /// Players were called to play this match
+    Called,
+    /// Match is being played
+    InProgress,
+    /// Match has a winner
+    Completed,
+}
+
+impl std::fmt::Display for State {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            State::Pending => write!(f, "pending"),
+            State::Ready => write!(f, "ready"),
+            State::Called => write!(f, "called"),
+            State::InProgress => write!(f, "in progress"),
+            State::Completed => write!(f, "completed"),
+        }
+    }
+}
+
+/// When a match entered each state of its lifecycle
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Lifecycle {
+    /// Both opponents became known
+    pub ready_at: Option<DateTime<Utc>>,
+    /// Players were called
+    pub called_at: Option<DateTime<Utc>>,
+    /// Match started
+    pub started_at: Option<DateTime<Utc>>,
+    /// Match got a winner
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+/// Slot of a match a player is sent to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Link {
+    /// Match where player plays next
+    pub match_id: MatchId,
+    /// Player plays next as player 1 of that match
+    pub is_player_1: bool,
+}
+
+/// Where players of a match are sent once it is over
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Links {
+    /// Where winner plays next
+    pub winner: Option<Link>,
+    /// Where loser plays next
+    pub loser: Option<Link>,
+}
+
+impl Links {
+    /// Links pointing to matches renamed with `rename`
+    #[must_use]
+    pub(crate) fn renamed(self, rename: impl Fn(MatchId) -> MatchId) -> Self {
+        let rename = |link: Option<Link>| {
+            link.map(|link| Link {
+                match_id: rename(link.match_id),
+                ..link
+            })
+        };
+        Self {
+            winner: rename(self.winner),
+            loser: rename(self.loser),
+        }
+    }
+}
+
+impl Lifecycle {
+    /// Use `at` for every timestamp that changed since `before`
+    #[must_use]
+    pub(crate) fn restamp(self, before: Lifecycle, at: DateTime<Utc>) -> Self {
+        let restamp = |now: Option<DateTime<Utc>>, before| match now {
+            Some(_) if now != before => Some(at),
+            _ => now,
+        };
+        Self {
+            ready_at: restamp(self.ready_at, before.ready_at),
+            called_at: restamp(self.called_at, before.called_at),
+            started_at: restamp(self.started_at, before.started_at),
+            completed_at: restamp(self.completed_at, before.completed_at),
+        }
+    }
+}
+
 /// A match between two players, resulting in a winner and a loser
 #[derive(Debug, Default, PartialEq, Eq, Clone, Serialize, Deserialize, Copy)]
 pub struct Match {
@@ -116,6 +276,16 @@ pub struct Match {
     pub(crate) automatic_loser: Opponent,
     /// Result reported by players
     pub(crate) reported_results: MatchReportedResult,
+    /// When match went through each state
+    #[serde(default)]
+    pub(crate) lifecycle: Lifecycle,
+    /// Where players are sent once match is over
+    #[serde(default)]
+    pub(crate) links: Links,
+    /// Swiss round of this match, counted from 1. 0 when match does not
+    /// record its round
+    #[serde(default)]
+    pub(crate) round: usize,
 }
 
 impl std::fmt::Display for Match {
@@ -156,11 +326,11 @@ impl Match {
     pub fn summary_with_name(&self, participants: &Participants) -> String {
         let p1 = match self.players[0] {
             Opponent::Player(id) => participants.get(id).expect("player").get_name(),
-            Opponent::Unknown => Opponent::Unknown.to_string(),
+            o => o.to_string(),
         };
         let p2 = match self.players[1] {
             Opponent::Player(id) => participants.get(id).expect("player").get_name(),
-            Opponent::Unknown => Opponent::Unknown.to_string(),
+            o => o.to_string(),
         };
 
         let p1_status = match (self.winner, self.automatic_loser, self.players[0]) {
@@ -181,43 +351,34 @@ impl Match {
 }
 
 /// Partitions double elimination bracket matches in winner bracket, looser
-/// bracket, grand finals and grand finals reset for `n` players
+/// bracket, grand finals and grand finals reset (when grand finals can be
+/// reset) for `n` players
 pub(crate) fn partition_double_elimination_matches(
     matches: &[Match],
     n: usize,
-) -> (Vec<Match>, Vec<Match>, Match, Match) {
-    assert_eq!(
-        matches.len(),
-        2 * n - 1,
-        "expected (2 * n) - 1 matches, where n is the number of players but got: {}",
+) -> (Vec<Match>, Vec<Match>, Match, Option<Match>) {
+    assert!(
+        matches.len() == 2 * n - 1 || matches.len() == 2 * n - 2,
+        "expected (2 * n) - 1 matches (or (2 * n) - 2 without grand finals reset), where n is the number of players but got: {}",
         matches.len()
     );
     let total_winner_bracket_matches = n - 1;
     let (winner_bracket, other) = matches.split_at(total_winner_bracket_matches);
-    let (grand_finals_reset, other) = other.split_last().expect("grand finals reset");
+    let (grand_finals_reset, other) = if matches.len() == 2 * n - 1 {
+        let (grand_finals_reset, other) = other.split_last().expect("grand finals reset");
+        (Some(*grand_finals_reset), other)
+    } else {
+        (None, other)
+    };
     let (grand_finals, loser_bracket) = other.split_last().expect("grand finals");
     (
         winner_bracket.to_vec(),
         loser_bracket.to_vec(),
         *grand_finals,
-        *grand_finals_reset,
+        grand_finals_reset,
     )
 }
 
-/// Compose double elimination matches from partition
-pub(crate) fn double_elimination_matches_from_partition(
-    winners: &[Match],
-    losers: &[Match],
-    grand_finals: Match,
-    reset: Match,
-) -> Vec<Match> {
-    let mut matches: Vec<Match> = winners.into();
-    matches.append(&mut losers.into());
-    matches.push(grand_finals);
-    matches.push(reset);
-    matches
-}
-
 impl Match {
     /// Clear result from match and returns updated match
     #[must_use]
@@ -239,6 +400,206 @@ impl Match {
         }
     }
 
+    /// Remove result and winner from match and returns updated match
+    #[must_use]
+    pub(crate) fn reopen(self) -> Self {
+        Self {
+            winner: Opponent::Unknown,
+            reported_results: [(0, 0), (0, 0)],
+            ..self
+        }
+    }
+
+    /// Replace reported results so `winner` wins `wins` games to none and
+    /// returns updated match
+    #[must_use]
+    pub(crate) fn with_forced_result(self, winner: PlayerId, wins: i8) -> Self {
+        let reported_results = if self.players[0] == Opponent::Player(winner) {
+            [(wins, 0), (0, wins)]
+        } else {
+            [(0, wins), (wins, 0)]
+        };
+        Self {
+            reported_results,
+            ..self
+        }
+    }
+
+    /// Put `player1` where `player2` plays and the other way around and
+    /// returns updated match. Players of a match whose players changed need
+    /// to be called again
+    #[must_use]
+    pub(crate) fn with_players_swapped(self, player1: PlayerId, player2: PlayerId) -> Self {
+        let swapped = self.players.map(|o| match o {
+            Opponent::Player(p) if p == player1 => Opponent::Player(player2),
+            Opponent::Player(p) if p == player2 => Opponent::Player(player1),
+            o => o,
+        });
+        if swapped == self.players {
+            return self;
+        }
+        Self {
+            players: swapped,
+            lifecycle: Lifecycle {
+                ready_at: self.lifecycle.ready_at,
+                ..Lifecycle::default()
+            },
+            ..self
+        }
+    }
+
+    /// Remove `player_id` from match (and their reported result) and returns
+    /// updated match
+    #[must_use]
+    pub(crate) fn remove_player(self, player_id: PlayerId) -> Self {
+        match self.players {
+            [Opponent::Player(p1), p2] if p1 == player_id => Self {
+                players: [Opponent::Unknown, p2],
+                reported_results: [(0, 0), self.reported_results[1]],
+                ..self
+            },
+            [p1, Opponent::Player(p2)] if p2 == player_id => Self {
+                players: [p1, Opponent::Unknown],
+                reported_results: [self.reported_results[0], (0, 0)],
+                ..self
+            },
+            _ => self,
+        }
+    }
+
+    /// Returns true if a result was reported or a winner was declared
+    #[must_use]
+    pub(crate) fn has_result(&self) -> bool {
+        self.winner != Opponent::Unknown || self.reported_results != [(0, 0), (0, 0)]
+    }
+
+    /// Returns where this match is in its lifecycle
+    #[must_use]
+    pub fn get_state(&self) -> State {
+        if self.winner != Opponent::Unknown {
+            State::Completed
+        } else if !self.needs_playing() {
+            State::Pending
+        } else if self.lifecycle.started_at.is_some() {
+            State::InProgress
+        } else if self.lifecycle.called_at.is_some() {
+            State::Called
+        } else {
+            State::Ready
+        }
+    }
+
+    /// Returns when this match went through each state
+    #[must_use]
+    pub fn get_lifecycle(&self) -> Lifecycle {
+        self.lifecycle
+    }
+
+    /// Returns where players of this match are sent once it is over
+    #[must_use]
+    pub fn get_links(&self) -> Links {
+        self.links
+    }
+
+    /// Expect players with `seeds` in this match
+    #[must_use]
+    pub(crate) fn with_seeds(self, seeds: Seeds) -> Self {
+        Self { seeds, ..self }
+    }
+
+    /// Send players of this match using `links`
+    #[must_use]
+    pub(crate) fn with_links(self, links: Links) -> Self {
+        Self { links, ..self }
+    }
+
+    /// Returns swiss round of this match, counted from 1. Returns 0 when
+    /// match does not record its round
+    #[must_use]
+    pub fn get_round(&self) -> usize {
+        self.round
+    }
+
+    /// Play this match in swiss `round`
+    #[must_use]
+    pub(crate) fn with_round(self, round: usize) -> Self {
+        Self { round, ..self }
+    }
+
+    /// Call players of this match at `at`
+    ///
+    /// # Errors
+    /// thrown when match is not ready
+    pub(crate) fn call(self, at: DateTime<Utc>) -> Result<Self, Error> {
+        if self.get_state() != State::Ready {
+            return Err(Error::InvalidTransition(
+                self.id,
+                self.get_state(),
+                State::Called,
+            ));
+        }
+        Ok(Self {
+            lifecycle: Lifecycle {
+                called_at: Some(at),
+                ..self.lifecycle
+            },
+            ..self
+        })
+    }
+
+    /// Start this match at `at`
+    ///
+    /// # Errors
+    /// thrown when match is neither ready nor called
+    pub(crate) fn start(self, at: DateTime<Utc>) -> Result<Self, Error> {
+        if !matches!(self.get_state(), State::Ready | State::Called) {
+            return Err(Error::InvalidTransition(
+                self.id,
+                self.get_state(),
+                State::InProgress,
+            ));
+        }
+        Ok(Self {
+            lifecycle: Lifecycle {
+                started_at: Some(at),
+                ..self.lifecycle
+            },
+            ..self
+        })
+    }
+
+    /// Timestamp at `at` the states this match entered since it was last
+    /// synchronised and forget states it left
+    #[must_use]
+    pub(crate) fn sync_lifecycle(self, at: DateTime<Utc>) -> Self {
+        let mut lifecycle = self.lifecycle;
+        if self.winner != Opponent::Unknown {
+            lifecycle.completed_at.get_or_insert(at);
+        } else if !self.needs_playing() {
+            lifecycle = Lifecycle::default();
+        } else {
+            if lifecycle.completed_at.is_some() {
+                // reopened match needs to be called again
+                lifecycle = Lifecycle {
+                    ready_at: lifecycle.ready_at,
+                    ..Lifecycle::default()
+                };
+            }
+            lifecycle.ready_at.get_or_insert(at);
+        }
+        Self { lifecycle, ..self }
+    }
+
+    /// Returns true if both players reported a result and they disagree. A
+    /// disputed match needs the tournament organiser to settle its outcome
+    #[must_use]
+    pub fn is_disputed(&self) -> bool {
+        self.winner == Opponent::Unknown
+            && !self.reported_results.contains(&(0, 0))
+            && ReportedResult(self.reported_results[0]).reverse()
+                != ReportedResult(self.reported_results[1])
+    }
+
     /// Returns true if one of the player has id `player_id`
     #[must_use]
     pub fn contains(&self, player_id: PlayerId) -> bool {
@@ -286,12 +647,6 @@ impl Match {
         matches!(self.automatic_loser, Opponent::Player(loser) if loser == player_id)
     }
 
-    #[must_use]
-    /// Returns true if this match is where loser arrives
-    pub(crate) fn is_first_loser_match(&self, expected_seed: usize) -> bool {
-        self.seeds[0] == expected_seed || self.seeds[1] == expected_seed
-    }
-
     /// Returns true if match is over
     #[must_use]
     pub fn is_over(&self) -> bool {
@@ -325,6 +680,9 @@ impl Match {
             winner: Opponent::Unknown,
             automatic_loser: Opponent::Unknown,
             reported_results: [(0, 0), (0, 0)],
+            lifecycle: Lifecycle::default(),
+            links: Links::default(),
+            round: 0,
         }
     }
 
@@ -355,6 +713,9 @@ impl Match {
                 automatic_loser: Opponent::Unknown,
                 seeds,
                 reported_results: [(0_i8, 0_i8), (0_i8, 0)],
+                lifecycle: Lifecycle::default(),
+                links: Links::default(),
+                round: 0,
             }),
         }
     }
@@ -375,6 +736,9 @@ impl Match {
             automatic_loser: Opponent::Unknown,
             seeds,
             reported_results: [(0_i8, 0_i8), (0_i8, 0)],
+            lifecycle: Lifecycle::default(),
+            links: Links::default(),
+            round: 0,
         }
     }
 
@@ -388,9 +752,34 @@ impl Match {
             winner: Opponent::Unknown,
             automatic_loser: Opponent::Unknown,
             reported_results: [(0, 0), (0, 0)],
+            lifecycle: Lifecycle::default(),
+            links: Links::default(),
+            round: 0,
+        }
+    }
+
+    /// Create match where `player_id` wins without an opponent
+    #[must_use]
+    pub(crate) fn new_bye(player_id: PlayerId, seed: usize) -> Self {
+        Match {
+            id: Id::new_v4(),
+            players: [Opponent::Player(player_id), Opponent::Bye],
+            seeds: [seed, 0],
+            winner: Opponent::Player(player_id),
+            automatic_loser: Opponent::Unknown,
+            reported_results: [(0, 0), (0, 0)],
+            lifecycle: Lifecycle::default(),
+            links: Links::default(),
+            round: 0,
         }
     }
 
+    /// Returns true if player of this match won without an opponent
+    #[must_use]
+    pub fn is_bye(&self) -> bool {
+        self.players.contains(&Opponent::Bye)
+    }
+
     /// Set looser of this match (when disqualified)
     ///
     /// # Errors
@@ -550,6 +939,9 @@ impl Match {
                 winner: Opponent::Player(winner),
                 automatic_loser: self.automatic_loser,
                 reported_results: self.reported_results,
+                lifecycle: self.lifecycle,
+                links: self.links,
+                round: self.round,
             },
             winner,
             loser,
@@ -579,6 +971,9 @@ impl Match {
                     winner: self.winner,
                     automatic_loser: self.automatic_loser,
                     reported_results,
+                    lifecycle: self.lifecycle,
+                    links: self.links,
+                    round: self.round,
                 })
             }
             [Opponent::Player(_), Opponent::Player(player2)] if player2 == player_id => {
@@ -591,6 +986,9 @@ impl Match {
                     winner: self.winner,
                     automatic_loser: self.automatic_loser,
                     reported_results,
+                    lifecycle: self.lifecycle,
+                    links: self.links,
+                    round: self.round,
                 })
             }
             _ => Err(Error::UnknownPlayer(player_id, self.players)),
@@ -656,7 +1054,7 @@ pub fn print_player_name(o: &Opponent, players: &[Player]) -> Option<String> {
             .iter()
             .find(|player| player.get_id() == *p)
             .map(Player::get_name),
-        Opponent::Unknown => Some(Opponent::Unknown.to_string()),
+        o => Some(o.to_string()),
     }
 }
 
@@ -693,6 +1091,17 @@ mod tests {
         }
     }
 
+    #[test]
+    fn player_against_bye_wins() {
+        let p = PlayerId::new_v4();
+        let m = Match::new_bye(p, 1);
+
+        assert!(m.is_bye());
+        assert_eq!(m.get_winner(), Opponent::Player(p));
+        assert!(!m.needs_playing());
+        assert_eq!("bye".parse::<Opponent>().expect("bye"), Opponent::Bye);
+    }
+
     #[test]
     fn stronger_seeds_wins() {
         let p1 = Player::new("p1".into());
@@ -12,6 +12,8 @@ pub enum Opponent {
     /// Opponent has not been decided yet
     #[default]
     Unknown,
+    /// There is no opponent: the other player advances without playing
+    Bye,
 }
 
 impl Opponent {
@@ -25,6 +27,7 @@ impl Opponent {
                 };
                 p.1.clone()
             }
+            Opponent::Bye => Opponent::Bye.to_string(),
             _ => Opponent::Unknown.to_string(),
         }
     }
@@ -35,6 +38,7 @@ impl std::fmt::Display for Opponent {
         match self {
             Opponent::Player(p) => write!(f, "{p}"),
             Opponent::Unknown => write!(f, "?"),
+            Opponent::Bye => write!(f, "bye"),
         }
     }
 }
@@ -53,6 +57,7 @@ impl std::str::FromStr for Opponent {
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         Ok(match s {
             "?" => Opponent::Unknown,
+            "bye" => Opponent::Bye,
             _ => Opponent::Player(PlayerId::parse_str(s)?),
         })
     }
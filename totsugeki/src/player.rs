@@ -199,6 +199,27 @@ impl Participants {
         }
     }
 
+    /// Put `new_player` at the seeding position of `participant_id`
+    ///
+    /// # Errors
+    /// thrown if participant does not belong to this group or new player is
+    /// already present
+    pub fn replace(self, participant_id: PlayerId, new_player: Player) -> Result<Self, Error> {
+        if self.get(new_player.get_id()).is_some() {
+            return Err(Error::AlreadyPresent);
+        }
+        let Some(position) = self
+            .participants
+            .iter()
+            .position(|p| p.get_id() == participant_id)
+        else {
+            return Err(Error::Unknown(participant_id));
+        };
+        let mut participants = self.participants;
+        participants[position] = new_player;
+        Ok(Self { participants })
+    }
+
     /// Add player to participants but does not check if player is already
     /// present in bracket. Use only for fuzzing tests when you can guarantee
     /// you do not add duplicate players.
@@ -0,0 +1,410 @@
+//! Rate players from the results of finished brackets
+//!
+//! Every bracket is one rating period: all sets of a bracket are rated
+//! against the ratings players had before the bracket, so the order in which
+//! sets were played does not matter. Sets won by disqualification or bye are
+//! not rated.
+//!
+//! Ratings are serializable so they can be kept between events and used to
+//! seed the next one.
+
+use crate::{
+    bracket::{Bracket, Id as BracketId},
+    matches::Match,
+    opponent::Opponent,
+    player::{Id as PlayerId, Participants},
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use thiserror::Error;
+
+/// Rating of players who never played a rated set
+const INITIAL_RATING: f64 = 1500.0;
+/// Deviation of players who never played a rated set
+const INITIAL_DEVIATION: f64 = 350.0;
+/// Volatility of players who never played a rated set
+const INITIAL_VOLATILITY: f64 = 0.06;
+/// Conversion factor between Glicko and Glicko-2 scales
+const GLICKO2_SCALE: f64 = 173.7178;
+/// Convergence tolerance of the volatility computation
+const VOLATILITY_TOLERANCE: f64 = 0.000_001;
+
+/// Rating system
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum System {
+    /// Elo: each set moves ratings by at most `k_factor` points
+    Elo {
+        /// Maximum number of points won or lost in a set
+        k_factor: f64,
+    },
+    /// Glicko-2: ratings come with a deviation that shrinks as players play
+    /// and grows when they do not
+    Glicko2 {
+        /// Constrains how fast volatility changes (between 0.3 and 1.2)
+        tau: f64,
+    },
+}
+
+impl Default for System {
+    fn default() -> Self {
+        Self::Glicko2 { tau: 0.5 }
+    }
+}
+
+impl std::fmt::Display for System {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            System::Elo { k_factor } => write!(f, "Elo (K = {k_factor})"),
+            System::Glicko2 { tau } => write!(f, "Glicko-2 (tau = {tau})"),
+        }
+    }
+}
+
+/// Rating of a player. Deviation and volatility are only used by Glicko-2
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Rating {
+    /// Estimated strength of player
+    pub rating: f64,
+    /// Uncertainty about rating
+    pub deviation: f64,
+    /// Expected fluctuation of rating
+    pub volatility: f64,
+    /// Number of rated sets played
+    pub sets_played: usize,
+}
+
+impl Default for Rating {
+    fn default() -> Self {
+        Self {
+            rating: INITIAL_RATING,
+            deviation: INITIAL_DEVIATION,
+            volatility: INITIAL_VOLATILITY,
+            sets_played: 0,
+        }
+    }
+}
+
+impl std::fmt::Display for Rating {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:.0} (±{:.0})", self.rating, self.deviation)
+    }
+}
+
+/// Error while rating players
+#[derive(Error, Debug)]
+pub enum Error {
+    /// Only finished brackets can be rated
+    #[error("Bracket {0} is not over")]
+    NotOver(BracketId),
+    /// A set was played by someone who is not a participant
+    #[error("Player {0} is not a participant")]
+    UnknownPlayer(PlayerId),
+}
+
+/// Ratings of players for a given rating system
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Ratings {
+    /// Rating system used
+    system: System,
+    /// Rating of every rated player
+    ratings: BTreeMap<PlayerId, Rating>,
+}
+
+impl Ratings {
+    /// No player rated with `system` yet
+    #[must_use]
+    pub fn new(system: System) -> Self {
+        Self {
+            system,
+            ratings: BTreeMap::new(),
+        }
+    }
+
+    /// Returns rating system used
+    #[must_use]
+    pub fn get_system(&self) -> System {
+        self.system
+    }
+
+    /// Returns rating of `player_id`. Unrated players get the initial rating
+    #[must_use]
+    pub fn get(&self, player_id: PlayerId) -> Rating {
+        self.ratings.get(&player_id).copied().unwrap_or_default()
+    }
+
+    /// Set rating of `player_id`, for instance with a rating from another
+    /// source
+    #[must_use]
+    pub fn set_rating(self, player_id: PlayerId, rating: Rating) -> Self {
+        let mut ratings = self.ratings;
+        ratings.insert(player_id, rating);
+        Self { ratings, ..self }
+    }
+
+    /// Returns all rated players with their rating
+    #[must_use]
+    pub fn get_ratings(&self) -> Vec<(PlayerId, Rating)> {
+        self.ratings.iter().map(|(p, r)| (*p, *r)).collect()
+    }
+
+    /// Returns `players` from highest to lowest rating. Players with the same
+    /// rating keep their relative order
+    #[must_use]
+    pub fn rank(&self, players: &[PlayerId]) -> Vec<PlayerId> {
+        let mut ranked = players.to_vec();
+        ranked.sort_by(|a, b| self.get(*b).rating.total_cmp(&self.get(*a).rating));
+        ranked
+    }
+
+    /// Update ratings with sets of finished `bracket`
+    ///
+    /// # Errors
+    /// thrown when bracket is not over
+    pub fn update(self, bracket: &Bracket) -> Result<Self, Error> {
+        if !bracket.is_over() {
+            return Err(Error::NotOver(bracket.get_id()));
+        }
+        self.update_with_matches(&bracket.get_matches(), &bracket.get_participants())
+    }
+
+    /// Update ratings of `participants` with `matches` played between them.
+    /// Matches without a winner are ignored
+    ///
+    /// # Errors
+    /// thrown when a match was played by someone who is not a participant
+    pub fn update_with_matches(
+        self,
+        matches: &[Match],
+        participants: &Participants,
+    ) -> Result<Self, Error> {
+        let mut results: BTreeMap<PlayerId, Vec<(Rating, f64)>> = participants
+            .get_seeding()
+            .into_iter()
+            .map(|p| (p, vec![]))
+            .collect();
+        for m in matches.iter().filter(|m| is_rated(m)) {
+            let Opponent::Player(winner) = m.get_winner() else {
+                continue;
+            };
+            let Ok(loser) = m.get_other_player(winner) else {
+                continue;
+            };
+            let (winner_rating, loser_rating) = (self.get(winner), self.get(loser));
+            results
+                .get_mut(&winner)
+                .ok_or(Error::UnknownPlayer(winner))?
+                .push((loser_rating, 1.0));
+            results
+                .get_mut(&loser)
+                .ok_or(Error::UnknownPlayer(loser))?
+                .push((winner_rating, 0.0));
+        }
+
+        let mut ratings = self.ratings.clone();
+        if let System::Glicko2 { .. } = self.system {
+            // rated players absent from this bracket become less certain
+            for (player, rating) in &mut ratings {
+                if !results.contains_key(player) {
+                    *rating = glicko2(*rating, &[], 0.0);
+                }
+            }
+        }
+        for (player, results) in results {
+            let rating = self.get(player);
+            let rating = match self.system {
+                System::Elo { k_factor } => elo(rating, &results, k_factor),
+                System::Glicko2 { tau } => glicko2(rating, &results, tau),
+            };
+            ratings.insert(player, rating);
+        }
+        Ok(Self { ratings, ..self })
+    }
+}
+
+/// Returns true if match was won by playing it
+fn is_rated(m: &Match) -> bool {
+    m.is_over() && m.get_automatic_loser() == Opponent::Unknown
+}
+
+/// Returns updated Elo rating of player after their `results` (opponent
+/// rating and score)
+fn elo(player: Rating, results: &[(Rating, f64)], k_factor: f64) -> Rating {
+    let change: f64 = results
+        .iter()
+        .map(|(opponent, score)| {
+            let expected = 1.0 / (1.0 + 10f64.powf((opponent.rating - player.rating) / 400.0));
+            k_factor * (score - expected)
+        })
+        .sum();
+    Rating {
+        rating: player.rating + change,
+        sets_played: player.sets_played + results.len(),
+        ..player
+    }
+}
+
+/// Returns updated Glicko-2 rating of player after their `results` (opponent
+/// rating and score)
+///
+/// See <http://www.glicko.net/glicko/glicko2.pdf>
+fn glicko2(player: Rating, results: &[(Rating, f64)], tau: f64) -> Rating {
+    let phi = player.deviation / GLICKO2_SCALE;
+    let sigma = player.volatility;
+    if results.is_empty() {
+        return Rating {
+            deviation: (phi * phi + sigma * sigma).sqrt() * GLICKO2_SCALE,
+            ..player
+        };
+    }
+    let mu = (player.rating - INITIAL_RATING) / GLICKO2_SCALE;
+    let (mut inverse_variance, mut improvement) = (0.0, 0.0);
+    for (opponent, score) in results {
+        let mu_j = (opponent.rating - INITIAL_RATING) / GLICKO2_SCALE;
+        let phi_j = opponent.deviation / GLICKO2_SCALE;
+        let g = 1.0 / (1.0 + 3.0 * phi_j * phi_j / (std::f64::consts::PI.powi(2))).sqrt();
+        let expected = 1.0 / (1.0 + (-g * (mu - mu_j)).exp());
+        inverse_variance += g * g * expected * (1.0 - expected);
+        improvement += g * (score - expected);
+    }
+    let v = 1.0 / inverse_variance;
+    let delta = v * improvement;
+
+    // volatility (Illinois algorithm)
+    let a = (sigma * sigma).ln();
+    let f = |x: f64| {
+        let ex = x.exp();
+        ex * (delta * delta - phi * phi - v - ex) / (2.0 * (phi * phi + v + ex).powi(2))
+            - (x - a) / (tau * tau)
+    };
+    let mut lower = a;
+    let mut upper = if delta * delta > phi * phi + v {
+        (delta * delta - phi * phi - v).ln()
+    } else {
+        let mut k = 1.0;
+        while f(a - k * tau) < 0.0 {
+            k += 1.0;
+        }
+        a - k * tau
+    };
+    let (mut f_lower, mut f_upper) = (f(lower), f(upper));
+    while (upper - lower).abs() > VOLATILITY_TOLERANCE {
+        let c = lower + (lower - upper) * f_lower / (f_upper - f_lower);
+        let f_c = f(c);
+        if f_c * f_upper <= 0.0 {
+            lower = upper;
+            f_lower = f_upper;
+        } else {
+            f_lower /= 2.0;
+        }
+        upper = c;
+        f_upper = f_c;
+    }
+    let volatility = (lower / 2.0).exp();
+
+    let pre_period_phi = (phi * phi + volatility * volatility).sqrt();
+    let new_phi = 1.0 / (1.0 / (pre_period_phi * pre_period_phi) + 1.0 / v).sqrt();
+    let new_mu = mu + new_phi * new_phi * improvement;
+    Rating {
+        rating: new_mu * GLICKO2_SCALE + INITIAL_RATING,
+        deviation: new_phi * GLICKO2_SCALE,
+        volatility,
+        sets_played: player.sets_played + results.len(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        bracket::builder::fixture::{play_all, started_bracket},
+        format::{Format, Playoffs},
+    };
+
+    fn rating(rating: f64, deviation: f64) -> Rating {
+        Rating {
+            rating,
+            deviation,
+            ..Rating::default()
+        }
+    }
+
+    fn assert_close(actual: f64, expected: f64, tolerance: f64) {
+        assert!(
+            (actual - expected).abs() < tolerance,
+            "expected {expected}, got {actual}"
+        );
+    }
+
+    /// Finished bracket where stronger seed always wins
+    fn finished_bracket(n: usize) -> (Bracket, Vec<PlayerId>) {
+        let (bracket, seeding) = started_bracket(
+            Format::SingleElimination {
+                playoffs: Playoffs::Disabled,
+            },
+            n,
+        );
+        (play_all(bracket), seeding)
+    }
+
+    #[test]
+    fn glicko2_matches_reference_example() {
+        // example from Glickman's paper
+        let player = rating(1500.0, 200.0);
+        let results = [
+            (rating(1400.0, 30.0), 1.0),
+            (rating(1550.0, 100.0), 0.0),
+            (rating(1700.0, 300.0), 0.0),
+        ];
+
+        let updated = glicko2(player, &results, 0.5);
+
+        assert_close(updated.rating, 1464.06, 0.01);
+        assert_close(updated.deviation, 151.52, 0.01);
+        assert_close(updated.volatility, 0.05999, 0.00001);
+        assert_eq!(updated.sets_played, 3);
+    }
+
+    #[test]
+    fn elo_ratings_follow_bracket_results() {
+        let (bracket, seeding) = finished_bracket(4);
+
+        let ratings = Ratings::new(System::Elo { k_factor: 32.0 })
+            .update(&bracket)
+            .expect("ratings");
+
+        // winner beat two players rated like them
+        assert_close(ratings.get(seeding[0]).rating, 1532.0, 0.01);
+        assert_eq!(ratings.get(seeding[0]).sets_played, 2);
+        let total: f64 = seeding.iter().map(|p| ratings.get(*p).rating).sum();
+        assert_close(total, 4.0 * INITIAL_RATING, 0.01);
+        let mut by_rating = seeding.clone();
+        by_rating.reverse();
+        assert_eq!(ratings.rank(&by_rating)[..2], seeding[..2]);
+    }
+
+    #[test]
+    fn only_finished_brackets_are_rated() {
+        let (bracket, seeding) = finished_bracket(3);
+        let (unfinished, _) = Bracket::default()
+            .add_participant("p1")
+            .expect("p1")
+            .add_participant("p2")
+            .expect("p2")
+            .add_participant("p3")
+            .expect("p3")
+            .start()
+            .expect("start");
+
+        assert!(matches!(
+            Ratings::default().update(&unfinished),
+            Err(Error::NotOver(id)) if id == unfinished.get_id()
+        ));
+        let ratings = Ratings::default().update(&bracket).expect("ratings");
+        assert!(ratings.get(seeding[0]).rating > INITIAL_RATING);
+        assert!(ratings.get(seeding[0]).deviation < INITIAL_DEVIATION);
+        // unrated player in next bracket becomes less certain
+        let (next, next_seeding) = finished_bracket(3);
+        let ratings = ratings.update(&next).expect("ratings");
+        assert!(ratings.get(seeding[0]).deviation > ratings.get(next_seeding[0]).deviation);
+    }
+}
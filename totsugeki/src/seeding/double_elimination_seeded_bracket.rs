@@ -1,8 +1,11 @@
 //! generate seeded matches for double elimination bracket
 
-use std::ops::ControlFlow;
+use std::{collections::HashMap, ops::ControlFlow};
 
-use crate::{matches::Match, player::Id as PlayerId, seeding::Error};
+use crate::{
+    bracket::matches::single_elimination_format::playoffs::trees, format::Playoffs, matches::Match,
+    player::Id as PlayerId, seeding::Error,
+};
 
 /// Get seed of player from seeding
 fn get_seed_of(player: &PlayerId, seeding: &[PlayerId]) -> usize {
@@ -110,6 +113,44 @@ pub fn get_loser_bracket_matches_top_seed_favored(
     Ok(matches)
 }
 
+/// Returns `loser_bracket` matches of a bracket of `n` players where players
+/// dropping from `winner_bracket`, starting from the second round, are placed
+/// in reverse order
+///
+/// Players dropping from a winner bracket round trade places: the expected
+/// seed of the first player to drop replaces the expected seed of the last
+/// one and so on. The loser bracket keeps its shape, only the side where
+/// players drop alternates, so players do not meet again in the loser bracket
+/// before its final when expected winners win.
+#[must_use]
+pub fn alternate_loser_drops(
+    winner_bracket: &[Match],
+    loser_bracket: Vec<Match>,
+    n: usize,
+) -> Vec<Match> {
+    let Some(tree) = trees(n, Playoffs::Disabled).into_iter().next() else {
+        return loser_bracket;
+    };
+    let mut relabel: HashMap<usize, usize> = HashMap::new();
+    for round in 1..tree.number_of_rounds() {
+        let droppers = winner_bracket[tree.round_range(round)]
+            .iter()
+            .map(|m| m.get_seeds()[1])
+            .collect::<Vec<_>>();
+        for (dropper, replaced) in droppers.iter().zip(droppers.iter().rev()) {
+            relabel.insert(*replaced, *dropper);
+        }
+    }
+    loser_bracket
+        .into_iter()
+        .map(|m| {
+            let [s1, s2] = m.get_seeds();
+            let relabel = |seed| relabel.get(&seed).copied().unwrap_or(seed);
+            m.with_seeds([relabel(s1), relabel(s2)])
+        })
+        .collect()
+}
+
 /// qiej
 fn fun_name(
     p_without_bye: &[uuid::Uuid],
@@ -246,11 +287,15 @@ fn partition_players_of_loser_bracket(
 
 #[cfg(test)]
 mod tests {
-    use crate::format::Format;
-    use crate::matches::{Id as MatchId, Match};
+    use crate::bracket::Bracket;
+    use crate::format::{Format, GrandFinals, LoserDrops};
+    use crate::matches::{Id as MatchId, Lifecycle, Link, Links, Match};
     use crate::opponent::Opponent;
-    use crate::player::{Participants, Player};
+    use crate::player::{Id as PlayerId, Participants, Player};
     use crate::seeding::double_elimination_seeded_bracket::get_loser_bracket_matches_top_seed_favored;
+    use crate::seeding::Method as SeedingMethod;
+    use chrono::prelude::*;
+    use std::collections::HashSet;
 
     #[test]
     fn matches_generation_3_man() {
@@ -266,14 +311,27 @@ mod tests {
         players.push(Player::new("don't use".into()));
         players.reverse();
 
-        let matches = Format::DoubleElimination
-            .generate_matches(&participants.get_seeding())
-            .expect("matches");
+        let matches = Format::DoubleElimination {
+            loser_drops: LoserDrops::Standard,
+            grand_finals: GrandFinals::Reset,
+        }
+        .generate_matches(&participants.get_seeding())
+        .expect("matches");
         let mut match_ids: Vec<MatchId> = matches
             .iter()
             .map(crate::matches::Match::get_id)
             .rev()
             .collect();
+        let ids = matches
+            .iter()
+            .map(crate::matches::Match::get_id)
+            .collect::<Vec<_>>();
+        let link = |i: usize, is_player_1| {
+            Some(Link {
+                match_id: ids[i],
+                is_player_1,
+            })
+        };
         assert_eq!(
             matches,
             vec![
@@ -287,6 +345,12 @@ mod tests {
                     winner: Opponent::Unknown,
                     automatic_loser: Opponent::Unknown,
                     reported_results: [(0, 0), (0, 0)],
+                    lifecycle: Lifecycle::default(),
+                    links: Links {
+                        winner: link(1, false),
+                        loser: link(2, false),
+                    },
+                    round: 0,
                 },
                 Match {
                     id: match_ids.pop().expect("id"),
@@ -295,8 +359,19 @@ mod tests {
                     winner: Opponent::Unknown,
                     automatic_loser: Opponent::Unknown,
                     reported_results: [(0, 0), (0, 0)],
+                    lifecycle: Lifecycle::default(),
+                    links: Links {
+                        winner: link(3, true),
+                        loser: link(2, true),
+                    },
+                    round: 0,
                 },
-                Match::looser_bracket_match(match_ids.pop().expect("id"), [2, 3]),
+                Match::looser_bracket_match(match_ids.pop().expect("id"), [2, 3]).with_links(
+                    Links {
+                        winner: link(3, false),
+                        loser: None,
+                    }
+                ),
                 Match::looser_bracket_match(match_ids.pop().expect("id"), [1, 2]),
                 Match::looser_bracket_match(match_ids.pop().expect("id"), [1, 2]),
             ],
@@ -770,4 +845,91 @@ mod tests {
                 .collect::<Vec<[usize; 2]>>()
         );
     }
+
+    /// Returns pairs of players meeting in the winner bracket and in the loser
+    /// bracket before its final when the best seed wins every match
+    fn top_seeds_win(n: usize, loser_drops: LoserDrops) -> (Vec<[usize; 2]>, Vec<[usize; 2]>) {
+        let mut bracket = Bracket::new(
+            "bracket",
+            Format::DoubleElimination {
+                loser_drops,
+                grand_finals: GrandFinals::Reset,
+            },
+            SeedingMethod::Strict,
+            Utc.with_ymd_and_hms(2000, 1, 1, 0, 0, 0).unwrap(),
+            true,
+        );
+        for i in 1..=n {
+            bracket = bracket
+                .add_participant(format!("p{i}").as_str())
+                .expect("participant");
+        }
+        let mut bracket = bracket.start().expect("start").0;
+        let seeding = bracket.get_participants().get_seeding();
+        let seed = |p: PlayerId| seeding.iter().position(|s| *s == p).expect("seed") + 1;
+        let mut played = vec![];
+        while let Some((i, p1, p2)) =
+            bracket
+                .get_matches()
+                .iter()
+                .enumerate()
+                .find_map(|(i, m)| match m.get_players() {
+                    [Opponent::Player(p1), Opponent::Player(p2)] if !m.is_over() => {
+                        Some((i, p1, p2))
+                    }
+                    _ => None,
+                })
+        {
+            let result = if seed(p1) < seed(p2) { (2, 0) } else { (0, 2) };
+            played.push((i, [seed(p1), seed(p2)]));
+            bracket = bracket
+                .tournament_organiser_reports_result(p1, result, p2)
+                .expect("result")
+                .0;
+        }
+        let winner_bracket = played
+            .iter()
+            .filter(|(i, _)| *i < n - 1)
+            .map(|(_, pair)| *pair)
+            .collect();
+        let loser_bracket = played
+            .iter()
+            .filter(|(i, _)| (n - 1..2 * n - 4).contains(i))
+            .map(|(_, pair)| *pair)
+            .collect();
+        (winner_bracket, loser_bracket)
+    }
+
+    #[test]
+    fn alternating_loser_drops_avoid_early_rematches() {
+        let rematches = |winner_bracket: &[[usize; 2]], loser_bracket: &[[usize; 2]]| {
+            let met = winner_bracket
+                .iter()
+                .map(|[a, b]| [*a.min(b), *a.max(b)])
+                .collect::<HashSet<_>>();
+            loser_bracket
+                .iter()
+                .filter(|[a, b]| met.contains(&[*a.min(b), *a.max(b)]))
+                .count()
+        };
+
+        let (winner_bracket, loser_bracket) = top_seeds_win(8, LoserDrops::Standard);
+        assert!(loser_bracket.contains(&[3, 6]));
+        assert_eq!(rematches(&winner_bracket, &loser_bracket), 2);
+        let (winner_bracket, loser_bracket) = top_seeds_win(8, LoserDrops::Alternating);
+        assert!(loser_bracket.contains(&[4, 6]));
+        assert!(loser_bracket.contains(&[3, 5]));
+        assert_eq!(rematches(&winner_bracket, &loser_bracket), 0);
+
+        for n in [16, 32, 64] {
+            let (winner_bracket, loser_bracket) = top_seeds_win(n, LoserDrops::Standard);
+            assert!(rematches(&winner_bracket, &loser_bracket) > 0);
+            let (winner_bracket, loser_bracket) = top_seeds_win(n, LoserDrops::Alternating);
+            assert_eq!(
+                rematches(&winner_bracket, &loser_bracket),
+                0,
+                "{n} players\nwinner bracket: {winner_bracket:?}\nloser bracket: {loser_bracket:?}"
+            );
+        }
+    }
 }
@@ -1,16 +1,23 @@
 //! Seed brackets with seeding methods
 
 pub mod double_elimination_seeded_bracket;
+pub mod round_robin_seeded_bracket;
+pub mod separation;
 pub mod single_elimination_seeded_bracket;
+pub mod swiss_seeded_bracket;
 
 use crate::{
-    matches::Match,
+    matches::{Error as MatchError, Match},
     opponent::Opponent,
     player::{Error as PlayerError, Id as PlayerId, Participants},
+    rating::Ratings,
+    seeding::separation::Separation,
 };
 use rand::prelude::*;
 use rand::rngs::OsRng;
+use rand_chacha::ChaCha8Rng;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use thiserror::Error;
 
 /// Seeding method
@@ -21,6 +28,8 @@ pub enum Method {
     /// Sort players by perceived strength to avoid pitting them against each
     /// other early in the bracket
     Strict,
+    /// Sort players from highest to lowest rating
+    ByRating,
 }
 
 impl std::fmt::Display for Method {
@@ -28,6 +37,7 @@ impl std::fmt::Display for Method {
         match self {
             Method::Random => write!(f, "random"),
             Method::Strict => write!(f, "strict"),
+            Method::ByRating => write!(f, "by-rating"),
         }
     }
 }
@@ -47,6 +57,7 @@ impl std::str::FromStr for Method {
         Ok(match s {
             "random" => Self::Random,
             "strict" => Self::Strict,
+            "by-rating" => Self::ByRating,
             _ => return Err(ParsingError::Unknown),
         })
     }
@@ -76,45 +87,104 @@ pub enum Error {
     /// Seeding needs to use the same participants
     #[error("Participants used for seeding differ from current participants:\nused: {0}\nactual participants: {1}")]
     DifferentParticipants(Participants, Participants),
+    /// Seeding by rating needs ratings
+    #[error("Ratings are needed to seed players by rating")]
+    MissingRatings,
+    /// Player to pair is not in the seeding
+    #[error("Player {0} is not seeded")]
+    UnknownPlayer(PlayerId),
+    /// Players of a swiss round could not be paired
+    #[error("Players of next swiss round cannot be paired")]
+    NoPairing,
+    /// Paired match could not be created
+    #[error("{0}")]
+    Match(#[from] MatchError),
 }
 
 /// Returns updated participants after changing seeding position
 ///
 /// With `Strict` method, `players` are expected to be ranked from strongest to
-/// weakest.
+/// weakest. With `ByRating` method, participants are ranked using `ratings`
+/// and participants with the same rating keep their order. With `Random`
+/// method, participants are shuffled with `rng`. Use [`draw_rng`] to get the
+/// same draw from the same value. When provided, `separation` then moves
+/// players sharing a tag away from each other.
 ///
 /// # Errors
-/// Returns an error when filling an empty bracket or group of players cannot
-/// be formed
+/// Returns an error when filling an empty bracket, group of players cannot
+/// be formed or ratings are missing
 pub fn seed(
     method: &Method,
     seeding: Participants,
     participants: Participants,
+    ratings: Option<&Ratings>,
+    separation: Option<&Separation>,
+    rng: &mut impl RngCore,
 ) -> Result<Participants, Error> {
     if participants.len() < 3 {
         return Err(Error::NotEnoughPlayers);
     }
 
-    match method {
+    let seeded = match method {
         Method::Random => {
-            let mut key = [0u8; 16];
-            OsRng.try_fill_bytes(&mut key)?;
-            let mut rng = OsRng;
             let mut players = participants.get_players_list();
-            players.shuffle(&mut rng);
-            let players = Participants::try_from(players)?;
-            Ok(players)
+            players.shuffle(rng);
+            Participants::try_from(players)?
         }
         Method::Strict => {
             if participants.have_same_participants(&seeding) {
-                Ok(seeding)
+                seeding
             } else {
-                Err(Error::DifferentParticipants(seeding, participants))
+                return Err(Error::DifferentParticipants(seeding, participants));
             }
         }
+        Method::ByRating => {
+            let Some(ratings) = ratings else {
+                return Err(Error::MissingRatings);
+            };
+            reorder(&participants, &ratings.rank(&participants.get_seeding()))?
+        }
+    };
+    match separation {
+        Some(separation) => reorder(&seeded, &separation.separate(&seeded.get_seeding())),
+        None => Ok(seeded),
     }
 }
 
+/// Returns a value to seed a random draw with, drawn from the OS
+///
+/// # Errors
+/// thrown when the OS random number generator is unavailable
+pub fn random_seed() -> Result<u64, Error> {
+    let mut bytes = [0u8; 8];
+    OsRng.try_fill_bytes(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+/// Returns random number generator used for random draws seeded with
+/// `random_seed`. The same value always gives the same draw
+#[must_use]
+pub fn draw_rng(random_seed: u64) -> ChaCha8Rng {
+    ChaCha8Rng::seed_from_u64(random_seed)
+}
+
+/// Returns `participants` ordered as `seeding`
+///
+/// # Errors
+/// thrown when `seeding` contains the same player twice
+fn reorder(participants: &Participants, seeding: &[PlayerId]) -> Result<Participants, Error> {
+    let players = participants
+        .get_players_list()
+        .into_iter()
+        .map(|p| (p.get_id(), p))
+        .collect::<HashMap<_, _>>();
+    let players = seeding
+        .iter()
+        .filter_map(|p| players.get(p).cloned())
+        .collect::<Vec<_>>();
+    Ok(Participants::try_from(players)?)
+}
+
 /// Pushes one seeded match matching top seed and bottom seed onto
 /// `this_round`. Because it's the initial round where either top seed is
 /// present (example: 8 man bracket) or they are not (3 man bracket), then the
@@ -144,13 +214,29 @@ fn seeding_initial_round(
 #[cfg(test)]
 mod tests {
     use crate::player::{Participants, Player};
-    use crate::seeding::{seed, Error, Method};
+    use crate::rating::{Rating, Ratings};
+    use crate::seeding::{draw_rng, seed, separation::Separation, Error, Method};
+
+    fn participants(n: usize) -> Participants {
+        (1..=n).fold(Participants::default(), |participants, i| {
+            participants
+                .add_participant(Player::new(format!("player{i}")))
+                .expect("player added")
+        })
+    }
 
     fn assert_seeding_returns_not_enough_player_error(
         players: Participants,
         current_participants: Participants,
     ) {
-        match seed(&Method::Random, players, current_participants) {
+        match seed(
+            &Method::Random,
+            players,
+            current_participants,
+            None,
+            None,
+            &mut draw_rng(0),
+        ) {
             Err(Error::NotEnoughPlayers) => {}
             Err(e) => panic!("expected error but got {e}"),
             Ok(_) => panic!("expected error but got none"),
@@ -179,4 +265,67 @@ mod tests {
             players.len()
         );
     }
+
+    #[test]
+    fn players_are_seeded_from_highest_to_lowest_rating() {
+        let participants = participants(4);
+        let seeding = participants.get_seeding();
+        let rating = |rating| Rating {
+            rating,
+            ..Rating::default()
+        };
+        let ratings = Ratings::default()
+            .set_rating(seeding[2], rating(1800.0))
+            .set_rating(seeding[0], rating(1600.0))
+            .set_rating(seeding[3], rating(1400.0));
+
+        let seeded = seed(
+            &Method::ByRating,
+            participants.clone(),
+            participants.clone(),
+            Some(&ratings),
+            None,
+            &mut draw_rng(0),
+        )
+        .expect("seeded");
+
+        // unrated player gets the initial rating
+        assert_eq!(
+            seeded.get_seeding(),
+            vec![seeding[2], seeding[0], seeding[1], seeding[3]]
+        );
+        let separation = Separation::new(1, 1)
+            .tag(seeding[2], "team")
+            .tag(seeding[3], "team");
+        let seeded = seed(
+            &Method::ByRating,
+            participants.clone(),
+            participants,
+            Some(&ratings),
+            Some(&separation),
+            &mut draw_rng(0),
+        )
+        .expect("seeded");
+        assert_eq!(
+            seeded.get_seeding(),
+            vec![seeding[2], seeding[0], seeding[3], seeding[1]]
+        );
+    }
+
+    #[test]
+    fn seeding_by_rating_needs_ratings() {
+        let participants = participants(3);
+
+        assert!(matches!(
+            seed(
+                &Method::ByRating,
+                participants.clone(),
+                participants,
+                None,
+                None,
+                &mut draw_rng(0)
+            ),
+            Err(Error::MissingRatings)
+        ));
+    }
 }
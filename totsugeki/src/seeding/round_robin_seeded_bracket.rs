@@ -0,0 +1,109 @@
+//! Generate seeded matches for round robin
+
+use crate::{matches::Match, opponent::Opponent, player::Id as PlayerId, seeding::Error};
+
+/// Returns all matches of a round robin pool, sorted by rounds. Every player
+/// plays every other player exactly once.
+///
+/// Rounds are generated with the circle method: top seed stays in place while
+/// everyone else rotates. When there is an odd number of players, one player
+/// sits out each round.
+///
+/// # Errors
+/// Throws error when math overflow happens
+///
+/// # Panics
+/// We do not expect any panics here because two different seeds always refer
+/// to two different players
+pub fn get_round_robin_matches(seeding: &[PlayerId]) -> Result<Vec<Match>, Error> {
+    let n = seeding.len();
+    // 0 is used as a placeholder for the player sitting out
+    let mut ring: Vec<usize> = (1..=n).collect();
+    if n % 2 == 1 {
+        ring.push(0);
+    }
+    let total_rounds = ring.len().checked_sub(1).ok_or(Error::MathOverflow)?;
+
+    let mut matches = vec![];
+    for _ in 0..total_rounds {
+        for i in 0..ring.len() / 2 {
+            let (s1, s2) = (ring[i], ring[ring.len() - 1 - i]);
+            if s1 == 0 || s2 == 0 {
+                continue;
+            }
+            let (top_seed, bottom_seed) = if s1 < s2 { (s1, s2) } else { (s2, s1) };
+            matches.push(
+                Match::new(
+                    [
+                        Opponent::Player(seeding[top_seed - 1]),
+                        Opponent::Player(seeding[bottom_seed - 1]),
+                    ],
+                    [top_seed, bottom_seed],
+                )
+                .expect("match"),
+            );
+        }
+        ring[1..].rotate_right(1);
+    }
+
+    Ok(matches)
+}
+
+/// Number of matches played in each round of a round robin pool of `n`
+/// players
+#[must_use]
+pub fn matches_per_round(n: usize) -> usize {
+    n / 2
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::player::Player;
+
+    fn assert_everyone_plays_each_other_once(n: usize) {
+        let seeding = (1..=n)
+            .map(|i| Player::new(format!("p{i}")).get_id())
+            .collect::<Vec<_>>();
+        let matches = get_round_robin_matches(&seeding).expect("matches");
+
+        assert_eq!(matches.len(), n * (n - 1) / 2);
+        for (i, p1) in seeding.iter().enumerate() {
+            for p2 in seeding.iter().skip(i + 1) {
+                assert_eq!(
+                    matches
+                        .iter()
+                        .filter(|m| m.contains(*p1) && m.contains(*p2))
+                        .count(),
+                    1,
+                    "expected exactly one match between {p1} and {p2} for {n} players"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn every_player_plays_every_other_player_once() {
+        for n in 3..=16 {
+            assert_everyone_plays_each_other_once(n);
+        }
+    }
+
+    #[test]
+    fn no_player_plays_twice_in_the_same_round() {
+        for n in 3..=16 {
+            let seeding = (1..=n)
+                .map(|i| Player::new(format!("p{i}")).get_id())
+                .collect::<Vec<_>>();
+            let matches = get_round_robin_matches(&seeding).expect("matches");
+            for round in matches.chunks(matches_per_round(n)) {
+                for p in &seeding {
+                    assert!(
+                        round.iter().filter(|m| m.contains(*p)).count() < 2,
+                        "{p} plays twice in the same round for {n} players"
+                    );
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,211 @@
+//! Keep players sharing a tag (region, team, training partners) apart in
+//! early rounds
+//!
+//! Players are moved within a tolerance band around their seed: a player
+//! never moves more than `tolerance` seeding positions away from where they
+//! were seeded. When no player within the band can be swapped, seeding is
+//! left as is and players sharing a tag may meet early.
+//!
+//! Rounds are those of the winner bracket of an elimination bracket where
+//! the top seed plays the bottom seed.
+
+use crate::player::Id as PlayerId;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+/// Tags of players and how far they can be moved to keep players sharing a
+/// tag apart
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Separation {
+    /// Tags of every tagged player
+    tags: BTreeMap<PlayerId, Vec<String>>,
+    /// Players sharing a tag should not meet before this round (included)
+    rounds: usize,
+    /// Maximum number of seeding positions a player can be moved
+    tolerance: usize,
+}
+
+impl Separation {
+    /// Players sharing a tag should not meet up to `rounds` (the first round
+    /// is round 1) and players are moved by at most `tolerance` positions
+    #[must_use]
+    pub fn new(rounds: usize, tolerance: usize) -> Self {
+        Self {
+            tags: BTreeMap::new(),
+            rounds,
+            tolerance,
+        }
+    }
+
+    /// Tag `player_id` with `tag`
+    #[must_use]
+    pub fn tag(self, player_id: PlayerId, tag: &str) -> Self {
+        let mut tags = self.tags;
+        let player_tags = tags.entry(player_id).or_default();
+        if !player_tags.iter().any(|t| t == tag) {
+            player_tags.push(tag.to_string());
+        }
+        Self { tags, ..self }
+    }
+
+    /// Returns tags of `player_id`
+    #[must_use]
+    pub fn get_tags(&self, player_id: PlayerId) -> Vec<String> {
+        self.tags.get(&player_id).cloned().unwrap_or_default()
+    }
+
+    /// Returns `seeding` where players sharing a tag do not meet early,
+    /// unless they cannot be moved within tolerance
+    ///
+    /// Players meeting a better seed sharing their tag early are swapped
+    /// with the closest player within tolerance when the swap lowers the
+    /// number of early meetings, until no swap helps.
+    #[must_use]
+    pub fn separate(&self, seeding: &[PlayerId]) -> Vec<PlayerId> {
+        let n = seeding.len();
+        let positions = bracket_positions(n);
+        let original: HashMap<PlayerId, usize> =
+            seeding.iter().enumerate().map(|(i, p)| (*p, i)).collect();
+        let is_within_tolerance = |player: PlayerId, position: usize| {
+            original[&player].abs_diff(position) <= self.tolerance
+        };
+        // players meeting `player` early if they were at `position`, not
+        // counting the player at `ignored`
+        let early_meetings = |seeding: &[PlayerId], player, position, ignored| {
+            seeding
+                .iter()
+                .enumerate()
+                .filter(|(other, p)| {
+                    *other != position
+                        && *other != ignored
+                        && self.share_tag(player, **p)
+                        && meeting_round(&positions, *other, position) <= self.rounds
+                })
+                .count()
+        };
+
+        let mut seeding = seeding.to_vec();
+        let mut has_improved = true;
+        while has_improved {
+            has_improved = false;
+            for i in 0..n {
+                let player = seeding[i];
+                // only the lowest seed of players meeting early is moved
+                if !self.tags.contains_key(&player)
+                    || early_meetings(&seeding[..i], player, i, i) == 0
+                {
+                    continue;
+                }
+                let lowest = i.saturating_sub(2 * self.tolerance);
+                let highest = (i + 2 * self.tolerance).min(n - 1);
+                let mut candidates = (lowest..=highest).filter(|k| *k != i).collect::<Vec<_>>();
+                candidates.sort_by_key(|k| k.abs_diff(i));
+                let swap = candidates.into_iter().find(|k| {
+                    let other = seeding[*k];
+                    let before = early_meetings(&seeding, player, i, *k)
+                        + early_meetings(&seeding, other, *k, i);
+                    let after = early_meetings(&seeding, player, *k, i)
+                        + early_meetings(&seeding, other, i, *k);
+                    is_within_tolerance(player, *k)
+                        && is_within_tolerance(other, i)
+                        && after < before
+                });
+                if let Some(k) = swap {
+                    seeding.swap(i, k);
+                    has_improved = true;
+                }
+            }
+        }
+        seeding
+    }
+
+    /// Returns true if both players have a tag in common
+    fn share_tag(&self, p1: PlayerId, p2: PlayerId) -> bool {
+        match (self.tags.get(&p1), self.tags.get(&p2)) {
+            (Some(t1), Some(t2)) => t1.iter().any(|t| t2.contains(t)),
+            _ => false,
+        }
+    }
+}
+
+/// Returns the position in the first round of every seeding position (0 is
+/// the top seed) of a bracket with `n` players
+fn bracket_positions(n: usize) -> Vec<usize> {
+    // 1 vs 8, 4 vs 5, 2 vs 7, 3 vs 6 for 8 players
+    let mut order = vec![0];
+    while order.len() < n {
+        let size = order.len() * 2;
+        order = order.iter().flat_map(|s| [*s, size - 1 - s]).collect();
+    }
+    let mut positions = vec![0; order.len()];
+    for (position, seed) in order.into_iter().enumerate() {
+        positions[seed] = position;
+    }
+    positions
+}
+
+/// Returns the earliest round (starting at 1) where seeding positions `a`
+/// and `b` can meet
+fn meeting_round(positions: &[usize], a: usize, b: usize) -> usize {
+    let differing_bits = positions[a] ^ positions[b];
+    (usize::BITS - differing_bits.leading_zeros()) as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn players(n: usize) -> Vec<PlayerId> {
+        (0..n).map(|_| PlayerId::new_v4()).collect()
+    }
+
+    #[test]
+    fn seeds_meet_in_standard_bracket_rounds() {
+        let positions = bracket_positions(8);
+
+        assert_eq!(meeting_round(&positions, 0, 7), 1);
+        assert_eq!(meeting_round(&positions, 3, 4), 1);
+        assert_eq!(meeting_round(&positions, 0, 3), 2);
+        assert_eq!(meeting_round(&positions, 1, 2), 2);
+        assert_eq!(meeting_round(&positions, 0, 1), 3);
+        // 5 players are seeded like 8 players with byes
+        assert_eq!(bracket_positions(5), positions);
+    }
+
+    #[test]
+    fn teammates_are_moved_away_from_each_other() {
+        let seeding = players(8);
+        let separation = Separation::new(1, 1)
+            .tag(seeding[0], "team")
+            .tag(seeding[7], "team");
+
+        let separated = separation.separate(&seeding);
+
+        // 8th seed swaps with 7th seed and plays 2nd seed instead
+        let mut expected = seeding.clone();
+        expected.swap(6, 7);
+        assert_eq!(separated, expected);
+        assert_eq!(Separation::new(1, 0).separate(&seeding), seeding);
+    }
+
+    #[test]
+    fn players_are_not_moved_outside_tolerance() {
+        let seeding = players(8);
+        let separation = Separation::new(2, 1)
+            .tag(seeding[0], "region")
+            .tag(seeding[3], "region")
+            .tag(seeding[4], "region");
+
+        let separated = separation.separate(&seeding);
+
+        // 4th seed moves up to 3rd seed, away from 1st and 5th seed
+        let mut expected = seeding.clone();
+        expected.swap(2, 3);
+        assert_eq!(separated, expected);
+        let separated = Separation::new(2, 0)
+            .tag(seeding[0], "region")
+            .tag(seeding[3], "region")
+            .separate(&seeding);
+        assert_eq!(separated, seeding);
+    }
+}
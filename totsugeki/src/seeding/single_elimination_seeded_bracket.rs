@@ -98,13 +98,54 @@ pub fn get_balanced_round_matches_top_seed_favored(
 
     Ok(round_matches.into_iter().flatten().collect())
 }
+
+/// Returns first round matches of top seeds advancing without an opponent in
+/// a single elimination bracket of `seeding`. There are as many byes as
+/// needed to fill the first round up to the next power of two.
+///
+/// Bye matches are not generated by [`get_balanced_round_matches_top_seed_favored`],
+/// where top seeds with a bye start in the second round.
+///
+/// # Errors
+/// Throws error when math overflow happens
+pub fn get_bye_matches(seeding: &[PlayerId]) -> Result<Vec<Match>, Error> {
+    let Some(first_round) = seeding.len().checked_next_power_of_two() else {
+        return Err(Error::MathOverflow);
+    };
+    let byes = first_round - seeding.len();
+    Ok(seeding[..byes]
+        .iter()
+        .enumerate()
+        .map(|(i, player)| Match::new_bye(*player, i + 1))
+        .collect())
+}
+
+/// Returns empty matches of a single elimination bracket for `n` players with
+/// seeds shifted by `offset`. Used for playoffs, where players are only known
+/// once they are eliminated from the main bracket
+///
+/// # Errors
+/// Throws error when math overflow happens
+pub(crate) fn get_empty_matches_with_seed_offset(
+    n: usize,
+    offset: usize,
+) -> Result<Vec<Match>, Error> {
+    let placeholders = (0..n).map(|_| PlayerId::new_v4()).collect::<Vec<_>>();
+    Ok(get_balanced_round_matches_top_seed_favored(&placeholders)?
+        .iter()
+        .map(|m| {
+            let [top_seed, bottom_seed] = m.get_seeds();
+            Match::new_empty([top_seed + offset, bottom_seed + offset])
+        })
+        .collect())
+}
 #[cfg(test)]
 mod tests {
     use crate::matches::Id as MatchId;
     use crate::seeding::single_elimination_seeded_bracket::get_balanced_round_matches_top_seed_favored;
-    use crate::seeding::{seed, Method};
+    use crate::seeding::{draw_rng, seed, Method};
     use crate::{
-        matches::Match,
+        matches::{Lifecycle, Links, Match},
         opponent::Opponent,
         player::{Participants, Player},
     };
@@ -147,6 +188,9 @@ mod tests {
                 winner: Opponent::Unknown,
                 automatic_loser: Opponent::Unknown,
                 reported_results: [(0, 0), (0, 0)],
+                lifecycle: Lifecycle::default(),
+                links: Links::default(),
+                round: 0,
             },
             Match {
                 id: match_ids.pop().expect("match id"),
@@ -155,6 +199,9 @@ mod tests {
                 winner: Opponent::Unknown,
                 automatic_loser: Opponent::Unknown,
                 reported_results: [(0, 0), (0, 0)],
+                lifecycle: Lifecycle::default(),
+                links: Links::default(),
+                round: 0,
             },
         ];
 
@@ -199,6 +246,9 @@ mod tests {
                 winner: Opponent::Unknown,
                 automatic_loser: Opponent::Unknown,
                 reported_results: [(0, 0), (0, 0)],
+                lifecycle: Lifecycle::default(),
+                links: Links::default(),
+                round: 0,
             },
             Match {
                 id: match_ids.pop().expect("match id"),
@@ -210,6 +260,9 @@ mod tests {
                 winner: Opponent::Unknown,
                 automatic_loser: Opponent::Unknown,
                 reported_results: [(0, 0), (0, 0)],
+                lifecycle: Lifecycle::default(),
+                links: Links::default(),
+                round: 0,
             },
             Match {
                 id: match_ids.pop().expect("match id"),
@@ -218,6 +271,9 @@ mod tests {
                 winner: Opponent::Unknown,
                 automatic_loser: Opponent::Unknown,
                 reported_results: [(0, 0), (0, 0)],
+                lifecycle: Lifecycle::default(),
+                links: Links::default(),
+                round: 0,
             },
         ];
 
@@ -263,6 +319,9 @@ mod tests {
                 winner: Opponent::Unknown,
                 automatic_loser: Opponent::Unknown,
                 reported_results: [(0, 0), (0, 0)],
+                lifecycle: Lifecycle::default(),
+                links: Links::default(),
+                round: 0,
             },
             Match {
                 id: match_ids.pop().expect("match id"),
@@ -271,6 +330,9 @@ mod tests {
                 winner: Opponent::Unknown,
                 automatic_loser: Opponent::Unknown,
                 reported_results: [(0, 0), (0, 0)],
+                lifecycle: Lifecycle::default(),
+                links: Links::default(),
+                round: 0,
             },
             Match {
                 id: match_ids.pop().expect("match id"),
@@ -282,6 +344,9 @@ mod tests {
                 winner: Opponent::Unknown,
                 automatic_loser: Opponent::Unknown,
                 reported_results: [(0, 0), (0, 0)],
+                lifecycle: Lifecycle::default(),
+                links: Links::default(),
+                round: 0,
             },
             Match {
                 id: match_ids.pop().expect("match id"),
@@ -290,6 +355,9 @@ mod tests {
                 winner: Opponent::Unknown,
                 automatic_loser: Opponent::Unknown,
                 reported_results: [(0, 0), (0, 0)],
+                lifecycle: Lifecycle::default(),
+                links: Links::default(),
+                round: 0,
             },
         ];
 
@@ -336,6 +404,9 @@ mod tests {
                 winner: Opponent::Unknown,
                 automatic_loser: Opponent::Unknown,
                 reported_results: [(0, 0), (0, 0)],
+                lifecycle: Lifecycle::default(),
+                links: Links::default(),
+                round: 0,
             },
             Match {
                 id: match_ids.pop().expect("match id"),
@@ -347,6 +418,9 @@ mod tests {
                 winner: Opponent::Unknown,
                 automatic_loser: Opponent::Unknown,
                 reported_results: [(0, 0), (0, 0)],
+                lifecycle: Lifecycle::default(),
+                links: Links::default(),
+                round: 0,
             },
             Match {
                 id: match_ids.pop().expect("match id"),
@@ -355,6 +429,9 @@ mod tests {
                 winner: Opponent::Unknown,
                 automatic_loser: Opponent::Unknown,
                 reported_results: [(0, 0), (0, 0)],
+                lifecycle: Lifecycle::default(),
+                links: Links::default(),
+                round: 0,
             },
             Match {
                 id: match_ids.pop().expect("match id"),
@@ -363,6 +440,9 @@ mod tests {
                 winner: Opponent::Unknown,
                 automatic_loser: Opponent::Unknown,
                 reported_results: [(0, 0), (0, 0)],
+                lifecycle: Lifecycle::default(),
+                links: Links::default(),
+                round: 0,
             },
             Match {
                 id: match_ids.pop().expect("match id"),
@@ -371,6 +451,9 @@ mod tests {
                 winner: Opponent::Unknown,
                 automatic_loser: Opponent::Unknown,
                 reported_results: [(0, 0), (0, 0)],
+                lifecycle: Lifecycle::default(),
+                links: Links::default(),
+                round: 0,
             },
         ];
 
@@ -378,6 +461,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::too_many_lines)]
     fn matches_generation_7_man_bracket() {
         let n = 7;
         let mut players: Vec<Player> = vec![];
@@ -418,6 +502,9 @@ mod tests {
                 winner: Opponent::Unknown,
                 automatic_loser: Opponent::Unknown,
                 reported_results: [(0, 0), (0, 0)],
+                lifecycle: Lifecycle::default(),
+                links: Links::default(),
+                round: 0,
             },
             Match {
                 id: match_ids.pop().expect("match id"),
@@ -429,6 +516,9 @@ mod tests {
                 winner: Opponent::Unknown,
                 automatic_loser: Opponent::Unknown,
                 reported_results: [(0, 0), (0, 0)],
+                lifecycle: Lifecycle::default(),
+                links: Links::default(),
+                round: 0,
             },
             Match {
                 id: match_ids.pop().expect("match id"),
@@ -440,6 +530,9 @@ mod tests {
                 winner: Opponent::Unknown,
                 automatic_loser: Opponent::Unknown,
                 reported_results: [(0, 0), (0, 0)],
+                lifecycle: Lifecycle::default(),
+                links: Links::default(),
+                round: 0,
             },
             Match {
                 id: match_ids.pop().expect("match id"),
@@ -448,6 +541,9 @@ mod tests {
                 winner: Opponent::Unknown,
                 automatic_loser: Opponent::Unknown,
                 reported_results: [(0, 0), (0, 0)],
+                lifecycle: Lifecycle::default(),
+                links: Links::default(),
+                round: 0,
             },
             Match {
                 id: match_ids.pop().expect("match id"),
@@ -456,6 +552,9 @@ mod tests {
                 winner: Opponent::Unknown,
                 automatic_loser: Opponent::Unknown,
                 reported_results: [(0, 0), (0, 0)],
+                lifecycle: Lifecycle::default(),
+                links: Links::default(),
+                round: 0,
             },
             Match {
                 id: match_ids.pop().expect("match id"),
@@ -464,6 +563,9 @@ mod tests {
                 winner: Opponent::Unknown,
                 automatic_loser: Opponent::Unknown,
                 reported_results: [(0, 0), (0, 0)],
+                lifecycle: Lifecycle::default(),
+                links: Links::default(),
+                round: 0,
             },
         ];
 
@@ -513,6 +615,9 @@ mod tests {
                 winner: Opponent::Unknown,
                 automatic_loser: Opponent::Unknown,
                 reported_results: [(0, 0), (0, 0)],
+                lifecycle: Lifecycle::default(),
+                links: Links::default(),
+                round: 0,
             },
             Match {
                 id: match_ids.pop().expect("match id"),
@@ -524,6 +629,9 @@ mod tests {
                 winner: Opponent::Unknown,
                 automatic_loser: Opponent::Unknown,
                 reported_results: [(0, 0), (0, 0)],
+                lifecycle: Lifecycle::default(),
+                links: Links::default(),
+                round: 0,
             },
             Match {
                 id: match_ids.pop().expect("match id"),
@@ -535,6 +643,9 @@ mod tests {
                 winner: Opponent::Unknown,
                 automatic_loser: Opponent::Unknown,
                 reported_results: [(0, 0), (0, 0)],
+                lifecycle: Lifecycle::default(),
+                links: Links::default(),
+                round: 0,
             },
             Match {
                 id: match_ids.pop().expect("match id"),
@@ -546,6 +657,9 @@ mod tests {
                 winner: Opponent::Unknown,
                 automatic_loser: Opponent::Unknown,
                 reported_results: [(0, 0), (0, 0)],
+                lifecycle: Lifecycle::default(),
+                links: Links::default(),
+                round: 0,
             },
             Match {
                 id: match_ids.pop().expect("match id"),
@@ -554,6 +668,9 @@ mod tests {
                 winner: Opponent::Unknown,
                 automatic_loser: Opponent::Unknown,
                 reported_results: [(0, 0), (0, 0)],
+                lifecycle: Lifecycle::default(),
+                links: Links::default(),
+                round: 0,
             },
             Match {
                 id: match_ids.pop().expect("match id"),
@@ -562,6 +679,9 @@ mod tests {
                 winner: Opponent::Unknown,
                 automatic_loser: Opponent::Unknown,
                 reported_results: [(0, 0), (0, 0)],
+                lifecycle: Lifecycle::default(),
+                links: Links::default(),
+                round: 0,
             },
             Match {
                 id: match_ids.pop().expect("match id"),
@@ -570,6 +690,9 @@ mod tests {
                 winner: Opponent::Unknown,
                 automatic_loser: Opponent::Unknown,
                 reported_results: [(0, 0), (0, 0)],
+                lifecycle: Lifecycle::default(),
+                links: Links::default(),
+                round: 0,
             },
         ];
 
@@ -587,7 +710,15 @@ mod tests {
                 players.push(Player::new(format!("player{i}")));
             });
             let players = Participants::try_from(players).expect("players");
-            let players = seed(&Method::Strict, players.clone(), players).expect("seeded players");
+            let players = seed(
+                &Method::Strict,
+                players.clone(),
+                players,
+                None,
+                None,
+                &mut draw_rng(0),
+            )
+            .expect("seeded players");
             let _matches = get_balanced_round_matches_top_seed_favored(
                 &players
                     .get_players_list()
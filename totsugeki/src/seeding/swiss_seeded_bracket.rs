@@ -0,0 +1,255 @@
+//! Generate pairings for swiss system, one round at a time
+
+use crate::{matches::Match, opponent::Opponent, player::Id as PlayerId, seeding::Error};
+
+/// Default number of rounds for a swiss tournament of `n` players: enough
+/// rounds to have a single undefeated player
+#[must_use]
+pub fn default_number_of_rounds(n: usize) -> usize {
+    match n.checked_next_power_of_two() {
+        Some(npo2) => npo2.trailing_zeros() as usize,
+        None => usize::BITS as usize,
+    }
+}
+
+/// Returns number of matches won by `player_id`. Byes count as a win
+pub(crate) fn wins_of(player_id: PlayerId, matches: &[Match]) -> usize {
+    matches
+        .iter()
+        .filter(|m| matches!(m.get_winner(), Opponent::Player(w) if w == player_id))
+        .count()
+}
+
+/// Number of partial pairings explored to avoid rematches before rematches
+/// are allowed. Searching every pairing of a large round where rematches
+/// cannot be avoided never ends
+const PAIRING_BUDGET: usize = 10_000;
+
+/// Returns true if `p1` and `p2` already played each other
+fn have_played(p1: PlayerId, p2: PlayerId, matches: &[Match]) -> bool {
+    matches.iter().any(|m| m.contains(p1) && m.contains(p2))
+}
+
+/// Returns matches of next swiss round using results of `previous_matches`.
+/// `excluded` players (disqualified) are not paired. Returned matches record
+/// the round that follows the last round of `previous_matches`.
+///
+/// Players are ranked by wins then seed. If there is an odd number of
+/// players, the lowest ranked player without a bye gets one. Then each
+/// player is paired, starting from the top, with a player of the same score
+/// group they have not played yet, preferring the opponent half a score group
+/// below them. When no pairing avoiding rematches is found in a bounded
+/// number of attempts, rematches are allowed.
+///
+/// # Errors
+/// thrown when there is not enough players to pair, a player is not seeded or
+/// players cannot be paired
+pub fn get_swiss_round_matches(
+    seeding: &[PlayerId],
+    previous_matches: &[Match],
+    excluded: &[PlayerId],
+) -> Result<Vec<Match>, Error> {
+    let seed_of = |p: PlayerId| match seeding.iter().position(|s| *s == p) {
+        Some(i) => Ok(i + 1),
+        None => Err(Error::UnknownPlayer(p)),
+    };
+    let mut ranked = seeding
+        .iter()
+        .copied()
+        .filter(|p| !excluded.contains(p))
+        .collect::<Vec<_>>();
+    if ranked.len() < 2 {
+        return Err(Error::NotEnoughPlayers);
+    }
+    // stable sort: seeding breaks ties
+    ranked.sort_by_key(|p| std::cmp::Reverse(wins_of(*p, previous_matches)));
+
+    let wins_by_player = |players: &[PlayerId]| {
+        players
+            .iter()
+            .map(|p| wins_of(*p, previous_matches))
+            .collect::<Vec<_>>()
+    };
+
+    let mut matches = vec![];
+    let mut budget = PAIRING_BUDGET;
+    let pairs = if ranked.len() % 2 == 1 {
+        // lowest ranked player without a bye gets one, as long as remaining
+        // players can be paired without rematches
+        let mut candidates = (0..ranked.len())
+            .rev()
+            .filter(|i| {
+                !previous_matches
+                    .iter()
+                    .any(|m| m.is_bye() && m.contains(ranked[*i]))
+            })
+            .collect::<Vec<_>>();
+        if candidates.is_empty() {
+            candidates.push(ranked.len() - 1);
+        }
+        let pairing_without_rematches = candidates.iter().find_map(|i| {
+            let mut others = ranked.clone();
+            others.remove(*i);
+            pair(
+                &others,
+                &wins_by_player(&others),
+                previous_matches,
+                Some(&mut budget),
+            )
+            .map(|p| (*i, p))
+        });
+        let (bye_index, pairs) = if let Some(pairing) = pairing_without_rematches {
+            pairing
+        } else {
+            let mut others = ranked.clone();
+            others.remove(candidates[0]);
+            let pairs = pair(&others, &wins_by_player(&others), previous_matches, None)
+                .ok_or(Error::NoPairing)?;
+            (candidates[0], pairs)
+        };
+        let player_with_bye = ranked[bye_index];
+        matches.push(Match::new_bye(player_with_bye, seed_of(player_with_bye)?));
+        pairs
+    } else {
+        let wins = wins_by_player(&ranked);
+        pair(&ranked, &wins, previous_matches, Some(&mut budget))
+            .or_else(|| pair(&ranked, &wins, previous_matches, None))
+            .ok_or(Error::NoPairing)?
+    };
+
+    let mut pairings = pairs
+        .into_iter()
+        .map(|(p1, p2)| {
+            let (s1, s2) = (seed_of(p1)?, seed_of(p2)?);
+            let m = if s1 < s2 {
+                Match::new([Opponent::Player(p1), Opponent::Player(p2)], [s1, s2])
+            } else {
+                Match::new([Opponent::Player(p2), Opponent::Player(p1)], [s2, s1])
+            }?;
+            Ok(m)
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+    pairings.append(&mut matches);
+
+    let round = previous_matches
+        .iter()
+        .map(Match::get_round)
+        .max()
+        .unwrap_or(0)
+        + 1;
+    Ok(pairings.into_iter().map(|m| m.with_round(round)).collect())
+}
+
+/// Pair all `ranked` players, sorted from strongest to weakest, with their
+/// number of `wins`. Rematches are avoided when given a `budget` of partial
+/// pairings to explore. Returns None if no pairing avoiding rematches is
+/// found before the budget runs out
+fn pair(
+    ranked: &[PlayerId],
+    wins: &[usize],
+    previous_matches: &[Match],
+    mut budget: Option<&mut usize>,
+) -> Option<Vec<(PlayerId, PlayerId)>> {
+    let Some((&player, others)) = ranked.split_first() else {
+        return Some(vec![]);
+    };
+    if let Some(budget) = budget.as_deref_mut() {
+        *budget = budget.checked_sub(1)?;
+    }
+    let avoid_rematches = budget.is_some();
+    let (&player_wins, other_wins) = wins.split_first().expect("wins of player");
+
+    // prefer opponents from the same score group, half the group below
+    let score_group_size = other_wins.iter().filter(|w| **w == player_wins).count();
+    let half = score_group_size.div_ceil(2);
+    let mut candidates = (0..others.len()).collect::<Vec<_>>();
+    candidates[..score_group_size].rotate_left(half.saturating_sub(1).min(score_group_size));
+
+    for i in candidates {
+        let opponent = others[i];
+        if avoid_rematches && have_played(player, opponent, previous_matches) {
+            continue;
+        }
+        let mut remaining = others.to_vec();
+        remaining.remove(i);
+        let mut remaining_wins = other_wins.to_vec();
+        remaining_wins.remove(i);
+        if let Some(mut pairs) = pair(
+            &remaining,
+            &remaining_wins,
+            previous_matches,
+            budget.as_deref_mut(),
+        ) {
+            pairs.insert(0, (player, opponent));
+            return Some(pairs);
+        }
+        if budget.as_deref() == Some(&0) {
+            return None;
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::player::Player;
+
+    fn seeding(n: usize) -> Vec<PlayerId> {
+        (1..=n)
+            .map(|i| Player::new(format!("p{i}")).get_id())
+            .collect()
+    }
+
+    #[test]
+    fn first_round_pairs_top_half_against_bottom_half() {
+        let seeding = seeding(8);
+        let matches = get_swiss_round_matches(&seeding, &[], &[]).expect("matches");
+        let seeds = matches.iter().map(Match::get_seeds).collect::<Vec<_>>();
+        assert_eq!(seeds, vec![[1, 5], [2, 6], [3, 7], [4, 8]]);
+    }
+
+    #[test]
+    fn lowest_seed_gets_bye_with_odd_number_of_players() {
+        let seeding = seeding(5);
+        let matches = get_swiss_round_matches(&seeding, &[], &[]).expect("matches");
+        assert_eq!(matches.len(), 3);
+        let bye = matches.last().expect("bye");
+        assert!(bye.is_bye());
+        assert!(bye.contains(seeding[4]));
+    }
+
+    #[test]
+    fn rematch_is_allowed_when_pairing_without_rematches_takes_too_long() {
+        let seeding = seeding(40);
+        let last = seeding.len();
+        // last seed played everyone: no pairing avoids rematches, which an
+        // unbounded search only finds out after trying every pairing of others
+        let previous_matches = seeding[..last - 1]
+            .iter()
+            .enumerate()
+            .map(|(i, p)| {
+                Match::new(
+                    [Opponent::Player(*p), Opponent::Player(seeding[last - 1])],
+                    [i + 1, last],
+                )
+                .expect("match")
+            })
+            .collect::<Vec<_>>();
+
+        let matches = get_swiss_round_matches(&seeding, &previous_matches, &[]).expect("matches");
+
+        assert_eq!(matches.len(), 20);
+        assert!(matches.iter().any(|m| m.contains(seeding[last - 1])));
+    }
+
+    #[test]
+    fn default_number_of_rounds_leaves_one_undefeated_player() {
+        assert_eq!(default_number_of_rounds(2), 1);
+        assert_eq!(default_number_of_rounds(3), 2);
+        assert_eq!(default_number_of_rounds(8), 3);
+        assert_eq!(default_number_of_rounds(9), 4);
+        assert_eq!(default_number_of_rounds(64), 6);
+    }
+}
@@ -0,0 +1,702 @@
+//! Tournament made of successive phases. Each phase is played in one or many
+//! pools (brackets) and the best players of every pool advance to the next
+//! phase.
+
+use crate::{
+    bracket::{Bracket, Error as BracketError, Id as BracketId},
+    format::Format,
+    matches::{Id as MatchId, Match},
+    player::{Id as PlayerId, Participants, Player},
+    seeding::Method as SeedingMethod,
+};
+use chrono::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use thiserror::Error;
+use uuid::Uuid;
+
+/// Tournament identifier
+pub type Id = Uuid;
+
+/// Error while managing a tournament
+#[derive(Error, Debug)]
+pub enum Error {
+    /// Error while updating one of the pools
+    #[error("{0}")]
+    Bracket(#[from] BracketError),
+    /// Tournament has no phase to play
+    #[error("Tournament {0} has no phases")]
+    NoPhases(Id),
+    /// Tournament has already started
+    #[error("Tournament {0} has started")]
+    Started(Id),
+    /// Tournament has not started
+    #[error("Tournament {0} has not started")]
+    NotStarted(Id),
+    /// Current phase still has matches to play
+    #[error("Phase \"{1}\" of tournament {0} is not over")]
+    PhaseIsNotOver(Id, String),
+    /// There is no phase after the current one
+    #[error("Tournament {0} has no phase left to play")]
+    NoNextPhase(Id),
+    /// Pool does not belong to the current phase
+    #[error("Bracket {1} is not a pool of the current phase of tournament {0}")]
+    UnknownPool(Id, BracketId),
+    /// Updated pool does not have the format or participants of the pool
+    /// it replaces
+    #[error("Bracket {1} does not have the format or participants of its pool in tournament {0}")]
+    MismatchedPool(Id, BracketId),
+    /// Phase requires more players than available
+    #[error(
+        "Phase \"{1}\" cannot be played with {2} players: every pool needs at least {3} players"
+    )]
+    NotEnoughPlayers(Id, String, usize, usize),
+    /// Phase still has matches to play
+    #[error("Phase \"{0}\" is not over")]
+    NotOver(String),
+    /// Player does not play in any pool of the current phase
+    #[error("Player {1} does not play in the current phase of tournament {0}")]
+    NotInCurrentPhase(Id, PlayerId),
+}
+
+/// Phase of a tournament, played in one or many pools of the same format
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Phase {
+    /// Name of this phase
+    name: String,
+    /// Format of all pools of this phase
+    format: Format,
+    /// Number of pools to play this phase
+    number_of_pools: usize,
+    /// Number of players advancing to the next phase from each pool
+    advancing_per_pool: usize,
+    /// Pools of this phase. Empty until phase starts
+    pools: Vec<Bracket>,
+}
+
+impl Phase {
+    /// Create phase played with `number_of_pools` pools of `format`, where the
+    /// best `advancing_per_pool` players of each pool advance to the next
+    /// phase
+    #[must_use]
+    pub fn new(
+        name: &str,
+        format: Format,
+        number_of_pools: usize,
+        advancing_per_pool: usize,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            format,
+            number_of_pools: number_of_pools.max(1),
+            advancing_per_pool,
+            pools: vec![],
+        }
+    }
+
+    /// Get name of phase
+    #[must_use]
+    pub fn get_name(&self) -> String {
+        self.name.clone()
+    }
+
+    /// Get format of pools of this phase
+    #[must_use]
+    pub fn get_format(&self) -> Format {
+        self.format
+    }
+
+    /// Get number of players advancing from each pool
+    #[must_use]
+    pub fn get_advancing_per_pool(&self) -> usize {
+        self.advancing_per_pool
+    }
+
+    /// Get pools of this phase
+    #[must_use]
+    pub fn get_pools(&self) -> Vec<Bracket> {
+        self.pools.clone()
+    }
+
+    /// Returns true if all pools of this phase are over
+    #[must_use]
+    pub fn is_over(&self) -> bool {
+        !self.pools.is_empty() && self.pools.iter().all(Bracket::is_over)
+    }
+
+    /// Returns players advancing to the next phase ordered by seed: all
+    /// pool winners first, then all second places (in reverse pool order) and
+    /// so on
+    ///
+    /// # Errors
+    /// thrown when phase is not over or standings of a pool cannot be read
+    pub fn advancing_players(&self) -> Result<Vec<Player>, Error> {
+        if !self.is_over() {
+            return Err(Error::NotOver(self.name.clone()));
+        }
+        let rankings = self
+            .pools
+            .iter()
+            .map(pool_ranking)
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut advancing = vec![];
+        for placement in 0..self.advancing_per_pool {
+            let mut players_with_placement = rankings
+                .iter()
+                .zip(&self.pools)
+                .filter_map(|(ranking, pool)| {
+                    ranking
+                        .get(placement)
+                        .and_then(|p| pool.get_participants().get(*p))
+                })
+                .collect::<Vec<_>>();
+            if placement % 2 == 1 {
+                players_with_placement.reverse();
+            }
+            advancing.append(&mut players_with_placement);
+        }
+        Ok(advancing)
+    }
+
+    /// Split `participants` into pools and start them. Returns started phase
+    /// and matches to play
+    fn start(
+        self,
+        tournament: &Tournament,
+        participants: &Participants,
+    ) -> Result<(Self, Vec<Match>), Error> {
+        let players = participants.get_players_list();
+        let minimum = self.format.minimum_number_of_participants();
+        if players.len() < minimum * self.number_of_pools {
+            return Err(Error::NotEnoughPlayers(
+                tournament.id,
+                self.name,
+                players.len(),
+                minimum,
+            ));
+        }
+        let mut pools = vec![];
+        let mut matches_to_play = vec![];
+        for (i, pool_players) in snake(&players, self.number_of_pools)
+            .into_iter()
+            .enumerate()
+        {
+            let name = if self.number_of_pools == 1 {
+                format!("{} - {}", tournament.name, self.name)
+            } else {
+                format!("{} - {} - pool {}", tournament.name, self.name, i + 1)
+            };
+            let mut pool = Bracket::new(
+                &name,
+                self.format,
+                SeedingMethod::Strict,
+                tournament.start_time,
+                tournament.automatic_match_validation,
+            );
+            for p in pool_players {
+                pool = pool.unchecked_join_skip_matches_generation(p)?;
+            }
+            let (pool, mut matches) = pool.generate_matches()?.start()?;
+            pools.push(pool);
+            matches_to_play.append(&mut matches);
+        }
+        Ok((Self { pools, ..self }, matches_to_play))
+    }
+}
+
+/// Distribute `players`, sorted by seed, into `n` pools. Players are dealt
+/// back and forth so every pool gets a balanced share of strong and weak
+/// players
+fn snake(players: &[Player], n: usize) -> Vec<Vec<Player>> {
+    let mut pools = vec![vec![]; n];
+    for (i, p) in players.iter().enumerate() {
+        let (wave, position) = (i / n, i % n);
+        let pool = if wave % 2 == 0 {
+            position
+        } else {
+            n - 1 - position
+        };
+        pools[pool].push(p.clone());
+    }
+    pools
+}
+
+/// Returns players of finished `pool` from first to last place. Players
+/// sharing a placement are sorted by seed
+fn pool_ranking(pool: &Bracket) -> Result<Vec<PlayerId>, Error> {
+    Ok(pool
+        .standings()?
+        .placements
+        .into_iter()
+        .flat_map(|g| g.players)
+        .collect())
+}
+
+/// Tournament made of phases played one after the other
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Tournament {
+    /// Identifier of this tournament
+    id: Id,
+    /// Name of this tournament
+    name: String,
+    /// Participants sorted by seed
+    participants: Participants,
+    /// Phases to play
+    phases: Vec<Phase>,
+    /// Index of phase being played. None if tournament has not started
+    current_phase: Option<usize>,
+    /// Advertised start time
+    start_time: DateTime<Utc>,
+    /// Matches are automatically validated if both players agree on result
+    automatic_match_validation: bool,
+}
+
+impl Tournament {
+    /// Create new tournament without phases
+    #[must_use]
+    pub fn new(
+        name: &str,
+        participants: Participants,
+        start_time: DateTime<Utc>,
+        automatic_match_validation: bool,
+    ) -> Self {
+        Self {
+            id: Id::new_v4(),
+            name: name.into(),
+            participants,
+            phases: vec![],
+            current_phase: None,
+            start_time,
+            automatic_match_validation,
+        }
+    }
+
+    /// Add phase after all existing phases
+    ///
+    /// # Errors
+    /// thrown when tournament has started
+    pub fn add_phase(self, phase: Phase) -> Result<Self, Error> {
+        if self.current_phase.is_some() {
+            return Err(Error::Started(self.id));
+        }
+        let mut phases = self.phases;
+        phases.push(phase);
+        Ok(Self { phases, ..self })
+    }
+
+    /// Start first phase: assign participants to pools by seed. Returns
+    /// updated tournament and matches to play
+    ///
+    /// # Errors
+    /// thrown when tournament has started, has no phases or has not enough
+    /// players
+    pub fn start(self) -> Result<(Self, Vec<Match>), Error> {
+        if self.current_phase.is_some() {
+            return Err(Error::Started(self.id));
+        }
+        let Some(first_phase) = self.phases.first().cloned() else {
+            return Err(Error::NoPhases(self.id));
+        };
+        let (first_phase, matches) = first_phase.start(&self, &self.participants)?;
+        let mut phases = self.phases;
+        phases[0] = first_phase;
+        Ok((
+            Self {
+                phases,
+                current_phase: Some(0),
+                ..self
+            },
+            matches,
+        ))
+    }
+
+    /// Replace pool of current phase with its updated version (after
+    /// reporting results for instance)
+    ///
+    /// # Errors
+    /// thrown when bracket is not a pool of the current phase or does not
+    /// have the format and participants of that pool
+    pub fn update_pool(self, pool: Bracket) -> Result<Self, Error> {
+        let Some(current) = self.current_phase else {
+            return Err(Error::NotStarted(self.id));
+        };
+        let phase = &self.phases[current];
+        let Some(index) = phase.pools.iter().position(|p| p.get_id() == pool.get_id()) else {
+            return Err(Error::UnknownPool(self.id, pool.get_id()));
+        };
+        let players = |b: &Bracket| {
+            b.get_participants()
+                .get_seeding()
+                .into_iter()
+                .collect::<HashSet<_>>()
+        };
+        if pool.get_format() != phase.format || players(&pool) != players(&phase.pools[index]) {
+            return Err(Error::MismatchedPool(self.id, pool.get_id()));
+        }
+        let mut phases = self.phases;
+        phases[current].pools[index] = pool;
+        Ok(Self { phases, ..self })
+    }
+
+    /// Report result of the match between `player1` and `player2` in their
+    /// pool of the current phase, as the tournament organiser. Returns updated
+    /// tournament, affected match id and new matches
+    ///
+    /// # Errors
+    /// thrown when tournament has not started, `player1` does not play in the
+    /// current phase or result cannot be reported in their pool
+    pub fn tournament_organiser_reports_result(
+        self,
+        player1: PlayerId,
+        result_player1: (i8, i8),
+        player2: PlayerId,
+    ) -> Result<(Self, MatchId, Vec<Match>), Error> {
+        let Some(current) = self.current_phase else {
+            return Err(Error::NotStarted(self.id));
+        };
+        let Some(pool) = self.phases[current]
+            .pools
+            .iter()
+            .find(|p| p.get_participants().get(player1).is_some())
+            .cloned()
+        else {
+            return Err(Error::NotInCurrentPhase(self.id, player1));
+        };
+        let (pool, match_id, new_matches) =
+            pool.tournament_organiser_reports_result(player1, result_player1, player2)?;
+        Ok((self.update_pool(pool)?, match_id, new_matches))
+    }
+
+    /// Promote best players of every pool of the current phase and start the
+    /// next phase with them. Returns updated tournament and matches to play
+    ///
+    /// # Errors
+    /// thrown when current phase is not over or there is no next phase
+    pub fn start_next_phase(self) -> Result<(Self, Vec<Match>), Error> {
+        let Some(current) = self.current_phase else {
+            return Err(Error::NotStarted(self.id));
+        };
+        if !self.phases[current].is_over() {
+            return Err(Error::PhaseIsNotOver(
+                self.id,
+                self.phases[current].get_name(),
+            ));
+        }
+        let Some(next_phase) = self.phases.get(current + 1).cloned() else {
+            return Err(Error::NoNextPhase(self.id));
+        };
+        let advancing = Participants::try_from(self.phases[current].advancing_players()?)
+            .map_err(BracketError::from)?;
+        let (next_phase, matches) = next_phase.start(&self, &advancing)?;
+        let mut phases = self.phases;
+        phases[current + 1] = next_phase;
+        Ok((
+            Self {
+                phases,
+                current_phase: Some(current + 1),
+                ..self
+            },
+            matches,
+        ))
+    }
+
+    /// Returns phase being played
+    #[must_use]
+    pub fn get_current_phase(&self) -> Option<Phase> {
+        self.current_phase.map(|i| self.phases[i].clone())
+    }
+
+    /// Get id of tournament
+    #[must_use]
+    pub fn get_id(&self) -> Id {
+        self.id
+    }
+
+    /// Get name of tournament
+    #[must_use]
+    pub fn get_name(&self) -> String {
+        self.name.clone()
+    }
+
+    /// Get participants of tournament
+    #[must_use]
+    pub fn get_participants(&self) -> Participants {
+        self.participants.clone()
+    }
+
+    /// Get all phases of tournament
+    #[must_use]
+    pub fn get_phases(&self) -> Vec<Phase> {
+        self.phases.clone()
+    }
+
+    /// Returns true if last phase is over
+    #[must_use]
+    pub fn is_over(&self) -> bool {
+        self.phases.last().is_some_and(Phase::is_over)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        format::{GrandFinals, LoserDrops, Playoffs},
+        opponent::Opponent,
+    };
+
+    fn participants(n: usize) -> Participants {
+        let mut participants = Participants::default();
+        for i in 1..=n {
+            participants = participants
+                .add_participant(Player::new(format!("p{i}")))
+                .expect("participant");
+        }
+        participants
+    }
+
+    /// Stronger seed wins all matches of all pools in current phase
+    fn play_current_phase(tournament: Tournament) -> Tournament {
+        let mut tournament = tournament;
+        loop {
+            let phase = tournament.get_current_phase().expect("phase");
+            let Some(m) = phase
+                .get_pools()
+                .iter()
+                .find_map(|pool| pool.matches_to_play().first().copied())
+            else {
+                return tournament;
+            };
+            let [Opponent::Player(p1), Opponent::Player(p2)] = m.get_players() else {
+                panic!("expected players");
+            };
+            let result = if m.get_seeds()[0] < m.get_seeds()[1] {
+                (2, 0)
+            } else {
+                (0, 2)
+            };
+            tournament = tournament
+                .tournament_organiser_reports_result(p1, result, p2)
+                .expect("result")
+                .0;
+        }
+    }
+
+    #[test]
+    fn snake_balances_pools() {
+        let players = participants(8).get_players_list();
+        let pools = snake(&players, 3);
+        let names = pools
+            .iter()
+            .map(|pool| pool.iter().map(Player::get_name).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            vec![
+                vec!["p1", "p6", "p7"],
+                vec!["p2", "p5", "p8"],
+                vec!["p3", "p4"],
+            ]
+        );
+    }
+
+    #[test]
+    fn pools_feed_top_cut_bracket() {
+        let participants = participants(16);
+        let tournament = Tournament::new(
+            "weekly",
+            participants,
+            Utc.with_ymd_and_hms(2000, 1, 1, 0, 0, 0).unwrap(),
+            true,
+        )
+        .add_phase(Phase::new("pools", Format::RoundRobin, 4, 2))
+        .expect("pools")
+        .add_phase(Phase::new(
+            "top 8",
+            Format::DoubleElimination {
+                loser_drops: LoserDrops::Standard,
+                grand_finals: GrandFinals::Reset,
+            },
+            1,
+            0,
+        ))
+        .expect("top cut");
+
+        let (tournament, matches) = tournament.start().expect("start");
+        // 4 pools of 4 players: 6 matches each
+        assert_eq!(matches.len(), 24);
+        match tournament.clone().start_next_phase() {
+            Err(Error::PhaseIsNotOver(_, name)) => assert_eq!(name, "pools"),
+            other => panic!("expected PhaseIsNotOver but got {other:?}"),
+        }
+
+        let tournament = play_current_phase(tournament);
+        let (tournament, matches) = tournament.start_next_phase().expect("top 8");
+        assert_eq!(matches.len(), 4);
+
+        let top_cut = tournament.get_current_phase().expect("top 8").get_pools()[0].clone();
+        let top_8 = top_cut
+            .get_participants()
+            .get_players_list()
+            .iter()
+            .map(Player::get_name)
+            .collect::<Vec<_>>();
+        // pool winners first, then second places in reverse pool order
+        assert_eq!(top_8, vec!["p1", "p2", "p3", "p4", "p5", "p6", "p7", "p8"]);
+
+        let tournament = play_current_phase(tournament);
+        assert!(tournament.is_over());
+        match tournament.start_next_phase() {
+            Err(Error::NoNextPhase(_)) => {}
+            other => panic!("expected NoNextPhase but got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn elimination_pools_rank_players_by_elimination_round() {
+        let participants = participants(8);
+        let tournament = Tournament::new(
+            "weekly",
+            participants,
+            Utc.with_ymd_and_hms(2000, 1, 1, 0, 0, 0).unwrap(),
+            true,
+        )
+        .add_phase(Phase::new(
+            "pools",
+            Format::SingleElimination {
+                playoffs: Playoffs::Disabled,
+            },
+            2,
+            2,
+        ))
+        .expect("pools")
+        .add_phase(Phase::new("finals", Format::RoundRobin, 1, 0))
+        .expect("finals");
+
+        let (tournament, _) = tournament.start().expect("start");
+        let tournament = play_current_phase(tournament);
+        let advancing = tournament
+            .get_current_phase()
+            .expect("pools")
+            .advancing_players()
+            .expect("advancing players")
+            .iter()
+            .map(Player::get_name)
+            .collect::<Vec<_>>();
+        // pool 1: p1, p4, p5, p8. pool 2: p2, p3, p6, p7
+        assert_eq!(advancing, vec!["p1", "p2", "p3", "p4"]);
+    }
+
+    #[test]
+    fn unfinished_phase_has_no_advancing_players() {
+        let tournament = Tournament::new(
+            "weekly",
+            participants(6),
+            Utc.with_ymd_and_hms(2000, 1, 1, 0, 0, 0).unwrap(),
+            true,
+        )
+        .add_phase(Phase::new("pools", Format::RoundRobin, 2, 1))
+        .expect("pools");
+        let phase = tournament.get_phases()[0].clone();
+        assert!(matches!(phase.advancing_players(), Err(Error::NotOver(_))));
+
+        let (tournament, _) = tournament.start().expect("start");
+        let phase = tournament.get_current_phase().expect("pools");
+        match phase.advancing_players() {
+            Err(Error::NotOver(name)) => assert_eq!(name, "pools"),
+            other => panic!("expected NotOver but got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn pools_of_two_players_need_a_format_playable_by_two() {
+        let tournament = |format| {
+            Tournament::new(
+                "weekly",
+                participants(4),
+                Utc.with_ymd_and_hms(2000, 1, 1, 0, 0, 0).unwrap(),
+                true,
+            )
+            .add_phase(Phase::new("pools", format, 2, 1))
+            .expect("pools")
+        };
+        match tournament(Format::SingleElimination {
+            playoffs: Playoffs::Disabled,
+        })
+        .start()
+        {
+            Err(Error::NotEnoughPlayers(_, _, 4, 3)) => {}
+            other => panic!("expected NotEnoughPlayers but got {other:?}"),
+        }
+
+        for format in [Format::RoundRobin, Format::Swiss { rounds: None }] {
+            let (t, matches) = tournament(format).start().expect("start");
+            assert_eq!(matches.len(), 2, "{format}");
+            let t = play_current_phase(t);
+            let advancing = t
+                .get_current_phase()
+                .expect("pools")
+                .advancing_players()
+                .expect("advancing players")
+                .iter()
+                .map(Player::get_name)
+                .collect::<Vec<_>>();
+            assert_eq!(advancing, vec!["p1", "p2"], "{format}");
+        }
+    }
+
+    #[test]
+    fn result_of_player_outside_current_phase_is_rejected() {
+        let (tournament, _) = Tournament::new(
+            "weekly",
+            participants(6),
+            Utc.with_ymd_and_hms(2000, 1, 1, 0, 0, 0).unwrap(),
+            true,
+        )
+        .add_phase(Phase::new("pools", Format::RoundRobin, 2, 1))
+        .expect("pools")
+        .start()
+        .expect("start");
+        let stranger = Player::new("stranger".into()).get_id();
+        let p1 = tournament.get_participants().get_seeding()[0];
+
+        match tournament.tournament_organiser_reports_result(stranger, (2, 0), p1) {
+            Err(Error::NotInCurrentPhase(_, player_id)) => assert_eq!(player_id, stranger),
+            other => panic!("expected NotInCurrentPhase but got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn pool_with_other_format_or_participants_is_rejected() {
+        let tournament = Tournament::new(
+            "weekly",
+            participants(8),
+            Utc.with_ymd_and_hms(2000, 1, 1, 0, 0, 0).unwrap(),
+            true,
+        )
+        .add_phase(Phase::new("pools", Format::RoundRobin, 2, 2))
+        .expect("pools");
+        let (tournament, _) = tournament.start().expect("start");
+        let pool = tournament.get_current_phase().expect("pools").get_pools()[0].clone();
+
+        let mut json = serde_json::to_value(&pool).expect("serialized");
+        json["format"] = serde_json::to_value(Format::default()).expect("format");
+        let other_format: Bracket = serde_json::from_value(json).expect("deserialized");
+        match tournament.clone().update_pool(other_format) {
+            Err(Error::MismatchedPool(_, id)) => assert_eq!(id, pool.get_id()),
+            other => panic!("expected MismatchedPool but got {other:?}"),
+        }
+
+        let player = pool.get_participants().get_seeding()[0];
+        let other_participants = pool
+            .clone()
+            .substitute_participant(player, Player::new("substitute".into()))
+            .expect("substituted");
+        match tournament.clone().update_pool(other_participants) {
+            Err(Error::MismatchedPool(_, id)) => assert_eq!(id, pool.get_id()),
+            other => panic!("expected MismatchedPool but got {other:?}"),
+        }
+
+        assert!(tournament.update_pool(pool).is_ok());
+    }
+}
@@ -42,9 +42,9 @@ struct BracketDisplay {
     loser_bracket_lines: Vec<Vec<BoxElement>>,
     /// Grand finals
     grand_finals: MinimalMatch,
-    /// Grand finals reset
-    grand_finals_reset: MinimalMatch,
-    /// Bracket object to update
+    /// Grand finals reset, if grand finals can be reset
+    grand_finals_reset: Option<MinimalMatch>,
+    /// Bracket object to update, without its event log
     bracket: Bracket,
 }
 
@@ -124,7 +124,7 @@ pub async fn new_bracket_from_players(Json(player_list): Json<PlayerList>) -> im
         }
     };
     let gf = from_participants(&gf, &participants);
-    let gf_reset = from_participants(&gf_reset, &participants);
+    let gf_reset = gf_reset.map(|gf_reset| from_participants(&gf_reset, &participants));
 
     let bracket = BracketDisplay {
         winner_bracket: winner_bracket_rounds,
@@ -133,7 +133,7 @@ pub async fn new_bracket_from_players(Json(player_list): Json<PlayerList>) -> im
         loser_bracket_lines,
         grand_finals: gf,
         grand_finals_reset: gf_reset,
-        bracket,
+        bracket: bracket.without_events(),
     };
     tracing::info!("new bracket {}", bracket.bracket.get_id());
     tracing::debug!("new bracket {:?}", bracket);
@@ -149,12 +149,17 @@ pub async fn new_bracket_from_players(Json(player_list): Json<PlayerList>) -> im
 /// May panic if I fucked up
 ///
 /// # Errors
-/// Error 500 if a user gets out of sync with the bracket in the database and
-/// the one displayed in the web page
+/// Error 400 if the bracket sent is malformed. Error 500 if a user gets out
+/// of sync with the bracket in the database and the one displayed in the web
+/// page
 #[instrument(name = "report_result", skip(report))]
 pub async fn report_result(Json(report): Json<ReportResultInput>) -> impl IntoResponse {
     tracing::debug!("new reported result");
     let mut bracket = report.bracket;
+    if let Err(issues) = bracket.validate() {
+        tracing::warn!("rejected malformed bracket: {issues:?}");
+        return Err(StatusCode::BAD_REQUEST);
+    }
 
     bracket = match bracket.tournament_organiser_reports_result(
         report.p1_id,
@@ -225,7 +230,7 @@ pub async fn report_result(Json(report): Json<ReportResultInput>) -> impl IntoRe
         }
     };
     let gf = from_participants(&gf, &participants);
-    let gf_reset = from_participants(&gf_reset, &participants);
+    let gf_reset = gf_reset.map(|gf_reset| from_participants(&gf_reset, &participants));
 
     let bracket = BracketDisplay {
         winner_bracket: winner_bracket_rounds,
@@ -234,7 +239,7 @@ pub async fn report_result(Json(report): Json<ReportResultInput>) -> impl IntoRe
         loser_bracket_lines,
         grand_finals: gf,
         grand_finals_reset: gf_reset,
-        bracket,
+        bracket: bracket.without_events(),
     };
     tracing::info!("updated bracket {}", bracket.bracket.get_id());
     tracing::debug!("updated bracket {:?}", bracket);